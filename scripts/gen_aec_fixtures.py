@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+"""Generate the echo cancellation test fixtures (src-tauri/tests/fixtures/aec/)
+
+Synthetic, so the files are small and reproducible (fixed seed):
+
+- far_end.wav:  what Emberleaf plays (speech-like noise bursts), 3 s
+- near_end.wav: a person talking near the mic, 2.2-2.8 s only
+- mic.wav:      far_end through a simulated room (30 ms delay, reflections)
+                plus near_end and a low noise floor
+
+All 16 kHz, mono, 16-bit PCM. Run from the repository root.
+"""
+
+import math
+import random
+import struct
+import wave
+from pathlib import Path
+
+RATE = 16000
+SECONDS = 3.0
+OUT = Path("src-tauri/tests/fixtures/aec")
+
+
+def speech_like(rng, n, cutoff_hz, syllable_hz, level):
+    """Low-passed noise with a syllable-rate envelope"""
+    alpha = 1.0 - math.exp(-2.0 * math.pi * cutoff_hz / RATE)
+    y = 0.0
+    out = []
+    for i in range(n):
+        y += alpha * (rng.uniform(-1.0, 1.0) - y)
+        envelope = 0.5 + 0.5 * math.sin(2.0 * math.pi * syllable_hz * i / RATE)
+        out.append(y * envelope * level)
+    return out
+
+
+def room(rng):
+    """Impulse response: direct path after 30 ms, decaying reflections up to 70 ms"""
+    taps = [0.0] * int(0.070 * RATE)
+    taps[int(0.030 * RATE)] = 0.4
+    for i in range(int(0.032 * RATE), len(taps)):
+        decay = math.exp(-(i - 0.030 * RATE) / (0.010 * RATE))
+        taps[i] = rng.gauss(0.0, 0.05) * decay
+    return taps
+
+
+def convolve(signal, taps):
+    nonzero = [(k, h) for k, h in enumerate(taps) if h != 0.0]
+    return [
+        sum(h * signal[i - k] for k, h in nonzero if i >= k) for i in range(len(signal))
+    ]
+
+
+def write(name, samples):
+    peak = 32767
+    frames = b"".join(
+        struct.pack("<h", max(-peak, min(peak, int(round(s * peak))))) for s in samples
+    )
+    with wave.open(str(OUT / name), "wb") as f:
+        f.setnchannels(1)
+        f.setsampwidth(2)
+        f.setframerate(RATE)
+        f.writeframes(frames)
+
+
+def main():
+    rng = random.Random(20261018)
+    n = int(SECONDS * RATE)
+
+    far = speech_like(rng, n, 2000.0, 4.0, 0.9)
+    near = [0.0] * n
+    start, end = int(2.2 * RATE), int(2.8 * RATE)
+    talk = speech_like(rng, end - start, 1500.0, 5.0, 0.8)
+    near[start:end] = talk
+
+    echo = convolve(far, room(rng))
+    mic = [e + v + rng.gauss(0.0, 0.0005) for e, v in zip(echo, near)]
+
+    OUT.mkdir(parents=True, exist_ok=True)
+    write("far_end.wav", far)
+    write("near_end.wav", near)
+    write("mic.wav", mic)
+
+
+if __name__ == "__main__":
+    main()
@@ -166,6 +166,8 @@ fn main() {
             // Only generate bindings for sherpa-onnx types (v1.10.30 API)
             .allowlist_type("SherpaOnnx.*")
             .allowlist_function("SherpaOnnx.*")
+            // Offline punctuation entry points lack the "Onnx" infix in v1.10.30
+            .allowlist_function("SherpaOfflinePunctuation.*")
             .allowlist_var("SHERPA_ONNX_.*")
             // Generate simpler C types
             .derive_default(true)
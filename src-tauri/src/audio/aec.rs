@@ -0,0 +1,509 @@
+//! Acoustic echo cancellation
+//!
+//! Everything Emberleaf plays (test tones, WAV assets, the mic monitor) is
+//! recorded into the [`FarEnd`] reference: a 16 kHz mono timeline anchored to
+//! a monotonic clock. Each capture stream runs an [`EchoCanceller`], an NLMS
+//! adaptive filter that learns the path from speaker to microphone (output and
+//! input latency plus the room) and subtracts the predicted echo before VAD
+//! and KWS see the frame.
+//!
+//! Adaptation pauses while someone talks over the playback (Geigel double-talk
+//! detector) so the filter does not learn to cancel the user's voice. Frames
+//! are passed through untouched when nothing was played within the tail.
+//!
+//! Sound from other applications is not part of the reference and is not
+//! cancelled.
+
+use super::TARGET_SAMPLE_RATE;
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// Echo cancellation configuration (`[audio.aec]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AecConfig {
+    pub enabled: bool,
+    /// Longest echo path to cancel in ms (output + input latency + room)
+    pub tail_ms: u32,
+}
+
+impl Default for AecConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tail_ms: 128,
+        }
+    }
+}
+
+/// Whether a capture cancels echo (`AudioDebugInfo::echo_cancellation`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AecState {
+    Active,
+    /// `audio.aec.enabled` is off
+    Disabled,
+    /// The processing rate is not 16 kHz, the only rate the reference is kept at
+    UnsupportedRate,
+}
+
+/// Reference kept behind the newest write (1 s); capture runs well within it
+const HISTORY: u64 = TARGET_SAMPLE_RATE as u64;
+
+/// An output stream that falls this far behind the clock (50 ms) resumes at
+/// the current time instead of writing into the past
+const MAX_LAG: u64 = TARGET_SAMPLE_RATE as u64 / 20;
+
+/// NLMS step size (0 < mu < 2; smaller converges slower but is more robust)
+const STEP_SIZE: f32 = 0.5;
+
+/// Near end louder than this share of the recent reference peak means double talk
+const GEIGEL_THRESHOLD: f32 = 0.6;
+
+/// Samples adaptation stays frozen after double talk was detected (30 ms)
+const DOUBLE_TALK_HOLD: usize = TARGET_SAMPLE_RATE as usize * 30 / 1000;
+
+/// Reference power below which the canceller is idle
+const IDLE_POWER: f32 = 1e-9;
+
+/// Reference one output stream can queue before a capture takes it (1 s);
+/// more is dropped, as when no capture is running
+const WRITER_CAPACITY: usize = HISTORY as usize;
+
+/// What Emberleaf played, as a 16 kHz mono timeline
+///
+/// Positions count samples since the reference was created, so an output
+/// stream and a capture stream agree on "when" through the shared clock.
+/// Output callbacks never lock the timeline: each [`FarEndWriter`] hands its
+/// samples over through its own lock-free ring, and the capture side mixes
+/// them in when it reads.
+pub struct FarEnd {
+    epoch: Instant,
+    timeline: Mutex<Timeline>,
+}
+
+struct Timeline {
+    /// Position of `samples[0]`
+    start: u64,
+    samples: VecDeque<f32>,
+    /// Rings of the output streams: (position, sample) not yet mixed in
+    writers: Vec<HeapCons<(u64, f32)>>,
+}
+
+impl Timeline {
+    fn forget_before(&mut self, position: u64) {
+        if position <= self.start {
+            return;
+        }
+        let n = ((position - self.start) as usize).min(self.samples.len());
+        self.samples.drain(..n);
+        self.start = position;
+    }
+
+    /// Sum `sample` in at `position`, as the speaker would
+    fn add(&mut self, position: u64, sample: f32) {
+        self.forget_before(position.saturating_sub(HISTORY));
+        // Whatever falls before the retained history is skipped
+        let Some(index) = position.checked_sub(self.start) else {
+            return;
+        };
+        let index = index as usize;
+        if self.samples.len() <= index {
+            self.samples.resize(index + 1, 0.0);
+        }
+        self.samples[index] += sample;
+    }
+
+    /// Mix in what the output streams queued, dropping rings of closed streams
+    fn drain_writers(&mut self) {
+        let mut writers = std::mem::take(&mut self.writers);
+        for ring in &mut writers {
+            while let Some((position, sample)) = ring.try_pop() {
+                self.add(position, sample);
+            }
+        }
+        writers.retain(|ring| ring.write_is_held());
+        self.writers = writers;
+    }
+}
+
+impl Default for FarEnd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FarEnd {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            timeline: Mutex::new(Timeline {
+                start: 0,
+                samples: VecDeque::new(),
+                writers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Reference shared by all output and capture streams of the process
+    pub fn global() -> Arc<FarEnd> {
+        static FAR_END: OnceLock<Arc<FarEnd>> = OnceLock::new();
+        FAR_END.get_or_init(|| Arc::new(FarEnd::new())).clone()
+    }
+
+    /// Timeline position of `at`
+    pub fn position(&self, at: Instant) -> u64 {
+        let micros = at.saturating_duration_since(self.epoch).as_micros();
+        (micros * TARGET_SAMPLE_RATE as u128 / 1_000_000) as u64
+    }
+
+    /// Add 16 kHz mono samples to the timeline starting at `at`
+    ///
+    /// Overlapping streams are summed, as the speaker would.
+    pub fn mix(&self, at: u64, samples: &[f32]) {
+        let mut timeline = self.timeline.lock().unwrap();
+        for (i, &sample) in samples.iter().enumerate() {
+            timeline.add(at + i as u64, sample);
+        }
+    }
+
+    /// Fill `out` with the reference starting at `from` (silence where nothing played)
+    pub fn read(&self, from: u64, out: &mut [f32]) {
+        let mut timeline = self.timeline.lock().unwrap();
+        timeline.drain_writers();
+        for (i, slot) in out.iter_mut().enumerate() {
+            let position = from + i as u64;
+            *slot = position
+                .checked_sub(timeline.start)
+                .and_then(|index| timeline.samples.get(index as usize))
+                .copied()
+                .unwrap_or(0.0);
+        }
+    }
+
+    /// Ring for a new output stream
+    fn register(&self) -> HeapProd<(u64, f32)> {
+        let (producer, consumer) = HeapRb::new(WRITER_CAPACITY).split();
+        self.timeline.lock().unwrap().writers.push(consumer);
+        producer
+    }
+}
+
+/// Records one output stream into the [`FarEnd`]
+///
+/// Lives in the stream's callback: downmixes to mono, converts to 16 kHz and
+/// places each buffer right after the previous one, starting at the time the
+/// first buffer was handed to the device. Created before the stream starts;
+/// `write` neither allocates nor locks.
+pub struct FarEndWriter {
+    far_end: Arc<FarEnd>,
+    ring: HeapProd<(u64, f32)>,
+    channels: usize,
+    /// Device samples per 16 kHz sample
+    step: f64,
+    /// Timeline position of the next converted sample (None before the first write)
+    cursor: Option<u64>,
+    /// Interleaved samples of the device frame being downmixed
+    frame_sum: f32,
+    frame_len: usize,
+    /// Device samples into the 16 kHz sample being converted
+    phase: f64,
+    /// Mono device samples it covers so far
+    sum: f32,
+    count: u32,
+    /// Latest converted sample, repeated for devices slower than 16 kHz
+    last: f32,
+}
+
+impl FarEndWriter {
+    pub fn new(far_end: Arc<FarEnd>, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            ring: far_end.register(),
+            far_end,
+            channels: channels.max(1),
+            step: sample_rate as f64 / TARGET_SAMPLE_RATE as f64,
+            cursor: None,
+            frame_sum: 0.0,
+            frame_len: 0,
+            phase: 0.0,
+            sum: 0.0,
+            count: 0,
+            last: 0.0,
+        }
+    }
+
+    /// Record interleaved samples (-1.0..1.0) handed to the device
+    pub fn write(&mut self, interleaved: impl IntoIterator<Item = f32>) {
+        let now = self.far_end.position(Instant::now());
+        let mut at = match self.cursor {
+            Some(cursor) if cursor + MAX_LAG >= now => cursor,
+            _ => now,
+        };
+
+        for sample in interleaved {
+            self.frame_sum += sample;
+            self.frame_len += 1;
+            if self.frame_len < self.channels {
+                continue;
+            }
+            let mono = self.frame_sum / self.channels as f32;
+            self.frame_sum = 0.0;
+            self.frame_len = 0;
+
+            // Each 16 kHz sample averages the device samples it covers
+            self.sum += mono;
+            self.count += 1;
+            self.phase += 1.0;
+            while self.phase >= self.step {
+                if self.count > 0 {
+                    self.last = self.sum / self.count as f32;
+                    self.sum = 0.0;
+                    self.count = 0;
+                }
+                let _ = self.ring.try_push((at, self.last));
+                at += 1;
+                self.phase -= self.step;
+            }
+        }
+        self.cursor = Some(at);
+    }
+}
+
+/// NLMS echo canceller for one 16 kHz capture stream
+pub struct EchoCanceller {
+    far_end: Arc<FarEnd>,
+    weights: Vec<f32>,
+    /// Reference history stored twice, so the window ending at the newest
+    /// sample is always the contiguous slice `history[head..head + taps]`
+    history: Vec<f32>,
+    head: usize,
+    /// Energy of the current window
+    power: f32,
+    /// Samples left before adaptation resumes after double talk
+    hold: usize,
+    reference: Vec<f32>,
+}
+
+impl EchoCanceller {
+    pub fn new(config: &AecConfig, far_end: Arc<FarEnd>) -> Self {
+        let taps = ((config.tail_ms * TARGET_SAMPLE_RATE / 1000) as usize).max(1);
+        Self {
+            far_end,
+            weights: vec![0.0; taps],
+            history: vec![0.0; 2 * taps],
+            head: 0,
+            power: 0.0,
+            hold: 0,
+            reference: Vec::new(),
+        }
+    }
+
+    /// Remove the echo from a captured frame whose last sample was recorded
+    /// at timeline position `end`
+    pub fn process(&mut self, frame: &mut [f32], end: u64) {
+        let taps = self.weights.len();
+        self.reference.resize(frame.len(), 0.0);
+        self.far_end
+            .read(end.saturating_sub(frame.len() as u64), &mut self.reference);
+
+        // Loudest echo the window can hold during this frame
+        let peak = self
+            .history
+            .iter()
+            .chain(&self.reference)
+            .fold(0.0f32, |peak, x| peak.max(x.abs()));
+
+        for (i, sample) in frame.iter_mut().enumerate() {
+            self.push(self.reference[i]);
+            if self.power < IDLE_POWER {
+                continue;
+            }
+
+            let window = &self.history[self.head..self.head + taps];
+            let near = *sample;
+            let echo: f32 = self.weights.iter().zip(window).map(|(w, x)| w * x).sum();
+            let error = near - echo;
+
+            if near.abs() > GEIGEL_THRESHOLD * peak {
+                self.hold = DOUBLE_TALK_HOLD;
+            }
+            if self.hold > 0 {
+                self.hold -= 1;
+            } else {
+                let gain = STEP_SIZE * error / (self.power + IDLE_POWER * taps as f32);
+                for (w, x) in self.weights.iter_mut().zip(window) {
+                    *w += gain * x;
+                }
+            }
+
+            *sample = error;
+        }
+    }
+
+    fn push(&mut self, x: f32) {
+        let taps = self.weights.len();
+        let oldest = self.history[self.head];
+        self.history[self.head] = x;
+        self.history[self.head + taps] = x;
+        self.head = (self.head + 1) % taps;
+
+        if self.head == 0 {
+            // Recompute now and then so rounding does not accumulate
+            self.power = self.history[..taps].iter().map(|x| x * x).sum();
+        } else {
+            self.power = (self.power + x * x - oldest * oldest).max(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: usize = 320;
+
+    fn fixture(name: &str) -> Vec<f32> {
+        let path = format!(
+            "{}/tests/fixtures/aec/{}.wav",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, TARGET_SAMPLE_RATE);
+        reader
+            .samples::<i16>()
+            .map(|s| s.unwrap() as f32 / i16::MAX as f32)
+            .collect()
+    }
+
+    /// Play `far_end.wav` and capture `mic.wav` in lockstep, 20 ms at a time
+    fn loopback(config: &AecConfig) -> Vec<f32> {
+        let far = fixture("far_end");
+        let mut mic = fixture("mic");
+        let far_end = Arc::new(FarEnd::new());
+        let mut canceller = config
+            .enabled
+            .then(|| EchoCanceller::new(config, far_end.clone()));
+
+        for (k, (played, captured)) in far.chunks(FRAME).zip(mic.chunks_mut(FRAME)).enumerate() {
+            far_end.mix((k * FRAME) as u64, played);
+            if let Some(canceller) = canceller.as_mut() {
+                canceller.process(captured, ((k + 1) * FRAME) as u64);
+            }
+        }
+        mic
+    }
+
+    fn energy(samples: impl Iterator<Item = f32>) -> f64 {
+        samples.map(|s| (s as f64).powi(2)).sum()
+    }
+
+    fn seconds(from: f32, to: f32) -> std::ops::Range<usize> {
+        (from * TARGET_SAMPLE_RATE as f32) as usize..(to * TARGET_SAMPLE_RATE as f32) as usize
+    }
+
+    #[test]
+    fn test_cancels_echo_of_own_playback() {
+        let mic = fixture("mic");
+        let out = loopback(&AecConfig::default());
+
+        // Echo only; the filter has had a second to converge
+        let range = seconds(1.0, 2.0);
+        let before = energy(mic[range.clone()].iter().copied());
+        let after = energy(out[range].iter().copied());
+        let erle = 10.0 * (before / after).log10();
+        assert!(erle >= 15.0, "echo return loss enhancement {:.1} dB", erle);
+    }
+
+    #[test]
+    fn test_keeps_near_end_speech() {
+        let near = fixture("near_end");
+        let out = loopback(&AecConfig::default());
+
+        let range = seconds(2.2, 2.8);
+        let speech = energy(near[range.clone()].iter().copied());
+        let residual = energy(
+            near[range.clone()]
+                .iter()
+                .zip(&out[range])
+                .map(|(&n, &o)| o - n),
+        );
+        let ratio = 10.0 * (speech / residual).log10();
+        assert!(ratio >= 10.0, "near end to residual {:.1} dB", ratio);
+    }
+
+    #[test]
+    fn test_disabled_passes_capture_through() {
+        let config = AecConfig {
+            enabled: false,
+            ..AecConfig::default()
+        };
+        assert_eq!(loopback(&config), fixture("mic"));
+    }
+
+    #[test]
+    fn test_silent_reference_leaves_frame_untouched() {
+        let mut canceller = EchoCanceller::new(&AecConfig::default(), Arc::new(FarEnd::new()));
+        let mut frame: Vec<f32> = (0..FRAME).map(|i| i as f32 / 1000.0).collect();
+        let original = frame.clone();
+        canceller.process(&mut frame, 10_000);
+        assert_eq!(frame, original);
+    }
+
+    #[test]
+    fn test_writer_downmixes_and_converts_to_16khz() {
+        let far_end = Arc::new(FarEnd::new());
+        let mut writer = FarEndWriter::new(far_end.clone(), 48_000, 2);
+
+        // 20 ms of stereo at 48 kHz: left 0.5, right -0.1
+        writer.write((0..960).flat_map(|_| [0.5, -0.1]));
+        let end = writer.cursor.unwrap();
+
+        let mut reference = vec![0.0; FRAME];
+        far_end.read(end - FRAME as u64, &mut reference);
+        assert!(reference.iter().all(|&s| (s - 0.2).abs() < 1e-6));
+
+        // The next buffer continues right after the first one
+        writer.write((0..960).flat_map(|_| [0.5, -0.1]));
+        assert_eq!(writer.cursor, Some(end + FRAME as u64));
+    }
+
+    #[test]
+    fn test_writer_does_not_wait_for_the_timeline() {
+        let far_end = Arc::new(FarEnd::new());
+        let mut writer = FarEndWriter::new(far_end.clone(), 16_000, 1);
+
+        // A capture reading at the same time holds the lock; a callback
+        // that waited for it would hang here
+        let held = far_end.timeline.lock().unwrap();
+        writer.write([0.5; FRAME]);
+        drop(held);
+
+        let end = writer.cursor.unwrap();
+        let mut reference = vec![0.0; FRAME];
+        far_end.read(end - FRAME as u64, &mut reference);
+        assert!(reference.iter().all(|&s| s == 0.5));
+
+        drop(writer);
+        far_end.read(end, &mut reference);
+        assert!(far_end.timeline.lock().unwrap().writers.is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_streams_are_summed() {
+        let far_end = FarEnd::new();
+        far_end.mix(100, &[0.25; 10]);
+        far_end.mix(105, &[0.5; 10]);
+
+        let mut reference = [0.0; 20];
+        far_end.read(100, &mut reference);
+        assert_eq!(reference[0], 0.25);
+        assert_eq!(reference[5], 0.75);
+        assert_eq!(reference[12], 0.5);
+        assert_eq!(reference[19], 0.0);
+    }
+}
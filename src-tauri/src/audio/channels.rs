@@ -0,0 +1,429 @@
+//! Channel selection for multi-channel microphones
+//!
+//! Runs in the capture callback on interleaved device samples and produces
+//! the mono signal the pipeline works on (`[audio.channels]`):
+//!
+//! - `average`: mean of all channels (default, right for stereo mics)
+//! - `channel`: one input, e.g. an interface with the mic on input 3
+//! - `subset`: mean of some inputs
+//! - `beam`: delay-and-sum beamformer for mic arrays, steered toward a direction
+//!
+//! Per-channel levels of the running capture are published through
+//! [`ChannelMeter`] and shown in the input device list. Nothing here allocates
+//! or blocks once the stream runs: the caller owns the output buffer and the
+//! meter skips a publish rather than wait for a reader.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Speed of sound at room temperature (mm/s)
+const SPEED_OF_SOUND_MM_S: f32 = 343_000.0;
+
+/// Most channels a selection or array may refer to
+pub const MAX_CHANNELS: u16 = 32;
+
+/// Largest distance of a mic from the array origin (mm)
+pub const MAX_MIC_OFFSET_MM: f32 = 1000.0;
+
+/// Per-channel levels are published this often
+const METER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Levels older than this are not reported (capture stopped)
+const METER_STALE: Duration = Duration::from_secs(2);
+
+/// Levels below this are reported as this (silence has no finite dB value)
+const FLOOR_DBFS: f32 = -100.0;
+
+/// How device channels become the mono capture signal (`[audio.channels]`)
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ChannelMix {
+    /// Mean of all channels
+    #[default]
+    Average,
+    /// One channel (0-based)
+    Channel { channel: u16 },
+    /// Mean of the listed channels (0-based)
+    Subset { channels: Vec<u16> },
+    /// Delay-and-sum beam toward `steer_deg`
+    Beam {
+        /// Mic positions `[x, y]` in mm in the array plane, one per channel from 0
+        mic_positions_mm: Vec<[f32; 2]>,
+        /// Direction to listen in: degrees counterclockwise from the +x axis
+        #[serde(default)]
+        steer_deg: f32,
+    },
+}
+
+impl ChannelMix {
+    /// Channels the device must provide for this mix
+    pub fn required_channels(&self) -> u16 {
+        match self {
+            Self::Average => 1,
+            Self::Channel { channel } => channel.saturating_add(1),
+            Self::Subset { channels } => channels.iter().max().map_or(1, |c| c.saturating_add(1)),
+            Self::Beam {
+                mic_positions_mm, ..
+            } => mic_positions_mm.len() as u16,
+        }
+    }
+}
+
+/// Fractional delay line per channel, summed after alignment
+struct Beam {
+    /// Delay per channel in samples (the last mic to hear the wave gets 0)
+    delays: Vec<f32>,
+    history: Vec<Vec<f32>>,
+    pos: usize,
+}
+
+impl Beam {
+    fn new(mic_positions_mm: &[[f32; 2]], steer_deg: f32, sample_rate: u32) -> Self {
+        let (sin, cos) = steer_deg.to_radians().sin_cos();
+        // Distance along the steering direction: the mic furthest along hears
+        // a wave from there first and is delayed most
+        let along: Vec<f32> = mic_positions_mm
+            .iter()
+            .map(|[x, y]| x * cos + y * sin)
+            .collect();
+        let last = along.iter().copied().fold(f32::INFINITY, f32::min);
+        let delays: Vec<f32> = along
+            .iter()
+            .map(|d| (d - last) / SPEED_OF_SOUND_MM_S * sample_rate as f32)
+            .collect();
+        let len = delays.iter().copied().fold(0.0, f32::max).floor() as usize + 2;
+        Self {
+            history: vec![vec![0.0; len]; delays.len()],
+            delays,
+            pos: 0,
+        }
+    }
+
+    fn next(&mut self, frame: &[f32]) -> f32 {
+        let len = self.history[0].len();
+        self.pos = (self.pos + 1) % len;
+        let mut sum = 0.0;
+        for ((history, delay), sample) in self.history.iter_mut().zip(&self.delays).zip(frame) {
+            history[self.pos] = *sample;
+            let whole = *delay as usize;
+            let frac = delay - whole as f32;
+            let newer = history[(self.pos + len - whole) % len];
+            let older = history[(self.pos + len - whole - 1) % len];
+            sum += newer + (older - newer) * frac;
+        }
+        sum / self.delays.len() as f32
+    }
+}
+
+enum Mode {
+    Average,
+    Select(Vec<usize>),
+    Beam(Beam),
+}
+
+/// Turns interleaved device frames into mono for one capture stream
+pub struct ChannelMixer {
+    channels: usize,
+    mode: Mode,
+    device_name: String,
+    meter: Arc<ChannelMeter>,
+    energy: Vec<f32>,
+    /// Per-channel levels handed to the meter
+    levels: Vec<f32>,
+    metered_frames: usize,
+    last_publish: Instant,
+}
+
+impl ChannelMixer {
+    /// Falls back to averaging when the device has fewer channels than the mix needs
+    pub fn new(
+        mix: &ChannelMix,
+        channels: usize,
+        sample_rate: u32,
+        device_name: &str,
+        meter: Arc<ChannelMeter>,
+    ) -> Self {
+        let channels = channels.max(1);
+        let mode = if mix.required_channels() as usize > channels {
+            log::warn!(
+                "Channel mix {:?} needs {} channels but '{}' has {}; averaging all channels",
+                mix,
+                mix.required_channels(),
+                device_name,
+                channels
+            );
+            Mode::Average
+        } else {
+            match mix {
+                ChannelMix::Average => Mode::Average,
+                ChannelMix::Channel { channel } => Mode::Select(vec![*channel as usize]),
+                ChannelMix::Subset { channels } => {
+                    Mode::Select(channels.iter().map(|&c| c as usize).collect())
+                }
+                ChannelMix::Beam {
+                    mic_positions_mm,
+                    steer_deg,
+                } => Mode::Beam(Beam::new(mic_positions_mm, *steer_deg, sample_rate)),
+            }
+        };
+        meter.start(device_name, channels);
+        Self {
+            channels,
+            mode,
+            device_name: device_name.to_string(),
+            meter,
+            energy: vec![0.0; channels],
+            levels: vec![0.0; channels],
+            metered_frames: 0,
+            last_publish: Instant::now(),
+        }
+    }
+
+    /// Mix interleaved input (-1.0..1.0) into `mono`, one sample per frame
+    ///
+    /// Returns the number of samples written; input beyond `mono.len()`
+    /// frames is ignored.
+    pub fn process(&mut self, interleaved: &[f32], mono: &mut [f32]) -> usize {
+        let mut written = 0;
+        for (frame, out) in interleaved.chunks_exact(self.channels).zip(mono.iter_mut()) {
+            for (energy, sample) in self.energy.iter_mut().zip(frame) {
+                *energy += sample * sample;
+            }
+            *out = match &mut self.mode {
+                Mode::Average => frame.iter().sum::<f32>() / self.channels as f32,
+                Mode::Select(selected) => {
+                    selected.iter().map(|&c| frame[c]).sum::<f32>() / selected.len() as f32
+                }
+                Mode::Beam(beam) => beam.next(frame),
+            };
+            written += 1;
+        }
+        self.metered_frames += written;
+
+        if self.last_publish.elapsed() >= METER_INTERVAL && self.metered_frames > 0 {
+            for (level, energy) in self.levels.iter_mut().zip(&self.energy) {
+                *level = to_db(energy / self.metered_frames as f32);
+            }
+            self.meter.record(&self.device_name, &self.levels);
+            self.energy.fill(0.0);
+            self.metered_frames = 0;
+            self.last_publish = Instant::now();
+        }
+        written
+    }
+}
+
+fn to_db(energy: f32) -> f32 {
+    if energy > 0.0 {
+        (10.0 * energy.log10()).max(FLOOR_DBFS)
+    } else {
+        FLOOR_DBFS
+    }
+}
+
+/// Per-channel levels of the running capture (`DeviceInfo::channel_levels_dbfs`)
+#[derive(Default)]
+pub struct ChannelMeter {
+    latest: Mutex<MeteredDevice>,
+}
+
+/// The device whose stream was built last, and its latest levels
+#[derive(Default)]
+struct MeteredDevice {
+    name: String,
+    /// When `levels` were published; None until the first publish
+    at: Option<Instant>,
+    levels: Vec<f32>,
+}
+
+impl ChannelMeter {
+    pub fn global() -> Arc<ChannelMeter> {
+        static METER: OnceLock<Arc<ChannelMeter>> = OnceLock::new();
+        METER.get_or_init(Default::default).clone()
+    }
+
+    /// Meter `device_name` from now on (when its stream is built, not in the callback)
+    fn start(&self, device_name: &str, channels: usize) {
+        let mut latest = self.latest.lock().unwrap();
+        latest.name = device_name.to_string();
+        latest.at = None;
+        latest.levels.clear();
+        latest.levels.reserve(channels);
+    }
+
+    /// Publish from the capture callback: skipped while a reader holds the
+    /// lock, or once another device's stream was built
+    fn record(&self, device_name: &str, levels: &[f32]) {
+        let Ok(mut latest) = self.latest.try_lock() else {
+            return;
+        };
+        if latest.name != device_name {
+            return;
+        }
+        latest.levels.clear();
+        latest.levels.extend_from_slice(levels);
+        latest.at = Some(Instant::now());
+    }
+
+    /// Levels (dBFS) per channel of `device_name`, None unless it is being captured
+    pub fn levels(&self, device_name: &str) -> Option<Vec<f32>> {
+        let latest = self.latest.lock().unwrap();
+        let fresh = latest.at.is_some_and(|at| at.elapsed() < METER_STALE);
+        (latest.name == device_name && fresh).then(|| latest.levels.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    fn mixer(mix: &ChannelMix, channels: usize) -> ChannelMixer {
+        ChannelMixer::new(
+            mix,
+            channels,
+            RATE,
+            "Array",
+            Arc::new(ChannelMeter::default()),
+        )
+    }
+
+    fn mono(mixer: &mut ChannelMixer, interleaved: &[f32]) -> Vec<f32> {
+        let mut mono = vec![0.0; interleaved.len() / mixer.channels];
+        let written = mixer.process(interleaved, &mut mono);
+        assert_eq!(written, mono.len());
+        mono
+    }
+
+    /// Plane wave from `from_deg` at `freq_hz` as the array hears it
+    fn plane_wave(positions: &[[f32; 2]], from_deg: f32, freq_hz: f32, frames: usize) -> Vec<f32> {
+        let (sin, cos) = from_deg.to_radians().sin_cos();
+        (0..frames)
+            .flat_map(|n| {
+                positions.iter().map(move |[x, y]| {
+                    // Mics further toward the source hear it earlier
+                    let lead = (x * cos + y * sin) / SPEED_OF_SOUND_MM_S;
+                    let t = n as f32 / RATE as f32 + lead;
+                    (2.0 * std::f32::consts::PI * freq_hz * t).sin()
+                })
+            })
+            .collect()
+    }
+
+    fn rms_db(samples: &[f32]) -> f32 {
+        to_db(samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32)
+    }
+
+    #[test]
+    fn test_selects_single_channel_and_subset() {
+        let frames = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+        assert_eq!(
+            mono(&mut mixer(&ChannelMix::Channel { channel: 2 }, 4), &frames),
+            [0.3, 0.7]
+        );
+
+        let subset = ChannelMix::Subset {
+            channels: vec![0, 3],
+        };
+        let mixed = mono(&mut mixer(&subset, 4), &frames);
+        assert!((mixed[0] - 0.25).abs() < 1e-6 && (mixed[1] - 0.65).abs() < 1e-6);
+
+        let all = mono(&mut mixer(&ChannelMix::Average, 4), &frames);
+        assert!((all[0] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_missing_channel_falls_back_to_average() {
+        let mixed = mono(
+            &mut mixer(&ChannelMix::Channel { channel: 5 }, 2),
+            &[0.2, 0.4],
+        );
+        assert!((mixed[0] - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_beam_passes_steered_direction_and_attenuates_opposite() {
+        // Linear array along x, 40 mm spacing
+        let positions = [[0.0, 0.0], [40.0, 0.0], [80.0, 0.0], [120.0, 0.0]];
+        let beam = ChannelMix::Beam {
+            mic_positions_mm: positions.to_vec(),
+            steer_deg: 0.0,
+        };
+        let frames = RATE as usize / 2;
+        let settle = 100;
+
+        let on = mono(
+            &mut mixer(&beam, 4),
+            &plane_wave(&positions, 0.0, 2000.0, frames),
+        );
+        let off = mono(
+            &mut mixer(&beam, 4),
+            &plane_wave(&positions, 180.0, 2000.0, frames),
+        );
+        let single = rms_db(&plane_wave(&[[0.0, 0.0]], 0.0, 2000.0, frames));
+
+        assert!(rms_db(&on[settle..]) > single - 1.0);
+        assert!(rms_db(&off[settle..]) < single - 6.0);
+    }
+
+    #[test]
+    fn test_required_channels() {
+        assert_eq!(ChannelMix::Average.required_channels(), 1);
+        assert_eq!(ChannelMix::Channel { channel: 2 }.required_channels(), 3);
+        let subset = ChannelMix::Subset {
+            channels: vec![1, 5],
+        };
+        assert_eq!(subset.required_channels(), 6);
+    }
+
+    #[test]
+    fn test_meter_reports_per_channel_levels_of_captured_device() {
+        let meter = Arc::new(ChannelMeter::default());
+        let mut mixer = ChannelMixer::new(&ChannelMix::Average, 2, RATE, "Array", meter.clone());
+        assert_eq!(meter.levels("Array"), None, "nothing published yet");
+        mixer.last_publish -= METER_INTERVAL;
+        mono(&mut mixer, &[0.5, 0.0, -0.5, 0.0]);
+
+        let levels = meter.levels("Array").unwrap();
+        assert!((levels[0] - to_db(0.25)).abs() < 1e-3);
+        assert_eq!(levels[1], FLOOR_DBFS);
+        assert_eq!(meter.levels("Other"), None);
+    }
+
+    #[test]
+    fn test_meter_skips_publish_instead_of_blocking() {
+        let meter = Arc::new(ChannelMeter::default());
+        let mut mixer = ChannelMixer::new(&ChannelMix::Average, 2, RATE, "Array", meter.clone());
+        let mut out = [0.0; 2];
+
+        let reader = meter.latest.lock().unwrap();
+        mixer.last_publish -= METER_INTERVAL;
+        assert_eq!(mixer.process(&[0.5, 0.0, -0.5, 0.0], &mut out), 2);
+        drop(reader);
+        assert_eq!(meter.levels("Array"), None);
+
+        // Output shorter than the input: only what fits is mixed
+        mixer.last_publish -= METER_INTERVAL;
+        assert_eq!(mixer.process(&[0.5, 0.0, -0.5, 0.0, 0.1, 0.1], &mut out), 2);
+        assert!(meter.levels("Array").is_some());
+
+        // A stream built for another device takes the meter over
+        ChannelMixer::new(&ChannelMix::Average, 1, RATE, "Other", meter.clone());
+        mixer.last_publish -= METER_INTERVAL;
+        mixer.process(&[0.5, 0.0], &mut out);
+        assert_eq!(meter.levels("Array"), None);
+    }
+
+    #[test]
+    fn test_config_toml_shape() {
+        let mix: ChannelMix = toml::from_str(
+            "mode = \"beam\"\nmic_positions_mm = [[32, 0], [0, 32], [-32, 0], [0, -32]]\nsteer_deg = 90.0",
+        )
+        .unwrap();
+        assert_eq!(mix.required_channels(), 4);
+        let mix: ChannelMix = toml::from_str("mode = \"channel\"\nchannel = 3").unwrap();
+        assert_eq!(mix, ChannelMix::Channel { channel: 3 });
+    }
+}
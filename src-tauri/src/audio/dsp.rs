@@ -0,0 +1,614 @@
+//! Capture pre-processing chain
+//!
+//! Applied to every frame in `AudioCapture::next_frame`, after echo
+//! cancellation and before VAD, KWS and tagging see it:
+//!
+//! 1. High-pass filter: removes rumble, handling noise and DC offset
+//! 2. Noise suppression: spectral gain against a tracked noise floor (fans, hum)
+//! 3. AGC: brings speech to a target level, boosting by at most `agc_max_gain_db`
+//! 4. Limiter: keeps the boosted signal below -1 dBFS
+//!
+//! Each stage is switched on or off in `[audio.dsp]`. Noise suppression and
+//! AGC are off by default: they change what the KWS model was tuned on, so
+//! existing setups only get them when they ask. The smoothed input and
+//! output levels are published through [`DspMeter`] for diagnostics.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Pre-processing configuration (`[audio.dsp]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DspConfig {
+    pub high_pass: bool,
+    /// High-pass cutoff frequency (Hz)
+    pub high_pass_hz: f32,
+    pub noise_suppression: bool,
+    /// Maximum attenuation of steady noise (dB)
+    pub noise_suppression_db: f32,
+    pub agc: bool,
+    /// Speech level the AGC aims for (dBFS RMS)
+    pub agc_target_dbfs: f32,
+    /// Largest boost the AGC applies to quiet input (dB)
+    pub agc_max_gain_db: f32,
+    pub limiter: bool,
+}
+
+impl Default for DspConfig {
+    fn default() -> Self {
+        Self {
+            high_pass: true,
+            high_pass_hz: 80.0,
+            noise_suppression: false,
+            noise_suppression_db: 12.0,
+            agc: false,
+            agc_target_dbfs: -23.0,
+            agc_max_gain_db: 24.0,
+            limiter: true,
+        }
+    }
+}
+
+/// Levels below this are reported as this (silence has no finite dB value)
+const FLOOR_DBFS: f32 = -100.0;
+
+/// Smoothing of the reported levels per frame
+const METER_SMOOTHING: f32 = 0.9;
+
+/// Levels older than this are not reported (capture stopped)
+const METER_STALE: Duration = Duration::from_secs(2);
+
+fn to_db(energy: f32) -> f32 {
+    if energy > 0.0 {
+        (10.0 * energy.log10()).max(FLOOR_DBFS)
+    } else {
+        FLOOR_DBFS
+    }
+}
+
+fn mean_square(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32
+}
+
+/// Second-order Butterworth high-pass (RBJ biquad, transposed direct form II)
+struct HighPass {
+    b: [f32; 3],
+    a: [f32; 2],
+    z: [f32; 2],
+}
+
+impl HighPass {
+    fn new(cutoff_hz: f32, sample_rate: u32) -> Self {
+        let w0 = 2.0 * std::f32::consts::PI * cutoff_hz / sample_rate as f32;
+        let alpha = w0.sin() / std::f32::consts::SQRT_2;
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [
+                (1.0 + cos) / 2.0 / a0,
+                -(1.0 + cos) / a0,
+                (1.0 + cos) / 2.0 / a0,
+            ],
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+            z: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        for x in samples {
+            let y = self.b[0] * *x + self.z[0];
+            self.z[0] = self.b[1] * *x - self.a[0] * y + self.z[1];
+            self.z[1] = self.b[2] * *x - self.a[1] * y;
+            *x = y;
+        }
+    }
+}
+
+/// Noise floor tracking: follows drops immediately, rises ~4 dB/s
+const NOISE_RISE: f32 = 1.01;
+
+/// Smoothing of the per-bin power used for the gain (against musical noise)
+const POWER_SMOOTHING: f32 = 0.7;
+
+/// Minimum tracking underestimates the mean noise power by about this factor
+const NOISE_BIAS: f32 = 1.5;
+
+/// Weight of the previous clean estimate in the a priori SNR (decision-directed)
+const DD_ALPHA: f32 = 0.98;
+
+/// Spectral noise suppression with 20 ms windows at 50% overlap
+///
+/// Wiener gain from a decision-directed SNR estimate over a minimum-tracked
+/// noise floor per bin.
+///
+/// Output lags input by one window (20 ms); frame sizes are unchanged.
+struct NoiseSuppressor {
+    hop: usize,
+    /// sqrt-Hann, so analysis times synthesis sums to one at 50% overlap
+    window: Vec<f32>,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    /// Last `window.len()` input samples
+    analysis: Vec<f32>,
+    /// Overlap-add accumulator
+    overlap: Vec<f32>,
+    pending: Vec<f32>,
+    ready: VecDeque<f32>,
+    time: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    power: Vec<f32>,
+    noise: Option<Vec<f32>>,
+    /// Clean power per bin from the previous window
+    clean: Vec<f32>,
+    /// Gain never goes below this (linear)
+    floor: f32,
+}
+
+impl NoiseSuppressor {
+    fn new(max_suppression_db: f32, sample_rate: u32) -> Self {
+        let size = (sample_rate as usize / 50).max(2) & !1;
+        let hop = size / 2;
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(size);
+        let inverse = planner.plan_fft_inverse(size);
+        let window = (0..size)
+            .map(|n| (std::f32::consts::PI * n as f32 / size as f32).sin())
+            .collect();
+        let spectrum = forward.make_output_vec();
+        Self {
+            hop,
+            window,
+            analysis: vec![0.0; size],
+            overlap: vec![0.0; size],
+            pending: Vec::new(),
+            // One hop of silence so any frame size can be returned right away
+            ready: VecDeque::from(vec![0.0; hop]),
+            time: forward.make_input_vec(),
+            power: vec![0.0; spectrum.len()],
+            clean: vec![0.0; spectrum.len()],
+            spectrum,
+            forward,
+            inverse,
+            noise: None,
+            floor: 10f32.powf(-max_suppression_db / 20.0),
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        self.pending.extend_from_slice(samples);
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= self.hop {
+            let size = self.analysis.len();
+            self.analysis.copy_within(self.hop.., 0);
+            self.analysis[size - self.hop..]
+                .copy_from_slice(&self.pending[consumed..consumed + self.hop]);
+            consumed += self.hop;
+            self.process_window();
+        }
+        self.pending.drain(..consumed);
+
+        for x in samples.iter_mut() {
+            *x = self.ready.pop_front().unwrap_or(0.0);
+        }
+    }
+
+    fn process_window(&mut self) {
+        let size = self.analysis.len();
+        for ((t, x), w) in self.time.iter_mut().zip(&self.analysis).zip(&self.window) {
+            *t = x * w;
+        }
+        if self
+            .forward
+            .process(&mut self.time, &mut self.spectrum)
+            .is_err()
+        {
+            return;
+        }
+
+        // The first window seeds the noise floor unsmoothed
+        let smoothing = if self.noise.is_some() {
+            POWER_SMOOTHING
+        } else {
+            0.0
+        };
+        for (p, bin) in self.power.iter_mut().zip(&self.spectrum) {
+            *p = smoothing * *p + (1.0 - smoothing) * bin.norm_sqr();
+        }
+        let noise = self.noise.get_or_insert_with(|| self.power.clone());
+        for (((bin, n), p), clean) in self
+            .spectrum
+            .iter_mut()
+            .zip(noise.iter_mut())
+            .zip(&self.power)
+            .zip(self.clean.iter_mut())
+        {
+            *n = if *p < *n { *p } else { *n * NOISE_RISE };
+            let noise_power = (*n * NOISE_BIAS).max(f32::MIN_POSITIVE);
+            let posterior = bin.norm_sqr() / noise_power;
+            let prior =
+                DD_ALPHA * *clean / noise_power + (1.0 - DD_ALPHA) * (posterior - 1.0).max(0.0);
+            *bin *= (prior / (1.0 + prior)).max(self.floor);
+            *clean = bin.norm_sqr();
+        }
+        // DC and Nyquist must stay real for the inverse transform
+        let last = self.spectrum.len() - 1;
+        self.spectrum[0].im = 0.0;
+        self.spectrum[last].im = 0.0;
+
+        if self
+            .inverse
+            .process(&mut self.spectrum, &mut self.time)
+            .is_err()
+        {
+            return;
+        }
+        for ((o, t), w) in self.overlap.iter_mut().zip(&self.time).zip(&self.window) {
+            *o += t * w / size as f32;
+        }
+        self.ready.extend(&self.overlap[..self.hop]);
+        self.overlap.copy_within(self.hop.., 0);
+        self.overlap[size - self.hop..].fill(0.0);
+    }
+}
+
+/// Frames quieter than this are not speech; the AGC holds its gain
+const AGC_GATE_DBFS: f32 = -55.0;
+
+/// Strongest attenuation the AGC applies to loud input (dB)
+const AGC_MAX_CUT_DB: f32 = 12.0;
+
+/// Time constant for lowering the gain (fast, avoids clipping)
+const AGC_ATTACK: Duration = Duration::from_millis(50);
+
+/// Time constant for raising the gain (slow, avoids pumping)
+const AGC_RELEASE: Duration = Duration::from_millis(1500);
+
+/// Automatic gain control on frame RMS
+struct Agc {
+    target_dbfs: f32,
+    max_gain_db: f32,
+    gain_db: f32,
+    sample_rate: u32,
+}
+
+impl Agc {
+    fn new(target_dbfs: f32, max_gain_db: f32, sample_rate: u32) -> Self {
+        Self {
+            target_dbfs,
+            max_gain_db,
+            gain_db: 0.0,
+            sample_rate,
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        let previous = 10f32.powf(self.gain_db / 20.0);
+
+        let level_db = to_db(mean_square(samples));
+        if level_db > AGC_GATE_DBFS {
+            let wanted = (self.target_dbfs - level_db).clamp(-AGC_MAX_CUT_DB, self.max_gain_db);
+            let tau = if wanted < self.gain_db {
+                AGC_ATTACK
+            } else {
+                AGC_RELEASE
+            };
+            let frame_s = samples.len() as f32 / self.sample_rate as f32;
+            let step = 1.0 - (-frame_s / tau.as_secs_f32()).exp();
+            self.gain_db += (wanted - self.gain_db) * step;
+        }
+
+        // Ramp across the frame so gain changes do not click
+        let current = 10f32.powf(self.gain_db / 20.0);
+        let n = samples.len().max(1) as f32;
+        for (i, x) in samples.iter_mut().enumerate() {
+            *x *= previous + (current - previous) * (i + 1) as f32 / n;
+        }
+    }
+}
+
+/// Limiter ceiling (-1 dBFS)
+const LIMITER_CEILING: f32 = 0.891;
+
+/// Time for the limiter gain to recover after a peak
+const LIMITER_RELEASE: Duration = Duration::from_millis(50);
+
+/// Peak limiter with instant attack
+struct Limiter {
+    gain: f32,
+    release: f32,
+}
+
+impl Limiter {
+    fn new(sample_rate: u32) -> Self {
+        let samples = LIMITER_RELEASE.as_secs_f32() * sample_rate as f32;
+        Self {
+            gain: 1.0,
+            release: 1.0 - (-1.0 / samples).exp(),
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        for x in samples {
+            let allowed = if x.abs() > LIMITER_CEILING {
+                LIMITER_CEILING / x.abs()
+            } else {
+                1.0
+            };
+            self.gain = if allowed < self.gain {
+                allowed
+            } else {
+                self.gain + (allowed - self.gain) * self.release
+            };
+            *x *= self.gain;
+        }
+    }
+}
+
+/// Smoothed levels around the chain (`AudioDebugInfo::dsp_levels`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DspLevels {
+    /// Level as captured (after echo cancellation)
+    pub input_rms_dbfs: f32,
+    /// Level handed to VAD/KWS
+    pub output_rms_dbfs: f32,
+    /// Current AGC gain (0 when the AGC is off)
+    pub agc_gain_db: f32,
+}
+
+/// Latest levels of the running capture
+///
+/// The capture stream records here; tagging listens to the KWS capture, so
+/// only one runs at a time.
+#[derive(Default)]
+pub struct DspMeter {
+    latest: Mutex<Option<(Instant, DspLevels)>>,
+}
+
+impl DspMeter {
+    pub fn global() -> Arc<DspMeter> {
+        static METER: OnceLock<Arc<DspMeter>> = OnceLock::new();
+        METER.get_or_init(Default::default).clone()
+    }
+
+    fn record(&self, levels: DspLevels) {
+        *self.latest.lock().unwrap() = Some((Instant::now(), levels));
+    }
+
+    /// Levels of the last frame, None if capture has not run for a while
+    pub fn levels(&self) -> Option<DspLevels> {
+        self.latest
+            .lock()
+            .unwrap()
+            .filter(|(at, _)| at.elapsed() < METER_STALE)
+            .map(|(_, levels)| levels)
+    }
+}
+
+/// The configured stages for one capture stream
+pub struct DspChain {
+    high_pass: Option<HighPass>,
+    noise: Option<NoiseSuppressor>,
+    agc: Option<Agc>,
+    limiter: Option<Limiter>,
+    meter: Arc<DspMeter>,
+    input_energy: f32,
+    output_energy: f32,
+}
+
+impl DspChain {
+    pub fn new(config: &DspConfig, sample_rate: u32, meter: Arc<DspMeter>) -> Self {
+        Self {
+            high_pass: config
+                .high_pass
+                .then(|| HighPass::new(config.high_pass_hz, sample_rate)),
+            noise: config
+                .noise_suppression
+                .then(|| NoiseSuppressor::new(config.noise_suppression_db, sample_rate)),
+            agc: config
+                .agc
+                .then(|| Agc::new(config.agc_target_dbfs, config.agc_max_gain_db, sample_rate)),
+            limiter: config.limiter.then(|| Limiter::new(sample_rate)),
+            meter,
+            input_energy: 0.0,
+            output_energy: 0.0,
+        }
+    }
+
+    /// Process one mono frame in place
+    pub fn process(&mut self, frame: &mut [f32]) {
+        let input = mean_square(frame);
+
+        if let Some(stage) = self.high_pass.as_mut() {
+            stage.process(frame);
+        }
+        if let Some(stage) = self.noise.as_mut() {
+            stage.process(frame);
+        }
+        if let Some(stage) = self.agc.as_mut() {
+            stage.process(frame);
+        }
+        if let Some(stage) = self.limiter.as_mut() {
+            stage.process(frame);
+        }
+
+        let output = mean_square(frame);
+        self.input_energy = METER_SMOOTHING * self.input_energy + (1.0 - METER_SMOOTHING) * input;
+        self.output_energy =
+            METER_SMOOTHING * self.output_energy + (1.0 - METER_SMOOTHING) * output;
+        self.meter.record(DspLevels {
+            input_rms_dbfs: to_db(self.input_energy),
+            output_rms_dbfs: to_db(self.output_energy),
+            agc_gain_db: self.agc.as_ref().map_or(0.0, |agc| agc.gain_db),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+    const FRAME: usize = 320;
+
+    fn sine(freq_hz: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|n| {
+                amplitude * (2.0 * std::f32::consts::PI * freq_hz * n as f32 / RATE as f32).sin()
+            })
+            .collect()
+    }
+
+    /// Deterministic white noise in -amplitude..amplitude
+    fn noise(amplitude: f32, seconds: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491u32;
+        (0..(seconds * RATE as f32) as usize)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                amplitude * (state as f32 / u32::MAX as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    fn run(stage: &mut impl FnMut(&mut [f32]), input: &[f32]) -> Vec<f32> {
+        let mut output = input.to_vec();
+        for frame in output.chunks_mut(FRAME) {
+            stage(frame);
+        }
+        output
+    }
+
+    fn db(samples: &[f32]) -> f32 {
+        to_db(mean_square(samples))
+    }
+
+    #[test]
+    fn test_high_pass_removes_rumble_keeps_speech_band() {
+        let mut filter = HighPass::new(80.0, RATE);
+        let rumble = run(&mut |f| filter.process(f), &sine(20.0, 0.5, 1.0));
+        let mut filter = HighPass::new(80.0, RATE);
+        let voice = run(&mut |f| filter.process(f), &sine(400.0, 0.5, 1.0));
+
+        let settled = RATE as usize / 2..;
+        assert!(db(&rumble[settled.clone()]) < db(&sine(20.0, 0.5, 1.0)) - 20.0);
+        assert!((db(&voice[settled]) - db(&sine(400.0, 0.5, 1.0))).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_noise_suppression_lowers_noise_and_keeps_tone() {
+        let background = noise(0.02, 3.0);
+        let mut input = background.clone();
+        for (x, t) in input[RATE as usize * 2..]
+            .iter_mut()
+            .zip(sine(1000.0, 0.3, 1.0))
+        {
+            *x += t;
+        }
+
+        let mut suppressor = NoiseSuppressor::new(12.0, RATE);
+        let output = run(&mut |f| suppressor.process(f), &input);
+
+        // Noise only, after the floor has been learned
+        let quiet = RATE as usize..RATE as usize * 2;
+        assert!(db(&output[quiet.clone()]) < db(&input[quiet]) - 8.0);
+
+        // The tone passes (output lags by one window; skip the edges)
+        let tone = RATE as usize * 2 + FRAME..RATE as usize * 3 - FRAME;
+        assert!((db(&output[tone.clone()]) - db(&input[tone])).abs() < 1.5);
+    }
+
+    #[test]
+    fn test_noise_suppression_keeps_frame_size() {
+        let mut suppressor = NoiseSuppressor::new(12.0, RATE);
+        for len in [320, 400, 160, 333] {
+            let mut frame = vec![0.1; len];
+            suppressor.process(&mut frame);
+            assert_eq!(frame.len(), len);
+        }
+    }
+
+    #[test]
+    fn test_agc_brings_quiet_speech_to_target_within_max_gain() {
+        let mut agc = Agc::new(-23.0, 24.0, RATE);
+        let quiet = sine(300.0, 0.02, 6.0); // about -37 dBFS
+        let output = run(&mut |f| agc.process(f), &quiet);
+        let end = output.len() - RATE as usize / 2..;
+        assert!((db(&output[end]) + 23.0).abs() < 1.0);
+
+        let mut agc = Agc::new(-23.0, 6.0, RATE);
+        run(&mut |f| agc.process(f), &quiet);
+        assert!(agc.gain_db <= 6.0);
+    }
+
+    #[test]
+    fn test_agc_does_not_boost_silence() {
+        let mut agc = Agc::new(-23.0, 24.0, RATE);
+        run(&mut |f| agc.process(f), &noise(0.0005, 2.0));
+        assert_eq!(agc.gain_db, 0.0);
+    }
+
+    #[test]
+    fn test_limiter_caps_peaks() {
+        let mut limiter = Limiter::new(RATE);
+        let output = run(&mut |f| limiter.process(f), &sine(200.0, 2.0, 0.5));
+        assert!(output.iter().all(|x| x.abs() <= LIMITER_CEILING + 1e-6));
+    }
+
+    #[test]
+    fn test_defaults_only_filter_and_limit() {
+        let config = DspConfig::default();
+        assert!(config.high_pass && config.limiter);
+        assert!(!config.noise_suppression && !config.agc);
+    }
+
+    #[test]
+    fn test_chain_with_all_stages_off_is_transparent_and_metered() {
+        let config = DspConfig {
+            high_pass: false,
+            noise_suppression: false,
+            agc: false,
+            limiter: false,
+            ..DspConfig::default()
+        };
+        let meter = Arc::new(DspMeter::default());
+        let mut chain = DspChain::new(&config, RATE, meter.clone());
+        assert_eq!(meter.levels(), None);
+
+        let mut frame: Vec<f32> = (0..FRAME as i32)
+            .map(|i| (i * 37 % 2000 - 1000) as f32 / 32768.0)
+            .collect();
+        let original = frame.clone();
+        chain.process(&mut frame);
+        assert_eq!(frame, original);
+
+        let levels = meter.levels().unwrap();
+        assert_eq!(levels.input_rms_dbfs, levels.output_rms_dbfs);
+        assert_eq!(levels.agc_gain_db, 0.0);
+    }
+
+    #[test]
+    fn test_chain_reports_gain_on_quiet_input() {
+        let config = DspConfig {
+            agc: true,
+            ..DspConfig::default()
+        };
+        let meter = Arc::new(DspMeter::default());
+        let mut chain = DspChain::new(&config, RATE, meter.clone());
+        let quiet = sine(300.0, 0.02, 3.0);
+        for frame in quiet.chunks(FRAME) {
+            let mut frame = frame.to_vec();
+            chain.process(&mut frame);
+        }
+        let levels = meter.levels().unwrap();
+        assert!(levels.agc_gain_db > 6.0);
+        assert!(levels.output_rms_dbfs > levels.input_rms_dbfs + 6.0);
+    }
+}
@@ -0,0 +1,326 @@
+//! Capture format negotiation
+//!
+//! Picks the configuration that costs the pipeline least instead of the
+//! device default (often 48 kHz stereo f32):
+//!
+//! 1. Rate: the processing rate itself, then an integer multiple of it
+//!    (48/32 kHz), then the nearest rate above, then the highest below
+//! 2. Channels: as many as the channel mix uses (`[audio.channels]`), then as
+//!    few as possible (less to downmix)
+//! 3. Sample format: cheapest conversion first (i16, f32, i32, ...)
+
+use super::resample::ResamplerKind;
+use cpal::{SampleFormat, SampleRate, SupportedStreamConfig, SupportedStreamConfigRange};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Sample formats the capture path converts, cheapest first
+pub const CAPTURE_FORMATS: [SampleFormat; 10] = [
+    SampleFormat::I16,
+    SampleFormat::F32,
+    SampleFormat::I32,
+    SampleFormat::U16,
+    SampleFormat::U8,
+    SampleFormat::I8,
+    SampleFormat::F64,
+    SampleFormat::U32,
+    SampleFormat::I64,
+    SampleFormat::U64,
+];
+
+/// Rates listed in `DeviceInfo::sample_rates` when a device supports them
+const COMMON_RATES: [u32; 10] = [
+    8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 192000,
+];
+
+/// Highest integer multiple of the processing rate considered cheap to resample
+const MAX_RATE_MULTIPLE: u32 = 12;
+
+/// Format a capture stream runs with (`AudioDebugInfo::capture_format`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// `i16`, `f32`, ...
+    pub sample_format: String,
+    /// Negotiated from the supported configs (false: device default)
+    pub negotiated: bool,
+    /// Resampler to the processing rate (None: the device runs at it)
+    pub resampler: Option<ResamplerKind>,
+}
+
+impl CaptureFormat {
+    pub fn new(
+        config: &SupportedStreamConfig,
+        negotiated: bool,
+        resampler: Option<ResamplerKind>,
+    ) -> Self {
+        Self {
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            sample_format: config.sample_format().to_string(),
+            negotiated,
+            resampler,
+        }
+    }
+}
+
+fn format_rank(format: SampleFormat) -> Option<usize> {
+    CAPTURE_FORMATS.iter().position(|f| *f == format)
+}
+
+/// Best rate in the range for `target` and its class (lower is better)
+fn rate_for(range: &SupportedStreamConfigRange, target: u32) -> (u8, u32) {
+    let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+    if (min..=max).contains(&target) {
+        return (0, target);
+    }
+    if let Some(rate) = (2..=MAX_RATE_MULTIPLE)
+        .map(|m| target * m)
+        .find(|rate| (min..=max).contains(rate))
+    {
+        return (1, rate);
+    }
+    if min > target {
+        (2, min)
+    } else {
+        (3, max)
+    }
+}
+
+/// The supported configuration cheapest to bring to `target_rate` mono
+///
+/// Only ranges with at least `min_channels` are considered (the channel mix
+/// needs them) unless the device has none. None when no range uses a sample
+/// format the capture path converts.
+pub fn choose(
+    ranges: impl IntoIterator<Item = SupportedStreamConfigRange>,
+    target_rate: u32,
+    min_channels: u16,
+) -> Option<SupportedStreamConfig> {
+    let mut ranges: Vec<_> = ranges
+        .into_iter()
+        .filter(|range| range.channels() > 0)
+        .collect();
+    if ranges.iter().any(|range| range.channels() >= min_channels) {
+        ranges.retain(|range| range.channels() >= min_channels);
+    }
+
+    ranges
+        .into_iter()
+        .filter_map(|range| {
+            let rank = format_rank(range.sample_format())?;
+            let (class, rate) = rate_for(&range, target_rate);
+            // Within a class, closer to the target is cheaper
+            let distance = rate.abs_diff(target_rate);
+            Some(((class, range.channels(), rank, distance), range, rate))
+        })
+        .min_by_key(|(key, _, _)| *key)
+        .map(|(_, range, rate)| range.with_sample_rate(SampleRate(rate)))
+}
+
+/// What a device offers, summarized over all its supported configs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub max_channels: u16,
+    pub sample_rates: Vec<u32>,
+    pub sample_formats: Vec<String>,
+}
+
+impl Capabilities {
+    /// None when the device lists no configs
+    pub fn from_ranges(
+        ranges: impl IntoIterator<Item = SupportedStreamConfigRange>,
+    ) -> Option<Self> {
+        let ranges: Vec<_> = ranges.into_iter().collect();
+        let max_channels = ranges.iter().map(|r| r.channels()).max()?;
+
+        let mut sample_rates: Vec<u32> = COMMON_RATES
+            .into_iter()
+            .filter(|rate| {
+                ranges
+                    .iter()
+                    .any(|r| (r.min_sample_rate().0..=r.max_sample_rate().0).contains(rate))
+            })
+            .collect();
+        if sample_rates.is_empty() {
+            // Only unusual rates; list the range bounds instead
+            sample_rates = ranges
+                .iter()
+                .flat_map(|r| [r.min_sample_rate().0, r.max_sample_rate().0])
+                .collect();
+            sample_rates.sort_unstable();
+            sample_rates.dedup();
+        }
+
+        let mut formats: Vec<SampleFormat> = ranges.iter().map(|r| r.sample_format()).collect();
+        formats.sort_by_key(|f| format_rank(*f).unwrap_or(usize::MAX));
+        formats.dedup();
+
+        Some(Self {
+            max_channels,
+            sample_rates,
+            sample_formats: formats.iter().map(|f| f.to_string()).collect(),
+        })
+    }
+}
+
+static ACTIVE: Mutex<Vec<(u64, CaptureFormat)>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Keeps a running capture's format listed in [`active`] until dropped
+pub struct ActiveFormat(u64);
+
+impl ActiveFormat {
+    pub fn register(format: CaptureFormat) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        ACTIVE.lock().unwrap().push((id, format));
+        Self(id)
+    }
+}
+
+impl Drop for ActiveFormat {
+    fn drop(&mut self) {
+        ACTIVE.lock().unwrap().retain(|(id, _)| *id != self.0);
+    }
+}
+
+/// Format of the most recently started capture that is still running
+pub fn active() -> Option<CaptureFormat> {
+    ACTIVE
+        .lock()
+        .unwrap()
+        .last()
+        .map(|(_, format)| format.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::SupportedBufferSize;
+
+    fn range(
+        channels: u16,
+        min: u32,
+        max: u32,
+        format: SampleFormat,
+    ) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(min),
+            SampleRate(max),
+            SupportedBufferSize::Unknown,
+            format,
+        )
+    }
+
+    fn summary(config: SupportedStreamConfig) -> (u32, u16, SampleFormat) {
+        (
+            config.sample_rate().0,
+            config.channels(),
+            config.sample_format(),
+        )
+    }
+
+    #[test]
+    fn test_prefers_native_16khz_mono() {
+        let ranges = vec![
+            range(2, 44100, 48000, SampleFormat::F32),
+            range(2, 8000, 96000, SampleFormat::F32),
+            range(1, 8000, 96000, SampleFormat::I16),
+        ];
+        assert_eq!(
+            summary(choose(ranges, 16000, 1).unwrap()),
+            (16000, 1, SampleFormat::I16)
+        );
+    }
+
+    #[test]
+    fn test_integer_multiple_beats_other_rates() {
+        let ranges = vec![
+            range(1, 44100, 44100, SampleFormat::I16),
+            range(1, 48000, 48000, SampleFormat::I16),
+        ];
+        assert_eq!(summary(choose(ranges, 16000, 1).unwrap()).0, 48000);
+
+        // Fixed 44.1 kHz device: nearest above the target
+        let ranges = vec![range(2, 44100, 44100, SampleFormat::F32)];
+        assert_eq!(summary(choose(ranges, 16000, 1).unwrap()).0, 44100);
+    }
+
+    #[test]
+    fn test_rate_outranks_channels_and_format() {
+        // Pro interface: 8 channels of i32 at 16 kHz vs mono f64 at 44.1 kHz
+        let ranges = vec![
+            range(1, 44100, 44100, SampleFormat::F64),
+            range(8, 16000, 192000, SampleFormat::I32),
+        ];
+        assert_eq!(
+            summary(choose(ranges, 16000, 1).unwrap()),
+            (16000, 8, SampleFormat::I32)
+        );
+    }
+
+    #[test]
+    fn test_channel_mix_requirement_keeps_array_channels() {
+        // 4-mic USB array that also offers a downmixed stereo config
+        let ranges = vec![
+            range(2, 16000, 16000, SampleFormat::I16),
+            range(4, 16000, 48000, SampleFormat::I16),
+            range(6, 16000, 48000, SampleFormat::I16),
+        ];
+        assert_eq!(summary(choose(ranges.clone(), 16000, 4).unwrap()).1, 4);
+        assert_eq!(summary(choose(ranges.clone(), 16000, 1).unwrap()).1, 2);
+        // Requirement the device can't meet: best of what it has
+        assert_eq!(summary(choose(ranges, 16000, 8).unwrap()).1, 2);
+    }
+
+    #[test]
+    fn test_accepts_formats_beyond_i16_f32_u16() {
+        for format in [SampleFormat::I32, SampleFormat::U8, SampleFormat::F64] {
+            let chosen = choose(vec![range(2, 48000, 48000, format)], 16000, 1).unwrap();
+            assert_eq!(chosen.sample_format(), format);
+        }
+    }
+
+    #[test]
+    fn test_lower_rate_only_as_last_resort() {
+        let ranges = vec![range(1, 8000, 8000, SampleFormat::I16)];
+        assert_eq!(summary(choose(ranges, 16000, 1).unwrap()).0, 8000);
+        assert!(choose(Vec::new(), 16000, 1).is_none());
+    }
+
+    #[test]
+    fn test_capabilities_cover_all_ranges() {
+        let caps = Capabilities::from_ranges(vec![
+            range(2, 44100, 48000, SampleFormat::F32),
+            range(4, 16000, 16000, SampleFormat::I16),
+        ])
+        .unwrap();
+        assert_eq!(caps.max_channels, 4);
+        assert_eq!(caps.sample_rates, vec![16000, 44100, 48000]);
+        assert_eq!(caps.sample_formats, vec!["i16", "f32"]);
+
+        let odd = Capabilities::from_ranges(vec![range(1, 12345, 12345, SampleFormat::I16)]);
+        assert_eq!(odd.unwrap().sample_rates, vec![12345]);
+        assert!(Capabilities::from_ranges(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_active_format_follows_running_captures() {
+        let format = |rate| CaptureFormat {
+            sample_rate: rate,
+            channels: 1,
+            sample_format: "i16".to_string(),
+            negotiated: true,
+            resampler: None,
+        };
+        let first = ActiveFormat::register(format(16000));
+        let second = ActiveFormat::register(format(48000));
+        assert_eq!(active().unwrap().sample_rate, 48000);
+        drop(second);
+        assert_eq!(active().unwrap().sample_rate, 16000);
+        drop(first);
+    }
+}
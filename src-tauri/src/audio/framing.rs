@@ -0,0 +1,117 @@
+//! Overlapping analysis windows
+//!
+//! [`AudioSource`](super::AudioSource) hands out contiguous `hop_ms` chunks;
+//! KWS consumes those as they are. Consumers that analyse a longer context
+//! (VAD) push the chunks into a [`Framer`], which yields a `frame_ms` window
+//! every `hop_ms`: with the defaults, 20 ms windows overlapping by 10 ms.
+
+use super::AudioConfig;
+use anyhow::{bail, Result};
+
+/// Sliding window over contiguous samples
+pub struct Framer {
+    frame: usize,
+    hop: usize,
+    /// Samples from the start of the next window on
+    buffer: Vec<i16>,
+}
+
+impl Framer {
+    /// Windows of `frame` samples, each starting `hop` after the previous one
+    ///
+    /// Fails unless `hop` is in `1..=frame`.
+    pub fn new(frame: usize, hop: usize) -> Result<Self> {
+        if hop == 0 || hop > frame {
+            bail!("hop of {} samples must be in 1..={}", hop, frame);
+        }
+        Ok(Self {
+            frame,
+            hop,
+            buffer: Vec::with_capacity(frame + hop),
+        })
+    }
+
+    /// `frame_ms` windows every `hop_ms` at the processing rate
+    pub fn from_config(config: &AudioConfig) -> Result<Self> {
+        Self::new(config.samples_per_frame(), config.samples_per_hop())
+    }
+
+    /// Append the next chunk (any length)
+    pub fn push(&mut self, chunk: &[i16]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// The next complete window, if enough audio has been pushed
+    pub fn pop(&mut self) -> Option<Vec<i16>> {
+        if self.buffer.len() < self.frame {
+            return None;
+        }
+        let window = self.buffer[..self.frame].to_vec();
+        self.buffer.drain(..self.hop);
+        Some(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows(framer: &mut Framer) -> Vec<Vec<i16>> {
+        std::iter::from_fn(|| framer.pop()).collect()
+    }
+
+    #[test]
+    fn test_hop_sized_chunks_give_one_overlapping_window_each() {
+        let config = AudioConfig::default(); // 20 ms frames, 10 ms hop
+        let mut framer = Framer::from_config(&config).unwrap();
+        let hop = config.samples_per_hop();
+        let chunk = |k: usize| -> Vec<i16> { (0..hop).map(|i| (k * hop + i) as i16).collect() };
+
+        framer.push(&chunk(0));
+        assert!(framer.pop().is_none(), "first 10 ms only primes the window");
+
+        for k in 1..5 {
+            framer.push(&chunk(k));
+            let found = windows(&mut framer);
+            assert_eq!(found.len(), 1);
+            // Previous hop followed by this one
+            let expected: Vec<i16> = ((k - 1) * hop..(k + 1) * hop).map(|s| s as i16).collect();
+            assert_eq!(found[0], expected);
+        }
+    }
+
+    #[test]
+    fn test_windows_do_not_depend_on_chunk_sizes() {
+        let signal: Vec<i16> = (0..1000).collect();
+        let mut whole = Framer::new(100, 40).unwrap();
+        whole.push(&signal);
+        let expected = windows(&mut whole);
+        assert_eq!(expected.len(), (1000 - 100) / 40 + 1);
+
+        let mut pieces = Framer::new(100, 40).unwrap();
+        let mut found = Vec::new();
+        for piece in signal.chunks(7) {
+            pieces.push(piece);
+            found.extend(windows(&mut pieces));
+        }
+        assert_eq!(found, expected);
+        assert!(found
+            .iter()
+            .enumerate()
+            .all(|(k, w)| w[0] == (k * 40) as i16));
+    }
+
+    #[test]
+    fn test_hop_equal_to_frame_is_plain_framing() {
+        let mut framer = Framer::new(4, 4).unwrap();
+        framer.push(&[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(windows(&mut framer), [vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+    }
+
+    #[test]
+    fn test_hop_outside_the_frame_is_rejected() {
+        assert!(Framer::new(100, 0).is_err());
+        assert!(Framer::new(100, 101).is_err());
+        assert!(Framer::new(100, 100).is_ok());
+    }
+}
@@ -0,0 +1,482 @@
+//! Audio device hotplug notifications
+//!
+//! A [`DeviceSource`] says when the devices may have changed and lists them;
+//! [`spawn`] diffs consecutive lists on a thread of its own and sends a
+//! [`DeviceChange`] for every device plugged in or removed.
+//!
+//! On Linux, [`SystemSource`] blocks on inotify events for the ALSA device
+//! nodes udev creates and removes in `/dev/snd`, so CPAL devices, whose
+//! enumeration opens every PCM, are only listed after a card came or went.
+//! Without a watch (other platforms, or containers lacking `/dev/snd`) it
+//! re-enumerates every 2 seconds. [`SimulatedSource`] replaces the hardware
+//! in tests.
+
+use super::{identity, DeviceId};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+/// Wait for udev to finish creating a card's nodes before enumerating
+const SETTLE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Enumeration interval when device nodes cannot be watched
+const ENUMERATE_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Input,
+    Output,
+}
+
+impl DeviceKind {
+    /// Name used in event payloads ("input" or "output")
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeviceKind::Input => "input",
+            DeviceKind::Output => "output",
+        }
+    }
+}
+
+/// Input and output devices at one point in time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceSet {
+    pub inputs: Vec<DeviceId>,
+    pub outputs: Vec<DeviceId>,
+}
+
+impl DeviceSet {
+    pub fn get(&self, kind: DeviceKind) -> &[DeviceId] {
+        match kind {
+            DeviceKind::Input => &self.inputs,
+            DeviceKind::Output => &self.outputs,
+        }
+    }
+}
+
+/// What happened to the preferred device in a [`DeviceChange`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition {
+    /// Removed; the default device takes over
+    Lost(DeviceId),
+    /// Plugged back in
+    Returned(DeviceId),
+}
+
+/// Devices plugged in or removed between two [`DeviceSet`]s
+#[derive(Debug, Clone)]
+pub struct DeviceChange {
+    pub before: DeviceSet,
+    pub now: DeviceSet,
+    pub added: Vec<(DeviceKind, DeviceId)>,
+    pub removed: Vec<(DeviceKind, DeviceId)>,
+}
+
+impl DeviceChange {
+    /// Diff two device lists
+    ///
+    /// Devices are compared by everything but their index, which shifts
+    /// whenever a device ahead of them comes or goes.
+    pub fn between(before: DeviceSet, now: DeviceSet) -> Self {
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        for kind in [DeviceKind::Input, DeviceKind::Output] {
+            let (plugged, unplugged) = diff(before.get(kind), now.get(kind));
+            added.extend(plugged.into_iter().map(|id| (kind, id)));
+            removed.extend(unplugged.into_iter().map(|id| (kind, id)));
+        }
+        Self {
+            before,
+            now,
+            added,
+            removed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// How the preferred device was affected, if at all
+    ///
+    /// The preferred device is found like the capture device: by its stable
+    /// ID if saved, else by name. Without either the default device is used,
+    /// which never changes here.
+    pub fn preferred(
+        &self,
+        kind: DeviceKind,
+        stable_id: Option<&DeviceId>,
+        name: Option<&str>,
+    ) -> Option<Transition> {
+        let before = find_preferred(self.before.get(kind), stable_id, name);
+        let now = find_preferred(self.now.get(kind), stable_id, name);
+        match (before, now) {
+            (Some(device), None) => Some(Transition::Lost(device.clone())),
+            (None, Some(device)) => Some(Transition::Returned(device.clone())),
+            _ => None,
+        }
+    }
+}
+
+fn find_preferred<'a>(
+    devices: &'a [DeviceId],
+    stable_id: Option<&DeviceId>,
+    name: Option<&str>,
+) -> Option<&'a DeviceId> {
+    match (stable_id, name) {
+        (Some(id), _) => identity::best_match(id, devices).map(|i| &devices[i]),
+        (None, Some(name)) => devices.iter().find(|d| d.name == name),
+        (None, None) => None,
+    }
+}
+
+fn same_device(a: &DeviceId, b: &DeviceId) -> bool {
+    a.host_api == b.host_api
+        && a.name == b.name
+        && a.bus_path == b.bus_path
+        && a.usb_id == b.usb_id
+        && a.serial == b.serial
+}
+
+/// (added, removed); twins are counted, so unplugging one of two is seen
+fn diff(before: &[DeviceId], now: &[DeviceId]) -> (Vec<DeviceId>, Vec<DeviceId>) {
+    let mut unmatched: Vec<&DeviceId> = now.iter().collect();
+    let mut removed = Vec::new();
+    for old in before {
+        match unmatched.iter().position(|new| same_device(old, new)) {
+            Some(position) => {
+                unmatched.remove(position);
+            }
+            None => removed.push(old.clone()),
+        }
+    }
+    (unmatched.into_iter().cloned().collect(), removed)
+}
+
+/// Where device lists come from
+pub trait DeviceSource: Send + 'static {
+    /// Block until the devices may have changed; false once the source is closed
+    fn wait(&mut self) -> bool;
+
+    /// Current devices
+    fn devices(&mut self) -> DeviceSet;
+}
+
+/// The audio hardware, as seen by CPAL
+pub struct SystemSource {
+    /// None when the device nodes cannot be watched
+    watcher: Option<NodeWatcher>,
+}
+
+impl SystemSource {
+    pub fn new() -> Self {
+        let watcher = NodeWatcher::open(std::path::Path::new("/dev/snd"))
+            .map_err(|e| {
+                log::warn!(
+                    "Cannot watch /dev/snd ({}), checking audio devices every {:?}",
+                    e,
+                    ENUMERATE_INTERVAL
+                )
+            })
+            .ok();
+        Self { watcher }
+    }
+}
+
+impl Default for SystemSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceSource for SystemSource {
+    fn wait(&mut self) -> bool {
+        let Some(watcher) = self.watcher.as_mut() else {
+            thread::sleep(ENUMERATE_INTERVAL);
+            return true;
+        };
+        if let Err(e) = watcher.wait() {
+            log::warn!(
+                "Watching /dev/snd failed ({}), checking audio devices every {:?}",
+                e,
+                ENUMERATE_INTERVAL
+            );
+            self.watcher = None;
+        }
+        true
+    }
+
+    fn devices(&mut self) -> DeviceSet {
+        DeviceSet {
+            inputs: super::input_device_ids(),
+            outputs: super::output_device_ids(),
+        }
+    }
+}
+
+/// inotify watch on a device node directory
+#[cfg(target_os = "linux")]
+struct NodeWatcher {
+    inotify: inotify::Inotify,
+    buffer: [u8; 4096],
+}
+
+#[cfg(target_os = "linux")]
+impl NodeWatcher {
+    fn open(dir: &std::path::Path) -> std::io::Result<Self> {
+        use inotify::{Inotify, WatchMask};
+        let inotify = Inotify::init()?;
+        inotify.watches().add(
+            dir,
+            WatchMask::CREATE | WatchMask::DELETE | WatchMask::MOVED_TO | WatchMask::MOVED_FROM,
+        )?;
+        Ok(Self {
+            inotify,
+            buffer: [0; 4096],
+        })
+    }
+
+    /// Block until a node comes or goes, then until udev is done
+    fn wait(&mut self) -> std::io::Result<()> {
+        self.inotify.read_events_blocking(&mut self.buffer)?;
+        // A card's nodes appear one by one
+        loop {
+            thread::sleep(SETTLE_INTERVAL);
+            let more = match self.inotify.read_events(&mut self.buffer) {
+                Ok(mut events) => events.next().is_some(),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => false,
+                Err(e) => return Err(e),
+            };
+            if !more {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Device nodes cannot be watched here
+#[cfg(not(target_os = "linux"))]
+struct NodeWatcher;
+
+#[cfg(not(target_os = "linux"))]
+impl NodeWatcher {
+    fn open(_dir: &std::path::Path) -> std::io::Result<Self> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+
+    fn wait(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Device source driven by hand, for tests
+///
+/// Every [`DeviceSet`] sent on the returned channel is a change; dropping the
+/// sender closes the source.
+pub struct SimulatedSource {
+    current: DeviceSet,
+    updates: mpsc::Receiver<DeviceSet>,
+}
+
+impl SimulatedSource {
+    pub fn new(initial: DeviceSet) -> (Self, mpsc::Sender<DeviceSet>) {
+        let (tx, rx) = mpsc::channel();
+        (
+            Self {
+                current: initial,
+                updates: rx,
+            },
+            tx,
+        )
+    }
+}
+
+impl DeviceSource for SimulatedSource {
+    fn wait(&mut self) -> bool {
+        match self.updates.recv() {
+            Ok(devices) => {
+                self.current = devices;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn devices(&mut self) -> DeviceSet {
+        self.current.clone()
+    }
+}
+
+/// Watch `source` on a thread of its own
+///
+/// The channel closes when the source does; the thread ends with the first
+/// change after the receiver was dropped.
+pub fn spawn(mut source: impl DeviceSource) -> UnboundedReceiver<DeviceChange> {
+    let (tx, rx) = unbounded_channel();
+    thread::Builder::new()
+        .name("device-hotplug".to_string())
+        .spawn(move || {
+            let mut known = source.devices();
+            while source.wait() {
+                let now = source.devices();
+                let change = DeviceChange::between(known, now.clone());
+                known = now;
+                if change.is_empty() {
+                    continue;
+                }
+                for (kind, id) in &change.added {
+                    log::info!("Audio {} device plugged in: {}", kind.as_str(), id.name);
+                }
+                for (kind, id) in &change.removed {
+                    log::info!("Audio {} device removed: {}", kind.as_str(), id.name);
+                }
+                if tx.send(change).is_err() {
+                    break;
+                }
+            }
+            log::info!("Device hotplug watcher stopped");
+        })
+        .expect("Failed to spawn device hotplug thread");
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(index: u32, name: &str, bus_path: Option<&str>) -> DeviceId {
+        DeviceId {
+            host_api: "ALSA".to_string(),
+            index,
+            name: name.to_string(),
+            bus_path: bus_path.map(String::from),
+            usb_id: bus_path.map(|_| "0d8c:0014".to_string()),
+            serial: None,
+        }
+    }
+
+    fn headset(index: u32) -> DeviceId {
+        id(
+            index,
+            "hw:CARD=Headset,DEV=0",
+            Some("pci0000:00/0000:00:14.0/usb1/1-3"),
+        )
+    }
+
+    fn devices(inputs: Vec<DeviceId>, outputs: Vec<DeviceId>) -> DeviceSet {
+        DeviceSet { inputs, outputs }
+    }
+
+    #[test]
+    fn test_diff_ignores_index_and_counts_twins() {
+        let builtin = id(0, "hw:CARD=PCH,DEV=0", None);
+        let webcam = id(1, "hw:CARD=Webcam,DEV=0", None);
+        let before = devices(vec![builtin.clone(), headset(1)], vec![]);
+
+        // Webcam plugged in ahead of the headset: only the webcam is new
+        let now = devices(vec![builtin.clone(), webcam.clone(), headset(2)], vec![]);
+        let change = DeviceChange::between(before.clone(), now);
+        assert_eq!(change.added, [(DeviceKind::Input, webcam)]);
+        assert!(change.removed.is_empty());
+
+        // One of two identical virtual devices goes away
+        let twins = devices(vec![builtin.clone(), builtin.clone()], vec![]);
+        let change = DeviceChange::between(twins, devices(vec![builtin.clone()], vec![]));
+        assert_eq!(change.removed, [(DeviceKind::Input, builtin)]);
+
+        assert!(DeviceChange::between(before.clone(), before).is_empty());
+    }
+
+    #[test]
+    fn test_preferred_lost_and_returned() {
+        let builtin = id(0, "hw:CARD=PCH,DEV=0", None);
+        let with_headset = devices(vec![builtin.clone(), headset(1)], vec![headset(1)]);
+        let without = devices(vec![builtin.clone()], vec![]);
+
+        let unplugged = DeviceChange::between(with_headset.clone(), without.clone());
+        let preferred = headset(1);
+        assert_eq!(
+            unplugged.preferred(DeviceKind::Input, Some(&preferred), None),
+            Some(Transition::Lost(headset(1)))
+        );
+        assert_eq!(
+            unplugged.preferred(DeviceKind::Output, None, Some("hw:CARD=Headset,DEV=0")),
+            Some(Transition::Lost(headset(1)))
+        );
+        // Default device or an unaffected preference
+        assert_eq!(unplugged.preferred(DeviceKind::Input, None, None), None);
+        assert_eq!(
+            unplugged.preferred(DeviceKind::Input, None, Some("hw:CARD=PCH,DEV=0")),
+            None
+        );
+
+        // Plugged back into another port, behind another device
+        let mut moved = headset(2);
+        moved.bus_path = Some("pci0000:00/0000:00:14.0/usb1/1-4".to_string());
+        let webcam = id(1, "hw:CARD=Webcam,DEV=0", None);
+        let replugged = DeviceChange::between(
+            without,
+            devices(vec![builtin, webcam, moved.clone()], vec![]),
+        );
+        assert_eq!(
+            replugged.preferred(DeviceKind::Input, Some(&preferred), None),
+            Some(Transition::Returned(moved))
+        );
+    }
+
+    #[test]
+    fn test_spawn_with_simulated_source() {
+        let builtin = id(0, "hw:CARD=PCH,DEV=0", None);
+        let (source, tx) = SimulatedSource::new(devices(vec![builtin.clone()], vec![]));
+        let mut changes = spawn(source);
+
+        // Unchanged lists are not reported
+        tx.send(devices(vec![builtin.clone()], vec![])).unwrap();
+        tx.send(devices(vec![builtin.clone(), headset(1)], vec![headset(0)]))
+            .unwrap();
+        tx.send(devices(vec![builtin], vec![])).unwrap();
+        drop(tx);
+
+        let plugged = changes.blocking_recv().unwrap();
+        assert_eq!(
+            plugged.added,
+            [
+                (DeviceKind::Input, headset(1)),
+                (DeviceKind::Output, headset(0))
+            ]
+        );
+        let unplugged = changes.blocking_recv().unwrap();
+        assert_eq!(unplugged.removed.len(), 2);
+        assert!(unplugged.added.is_empty());
+        assert!(changes.blocking_recv().is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_node_watcher_wakes_on_new_nodes() {
+        use std::fs;
+        let dir = std::env::temp_dir().join(format!("ember-hotplug-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(NodeWatcher::open(&dir).is_err());
+        fs::create_dir_all(&dir).unwrap();
+        let mut watcher = NodeWatcher::open(&dir).unwrap();
+
+        let (woke_tx, woke_rx) = mpsc::channel();
+        let waiting = thread::spawn(move || {
+            watcher.wait().unwrap();
+            woke_tx.send(()).unwrap();
+        });
+
+        // Blocks while nothing happens
+        assert!(woke_rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+        // A card's nodes arriving one by one wake it once
+        fs::write(dir.join("controlC1"), "").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        fs::write(dir.join("pcmC1D0c"), "").unwrap();
+        woke_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        waiting.join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
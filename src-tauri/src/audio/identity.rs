@@ -0,0 +1,368 @@
+//! Hardware identity of audio devices and matching of saved [`DeviceId`]s
+//!
+//! CPAL only gives a device name and its position in the enumeration. The
+//! position changes whenever another device is plugged in, and identical
+//! headsets share a name, so a saved device is found again by a score over
+//! everything known about it:
+//!
+//! | Property | Source | Score |
+//! |----------|--------|-------|
+//! | USB serial number | sysfs `serial` | 100 |
+//! | Physical location (USB port, PCI slot) | sysfs device path of the ALSA card | 60 |
+//! | Name | CPAL | 30 |
+//! | USB vendor:product | sysfs `idVendor`/`idProduct` | 20 |
+//! | Enumeration index | CPAL | 5 |
+//!
+//! A different host API, serial number or vendor:product rules a device out,
+//! as does matching on nothing but vendor:product and index. Virtual devices
+//! (`default`, `pipewire`, `pulse`) and other platforms have no hardware
+//! identity and are matched by name, with the index deciding between twins.
+
+use super::DeviceId;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Hardware properties read for one device (all optional)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hardware {
+    pub bus_path: Option<String>,
+    pub usb_id: Option<String>,
+    pub serial: Option<String>,
+}
+
+/// Build the identifier of the device at `index` in the host's enumeration
+pub fn device_id(host_api: &str, index: usize, name: &str) -> DeviceId {
+    let hardware = probe(host_api, name);
+    DeviceId {
+        host_api: host_api.to_string(),
+        index: index as u32,
+        name: name.to_string(),
+        bus_path: hardware.bus_path,
+        usb_id: hardware.usb_id,
+        serial: hardware.serial,
+    }
+}
+
+/// Read the hardware identity of a device, if its host exposes one
+pub fn probe(host_api: &str, device_name: &str) -> Hardware {
+    #[cfg(target_os = "linux")]
+    if host_api == "ALSA" {
+        return probe_alsa(Path::new("/"), device_name);
+    }
+    let _ = (host_api, device_name);
+    Hardware::default()
+}
+
+/// ALSA PCM names carry the card (`hw:CARD=Headset,DEV=0`); the card's sysfs
+/// device is the USB interface or PCI function behind it
+///
+/// `root` is `/` except in tests.
+fn probe_alsa(root: &Path, device_name: &str) -> Hardware {
+    let Some(card) = alsa_card(device_name) else {
+        return Hardware::default();
+    };
+
+    // Card IDs are symlinks to cardN in /proc/asound; numeric IDs are the index
+    let card_dir = if card.chars().all(|c| c.is_ascii_digit()) {
+        format!("card{}", card)
+    } else {
+        match fs::read_link(root.join("proc/asound").join(card)) {
+            Ok(target) => target.to_string_lossy().into_owned(),
+            Err(_) => return Hardware::default(),
+        }
+    };
+
+    let Ok(device) = fs::canonicalize(root.join("sys/class/sound").join(card_dir).join("device"))
+    else {
+        return Hardware::default();
+    };
+
+    let devices_root = fs::canonicalize(root.join("sys/devices")).unwrap_or_default();
+    let bus_path = device
+        .strip_prefix(&devices_root)
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned());
+
+    // The USB device is the nearest ancestor with idVendor (the card is one of its interfaces)
+    let usb_device = device
+        .ancestors()
+        .take_while(|dir| dir.starts_with(&devices_root))
+        .find(|dir| dir.join("idVendor").is_file())
+        .map(PathBuf::from);
+    let read = |file: &str| {
+        usb_device
+            .as_ref()
+            .and_then(|dir| fs::read_to_string(dir.join(file)).ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let usb_id = match (read("idVendor"), read("idProduct")) {
+        (Some(vendor), Some(product)) => Some(format!("{}:{}", vendor, product)),
+        _ => None,
+    };
+
+    Hardware {
+        bus_path,
+        usb_id,
+        serial: read("serial"),
+    }
+}
+
+/// `CARD=` value of an ALSA PCM name
+fn alsa_card(device_name: &str) -> Option<&str> {
+    let start = device_name.find("CARD=")? + "CARD=".len();
+    let rest = &device_name[start..];
+    let card = &rest[..rest.find(',').unwrap_or(rest.len())];
+    (!card.is_empty() && !card.contains('/') && card != "..").then_some(card)
+}
+
+/// How well `candidate` matches the saved `wanted` device; `None` if it cannot be it
+pub fn match_score(wanted: &DeviceId, candidate: &DeviceId) -> Option<u32> {
+    if wanted.host_api != candidate.host_api {
+        return None;
+    }
+
+    let mut score = 0;
+    let mut identified = false;
+    // (saved, candidate, points, identifies the device, must agree when both are known)
+    for (a, b, points, identifies, must_agree) in [
+        (&wanted.serial, &candidate.serial, 100, true, true),
+        // Another port is fine: the device may have been moved
+        (&wanted.bus_path, &candidate.bus_path, 60, true, false),
+        (&wanted.usb_id, &candidate.usb_id, 20, false, true),
+    ] {
+        match (a, b) {
+            (Some(a), Some(b)) if a == b => {
+                score += points;
+                identified |= identifies;
+            }
+            (Some(_), Some(_)) if must_agree => return None,
+            _ => {}
+        }
+    }
+    if wanted.name == candidate.name {
+        score += 30;
+        identified = true;
+    }
+    if wanted.index == candidate.index {
+        score += 5;
+    }
+
+    identified.then_some(score)
+}
+
+/// Position in `candidates` of the best match for `wanted`
+///
+/// Ties (e.g. two identical headsets without serial numbers, neither in the
+/// saved port) go to the first candidate and are logged.
+pub fn best_match(wanted: &DeviceId, candidates: &[DeviceId]) -> Option<usize> {
+    let mut best: Option<(usize, u32)> = None;
+    let mut tied = false;
+    for (position, candidate) in candidates.iter().enumerate() {
+        let Some(score) = match_score(wanted, candidate) else {
+            continue;
+        };
+        match best {
+            Some((_, top)) if score < top => {}
+            Some((_, top)) if score == top => tied = true,
+            _ => {
+                best = Some((position, score));
+                tied = false;
+            }
+        }
+    }
+
+    if tied {
+        log::warn!(
+            "Several devices match '{}' equally well, using the first",
+            wanted.name
+        );
+    }
+    best.map(|(position, _)| position)
+}
+
+/// Display labels that tell devices with the same name apart
+///
+/// Unique names are kept; duplicates get their USB port or physical location,
+/// or a number if that is unknown (e.g. `USB Headset (port 1-3.2)`).
+pub fn labels(ids: &[DeviceId]) -> Vec<String> {
+    ids.iter()
+        .enumerate()
+        .map(|(position, id)| {
+            let twins: Vec<_> = ids
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| other.name == id.name)
+                .collect();
+            if twins.len() == 1 {
+                return id.name.clone();
+            }
+            match id.bus_path.as_deref().and_then(port) {
+                Some(port) => format!("{} (port {})", id.name, port),
+                None => {
+                    let n = twins.iter().position(|(p, _)| *p == position).unwrap_or(0);
+                    format!("{} #{}", id.name, n + 1)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Short location from a sysfs path: the USB port (`1-3.2`), or the last component
+fn port(bus_path: &str) -> Option<&str> {
+    let mut components = bus_path.rsplit('/');
+    let last = components.next()?;
+    // USB interfaces look like 1-3.2:1.0; the port is their parent
+    if last.contains(':') && last.contains('-') {
+        return components.next().or(Some(last));
+    }
+    Some(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(index: u32, name: &str, bus_path: Option<&str>, serial: Option<&str>) -> DeviceId {
+        DeviceId {
+            host_api: "ALSA".to_string(),
+            index,
+            name: name.to_string(),
+            bus_path: bus_path.map(String::from),
+            usb_id: bus_path.map(|_| "0d8c:0014".to_string()),
+            serial: serial.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_reordered_indices_still_match() {
+        let wanted = id(2, "hw:CARD=Headset,DEV=0", Some("usb1/1-3/1-3.2:1.0"), None);
+        let candidates = [
+            id(
+                0,
+                "hw:CARD=PCH,DEV=0",
+                Some("pci0000:00/0000:00:1f.3"),
+                None,
+            ),
+            id(1, "hw:CARD=Dock,DEV=0", Some("usb1/1-4/1-4:1.0"), None),
+            id(3, "hw:CARD=Headset,DEV=0", Some("usb1/1-3/1-3.2:1.0"), None),
+        ];
+        assert_eq!(best_match(&wanted, &candidates), Some(2));
+    }
+
+    #[test]
+    fn test_identical_headsets() {
+        // ALSA names the second card Headset_1; the port tells them apart
+        let wanted = id(4, "hw:CARD=Headset_1,DEV=0", Some("usb1/1-4/1-4:1.0"), None);
+        let candidates = [
+            id(3, "hw:CARD=Headset,DEV=0", Some("usb1/1-4/1-4:1.0"), None),
+            id(4, "hw:CARD=Headset_1,DEV=0", Some("usb1/1-3/1-3:1.0"), None),
+        ];
+        assert_eq!(best_match(&wanted, &candidates), Some(0));
+
+        // A serial number wins over the port
+        let wanted = id(0, "USB Headset", Some("usb1/1-3/1-3:1.0"), Some("A1"));
+        let candidates = [
+            id(0, "USB Headset", Some("usb1/1-3/1-3:1.0"), Some("B2")),
+            id(1, "USB Headset", Some("usb1/1-4/1-4:1.0"), Some("A1")),
+        ];
+        assert_eq!(best_match(&wanted, &candidates), Some(1));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let wanted = id(0, "USB Mic", Some("usb1/1-3/1-3:1.0"), None);
+        let mut other_model = id(0, "Webcam", Some("usb1/1-3/1-3:1.0"), None);
+        other_model.usb_id = Some("046d:0825".to_string());
+        assert_eq!(match_score(&wanted, &other_model), None);
+
+        // Only the index in common
+        assert_eq!(match_score(&wanted, &id(0, "Webcam", None, None)), None);
+
+        let mut other_host = wanted.clone();
+        other_host.host_api = "JACK".to_string();
+        assert_eq!(match_score(&wanted, &other_host), None);
+    }
+
+    #[test]
+    fn test_old_ids_match_by_name_and_index() {
+        let wanted = id(1, "USB Headset", None, None);
+        let candidates = [
+            id(0, "USB Headset", Some("usb1/1-3/1-3:1.0"), None),
+            id(1, "USB Headset", Some("usb1/1-4/1-4:1.0"), None),
+        ];
+        assert_eq!(best_match(&wanted, &candidates), Some(1));
+    }
+
+    #[test]
+    fn test_labels() {
+        let ids = [
+            id(0, "default", None, None),
+            id(
+                1,
+                "USB Headset",
+                Some("pci0000:00/0000:00:14.0/usb1/1-3/1-3.2/1-3.2:1.0"),
+                None,
+            ),
+            id(
+                2,
+                "USB Headset",
+                Some("pci0000:00/0000:00:14.0/usb1/1-4/1-4:1.0"),
+                None,
+            ),
+            id(3, "Mic", None, None),
+            id(4, "Mic", None, None),
+        ];
+        assert_eq!(
+            labels(&ids),
+            [
+                "default",
+                "USB Headset (port 1-3.2)",
+                "USB Headset (port 1-4)",
+                "Mic #1",
+                "Mic #2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alsa_card() {
+        assert_eq!(alsa_card("hw:CARD=Headset,DEV=0"), Some("Headset"));
+        assert_eq!(alsa_card("sysdefault:CARD=PCH"), Some("PCH"));
+        assert_eq!(alsa_card("pipewire"), None);
+        assert_eq!(alsa_card("hw:CARD=..,DEV=0"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_probe_alsa_sysfs() {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join("ember-identity-sysfs");
+        let _ = fs::remove_dir_all(&root);
+        let usb = root.join("sys/devices/pci0000:00/0000:00:14.0/usb1/1-3/1-3.2");
+        let interface = usb.join("1-3.2:1.0");
+        fs::create_dir_all(interface.join("sound/card2")).unwrap();
+        fs::write(usb.join("idVendor"), "0d8c\n").unwrap();
+        fs::write(usb.join("idProduct"), "0014\n").unwrap();
+        fs::write(usb.join("serial"), "HS-0042\n").unwrap();
+
+        fs::create_dir_all(root.join("sys/class/sound/card2")).unwrap();
+        symlink(&interface, root.join("sys/class/sound/card2/device")).unwrap();
+        fs::create_dir_all(root.join("proc/asound")).unwrap();
+        symlink("card2", root.join("proc/asound/Headset")).unwrap();
+
+        let hardware = probe_alsa(&root, "hw:CARD=Headset,DEV=0");
+        assert_eq!(
+            hardware.bus_path.as_deref(),
+            Some("pci0000:00/0000:00:14.0/usb1/1-3/1-3.2/1-3.2:1.0")
+        );
+        assert_eq!(hardware.usb_id.as_deref(), Some("0d8c:0014"));
+        assert_eq!(hardware.serial.as_deref(), Some("HS-0042"));
+
+        assert_eq!(
+            probe_alsa(&root, "hw:CARD=Missing,DEV=0"),
+            Hardware::default()
+        );
+    }
+}
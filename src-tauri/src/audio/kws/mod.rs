@@ -8,7 +8,12 @@
 //! All FFI pointers and non-Send types are confined to a worker thread.
 //! Communication happens via crossbeam channels.
 
+use crate::audio::runtime::StopSignal;
+use crossbeam_channel::{Receiver, TryRecvError};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::time::Duration;
 
 // Always compile stub for fallback support
 pub mod stub;
@@ -32,11 +37,13 @@ impl KwsWorker {
     /// Start the appropriate KWS worker based on configuration
     #[allow(dead_code)]
     pub fn start(
-        app_handle: tauri::AppHandle,
+        sink: crate::events::SharedEventSink,
         paths: crate::paths::AppPaths,
         config: KwsConfig,
         vad_config: crate::audio::vad::VadConfig,
         audio_config: crate::audio::AudioConfig,
+        tap: Option<crate::audio::ChunkTap>,
+        stop: Receiver<StopSignal>,
     ) -> anyhow::Result<Self> {
         #[cfg(feature = "kws_real")]
         {
@@ -46,35 +53,44 @@ impl KwsWorker {
                 .clone()
                 .unwrap_or_else(|| "default".to_string());
             real::KwsWorker::start(
-                app_handle,
+                sink,
                 paths,
                 config,
                 vad_config,
                 audio_config,
                 model_id,
+                tap,
+                stop,
             )
             .map(KwsWorker::Real)
         }
         #[cfg(not(feature = "kws_real"))]
         {
-            stub::KwsWorker::start(app_handle, paths, config, vad_config, audio_config)
+            stub::KwsWorker::start(sink, paths, config, vad_config, audio_config, tap, stop)
                 .map(KwsWorker::Stub)
         }
     }
 
     /// Start stub worker directly (for fallback when real fails)
     pub fn start_stub(
-        app_handle: tauri::AppHandle,
+        sink: crate::events::SharedEventSink,
         paths: crate::paths::AppPaths,
         config: KwsConfig,
         vad_config: crate::audio::vad::VadConfig,
         audio_config: crate::audio::AudioConfig,
+        tap: Option<crate::audio::ChunkTap>,
+        stop: Receiver<StopSignal>,
     ) -> anyhow::Result<Self> {
-        stub::KwsWorker::start(app_handle, paths, config, vad_config, audio_config)
+        stub::KwsWorker::start(sink, paths, config, vad_config, audio_config, tap, stop)
             .map(KwsWorker::Stub)
     }
 }
 
+/// The runtime sent a stop signal or was dropped
+fn stop_requested(stop: &Receiver<StopSignal>) -> bool {
+    !matches!(stop.try_recv(), Err(TryRecvError::Empty))
+}
+
 /// KWS configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KwsConfig {
@@ -113,6 +129,43 @@ impl Default for KwsConfig {
     }
 }
 
+/// Speech runs long enough to have been a wake word, but not a sentence
+const NEAR_MISS_RUN: RangeInclusive<Duration> =
+    Duration::from_millis(300)..=Duration::from_millis(2000);
+
+/// Near misses for a spotter that only reports detections (Sherpa-ONNX)
+///
+/// A VAD speech run of about wake word length that ends without a detection
+/// is the closest such a spotter comes to "almost triggered"; the debug
+/// recorder keeps a clip of it.
+#[cfg_attr(not(feature = "kws_real"), allow(dead_code))]
+#[derive(Debug, Default)]
+pub struct NearMissTracker {
+    /// Length of the current speech run
+    speech: Duration,
+    /// Whether the current run produced a detection
+    detected: bool,
+}
+
+#[cfg_attr(not(feature = "kws_real"), allow(dead_code))]
+impl NearMissTracker {
+    /// Account for a chunk; true when a keyword-length run just ended undetected
+    pub fn update(&mut self, chunk: Duration, speech: bool) -> bool {
+        if speech {
+            self.speech += chunk;
+            return false;
+        }
+        let run = std::mem::take(&mut self.speech);
+        let detected = std::mem::take(&mut self.detected);
+        !detected && NEAR_MISS_RUN.contains(&run)
+    }
+
+    /// The current speech run triggered
+    pub fn detected(&mut self) {
+        self.detected = true;
+    }
+}
+
 /// Sensitivity presets
 #[derive(Debug, Clone)]
 pub enum Sensitivity {
@@ -138,6 +191,7 @@ impl Sensitivity {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "low" => Some(Sensitivity::Low),
@@ -149,8 +203,48 @@ impl Sensitivity {
 }
 
 /// Wake-word detection event
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct WakeWordEvent {
     pub keyword: String,
     pub score: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK: Duration = Duration::from_millis(10);
+
+    fn run(tracker: &mut NearMissTracker, speech_ms: u64) -> bool {
+        for _ in 0..speech_ms / 10 {
+            assert!(!tracker.update(CHUNK, true));
+        }
+        tracker.update(CHUNK, false)
+    }
+
+    #[test]
+    fn test_near_miss_is_a_keyword_length_run_without_detection() {
+        let mut tracker = NearMissTracker::default();
+        assert!(!tracker.update(CHUNK, false), "silence");
+        assert!(!run(&mut tracker, 100), "click");
+        assert!(run(&mut tracker, 800));
+        assert!(!run(&mut tracker, 5000), "sentence");
+
+        tracker.update(CHUNK, true);
+        tracker.detected();
+        assert!(!run(&mut tracker, 800), "detected");
+        assert!(run(&mut tracker, 800), "next run starts undetected");
+    }
+
+    #[test]
+    fn test_stop_requested_by_signal_or_dropped_runtime() {
+        let (stop_tx, stop) = crossbeam_channel::bounded(1);
+        assert!(!stop_requested(&stop));
+        stop_tx.send(StopSignal).unwrap();
+        assert!(stop_requested(&stop));
+
+        let (stop_tx, stop) = crossbeam_channel::bounded::<StopSignal>(1);
+        drop(stop_tx);
+        assert!(stop_requested(&stop));
+    }
+}
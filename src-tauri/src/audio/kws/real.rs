@@ -4,18 +4,23 @@
 
 #![cfg(feature = "kws_real")]
 
+use super::super::framing::Framer;
+use super::super::metrics::{PipelineMetrics, Stage, WorkerTicker};
+use super::super::recorder::ClipReason;
+use super::super::runtime::StopSignal;
 use super::super::vad::{VadConfig, VoiceActivityDetector};
-use super::super::{AudioCapture, AudioConfig, AudioSource};
-use super::{KwsConfig, WakeWordEvent};
+use super::super::{AudioCapture, AudioConfig, AudioSource, ChunkTap, FRAME_TIMEOUT};
+use super::{stop_requested, KwsConfig, NearMissTracker, WakeWordEvent};
 use crate::audio::level;
+use crate::events::{Event, KwsDetectionPayload, SharedEventSink};
 use crate::ffi::sherpa_onnx_bindings::*;
 use crate::paths::AppPaths;
 use anyhow::{bail, Context, Result};
+use crossbeam_channel::Receiver;
 use std::collections::HashSet;
 use std::ffi::CString;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
 
 /// Find a model file by pattern (e.g., "encoder*.onnx"), excluding int8 quantized versions
 fn find_model_file(model_dir: &Path, pattern_prefix: &str, extension: &str) -> Result<PathBuf> {
@@ -112,25 +117,32 @@ fn normalize_keyword_against_vocab(raw: &str, tokens_path: &Path) -> Result<Stri
 }
 
 /// Real KWS worker using Sherpa-ONNX
+///
+/// Dropping it waits for the thread, which exits within [`FRAME_TIMEOUT`] of
+/// the runtime's stop signal and releases the capture device.
 pub struct KwsWorker {
-    _thread_handle: Option<std::thread::JoinHandle<()>>,
+    thread_handle: Option<std::thread::JoinHandle<()>>,
 }
 
 impl KwsWorker {
     /// Start the KWS worker thread with real Sherpa-ONNX implementation
+    #[allow(clippy::too_many_arguments)]
     pub fn start(
-        app_handle: AppHandle,
+        sink: SharedEventSink,
         paths: AppPaths,
         config: KwsConfig,
         vad_config: VadConfig,
         audio_config: AudioConfig,
         model_id: String,
+        tap: Option<ChunkTap>,
+        stop: Receiver<StopSignal>,
     ) -> Result<Self> {
         log::info!("Starting real KWS worker with Sherpa-ONNX v1.10.30");
         log::info!("  Model ID: {}", model_id);
 
         // Get model directory for the specified model_id
         let model_dir = paths.kws_model_dir(&model_id);
+        let recordings_dir = paths.tmp_audio_dir();
 
         if !model_dir.exists() {
             bail!(
@@ -142,12 +154,15 @@ impl KwsWorker {
         // Spawn worker thread (std::thread to avoid Send issues with FFI pointers)
         let handle = std::thread::spawn(move || {
             if let Err(e) = run_real_kws_worker(
-                app_handle,
+                sink,
                 config,
                 vad_config,
                 audio_config,
                 model_dir,
                 model_id,
+                recordings_dir,
+                tap,
+                stop,
             ) {
                 log::error!("Real KWS worker thread error: {}", e);
             }
@@ -155,19 +170,31 @@ impl KwsWorker {
 
         log::info!("Real KWS worker started");
         Ok(Self {
-            _thread_handle: Some(handle),
+            thread_handle: Some(handle),
         })
     }
 }
 
+impl Drop for KwsWorker {
+    fn drop(&mut self) {
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
 /// Real KWS worker loop with Sherpa-ONNX
+#[allow(clippy::too_many_arguments)]
 fn run_real_kws_worker(
-    app_handle: AppHandle,
+    sink: SharedEventSink,
     config: KwsConfig,
     vad_config: VadConfig,
     audio_config: AudioConfig,
     model_dir: std::path::PathBuf,
     model_id: String,
+    recordings_dir: PathBuf,
+    tap: Option<ChunkTap>,
+    stop: Receiver<StopSignal>,
 ) -> Result<()> {
     log::info!("Initializing real KWS worker with Sherpa-ONNX");
     log::info!("  Keyword: '{}'", config.keyword);
@@ -298,7 +325,12 @@ fn run_real_kws_worker(
     log::info!("Sherpa-ONNX keyword spotter initialized successfully");
 
     // Initialize audio capture
-    let mut audio_source = AudioCapture::new(audio_config)?;
+    let metrics = PipelineMetrics::global();
+    let mut framer = Framer::from_config(&audio_config)?;
+    let mut audio_source = AudioCapture::new(audio_config)?
+        .with_metrics(metrics.clone())
+        .with_recorder(&recordings_dir)
+        .with_tap(tap);
     log::info!(
         "Audio capture initialized @{}Hz",
         audio_source.sample_rate()
@@ -306,6 +338,7 @@ fn run_real_kws_worker(
 
     let mut vad = VoiceActivityDetector::new(vad_config, audio_source.sample_rate())?;
     let mut last_detection: Option<Instant> = None;
+    let mut near_miss = NearMissTracker::default();
     let mut frame_count = 0u64;
 
     // RMS emission throttle (20 Hz = 50ms)
@@ -313,21 +346,45 @@ fn run_real_kws_worker(
 
     log::info!("Real KWS worker loop started");
 
+    let mut ticker = WorkerTicker::new(metrics.clone(), "kws");
+
     // Main processing loop
     loop {
+        if stop_requested(&stop) {
+            log::info!("Real KWS worker stopping");
+            break;
+        }
+        if let Some(snapshot) = ticker.tick() {
+            if let Err(e) = sink.publish(Event::AudioMetrics(snapshot)) {
+                log::error!("Failed to emit metrics event: {}", e);
+            }
+        }
+
         // Get next audio frame
-        if let Some(samples) = audio_source.next_frame() {
+        if let Some(samples) = audio_source.next_frame(FRAME_TIMEOUT) {
             frame_count += 1;
 
             // Emit RMS for UI meter (throttled to 20 Hz)
             let now = Instant::now();
             if now.duration_since(last_rms_emit) >= Duration::from_millis(50) {
-                level::emit_rms_i16(&app_handle, &samples);
+                level::emit_rms_i16(sink.as_ref(), &samples);
                 last_rms_emit = now;
             }
 
-            // VAD gating (optional, can improve efficiency)
-            if !vad.process_frame(&samples) {
+            // VAD gating (optional, can improve efficiency), on the frame_ms window ending with this chunk
+            let started = Instant::now();
+            framer.push(&samples);
+            let mut speech = false;
+            while let Some(window) = framer.pop() {
+                speech = vad.process_frame(&window);
+            }
+            metrics.record(Stage::Vad, started.elapsed());
+            let chunk =
+                Duration::from_secs_f64(samples.len() as f64 / audio_source.sample_rate() as f64);
+            if near_miss.update(chunk, speech) {
+                audio_source.mark_clip(ClipReason::NearMiss);
+            }
+            if !speech {
                 continue;
             }
 
@@ -342,6 +399,7 @@ fn run_real_kws_worker(
             let samples_f32: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
 
             // Feed audio to keyword spotter
+            let started = Instant::now();
             unsafe {
                 SherpaOnnxOnlineStreamAcceptWaveform(
                     stream,
@@ -357,7 +415,10 @@ fn run_real_kws_worker(
             if is_ready != 0 {
                 // Decode result
                 unsafe { SherpaOnnxDecodeKeywordStream(kws, stream) };
+            }
+            metrics.record_decode(chunk, started.elapsed());
 
+            if is_ready != 0 {
                 // Get keyword result
                 let result_ptr = unsafe { SherpaOnnxGetKeywordResult(kws, stream) };
 
@@ -383,26 +444,26 @@ fn run_real_kws_worker(
                                     score,
                                 };
 
-                                if let Err(e) = app_handle.emit("wakeword::detected", &event) {
+                                let started = Instant::now();
+                                if let Err(e) = sink.publish(Event::WakewordDetected(event)) {
                                     log::error!("Failed to emit wake-word event: {}", e);
                                 }
+                                metrics.record(Stage::Emit, started.elapsed());
+                                if let Some(captured_at) = audio_source.captured_at() {
+                                    metrics.record(Stage::EndToEnd, captured_at.elapsed());
+                                }
+                                audio_source.mark_clip(ClipReason::Detection);
+                                near_miss.detected();
 
                                 // QA-019: Check if test window is armed and emit test pass event
                                 // We emit a separate internal event that main.rs will listen for
                                 // to check test window state and conditionally emit kws:wake_test_pass
-                                #[derive(serde::Serialize, Clone)]
-                                struct TestDetectionPayload {
-                                    model_id: String,
-                                    keyword: String,
-                                    ts: u64,
-                                }
-
                                 let ts = std::time::SystemTime::now()
                                     .duration_since(std::time::UNIX_EPOCH)
                                     .unwrap_or_default()
                                     .as_millis() as u64;
 
-                                let test_payload = TestDetectionPayload {
+                                let test_payload = KwsDetectionPayload {
                                     model_id: model_id.clone(),
                                     keyword: keyword_str.to_string(),
                                     ts,
@@ -410,7 +471,7 @@ fn run_real_kws_worker(
 
                                 // Emit internal event for test window checker
                                 if let Err(e) =
-                                    app_handle.emit("_kws_internal_detection", &test_payload)
+                                    sink.publish(Event::KwsInternalDetection(test_payload))
                                 {
                                     log::error!("Failed to emit internal detection event: {}", e);
                                 }
@@ -425,19 +486,16 @@ fn run_real_kws_worker(
                 }
             }
         } else {
-            // Audio source ended (shouldn't happen in normal operation)
-            log::warn!("Audio source ended unexpectedly");
-            std::thread::sleep(Duration::from_millis(100));
+            // Device stopped delivering (counted as an underrun)
+            log::debug!("No audio from capture for {:?}", FRAME_TIMEOUT);
         }
     }
 
-    // Cleanup (unreachable in infinite loop, but good practice)
-    // unsafe {
-    //     SherpaOnnxDestroyOnlineStream(stream);
-    //     SherpaOnnxDestroyKeywordSpotter(kws);
-    // }
-
-    // Ok(())
+    unsafe {
+        SherpaOnnxDestroyOnlineStream(stream);
+        SherpaOnnxDestroyKeywordSpotter(kws);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -3,50 +3,86 @@
 //! Provides a simple energy-based wake-word simulator for testing the audio
 //! pipeline and UI without requiring the full Sherpa-ONNX library.
 
+use super::super::framing::Framer;
+use super::super::metrics::{PipelineMetrics, Stage, WorkerTicker};
+use super::super::recorder::ClipReason;
+use super::super::runtime::StopSignal;
 use super::super::vad::{VadConfig, VoiceActivityDetector};
-use super::super::{AudioCapture, AudioConfig, AudioSource};
-use super::{KwsConfig, WakeWordEvent};
+use super::super::{AudioCapture, AudioConfig, AudioSource, ChunkTap, FRAME_TIMEOUT};
+use super::{stop_requested, KwsConfig, WakeWordEvent};
 use crate::audio::level;
+use crate::events::{Event, SharedEventSink};
 use anyhow::Result;
+use crossbeam_channel::Receiver;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
+
+/// Sustained high energy that counts as a detection
+const MIN_ENERGY_MS: u64 = 60;
+/// Audio between progress trace logs
+const PROGRESS_LOG_MS: u64 = 2000;
 
 /// Stub KWS worker that runs in a dedicated thread
+///
+/// Dropping it waits for the thread, which exits within [`FRAME_TIMEOUT`] of
+/// the runtime's stop signal and releases the capture device.
 pub struct KwsWorker {
-    _thread_handle: Option<std::thread::JoinHandle<()>>,
+    thread_handle: Option<std::thread::JoinHandle<()>>,
 }
 
 impl KwsWorker {
     /// Start the KWS worker thread with stub implementation
     pub fn start(
-        app_handle: AppHandle,
-        _paths: crate::paths::AppPaths,
+        sink: SharedEventSink,
+        paths: crate::paths::AppPaths,
         config: KwsConfig,
         vad_config: VadConfig,
         audio_config: AudioConfig,
+        tap: Option<ChunkTap>,
+        stop: Receiver<StopSignal>,
     ) -> Result<Self> {
         log::info!("Starting stub KWS worker (energy-based detection)");
 
         // Spawn worker thread (NOT tokio::spawn - std::thread to avoid Send issues)
+        let recordings_dir = paths.tmp_audio_dir();
         let handle = std::thread::spawn(move || {
-            if let Err(e) = run_stub_kws_worker(app_handle, config, vad_config, audio_config) {
+            if let Err(e) = run_stub_kws_worker(
+                sink,
+                config,
+                vad_config,
+                audio_config,
+                recordings_dir,
+                tap,
+                stop,
+            ) {
                 log::error!("KWS worker thread error: {}", e);
             }
         });
 
         log::info!("Stub KWS worker started");
         Ok(Self {
-            _thread_handle: Some(handle),
+            thread_handle: Some(handle),
         })
     }
 }
 
+impl Drop for KwsWorker {
+    fn drop(&mut self) {
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
 /// Stub KWS worker loop
 fn run_stub_kws_worker(
-    app_handle: AppHandle,
+    sink: SharedEventSink,
     config: KwsConfig,
     vad_config: VadConfig,
     audio_config: AudioConfig,
+    recordings_dir: PathBuf,
+    tap: Option<ChunkTap>,
+    stop: Receiver<StopSignal>,
 ) -> Result<()> {
     log::info!("Stub KWS worker: simulating wake-word detection");
     log::info!("  Keyword: '{}'", config.keyword);
@@ -54,7 +90,13 @@ fn run_stub_kws_worker(
     log::info!("  Refractory: {}ms", config.refractory_ms);
 
     // Initialize audio capture (happens in this thread, so no Send issues)
-    let mut audio_source = AudioCapture::new(audio_config)?;
+    let metrics = PipelineMetrics::global();
+    let mut framer = Framer::from_config(&audio_config)?;
+    let hop_ms = u64::from(audio_config.hop_ms);
+    let mut audio_source = AudioCapture::new(audio_config)?
+        .with_metrics(metrics.clone())
+        .with_recorder(&recordings_dir)
+        .with_tap(tap);
     log::info!(
         "  Audio capture initialized @{}Hz",
         audio_source.sample_rate()
@@ -68,24 +110,45 @@ fn run_stub_kws_worker(
     let mut last_rms_emit = Instant::now();
 
     // Energy-based detection parameters (stub heuristic)
+    // Chunks are hop_ms long, so durations are converted to chunk counts
     let energy_threshold = 3000.0; // Arbitrary threshold for demo
-    let min_energy_frames = 3; // Require sustained energy
+    let min_energy_chunks = MIN_ENERGY_MS.div_ceil(hop_ms); // Require sustained energy
+    let progress_chunks = (PROGRESS_LOG_MS / hop_ms).max(1);
     let mut high_energy_count = 0;
 
+    let mut ticker = WorkerTicker::new(metrics.clone(), "kws");
+
     loop {
+        if stop_requested(&stop) {
+            log::info!("Stub KWS worker stopping");
+            return Ok(());
+        }
+        if let Some(snapshot) = ticker.tick() {
+            if let Err(e) = sink.publish(Event::AudioMetrics(snapshot)) {
+                log::error!("Failed to emit metrics event: {}", e);
+            }
+        }
+
         // Get next audio frame
-        if let Some(samples) = audio_source.next_frame() {
+        if let Some(samples) = audio_source.next_frame(FRAME_TIMEOUT) {
             frame_count += 1;
 
             // Emit RMS for UI meter (throttled to 20 Hz)
             let now = Instant::now();
             if now.duration_since(last_rms_emit) >= Duration::from_millis(50) {
-                level::emit_rms_i16(&app_handle, &samples);
+                level::emit_rms_i16(sink.as_ref(), &samples);
                 last_rms_emit = now;
             }
 
-            // VAD gating
-            if !vad.process_frame(&samples) {
+            // VAD gating, on the frame_ms window ending with this chunk
+            let started = Instant::now();
+            framer.push(&samples);
+            let mut speech = false;
+            while let Some(window) = framer.pop() {
+                speech = vad.process_frame(&window);
+            }
+            metrics.record(Stage::Vad, started.elapsed());
+            if !speech {
                 high_energy_count = 0;
                 continue;
             }
@@ -98,17 +161,25 @@ fn run_stub_kws_worker(
             }
 
             // Compute energy (simple RMS)
+            let started = Instant::now();
             let energy = compute_rms_energy(&samples);
+            let audio =
+                Duration::from_secs_f64(samples.len() as f64 / audio_source.sample_rate() as f64);
+            metrics.record_decode(audio, started.elapsed());
 
-            // Count consecutive high-energy frames
+            // Count consecutive high-energy chunks; a run that ends short of
+            // min_energy_chunks is a near miss
             if energy > energy_threshold {
                 high_energy_count += 1;
             } else {
+                if high_energy_count > 0 {
+                    audio_source.mark_clip(ClipReason::NearMiss);
+                }
                 high_energy_count = 0;
             }
 
             // Trigger detection on sustained high energy
-            if high_energy_count >= min_energy_frames {
+            if high_energy_count >= min_energy_chunks {
                 let score = (energy / energy_threshold).clamp(0.0, 1.0);
 
                 if score >= config.score_threshold {
@@ -123,22 +194,27 @@ fn run_stub_kws_worker(
                         score,
                     };
 
-                    // Emit Tauri event
-                    if let Err(e) = app_handle.emit("wakeword::detected", &event) {
+                    // Emit wake-word event
+                    let started = Instant::now();
+                    if let Err(e) = sink.publish(Event::WakewordDetected(event)) {
                         log::error!("Failed to emit wake-word event: {}", e);
                     }
+                    metrics.record(Stage::Emit, started.elapsed());
+                    if let Some(captured_at) = audio_source.captured_at() {
+                        metrics.record(Stage::EndToEnd, captured_at.elapsed());
+                    }
+                    audio_source.mark_clip(ClipReason::Detection);
 
                     last_detection = Some(Instant::now());
                     high_energy_count = 0;
                 }
             }
 
-            if frame_count.is_multiple_of(100) {
-                log::trace!("[STUB] Processed {} frames", frame_count);
+            if frame_count.is_multiple_of(progress_chunks) {
+                log::trace!("[STUB] Processed {} chunks", frame_count);
             }
         } else {
-            // No frame available, yield briefly
-            std::thread::sleep(Duration::from_millis(1));
+            log::debug!("[STUB] No audio for {:?}", FRAME_TIMEOUT);
         }
     }
 }
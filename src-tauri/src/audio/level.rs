@@ -1,17 +1,17 @@
 //! Audio level metering and RMS emission for UI visualization
 
-use tauri::{AppHandle, Emitter};
+use crate::events::{Event, EventSink};
 
-/// Emit a normalized 0..1 RMS value to the frontend (`audio:rms`).
+/// Emit a normalized 0..1 RMS value (`audio:rms`).
 ///
 /// `frame` is mono f32 at any rate; call this ~20–50ms for smooth UI updates.
 ///
 /// The RMS is normalized such that ~0.20 RMS ≈ strong speech,
 /// values are soft-clipped to [0.0, 1.0] range.
 #[allow(dead_code)]
-pub fn emit_rms(app: &AppHandle, frame: &[f32]) {
+pub fn emit_rms(sink: &dyn EventSink, frame: &[f32]) {
     if frame.is_empty() {
-        let _ = app.emit("audio:rms", 0.0f32);
+        let _ = sink.publish(Event::AudioRms(0.0));
         return;
     }
 
@@ -20,13 +20,13 @@ pub fn emit_rms(app: &AppHandle, frame: &[f32]) {
 
     // Simple soft clip/normalization. ~0.20 RMS ≈ strong speech.
     let norm = (rms / 0.20).clamp(0.0, 1.0);
-    let _ = app.emit("audio:rms", norm);
+    let _ = sink.publish(Event::AudioRms(norm));
 }
 
 /// Emit RMS from i16 samples (converts to f32 internally)
-pub fn emit_rms_i16(app: &AppHandle, frame: &[i16]) {
+pub fn emit_rms_i16(sink: &dyn EventSink, frame: &[i16]) {
     if frame.is_empty() {
-        let _ = app.emit("audio:rms", 0.0f32);
+        let _ = sink.publish(Event::AudioRms(0.0));
         return;
     }
 
@@ -43,5 +43,5 @@ pub fn emit_rms_i16(app: &AppHandle, frame: &[i16]) {
 
     // Normalize: ~0.20 RMS ≈ strong speech
     let norm = (rms / 0.20).clamp(0.0, 1.0);
-    let _ = app.emit("audio:rms", norm);
+    let _ = sink.publish(Event::AudioRms(norm));
 }
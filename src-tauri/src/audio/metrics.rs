@@ -0,0 +1,398 @@
+//! Pipeline latency and CPU telemetry
+//!
+//! Every frame the KWS worker processes is timed per stage and added to a
+//! histogram; detections also record the end-to-end latency from the sound
+//! reaching the mic to `wakeword::detected` being emitted:
+//!
+//! - `capture`: capture callback to frame ready (queue wait and framing)
+//! - `resample`, `preprocess` (echo cancellation and DSP), `vad`
+//! - `kws_decode`: feeding and decoding the frame (Sherpa, or the stub heuristic)
+//! - `emit`: publishing the detection
+//!
+//! Workers also report their thread CPU time. Read with `get_pipeline_metrics`
+//! or the periodic `audio:metrics` event; counts are since startup.
+
+use super::queue::{self, QueueStats};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How often the KWS worker publishes `audio:metrics`
+pub const METRICS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often workers sample their thread CPU time
+pub const CPU_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Upper bucket bounds (µs); larger values land in an overflow bucket
+const BUCKET_BOUNDS_US: [u64; 16] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000,
+];
+
+/// Timed pipeline stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Capture,
+    Resample,
+    Preprocess,
+    Vad,
+    KwsDecode,
+    Emit,
+    /// Mic to `wakeword::detected`, per detection
+    EndToEnd,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Capture => "capture",
+            Stage::Resample => "resample",
+            Stage::Preprocess => "preprocess",
+            Stage::Vad => "vad",
+            Stage::KwsDecode => "kws_decode",
+            Stage::Emit => "emit",
+            Stage::EndToEnd => "end_to_end",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// One count per bound plus the overflow bucket
+    counts: [u64; BUCKET_BOUNDS_US.len() + 1],
+    total: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        let bucket = BUCKET_BOUNDS_US.partition_point(|bound| *bound < us);
+        self.counts[bucket] += 1;
+        self.total += 1;
+        self.sum_us = self.sum_us.saturating_add(us);
+        self.max_us = self.max_us.max(us);
+    }
+
+    /// Upper bound of the bucket holding quantile `q` (the maximum if above the last bound)
+    fn quantile_us(&self, q: f64) -> u64 {
+        let rank = (q * self.total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return BUCKET_BOUNDS_US
+                    .get(bucket)
+                    .map_or(self.max_us, |bound| (*bound).min(self.max_us));
+            }
+        }
+        self.max_us
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let ms = |us: u64| us as f64 / 1000.0;
+        HistogramSnapshot {
+            count: self.total,
+            mean_ms: if self.total > 0 {
+                ms(self.sum_us) / self.total as f64
+            } else {
+                0.0
+            },
+            p50_ms: ms(self.quantile_us(0.50)),
+            p95_ms: ms(self.quantile_us(0.95)),
+            p99_ms: ms(self.quantile_us(0.99)),
+            max_ms: ms(self.max_us),
+            buckets: self
+                .counts
+                .iter()
+                .enumerate()
+                .map(|(bucket, count)| HistogramBucket {
+                    le_ms: BUCKET_BOUNDS_US.get(bucket).map(|bound| ms(*bound)),
+                    count: *count,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Latency distribution of one stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistogramSnapshot {
+    pub count: u64,
+    pub mean_ms: f64,
+    /// Percentiles are bucket upper bounds (never above `max_ms`)
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// Values at or below `le_ms` and above the previous bucket's bound
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistogramBucket {
+    /// Upper bound; absent for the overflow bucket
+    pub le_ms: Option<f64>,
+    pub count: u64,
+}
+
+/// Timing of one pipeline stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StageMetrics {
+    /// `capture`, `resample`, `preprocess`, `vad`, `kws_decode`, `emit` or `end_to_end`
+    pub stage: String,
+    pub latency: HistogramSnapshot,
+}
+
+/// CPU use of one worker thread
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ThreadCpu {
+    /// `kws` or `tagging`
+    pub thread: String,
+    /// Share of one core over the last sample interval (100 = one full core)
+    pub cpu_percent: f32,
+    /// CPU time since the thread started
+    pub cpu_ms: u64,
+}
+
+/// Pipeline telemetry (`get_pipeline_metrics`, `audio:metrics` payload)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PipelineMetricsSnapshot {
+    /// Time the counts cover (since startup)
+    pub uptime_ms: u64,
+    pub stages: Vec<StageMetrics>,
+    /// KWS decode time per second of audio fed (below 1.0 keeps up with real time)
+    pub kws_rtf: Option<f64>,
+    /// Empty where per-thread CPU time is not available (Linux only)
+    pub threads: Vec<ThreadCpu>,
+    /// Dropped audio: capture queue overruns and underruns
+    pub queue: QueueStats,
+}
+
+struct CpuSample {
+    at: Instant,
+    cpu: Duration,
+    percent: f32,
+}
+
+struct Inner {
+    stages: BTreeMap<Stage, Histogram>,
+    decode_time: Duration,
+    decoded_audio: Duration,
+    threads: BTreeMap<String, CpuSample>,
+}
+
+/// Collected telemetry, shared by the workers
+pub struct PipelineMetrics {
+    started: Instant,
+    inner: Mutex<Inner>,
+}
+
+impl Default for PipelineMetrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            inner: Mutex::new(Inner {
+                stages: BTreeMap::new(),
+                decode_time: Duration::ZERO,
+                decoded_audio: Duration::ZERO,
+                threads: BTreeMap::new(),
+            }),
+        }
+    }
+}
+
+impl PipelineMetrics {
+    pub fn global() -> Arc<PipelineMetrics> {
+        static METRICS: OnceLock<Arc<PipelineMetrics>> = OnceLock::new();
+        METRICS.get_or_init(Default::default).clone()
+    }
+
+    pub fn record(&self, stage: Stage, elapsed: Duration) {
+        self.inner
+            .lock()
+            .unwrap()
+            .stages
+            .entry(stage)
+            .or_default()
+            .record(elapsed);
+    }
+
+    /// KWS decode of `audio` took `elapsed` (also recorded as `kws_decode`)
+    pub fn record_decode(&self, audio: Duration, elapsed: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .stages
+            .entry(Stage::KwsDecode)
+            .or_default()
+            .record(elapsed);
+        inner.decode_time += elapsed;
+        inner.decoded_audio += audio;
+    }
+
+    /// Sample the calling thread's CPU time under `thread`
+    pub fn sample_thread_cpu(&self, thread: &str) {
+        if let Some(cpu) = thread_cpu_time() {
+            self.record_thread_cpu(thread, cpu, Instant::now());
+        }
+    }
+
+    fn record_thread_cpu(&self, thread: &str, cpu: Duration, at: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        let percent = inner.threads.get(thread).map_or(0.0, |prev| {
+            let wall = at.saturating_duration_since(prev.at).as_secs_f32();
+            if wall > 0.0 {
+                cpu.saturating_sub(prev.cpu).as_secs_f32() / wall * 100.0
+            } else {
+                prev.percent
+            }
+        });
+        inner
+            .threads
+            .insert(thread.to_string(), CpuSample { at, cpu, percent });
+    }
+
+    pub fn snapshot(&self) -> PipelineMetricsSnapshot {
+        let inner = self.inner.lock().unwrap();
+        PipelineMetricsSnapshot {
+            uptime_ms: self.started.elapsed().as_millis() as u64,
+            stages: inner
+                .stages
+                .iter()
+                .map(|(stage, histogram)| StageMetrics {
+                    stage: stage.name().to_string(),
+                    latency: histogram.snapshot(),
+                })
+                .collect(),
+            kws_rtf: (!inner.decoded_audio.is_zero())
+                .then(|| inner.decode_time.as_secs_f64() / inner.decoded_audio.as_secs_f64()),
+            threads: inner
+                .threads
+                .iter()
+                .map(|(thread, sample)| ThreadCpu {
+                    thread: thread.clone(),
+                    cpu_percent: sample.percent,
+                    cpu_ms: sample.cpu.as_millis() as u64,
+                })
+                .collect(),
+            queue: queue::stats(),
+        }
+    }
+}
+
+/// Per-iteration housekeeping for a worker loop: samples the thread's CPU
+/// time and hands out a snapshot to publish every [`METRICS_INTERVAL`]
+pub struct WorkerTicker {
+    metrics: Arc<PipelineMetrics>,
+    thread: &'static str,
+    next_cpu_sample: Instant,
+    next_publish: Instant,
+}
+
+impl WorkerTicker {
+    pub fn new(metrics: Arc<PipelineMetrics>, thread: &'static str) -> Self {
+        let now = Instant::now();
+        Self {
+            metrics,
+            thread,
+            next_cpu_sample: now,
+            next_publish: now + METRICS_INTERVAL,
+        }
+    }
+
+    /// Call from the worker thread on every loop iteration
+    pub fn tick(&mut self) -> Option<PipelineMetricsSnapshot> {
+        let now = Instant::now();
+        if now >= self.next_cpu_sample {
+            self.metrics.sample_thread_cpu(self.thread);
+            self.next_cpu_sample = now + CPU_SAMPLE_INTERVAL;
+        }
+        if now < self.next_publish {
+            return None;
+        }
+        self.next_publish = now + METRICS_INTERVAL;
+        Some(self.metrics.snapshot())
+    }
+}
+
+/// CPU time of the calling thread (Linux: first field of `schedstat`, in ns)
+fn thread_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/thread-self/schedstat").ok()?;
+    let ns = stat.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_nanos(ns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_histogram_percentiles_and_buckets() {
+        let mut histogram = Histogram::default();
+        for _ in 0..90 {
+            histogram.record(ms(3));
+        }
+        for _ in 0..10 {
+            histogram.record(ms(40));
+        }
+        let snapshot = histogram.snapshot();
+
+        assert_eq!(snapshot.count, 100);
+        assert!((snapshot.mean_ms - 6.7).abs() < 1e-9);
+        assert_eq!(snapshot.p50_ms, 5.0);
+        assert_eq!(snapshot.p95_ms, 40.0);
+        assert_eq!(snapshot.max_ms, 40.0);
+        assert_eq!(snapshot.buckets.len(), BUCKET_BOUNDS_US.len() + 1);
+        assert_eq!(snapshot.buckets.iter().map(|b| b.count).sum::<u64>(), 100);
+        assert_eq!(snapshot.buckets.last().unwrap().le_ms, None);
+    }
+
+    #[test]
+    fn test_overflow_reports_maximum() {
+        let mut histogram = Histogram::default();
+        histogram.record(Duration::from_secs(9));
+        assert_eq!(histogram.snapshot().p99_ms, 9000.0);
+        assert_eq!(*histogram.counts.last().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_stages_rtf_and_cpu() {
+        let metrics = PipelineMetrics::default();
+        assert_eq!(metrics.snapshot().kws_rtf, None);
+
+        metrics.record(Stage::Vad, ms(1));
+        metrics.record_decode(ms(20), ms(5));
+        metrics.record_decode(ms(20), ms(3));
+        let start = Instant::now();
+        metrics.record_thread_cpu("kws", ms(100), start);
+        metrics.record_thread_cpu("kws", ms(350), start + ms(1000));
+
+        let snapshot = metrics.snapshot();
+        let stages: Vec<_> = snapshot.stages.iter().map(|s| s.stage.as_str()).collect();
+        assert_eq!(stages, ["vad", "kws_decode"]);
+        assert!((snapshot.kws_rtf.unwrap() - 0.2).abs() < 1e-9);
+        assert_eq!(snapshot.threads[0].thread, "kws");
+        assert!((snapshot.threads[0].cpu_percent - 25.0).abs() < 0.01);
+        assert_eq!(snapshot.threads[0].cpu_ms, 350);
+    }
+
+    #[test]
+    fn test_thread_cpu_time_advances() {
+        let Some(before) = thread_cpu_time() else {
+            return; // not Linux
+        };
+        let mut x = 0u64;
+        for i in 0..5_000_000u64 {
+            x = x.wrapping_mul(31).wrapping_add(i);
+        }
+        std::hint::black_box(x);
+        assert!(thread_cpu_time().unwrap() > before);
+    }
+}
@@ -1,18 +1,41 @@
+pub mod aec;
+pub mod channels;
+pub mod dsp;
+pub mod format;
+pub mod framing;
+pub mod hotplug;
+pub mod identity;
 pub mod kws;
 pub mod level;
+pub mod metrics;
 pub mod monitor;
 pub mod probe;
+pub mod queue;
+pub mod recorder;
+pub mod resample;
 pub mod runtime;
+pub mod tagging;
 pub mod test_tone;
 pub mod vad;
 
+use crate::error::CommandError;
+use aec::{AecConfig, AecState, EchoCanceller, FarEnd};
 use anyhow::{Context, Result};
+use channels::{ChannelMeter, ChannelMix, ChannelMixer};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, Stream, StreamConfig};
-use rubato::{FftFixedIn, Resampler};
+use cpal::{FromSample, Sample, SampleFormat, Stream, StreamConfig};
+use crossbeam_channel::{Sender, TrySendError};
+use dsp::{DspChain, DspConfig, DspLevels, DspMeter};
+use format::{ActiveFormat, Capabilities, CaptureFormat};
+use metrics::{PipelineMetrics, Stage};
+use queue::{QueueReader, QueueWriter};
+use recorder::{ClipReason, DebugRecorder, RecorderConfig};
+use resample::{ResamplerKind, StreamResampler};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Target sample rate for all audio processing (16 kHz)
 pub const TARGET_SAMPLE_RATE: u32 = 16000;
@@ -20,25 +43,60 @@ pub const TARGET_SAMPLE_RATE: u32 = 16000;
 /// Target number of channels for processing (always mono)
 pub const TARGET_CHANNELS: usize = 1;
 
+/// How long workers wait for a frame before giving up (counted as an underrun)
+pub const FRAME_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Chunks a tap holds for a slow consumer before dropping them (2 s of 10 ms hops)
+pub const TAP_CAPACITY: usize = 200;
+
+/// Frames the input callback converts and mixes at a time; longer callbacks
+/// are handled in several blocks
+const CALLBACK_BLOCK_FRAMES: usize = 1024;
+
+/// Processed chunks of a capture, for a second consumer (see [`AudioCapture::with_tap`])
+pub type ChunkTap = Sender<Vec<i16>>;
+
 /// Stable device identifier for persistence across reboots
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+///
+/// Matched by a score over all fields rather than compared exactly, so a
+/// device is found again after indices shift (see `audio/identity.rs`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub struct DeviceId {
     pub host_api: String,
+    /// Position in the host's enumeration (changes when devices are added)
     pub index: u32,
     pub name: String,
+    /// Physical location: sysfs device path (USB port or PCI slot)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bus_path: Option<String>,
+    /// USB vendor and product ID (`0d8c:0014`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usb_id: Option<String>,
+    /// USB serial number, if the device reports one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
 }
 
 /// Device information for enumeration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub name: String,
+    /// Name for display; devices sharing a name get their port or a number
+    pub label: String,
     pub is_default: bool,
     pub host: String,
     pub max_channels: u16,
+    /// Common rates the device supports (range bounds if it supports none)
     pub sample_rates: Vec<u32>,
+    /// Sample formats over all supported configs, cheapest to convert first
+    #[serde(default)]
+    pub sample_formats: Vec<String>,
     /// Stable identifier for device persistence
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stable_id: Option<DeviceId>,
+    /// Level per channel (dBFS) while the device is being captured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_levels_dbfs: Option<Vec<f32>>,
 }
 
 /// Audio pipeline debug information
@@ -52,84 +110,81 @@ pub struct AudioDebugInfo {
     pub samples_per_hop: usize,
     pub input_device: Option<String>,
     pub output_device: Option<String>,
+    /// Levels before and after pre-processing (None while capture is stopped)
+    pub dsp_levels: Option<DspLevels>,
+    /// Format the running capture opened the device with
+    pub capture_format: Option<CaptureFormat>,
+    /// Chunks lost to resampler failures since startup
+    pub resample_errors: u64,
+    pub echo_cancellation: AecState,
 }
 
-/// Friendly error message with optional error code
-#[derive(Debug, Clone, Serialize)]
-pub struct FriendlyError {
-    pub message: String,
-    pub code: String,
-    pub technical: String,
-}
-
-/// Map common CPAL/audio errors to user-friendly messages
-pub fn friendly_audio_error(error: &anyhow::Error) -> FriendlyError {
-    let error_str = format!("{:#}", error);
-    let error_lower = error_str.to_lowercase();
-
-    // Check for common patterns
-    if error_lower.contains("device busy")
-        || error_lower.contains("in use")
-        || error_lower.contains("already in use")
-    {
-        return FriendlyError {
-            message: "Another app is using this microphone. Close other audio apps and try again."
-                .to_string(),
-            code: "device_busy".to_string(),
-            technical: error_str,
-        };
-    }
-
-    if error_lower.contains("no such device")
-        || error_lower.contains("not found")
-        || error_lower.contains("does not exist")
-        || error_lower.contains("disconnected")
-    {
-        return FriendlyError {
-            message: "Microphone was unplugged or is no longer available. Please reconnect it."
-                .to_string(),
-            code: "device_not_found".to_string(),
-            technical: error_str,
-        };
-    }
-
-    if error_lower.contains("permission")
-        || error_lower.contains("access denied")
-        || error_lower.contains("denied")
-    {
-        return FriendlyError {
-            message: "Permission denied. Check your system's microphone settings.".to_string(),
-            code: "permission_denied".to_string(),
-            technical: error_str,
-        };
-    }
-
-    if error_lower.contains("timeout") {
-        return FriendlyError {
-            message: "Audio device timed out. Try unplugging and reconnecting it.".to_string(),
-            code: "timeout".to_string(),
-            technical: error_str,
-        };
+impl AudioDebugInfo {
+    /// Pipeline parameters and selected devices from the audio config, plus
+    /// the format and levels of the running capture
+    pub fn from_config(config: &AudioConfig) -> Self {
+        Self {
+            processing_rate: config.sample_rate_hz,
+            processing_channels: TARGET_CHANNELS,
+            frame_ms: config.frame_ms,
+            hop_ms: config.hop_ms,
+            samples_per_frame: config.samples_per_frame(),
+            samples_per_hop: config.samples_per_hop(),
+            input_device: config.device_name.clone(),
+            output_device: config.output_device_name.clone(),
+            dsp_levels: DspMeter::global().levels(),
+            capture_format: format::active(),
+            resample_errors: resample::errors(),
+            echo_cancellation: config.aec_state(),
+        }
     }
+}
 
-    if error_lower.contains("no default")
-        || error_lower.contains("no input device")
-        || error_lower.contains("no microphone")
-    {
-        return FriendlyError {
-            message: "No microphone found. Please connect a microphone.".to_string(),
-            code: "no_device".to_string(),
-            technical: error_str,
-        };
-    }
+/// Enumerate devices with their stable identifiers
+///
+/// Devices whose name cannot be read are skipped but keep their index.
+fn enumerate_devices(
+    host_id: &str,
+    devices: impl Iterator<Item = cpal::Device>,
+) -> Vec<(cpal::Device, DeviceId)> {
+    devices
+        .enumerate()
+        .filter_map(|(index, device)| {
+            let name = device.name().ok()?;
+            let id = identity::device_id(host_id, index, &name);
+            Some((device, id))
+        })
+        .collect()
+}
 
-    // Default fallback
-    FriendlyError {
-        message: "Audio system error. Try restarting the app or reconnecting your microphone."
-            .to_string(),
-        code: "unknown".to_string(),
-        technical: error_str,
-    }
+/// Device infos with labels that tell duplicate names apart
+fn describe_devices(
+    host_id: &str,
+    devices: Vec<(cpal::Device, DeviceId)>,
+    default_device_name: Option<String>,
+    capabilities: impl Fn(&cpal::Device) -> Capabilities,
+) -> Vec<DeviceInfo> {
+    let ids: Vec<DeviceId> = devices.iter().map(|(_, id)| id.clone()).collect();
+    let labels = identity::labels(&ids);
+
+    devices
+        .into_iter()
+        .zip(labels)
+        .map(|((device, id), label)| {
+            let capabilities = capabilities(&device);
+            DeviceInfo {
+                is_default: default_device_name.as_deref() == Some(id.name.as_str()),
+                name: id.name.clone(),
+                label,
+                host: host_id.to_string(),
+                max_channels: capabilities.max_channels,
+                sample_rates: capabilities.sample_rates,
+                sample_formats: capabilities.sample_formats,
+                channel_levels_dbfs: None,
+                stable_id: Some(id),
+            }
+        })
+        .collect()
 }
 
 /// List all available input devices
@@ -138,43 +193,26 @@ pub fn list_input_devices() -> Result<Vec<DeviceInfo>> {
     let default_device_name = host.default_input_device().and_then(|d| d.name().ok());
 
     let host_id = host.id().name();
-    let mut devices = Vec::new();
-
-    for (index, device) in host.input_devices()?.enumerate() {
-        if let Ok(name) = device.name() {
-            let is_default = Some(name.clone()) == default_device_name;
+    let devices = enumerate_devices(host_id, host.input_devices()?);
+
+    let mut infos = describe_devices(host_id, devices, default_device_name, |device| {
+        // Get device capabilities over all supported configs
+        device
+            .supported_input_configs()
+            .ok()
+            .and_then(Capabilities::from_ranges)
+            .unwrap_or_else(|| Capabilities {
+                max_channels: 1,
+                sample_rates: vec![16000],
+                sample_formats: Vec::new(),
+            })
+    });
 
-            // Get device capabilities
-            let (max_channels, sample_rates) = device
-                .supported_input_configs()
-                .ok()
-                .and_then(|mut configs| configs.next())
-                .map(|config| {
-                    let channels = config.channels();
-                    let rates = vec![config.min_sample_rate().0, config.max_sample_rate().0];
-                    (channels, rates)
-                })
-                .unwrap_or((1, vec![16000]));
-
-            // Create stable device identifier
-            let stable_id = Some(DeviceId {
-                host_api: host_id.to_string(),
-                index: index as u32,
-                name: name.clone(),
-            });
-
-            devices.push(DeviceInfo {
-                name,
-                is_default,
-                host: host_id.to_string(),
-                max_channels,
-                sample_rates,
-                stable_id,
-            });
-        }
+    let meter = ChannelMeter::global();
+    for info in &mut infos {
+        info.channel_levels_dbfs = meter.levels(&info.name);
     }
-
-    Ok(devices)
+    Ok(infos)
 }
 
 /// List all available output devices
@@ -183,43 +221,51 @@ pub fn list_output_devices() -> Result<Vec<DeviceInfo>> {
     let default_device_name = host.default_output_device().and_then(|d| d.name().ok());
 
     let host_id = host.id().name();
-    let mut devices = Vec::new();
-
-    for (index, device) in host.output_devices()?.enumerate() {
-        if let Ok(name) = device.name() {
-            let is_default = Some(name.clone()) == default_device_name;
-
-            // Get device capabilities
-            let (max_channels, sample_rates) = device
+    let devices = enumerate_devices(host_id, host.output_devices()?);
+
+    Ok(describe_devices(
+        host_id,
+        devices,
+        default_device_name,
+        |device| {
+            // Get device capabilities over all supported configs
+            device
                 .supported_output_configs()
                 .ok()
-                .and_then(|mut configs| configs.next())
-                .map(|config| {
-                    let channels = config.channels();
-                    let rates = vec![config.min_sample_rate().0, config.max_sample_rate().0];
-                    (channels, rates)
+                .and_then(Capabilities::from_ranges)
+                .unwrap_or_else(|| Capabilities {
+                    max_channels: 2,
+                    sample_rates: vec![44100, 48000],
+                    sample_formats: Vec::new(),
                 })
-                .unwrap_or((2, vec![44100, 48000]));
-
-            // Create stable device identifier
-            let stable_id = Some(DeviceId {
-                host_api: host_id.to_string(),
-                index: index as u32,
-                name: name.clone(),
-            });
-
-            devices.push(DeviceInfo {
-                name,
-                is_default,
-                host: host_id.to_string(),
-                max_channels,
-                sample_rates,
-                stable_id,
-            });
-        }
-    }
+        },
+    ))
+}
 
-    Ok(devices)
+/// Stable identifiers of the input devices, without querying their capabilities
+///
+/// Cheap enough to list on every hotplug notification.
+pub fn input_device_ids() -> Vec<DeviceId> {
+    let host = cpal::default_host();
+    device_ids(host.id().name(), host.input_devices())
+}
+
+/// Stable identifiers of the output devices, without querying their capabilities
+pub fn output_device_ids() -> Vec<DeviceId> {
+    let host = cpal::default_host();
+    device_ids(host.id().name(), host.output_devices())
+}
+
+fn device_ids<I: Iterator<Item = cpal::Device>>(
+    host_id: &str,
+    devices: Result<I, cpal::DevicesError>,
+) -> Vec<DeviceId> {
+    devices
+        .map(|devices| enumerate_devices(host_id, devices))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect()
 }
 
 /// Audio configuration
@@ -238,6 +284,21 @@ pub struct AudioConfig {
     /// Stable output device identifier (primary key for persistence)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stable_output_id: Option<DeviceId>,
+    /// Echo cancellation against Emberleaf's own playback
+    #[serde(default)]
+    pub aec: AecConfig,
+    /// Pre-processing before VAD/KWS (high-pass, noise suppression, AGC, limiter)
+    #[serde(default)]
+    pub dsp: DspConfig,
+    /// How device channels are combined (average, one channel, subset, beam)
+    #[serde(default)]
+    pub channels: ChannelMix,
+    /// Resampler for devices not running at `sample_rate_hz` (sinc, fft, linear)
+    #[serde(default)]
+    pub resampler: ResamplerKind,
+    /// Debug recordings to `<cache>/tmp_audio` (opt-in)
+    #[serde(default)]
+    pub recorder: RecorderConfig,
 }
 
 impl Default for AudioConfig {
@@ -250,6 +311,11 @@ impl Default for AudioConfig {
             output_device_name: None,
             stable_input_id: None,
             stable_output_id: None,
+            aec: AecConfig::default(),
+            dsp: DspConfig::default(),
+            channels: ChannelMix::default(),
+            resampler: ResamplerKind::default(),
+            recorder: RecorderConfig::default(),
         }
     }
 }
@@ -262,12 +328,23 @@ impl AudioConfig {
     pub fn samples_per_hop(&self) -> usize {
         (self.sample_rate_hz * self.hop_ms / 1000) as usize
     }
+
+    /// Echo cancellation runs only at 16 kHz, the rate of the playback reference
+    pub fn aec_state(&self) -> AecState {
+        if !self.aec.enabled {
+            AecState::Disabled
+        } else if self.sample_rate_hz != TARGET_SAMPLE_RATE {
+            AecState::UnsupportedRate
+        } else {
+            AecState::Active
+        }
+    }
 }
 
 /// Resolve preferred input device using stable_id (primary), name (fallback), or default
 ///
 /// Resolution order:
-/// 1. Try to match by stable_id (host_api + index + name)
+/// 1. Best scored match for stable_id (survives index changes, see [`identity`])
 /// 2. Fall back to exact name match
 /// 3. Fall back to system default
 pub fn resolve_preferred_input_device(
@@ -275,113 +352,116 @@ pub fn resolve_preferred_input_device(
     name: Option<&str>,
 ) -> Result<Option<cpal::Device>> {
     let host = cpal::default_host();
+    let devices = host
+        .input_devices()
+        .map(|devices| enumerate_devices(host.id().name(), devices))
+        .unwrap_or_default();
 
-    // If we have a stable_id, try to resolve by it first
-    if let Some(sid) = stable_id {
-        log::debug!("Attempting to resolve device by stable_id: {:?}", sid);
-
-        // Try exact match: host_api + index + name
-        if let Ok(devices) = host.input_devices() {
-            for (idx, device) in devices.enumerate() {
-                if let Ok(dev_name) = device.name() {
-                    if idx as u32 == sid.index
-                        && dev_name == sid.name
-                        && host.id().name() == sid.host_api
-                    {
-                        log::info!("✓ Resolved device by stable_id: {}", dev_name);
-                        return Ok(Some(device));
-                    }
-                }
-            }
-        }
-
-        log::warn!("Device stable_id not found, falling back to name match");
-    }
-
-    // Fall back to name match
-    if let Some(device_name) = name {
-        log::debug!("Attempting to resolve device by name: {}", device_name);
-
-        if let Ok(devices) = host.input_devices() {
-            for device in devices {
-                if let Ok(dev_name) = device.name() {
-                    if dev_name == device_name {
-                        log::info!("✓ Resolved device by name: {}", dev_name);
-                        return Ok(Some(device));
-                    }
-                }
-            }
-        }
-
-        log::warn!(
-            "Device name '{}' not found, falling back to default",
-            device_name
-        );
-    }
-
-    // Fall back to system default
-    Ok(None) // None means "use default"
+    Ok(resolve_preferred_device("input", devices, stable_id, name))
 }
 
 /// Resolve preferred output device using stable_id (primary), name (fallback), or default
-#[allow(dead_code)]
 pub fn resolve_preferred_output_device(
     stable_id: Option<&DeviceId>,
     name: Option<&str>,
 ) -> Result<Option<cpal::Device>> {
     let host = cpal::default_host();
+    let devices = host
+        .output_devices()
+        .map(|devices| enumerate_devices(host.id().name(), devices))
+        .unwrap_or_default();
 
-    // If we have a stable_id, try to resolve by it first
+    Ok(resolve_preferred_device("output", devices, stable_id, name))
+}
+
+/// Pick `stable_id`'s best match among `devices`, else the exact `name`
+///
+/// None means "use default".
+fn resolve_preferred_device(
+    kind: &str,
+    mut devices: Vec<(cpal::Device, DeviceId)>,
+    stable_id: Option<&DeviceId>,
+    name: Option<&str>,
+) -> Option<cpal::Device> {
     if let Some(sid) = stable_id {
         log::debug!(
-            "Attempting to resolve output device by stable_id: {:?}",
+            "Attempting to resolve {} device by stable_id: {:?}",
+            kind,
             sid
         );
 
-        // Try exact match: host_api + index + name
-        if let Ok(devices) = host.output_devices() {
-            for (idx, device) in devices.enumerate() {
-                if let Ok(dev_name) = device.name() {
-                    if idx as u32 == sid.index
-                        && dev_name == sid.name
-                        && host.id().name() == sid.host_api
-                    {
-                        log::info!("✓ Resolved output device by stable_id: {}", dev_name);
-                        return Ok(Some(device));
-                    }
-                }
-            }
+        let ids: Vec<DeviceId> = devices.iter().map(|(_, id)| id.clone()).collect();
+        if let Some(position) = identity::best_match(sid, &ids) {
+            let (device, id) = devices.swap_remove(position);
+            log::info!("✓ Resolved {} device by stable_id: {}", kind, id.name);
+            return Some(device);
         }
 
-        log::warn!("Output device stable_id not found, falling back to name match");
+        log::warn!(
+            "{} device stable_id not found, falling back to name match",
+            kind
+        );
     }
 
-    // Fall back to name match
     if let Some(device_name) = name {
         log::debug!(
-            "Attempting to resolve output device by name: {}",
+            "Attempting to resolve {} device by name: {}",
+            kind,
             device_name
         );
 
-        if let Ok(devices) = host.output_devices() {
-            for device in devices {
-                if let Ok(dev_name) = device.name() {
-                    if dev_name == device_name {
-                        log::info!("✓ Resolved output device by name: {}", dev_name);
-                        return Ok(Some(device));
-                    }
-                }
-            }
+        if let Some((device, _)) = devices.into_iter().find(|(_, id)| id.name == device_name) {
+            log::info!("✓ Resolved {} device by name: {}", kind, device_name);
+            return Some(device);
         }
 
         log::warn!(
-            "Output device name '{}' not found, falling back to default",
+            "{} device name '{}' not found, falling back to default",
+            kind,
             device_name
         );
     }
 
-    // Fall back to system default
-    Ok(None) // None means "use default"
+    None
+}
+
+/// Output device resolved for playback
+pub struct OutputDevice {
+    pub device: cpal::Device,
+    pub name: String,
+    /// A device was configured but is missing, so the default plays instead
+    pub fallback: bool,
+}
+
+/// Resolve the output device to play on, falling back to the system default
+///
+/// Playback only fails when there is no output device at all, never because
+/// the configured one was unplugged.
+pub fn open_output_device(
+    stable_id: Option<&DeviceId>,
+    name: Option<&str>,
+) -> Result<OutputDevice> {
+    if let Some(device) = resolve_preferred_output_device(stable_id, name)? {
+        let name = device.name()?;
+        return Ok(OutputDevice {
+            device,
+            name,
+            fallback: false,
+        });
+    }
+
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| CommandError::NoDevice("no default output device".to_string()))?;
+    let fallback = stable_id.is_some() || name.is_some();
+    if fallback {
+        log::warn!("Configured output device unavailable, playing on the default device");
+    }
+    Ok(OutputDevice {
+        name: device.name()?,
+        device,
+        fallback,
+    })
 }
 
 /// Check if an input device with the given stable_id or name still exists
@@ -393,7 +473,6 @@ pub fn check_input_device_exists(stable_id: Option<&DeviceId>, name: Option<&str
 }
 
 /// Check if an output device with the given stable_id or name still exists
-#[allow(dead_code)]
 pub fn check_output_device_exists(stable_id: Option<&DeviceId>, name: Option<&str>) -> bool {
     resolve_preferred_output_device(stable_id, name)
         .ok()
@@ -403,35 +482,53 @@ pub fn check_output_device_exists(stable_id: Option<&DeviceId>, name: Option<&st
 
 /// Trait for audio sources that can provide frames
 ///
+/// Frames are contiguous `hop_ms` chunks, so consumers see every sample once;
+/// [`framing::Framer`] turns them into overlapping `frame_ms` windows.
+///
 /// Note: This trait does not require Send since audio sources are confined to a single worker thread
 pub trait AudioSource {
-    /// Get the next frame of audio samples
-    /// Returns None if no frame is available yet
-    fn next_frame(&mut self) -> Option<Vec<i16>>;
+    /// Get the next chunk of audio samples, waiting up to `timeout` for it
+    /// Returns None if no chunk arrived in time
+    fn next_frame(&mut self, timeout: Duration) -> Option<Vec<i16>>;
+
+    /// When the last sample of the latest frame was captured
+    fn captured_at(&self) -> Option<Instant>;
 
     /// Get the sample rate of this source
     fn sample_rate(&self) -> u32;
 
-    /// Get the frame size in samples
+    /// Get the chunk size in samples (`hop_ms` at the sample rate)
     #[allow(dead_code)]
     fn frame_size(&self) -> usize;
 }
 
 /// Audio capture system using CPAL
 pub struct AudioCapture {
-    _stream: Stream,
-    receiver: mpsc::UnboundedReceiver<Vec<i16>>,
+    /// None for a capture fed directly through its queue (tests)
+    _stream: Option<Stream>,
+    queue: QueueReader,
     config: AudioConfig,
-    #[allow(dead_code)]
     device_rate: u32,
     #[allow(dead_code)]
     device_channels: usize,
-    needs_resampling: bool,
-    resampler: Option<Arc<Mutex<FftFixedIn<f32>>>>,
-    buffer: Vec<i16>,
-    resample_input_buffer: Vec<f32>,
-    #[allow(dead_code)]
-    resample_output_buffer: Vec<f32>,
+    /// None when the device runs at the processing rate
+    resampler: Option<StreamResampler>,
+    /// Mono samples at the processing rate, not yet taken as a frame
+    buffer: Vec<f32>,
+    /// None when `audio.aec.enabled` is off
+    echo_canceller: Option<EchoCanceller>,
+    /// When the newest received samples were captured
+    last_arrival: Option<Instant>,
+    dsp: DspChain,
+    /// End of the latest frame (see [`AudioSource::captured_at`])
+    frame_captured_at: Option<Instant>,
+    /// Stage timings, for the capture that feeds KWS
+    metrics: Option<Arc<PipelineMetrics>>,
+    /// Debug recordings, for the capture that feeds KWS when opted in
+    recorder: Option<DebugRecorder>,
+    /// Audio tagging, listening to the capture that feeds KWS
+    tap: Option<ChunkTap>,
+    _format: Option<ActiveFormat>,
 }
 
 impl AudioCapture {
@@ -449,7 +546,7 @@ impl AudioCapture {
             // Fall back to system default
             let host = cpal::default_host();
             host.default_input_device()
-                .context("No input device available")?
+                .ok_or_else(|| CommandError::NoDevice("no default input device".to_string()))?
         };
 
         Self::new_with_device(config, device)
@@ -464,7 +561,7 @@ impl AudioCapture {
         let device = host
             .input_devices()?
             .find(|d| d.name().ok().as_deref() == Some(device_name))
-            .with_context(|| format!("Device '{}' not found", device_name))?;
+            .ok_or_else(|| CommandError::DeviceNotFound(device_name.to_string()))?;
 
         Self::new_with_device(config, device)
     }
@@ -473,44 +570,102 @@ impl AudioCapture {
     fn new_with_device(config: AudioConfig, device: cpal::Device) -> Result<Self> {
         log::info!("Using audio device: {}", device.name()?);
 
-        // Get supported config
-        let supported_config = device
+        // Cheapest supported config for the pipeline; the device default if
+        // none fits or the device refuses it
+        let default_config = device
             .default_input_config()
             .context("Failed to get default input config")?;
+        let negotiated = device
+            .supported_input_configs()
+            .ok()
+            .and_then(|ranges| {
+                format::choose(
+                    ranges,
+                    config.sample_rate_hz,
+                    config.channels.required_channels(),
+                )
+            })
+            .filter(|chosen| *chosen != default_config);
+
+        let (supported_config, (stream, queue), negotiated) = match negotiated {
+            Some(chosen) => match Self::build_stream(&device, &chosen, &config.channels) {
+                Ok(built) => (chosen, built, true),
+                Err(e) => {
+                    log::warn!(
+                        "Negotiated format {} Hz, {} ch, {} failed ({}); using device default",
+                        chosen.sample_rate().0,
+                        chosen.channels(),
+                        chosen.sample_format(),
+                        e
+                    );
+                    let built = Self::build_stream(&device, &default_config, &config.channels)?;
+                    (default_config, built, false)
+                }
+            },
+            None => {
+                let built = Self::build_stream(&device, &default_config, &config.channels)?;
+                (default_config, built, false)
+            }
+        };
 
         let sample_rate = supported_config.sample_rate().0;
         let channels = supported_config.channels();
 
         log::info!(
-            "Device config: {} Hz, {} channels, format: {:?}",
+            "Device config: {} Hz, {} channels, format: {} ({})",
             sample_rate,
             channels,
-            supported_config.sample_format()
+            supported_config.sample_format(),
+            if negotiated {
+                "negotiated"
+            } else {
+                "device default"
+            }
+        );
+
+        let mut capture = Self::from_queue(config, queue, sample_rate, channels)?;
+        let capture_format = CaptureFormat::new(
+            &supported_config,
+            negotiated,
+            capture.resampler.as_ref().map(StreamResampler::kind),
         );
 
-        // Determine if resampling is needed
-        let needs_resampling = sample_rate != config.sample_rate_hz;
+        stream.play()?;
+
+        log::info!("Audio capture started successfully");
 
-        // Create resampler if needed (always for mono processing)
-        let resampler = if needs_resampling {
+        capture._stream = Some(stream);
+        capture._format = Some(ActiveFormat::register(capture_format));
+        Ok(capture)
+    }
+
+    /// The processing side of a capture: everything after the queue
+    fn from_queue(
+        config: AudioConfig,
+        queue: QueueReader,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self> {
+        // Resample to the processing rate (always mono after the channel mix)
+        let resampler = if sample_rate != config.sample_rate_hz {
+            let resampler =
+                StreamResampler::new(config.resampler, sample_rate, config.sample_rate_hz)
+                    .with_context(|| {
+                        format!(
+                            "Failed to create resampler {} -> {} Hz",
+                            sample_rate, config.sample_rate_hz
+                        )
+                    })?;
             log::info!(
-                "Audio: device={}Hz, {}ch -> processing={}Hz, {}ch (downmix={})",
+                "Audio: device={}Hz, {}ch -> processing={}Hz, {}ch (resampler={:?}, downmix={})",
                 sample_rate,
                 channels,
                 config.sample_rate_hz,
                 TARGET_CHANNELS,
+                resampler.kind(),
                 channels != TARGET_CHANNELS as u16
             );
-
-            let params = rubato::FftFixedIn::<f32>::new(
-                sample_rate as usize,
-                config.sample_rate_hz as usize,
-                config.samples_per_frame(),
-                2,               // Max number of channels (rubato parameter)
-                TARGET_CHANNELS, // Always mono for processing
-            )?;
-
-            Some(Arc::new(Mutex::new(params)))
+            Some(resampler)
         } else {
             log::info!(
                 "Audio: device={}Hz, {}ch (no resampling needed, downmix={})",
@@ -521,175 +676,254 @@ impl AudioCapture {
             None
         };
 
-        // Create channel for audio data
-        let (sender, receiver) = mpsc::unbounded_channel();
-
-        // Build stream
-        let stream_config = StreamConfig {
-            channels,
-            sample_rate: cpal::SampleRate(sample_rate),
-            buffer_size: cpal::BufferSize::Default,
-        };
-
-        let sender_clone = sender.clone();
-        let channels_count = channels as usize;
-
-        let stream = match supported_config.sample_format() {
-            SampleFormat::F32 => device.build_input_stream(
-                &stream_config,
-                move |data: &[f32], _: &_| {
-                    Self::handle_input_f32(data, channels_count, &sender_clone);
-                },
-                |err| log::error!("Audio stream error: {}", err),
-                None,
-            )?,
-            SampleFormat::I16 => device.build_input_stream(
-                &stream_config,
-                move |data: &[i16], _: &_| {
-                    Self::handle_input_i16(data, channels_count, &sender_clone);
-                },
-                |err| log::error!("Audio stream error: {}", err),
-                None,
-            )?,
-            SampleFormat::U16 => device.build_input_stream(
-                &stream_config,
-                move |data: &[u16], _: &_| {
-                    Self::handle_input_u16(data, channels_count, &sender_clone);
-                },
-                |err| log::error!("Audio stream error: {}", err),
-                None,
-            )?,
-            _ => anyhow::bail!("Unsupported sample format"),
+        let echo_canceller = match config.aec_state() {
+            AecState::Active => Some(EchoCanceller::new(&config.aec, FarEnd::global())),
+            AecState::Disabled => None,
+            AecState::UnsupportedRate => {
+                log::warn!(
+                    "Echo cancellation off: it needs a {} Hz processing rate, not {} Hz",
+                    TARGET_SAMPLE_RATE,
+                    config.sample_rate_hz
+                );
+                None
+            }
         };
 
-        stream.play()?;
-
-        log::info!("Audio capture started successfully");
-
         Ok(Self {
-            _stream: stream,
-            receiver,
-            config,
+            _stream: None,
+            queue,
             device_rate: sample_rate,
             device_channels: channels as usize,
-            needs_resampling,
             resampler,
             buffer: Vec::new(),
-            resample_input_buffer: Vec::new(),
-            resample_output_buffer: Vec::new(),
+            echo_canceller,
+            last_arrival: None,
+            dsp: DspChain::new(&config.dsp, config.sample_rate_hz, DspMeter::global()),
+            frame_captured_at: None,
+            metrics: None,
+            recorder: None,
+            tap: None,
+            config,
+            _format: None,
         })
     }
 
-    /// Get the device sample rate (actual mic rate)
-    #[allow(dead_code)]
-    pub fn device_rate(&self) -> u32 {
-        self.device_rate
+    /// Record capture, resample and pre-processing times in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<PipelineMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
     }
 
-    fn handle_input_f32(data: &[f32], channels: usize, sender: &mpsc::UnboundedSender<Vec<i16>>) {
-        // Convert to mono i16
-        let mono: Vec<i16> = data
-            .chunks(channels)
-            .map(|chunk| {
-                let avg = chunk.iter().sum::<f32>() / channels as f32;
-                (avg * i16::MAX as f32) as i16
-            })
-            .collect();
+    /// Record into `dir` if `audio.recorder.enabled` is set
+    pub fn with_recorder(mut self, dir: &Path) -> Self {
+        if self.config.recorder.enabled {
+            match DebugRecorder::new(
+                &self.config.recorder,
+                dir,
+                self.config.sample_rate_hz,
+                self.device_rate,
+            ) {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(e) => log::warn!("Debug recorder unavailable: {:#}", e),
+            }
+        }
+        self
+    }
 
-        let _ = sender.send(mono);
+    /// Also hand every processed chunk to `tap`
+    ///
+    /// A consumer that falls [`TAP_CAPACITY`] chunks behind loses chunks;
+    /// the capture never waits for it.
+    pub fn with_tap(mut self, tap: Option<ChunkTap>) -> Self {
+        self.tap = tap;
+        self
     }
 
-    fn handle_input_i16(data: &[i16], channels: usize, sender: &mpsc::UnboundedSender<Vec<i16>>) {
-        // Convert to mono
-        let mono: Vec<i16> = data
-            .chunks(channels)
-            .map(|chunk| {
-                let avg: i32 = chunk.iter().map(|&s| s as i32).sum();
-                (avg / channels as i32) as i16
-            })
-            .collect();
+    /// Save a debug clip around the latest frame (no-op unless recording)
+    pub fn mark_clip(&mut self, reason: ClipReason) {
+        self.record(|recorder| recorder.mark(reason));
+    }
 
-        let _ = sender.send(mono);
+    /// Run a recorder step; a recorder that fails is turned off, not retried
+    fn record(&mut self, step: impl FnOnce(&mut DebugRecorder) -> Result<()>) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = step(recorder) {
+                log::warn!("Debug recorder stopped: {:#}", e);
+                self.recorder = None;
+            }
+        }
     }
 
-    fn handle_input_u16(data: &[u16], channels: usize, sender: &mpsc::UnboundedSender<Vec<i16>>) {
-        // Convert to mono i16
-        let mono: Vec<i16> = data
-            .chunks(channels)
-            .map(|chunk| {
-                let avg: i32 = chunk.iter().map(|&s| s as i32).sum();
-                let avg_u16 = (avg / channels as i32) as u16;
-                (avg_u16 as i32 - 32768) as i16 // Convert u16 to i16
-            })
-            .collect();
+    /// Build the input stream for a config in any of [`format::CAPTURE_FORMATS`],
+    /// with the queue its samples arrive in
+    fn build_stream(
+        device: &cpal::Device,
+        supported_config: &cpal::SupportedStreamConfig,
+        mix: &ChannelMix,
+    ) -> Result<(Stream, QueueReader)> {
+        let stream_config = StreamConfig {
+            channels: supported_config.channels(),
+            sample_rate: supported_config.sample_rate(),
+            buffer_size: cpal::BufferSize::Default,
+        };
+        let mut mixer = ChannelMixer::new(
+            mix,
+            supported_config.channels() as usize,
+            supported_config.sample_rate().0,
+            &device.name().unwrap_or_default(),
+            ChannelMeter::global(),
+        );
+        let (mut writer, reader) = queue::channel(supported_config.sample_rate().0);
+        // Scratch for the callback, which must not allocate
+        let mut interleaved =
+            vec![0.0; CALLBACK_BLOCK_FRAMES * stream_config.channels.max(1) as usize];
+        let mut mono = vec![0.0; CALLBACK_BLOCK_FRAMES];
+
+        macro_rules! build {
+            ($sample:ty) => {
+                device.build_input_stream(
+                    &stream_config,
+                    move |data: &[$sample], _: &_| {
+                        Self::handle_input(
+                            data,
+                            &mut interleaved,
+                            &mut mono,
+                            &mut mixer,
+                            &mut writer,
+                        )
+                    },
+                    |err| log::error!("Audio stream error: {}", err),
+                    None,
+                )?
+            };
+        }
 
-        let _ = sender.send(mono);
+        let stream = match supported_config.sample_format() {
+            SampleFormat::I8 => build!(i8),
+            SampleFormat::I16 => build!(i16),
+            SampleFormat::I32 => build!(i32),
+            SampleFormat::I64 => build!(i64),
+            SampleFormat::U8 => build!(u8),
+            SampleFormat::U16 => build!(u16),
+            SampleFormat::U32 => build!(u32),
+            SampleFormat::U64 => build!(u64),
+            SampleFormat::F32 => build!(f32),
+            SampleFormat::F64 => build!(f64),
+            format => return Err(CommandError::UnsupportedFormat(format!("{:?}", format)).into()),
+        };
+        Ok((stream, reader))
+    }
+
+    /// Get the device sample rate (actual mic rate)
+    #[allow(dead_code)]
+    pub fn device_rate(&self) -> u32 {
+        self.device_rate
+    }
+
+    /// Mix a callback's samples into the queue, a scratch buffer at a time
+    fn handle_input<T>(
+        data: &[T],
+        interleaved: &mut [f32],
+        mono: &mut [f32],
+        mixer: &mut ChannelMixer,
+        writer: &mut QueueWriter,
+    ) where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        for block in data.chunks(interleaved.len()) {
+            let interleaved = &mut interleaved[..block.len()];
+            for (out, &sample) in interleaved.iter_mut().zip(block) {
+                *out = sample.to_sample::<f32>();
+            }
+            let frames = mixer.process(interleaved, mono);
+            writer.write(&mono[..frames]);
+        }
+    }
+}
+
+impl AudioCapture {
+    /// Move queued samples into the frame buffer, resampling if needed
+    fn drain_queue(&mut self) {
+        let Some((arrival, data)) = self.queue.read() else {
+            return;
+        };
+        self.last_arrival = Some(arrival);
+        self.record(|recorder| recorder.raw(&data));
+        let Some(resampler) = self.resampler.as_mut() else {
+            self.buffer.extend_from_slice(&data);
+            return;
+        };
+
+        let started = Instant::now();
+        if let Err(e) = resampler.process(&data, &mut self.buffer) {
+            // Counted in `AudioDebugInfo::resample_errors`; the resampler
+            // restarts clean with the next samples
+            log::error!("Resampling failed, audio dropped: {:#}", e);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record(Stage::Resample, started.elapsed());
+        }
     }
 }
 
 impl AudioSource for AudioCapture {
-    fn next_frame(&mut self) -> Option<Vec<i16>> {
-        let frame_size = self.config.samples_per_frame();
-
-        // Accumulate data from receiver
-        while let Ok(data) = self.receiver.try_recv() {
-            if self.needs_resampling {
-                if let Some(ref resampler) = self.resampler {
-                    // Convert i16 to f32 for resampling
-                    let f32_samples: Vec<f32> =
-                        data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-
-                    self.resample_input_buffer.extend(f32_samples);
-
-                    // Process resampling when we have enough input
-                    let mut resampler = resampler.lock().unwrap();
-                    let input_frames_needed = resampler.input_frames_next();
-
-                    while self.resample_input_buffer.len() >= input_frames_needed {
-                        // Prepare input for resampler (mono channel)
-                        let input_chunk: Vec<f32> = self
-                            .resample_input_buffer
-                            .drain(..input_frames_needed)
-                            .collect();
-
-                        // Resample (single channel)
-                        let input_vec = vec![input_chunk];
-                        match resampler.process(&input_vec, None) {
-                            Ok(output_vec) => {
-                                // Convert resampled f32 back to i16
-                                let resampled_i16: Vec<i16> = output_vec[0]
-                                    .iter()
-                                    .map(|&s| {
-                                        (s * i16::MAX as f32)
-                                            .clamp(i16::MIN as f32, i16::MAX as f32)
-                                            as i16
-                                    })
-                                    .collect();
-
-                                self.buffer.extend(resampled_i16);
-                            }
-                            Err(e) => {
-                                log::error!("Resampling error: {}", e);
-                            }
-                        }
-                    }
-                } else {
-                    // Fallback if resampler not available
-                    self.buffer.extend_from_slice(&data);
-                }
-            } else {
-                // No resampling needed, pass through
-                self.buffer.extend_from_slice(&data);
+    fn next_frame(&mut self, timeout: Duration) -> Option<Vec<i16>> {
+        let frame_size = self.config.samples_per_hop();
+        let deadline = Instant::now() + timeout;
+
+        // Wait for the callback until a whole frame is buffered
+        loop {
+            self.drain_queue();
+            if self.buffer.len() >= frame_size {
+                break;
+            }
+            if !self.queue.wait_until(deadline) {
+                return None;
             }
         }
 
-        // Return frame if we have enough data
-        if self.buffer.len() >= frame_size {
-            let frame = self.buffer.drain(..frame_size).collect();
-            Some(frame)
-        } else {
-            None
+        let mut frame: Vec<f32> = self.buffer.drain(..frame_size).collect();
+
+        // The frame ends before whatever is still buffered behind it
+        let pending_input = self
+            .resampler
+            .as_ref()
+            .map_or(0, |r| (r.pending() as f64 * r.ratio()) as u64);
+        let behind = self.buffer.len() as u64 + pending_input;
+        self.frame_captured_at = self.last_arrival.map(|arrival| {
+            let behind = Duration::from_secs_f64(behind as f64 / self.config.sample_rate_hz as f64);
+            arrival.checked_sub(behind).unwrap_or(arrival)
+        });
+        if let (Some(metrics), Some(captured_at)) = (&self.metrics, self.frame_captured_at) {
+            metrics.record(Stage::Capture, captured_at.elapsed());
+        }
+
+        let started = Instant::now();
+        if let (Some(canceller), Some(arrival)) = (self.echo_canceller.as_mut(), self.last_arrival)
+        {
+            let end = FarEnd::global().position(arrival).saturating_sub(behind);
+            canceller.process(&mut frame, end);
+        }
+        self.dsp.process(&mut frame);
+        if let Some(metrics) = &self.metrics {
+            metrics.record(Stage::Preprocess, started.elapsed());
         }
+        let frame: Vec<i16> = frame
+            .iter()
+            .map(|x| (x * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+            .collect();
+        self.record(|recorder| recorder.processed(&frame));
+        if let Some(tap) = &self.tap {
+            if let Err(TrySendError::Disconnected(_)) = tap.try_send(frame.clone()) {
+                self.tap = None;
+            }
+        }
+        Some(frame)
+    }
+
+    fn captured_at(&self) -> Option<Instant> {
+        self.frame_captured_at
     }
 
     fn sample_rate(&self) -> u32 {
@@ -698,7 +932,7 @@ impl AudioSource for AudioCapture {
     }
 
     fn frame_size(&self) -> usize {
-        self.config.samples_per_frame()
+        self.config.samples_per_hop()
     }
 }
 
@@ -712,5 +946,93 @@ mod tests {
         assert_eq!(config.sample_rate_hz, 16000);
         assert_eq!(config.samples_per_frame(), 320); // 20ms @ 16kHz
         assert_eq!(config.samples_per_hop(), 160); // 10ms @ 16kHz
+        assert_eq!(config.aec_state(), AecState::Active);
+    }
+
+    #[test]
+    fn test_aec_off_at_other_rates_is_reported() {
+        let config = AudioConfig {
+            sample_rate_hz: 48000,
+            ..AudioConfig::default()
+        };
+        assert_eq!(config.aec_state(), AecState::UnsupportedRate);
+        assert_eq!(
+            serde_json::to_value(AudioDebugInfo::from_config(&config).echo_cancellation).unwrap(),
+            "unsupported_rate"
+        );
+    }
+
+    #[test]
+    fn test_capture_saves_clips_through_mark_clip() {
+        let dir = std::env::temp_dir().join(format!("ember-capture-clip-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = AudioConfig {
+            recorder: RecorderConfig {
+                enabled: true,
+                clip_pre_ms: 100,
+                clip_post_ms: 50,
+                raw: true,
+                ..RecorderConfig::default()
+            },
+            aec: AecConfig {
+                enabled: false,
+                ..AecConfig::default()
+            },
+            ..AudioConfig::default()
+        };
+        let (mut writer, reader) = queue::channel(48000);
+        let mut capture = AudioCapture::from_queue(config, reader, 48000, 1)
+            .unwrap()
+            .with_recorder(&dir);
+
+        // 300 ms of tone at the device rate, resampled to 10 ms chunks
+        let tone: Vec<f32> = (0..14400)
+            .map(|i| 0.5 * (i as f32 * 440.0 * std::f32::consts::TAU / 48000.0).sin())
+            .collect();
+        writer.write(&tone);
+        for chunk in 0..25 {
+            let frame = capture.next_frame(Duration::from_millis(100)).unwrap();
+            assert_eq!(frame.len(), 160);
+            if chunk == 19 {
+                capture.mark_clip(ClipReason::Detection);
+            }
+        }
+        drop(capture);
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert!(names[0].starts_with("detection-") && names[0].ends_with("-16000hz.wav"));
+        assert!(names[1].starts_with("raw-") && names[1].ends_with("-48000hz.wav"));
+
+        // 100 ms before the mark and 50 ms after, cut from the processed stream
+        let clip = hound::WavReader::open(dir.join(&names[0])).unwrap();
+        assert_eq!(clip.spec().sample_rate, 16000);
+        assert_eq!(clip.len(), 2400);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tap_gets_processed_chunks_without_blocking_capture() {
+        let (mut writer, reader) = queue::channel(16000);
+        let (tap, chunks) = crossbeam_channel::bounded(2);
+        let mut capture = AudioCapture::from_queue(AudioConfig::default(), reader, 16000, 1)
+            .unwrap()
+            .with_tap(Some(tap));
+
+        writer.write(&[0.25; 800]);
+        let first = capture.next_frame(Duration::from_millis(100)).unwrap();
+        for _ in 0..3 {
+            capture.next_frame(Duration::from_millis(100)).unwrap();
+        }
+        assert_eq!(chunks.len(), 2, "full tap drops chunks");
+        assert_eq!(chunks.recv().unwrap(), first);
+
+        drop(chunks);
+        assert!(capture.next_frame(Duration::from_millis(100)).is_some());
+        assert!(capture.tap.is_none(), "closed tap is let go");
     }
 }
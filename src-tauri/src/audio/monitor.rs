@@ -4,9 +4,12 @@
 //! at a safe, low gain level. Includes automatic feedback prevention when
 //! input and output devices are the same.
 
-use anyhow::{Context, Result};
+use super::aec::{FarEnd, FarEndWriter};
+use super::AudioConfig;
+use crate::error::CommandError;
+use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, Stream};
+use cpal::{Sample, SampleFormat, Stream};
 use crossbeam_channel::{bounded, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -19,28 +22,29 @@ pub struct StopMonitor;
 pub struct MicMonitor {
     stop_tx: Sender<StopMonitor>,
     _thread_handle: Option<thread::JoinHandle<()>>,
+    output_fallback: bool,
 }
 
 impl MicMonitor {
-    /// Start monitoring from input device to output device
+    /// Start monitoring from the configured input device to the configured output device
+    ///
+    /// Devices are resolved like for capture (stable ID, then name); one that
+    /// is unplugged is replaced by the system default.
     ///
     /// # Arguments
-    /// * `input_device_name` - Optional input device name
-    /// * `output_device_name` - Optional output device name
+    /// * `audio` - Audio config holding the device choices
     /// * `gain` - Monitor gain level (0.0-1.0, clamped to 0.0-0.5 for safety)
     ///
     /// # Safety
     /// If input and output device names match, monitoring is disabled to prevent feedback
-    pub fn start(
-        input_device_name: Option<String>,
-        output_device_name: Option<String>,
-        gain: f32,
-    ) -> Result<Self> {
+    pub fn start(audio: &AudioConfig, gain: f32) -> Result<Self> {
         // Safety: prevent feedback loop
-        if input_device_name == output_device_name && input_device_name.is_some() {
-            anyhow::bail!(
+        if audio.device_name == audio.output_device_name && audio.device_name.is_some() {
+            return Err(CommandError::InvalidState(
                 "Cannot monitor when input and output are the same device (feedback prevention)"
-            );
+                    .to_string(),
+            )
+            .into());
         }
 
         // Clamp gain to safe range (max 0.5 to prevent distortion/feedback)
@@ -48,13 +52,21 @@ impl MicMonitor {
 
         log::info!("Starting mic monitor with gain={:.2}", gain);
 
+        let output_configured =
+            audio.stable_output_id.is_some() || audio.output_device_name.is_some();
+        let output_fallback = output_configured
+            && !super::check_output_device_exists(
+                audio.stable_output_id.as_ref(),
+                audio.output_device_name.as_deref(),
+            );
+
         // Create stop channel
         let (stop_tx, stop_rx) = bounded::<StopMonitor>(1);
 
         // Spawn monitoring thread
+        let audio = audio.clone();
         let thread_handle = thread::spawn(move || {
-            if let Err(e) = run_monitor_worker(input_device_name, output_device_name, gain, stop_rx)
-            {
+            if let Err(e) = run_monitor_worker(&audio, gain, stop_rx) {
                 log::error!("Mic monitor worker error: {}", e);
             }
         });
@@ -64,9 +76,15 @@ impl MicMonitor {
         Ok(Self {
             stop_tx,
             _thread_handle: Some(thread_handle),
+            output_fallback,
         })
     }
 
+    /// The configured output device is missing and the default plays instead
+    pub fn output_fallback(&self) -> bool {
+        self.output_fallback
+    }
+
     /// Stop the monitor
     pub fn stop(self) {
         log::info!("Stopping mic monitor...");
@@ -78,32 +96,22 @@ impl MicMonitor {
 
 /// Monitor worker function that runs in a dedicated thread
 fn run_monitor_worker(
-    input_device_name: Option<String>,
-    output_device_name: Option<String>,
+    audio: &AudioConfig,
     gain: f32,
     stop_rx: Receiver<StopMonitor>,
 ) -> Result<()> {
-    let host = cpal::default_host();
-
-    // Get input device
-    let input_device = if let Some(ref name) = input_device_name {
-        host.input_devices()?
-            .find(|d| d.name().ok().as_deref() == Some(name.as_str()))
-            .with_context(|| format!("Input device not found: {}", name))?
-    } else {
-        host.default_input_device()
-            .context("No default input device")?
-    };
-
-    // Get output device
-    let output_device = if let Some(ref name) = output_device_name {
-        host.output_devices()?
-            .find(|d| d.name().ok().as_deref() == Some(name.as_str()))
-            .with_context(|| format!("Output device not found: {}", name))?
-    } else {
-        host.default_output_device()
-            .context("No default output device")?
-    };
+    // Get devices; one that was unplugged is replaced by the default, like for capture
+    let input_device = super::resolve_preferred_input_device(
+        audio.stable_input_id.as_ref(),
+        audio.device_name.as_deref(),
+    )?
+    .or_else(|| cpal::default_host().default_input_device())
+    .ok_or_else(|| CommandError::NoDevice("no default input device".to_string()))?;
+    let output_device = super::open_output_device(
+        audio.stable_output_id.as_ref(),
+        audio.output_device_name.as_deref(),
+    )?
+    .device;
 
     log::info!(
         "Monitor: {} -> {}",
@@ -122,7 +130,9 @@ fn run_monitor_worker(
         SampleFormat::F32 => build_input_stream_f32(&input_device, input_config, buffer_in)?,
         SampleFormat::I16 => build_input_stream_i16(&input_device, input_config, buffer_in)?,
         SampleFormat::U16 => build_input_stream_u16(&input_device, input_config, buffer_in)?,
-        _ => anyhow::bail!("Unsupported input sample format"),
+        format => {
+            return Err(CommandError::UnsupportedFormat(format!("input {:?}", format)).into())
+        }
     };
 
     // Build output stream
@@ -149,7 +159,9 @@ fn run_monitor_worker(
             gain,
             stop_rx.clone(),
         )?,
-        _ => anyhow::bail!("Unsupported output sample format"),
+        format => {
+            return Err(CommandError::UnsupportedFormat(format!("output {:?}", format)).into())
+        }
     };
 
     // Start streams
@@ -262,6 +274,8 @@ fn build_output_stream_f32(
     stop_rx: Receiver<StopMonitor>,
 ) -> Result<Stream> {
     let channels = config.channels() as usize;
+    // Monitored audio reaches the mic again; echo cancellation subtracts it
+    let mut reference = FarEndWriter::new(FarEnd::global(), config.sample_rate().0, channels);
     let stream = device.build_output_stream(
         &config.config(),
         move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
@@ -282,6 +296,7 @@ fn build_output_stream_f32(
                     *s = sample;
                 }
             }
+            reference.write(data.iter().map(|&s| s.to_sample::<f32>()));
         },
         |err| log::error!("Monitor output error: {}", err),
         None,
@@ -297,6 +312,7 @@ fn build_output_stream_i16(
     stop_rx: Receiver<StopMonitor>,
 ) -> Result<Stream> {
     let channels = config.channels() as usize;
+    let mut reference = FarEndWriter::new(FarEnd::global(), config.sample_rate().0, channels);
     let stream = device.build_output_stream(
         &config.config(),
         move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
@@ -316,6 +332,7 @@ fn build_output_stream_i16(
                     *s = sample;
                 }
             }
+            reference.write(data.iter().map(|&s| s.to_sample::<f32>()));
         },
         |err| log::error!("Monitor output error: {}", err),
         None,
@@ -331,6 +348,7 @@ fn build_output_stream_u16(
     stop_rx: Receiver<StopMonitor>,
 ) -> Result<Stream> {
     let channels = config.channels() as usize;
+    let mut reference = FarEndWriter::new(FarEnd::global(), config.sample_rate().0, channels);
     let stream = device.build_output_stream(
         &config.config(),
         move |data: &mut [u16], _: &cpal::OutputCallbackInfo| {
@@ -351,6 +369,7 @@ fn build_output_stream_u16(
                     *s = sample;
                 }
             }
+            reference.write(data.iter().map(|&s| s.to_sample::<f32>()));
         },
         |err| log::error!("Monitor output error: {}", err),
         None,
@@ -5,7 +5,8 @@
 //! - Compare RMS levels across multiple devices
 //! - Suggest the best input device based on actual audio activity
 
-use anyhow::{Context, Result};
+use crate::error::CommandError;
+use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{SampleFormat, StreamConfig};
 use serde::Serialize;
@@ -34,10 +35,10 @@ pub fn probe_current_device(device_name: Option<&str>, duration_ms: u64) -> Resu
     let device = if let Some(name) = device_name {
         host.input_devices()?
             .find(|d| d.name().map(|n| n == name).unwrap_or(false))
-            .with_context(|| format!("Device not found: {}", name))?
+            .ok_or_else(|| CommandError::DeviceNotFound(name.to_string()))?
     } else {
         host.default_input_device()
-            .context("No default input device")?
+            .ok_or_else(|| CommandError::NoDevice("no default input device".to_string()))?
     };
 
     probe_device(&device, duration_ms)
@@ -62,7 +63,7 @@ fn probe_device(device: &cpal::Device, duration_ms: u64) -> Result<f32> {
         SampleFormat::U16 => {
             build_probe_stream_u16(device, config.into(), peak_rms_clone, sample_rate)?
         }
-        format => anyhow::bail!("Unsupported format: {:?}", format),
+        format => return Err(CommandError::UnsupportedFormat(format!("{:?}", format)).into()),
     };
 
     stream.play()?;
@@ -0,0 +1,248 @@
+//! Bounded queue between the capture callback and the audio worker
+//!
+//! A lock-free ring of mono f32 samples at the device rate. The callback never
+//! blocks: when the worker stalls and the ring is full, new samples are
+//! dropped and counted as an overrun instead of piling up in memory. The
+//! worker waits on the ring (parked, woken by the callback) rather than
+//! polling, and counts an underrun whenever a wait times out without audio.
+
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{Thread, ThreadId};
+use std::time::{Duration, Instant};
+
+/// Audio the ring holds before the callback starts dropping samples
+pub const CAPACITY_MS: u32 = 2000;
+
+/// Overrun and underrun counts over all captures since startup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct QueueStats {
+    /// Callbacks that found the ring full (worker stalled)
+    pub overruns: u64,
+    /// Samples dropped by those callbacks
+    pub dropped_samples: u64,
+    /// Waits for audio that timed out (device stopped delivering)
+    pub underruns: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    overruns: AtomicU64,
+    dropped_samples: AtomicU64,
+    underruns: AtomicU64,
+}
+
+fn counters() -> &'static Counters {
+    static COUNTERS: OnceLock<Counters> = OnceLock::new();
+    COUNTERS.get_or_init(Default::default)
+}
+
+/// Current counts for `get_audio_snapshot`
+pub fn stats() -> QueueStats {
+    let counters = counters();
+    QueueStats {
+        overruns: counters.overruns.load(Ordering::Relaxed),
+        dropped_samples: counters.dropped_samples.load(Ordering::Relaxed),
+        underruns: counters.underruns.load(Ordering::Relaxed),
+    }
+}
+
+struct Shared {
+    sample_rate: u32,
+    start: Instant,
+    /// Samples pushed so far
+    written: AtomicU64,
+    /// When the latest push happened (ns since `start`)
+    last_write_ns: AtomicU64,
+    /// The thread in [`QueueReader::wait_until`], replaced when the reader
+    /// moves; the callback only try-locks it
+    reader: Mutex<Option<Thread>>,
+}
+
+/// Ring for mono samples at `sample_rate`, holding [`CAPACITY_MS`] of audio
+pub fn channel(sample_rate: u32) -> (QueueWriter, QueueReader) {
+    let capacity = (sample_rate as usize * CAPACITY_MS as usize / 1000).max(1);
+    let (producer, consumer) = HeapRb::new(capacity).split();
+    let shared = Arc::new(Shared {
+        sample_rate,
+        start: Instant::now(),
+        written: AtomicU64::new(0),
+        last_write_ns: AtomicU64::new(0),
+        reader: Mutex::new(None),
+    });
+    (
+        QueueWriter {
+            producer,
+            shared: shared.clone(),
+        },
+        QueueReader {
+            consumer,
+            shared,
+            read: 0,
+            waiter: None,
+        },
+    )
+}
+
+/// Capture callback side
+pub struct QueueWriter {
+    producer: HeapProd<f32>,
+    shared: Arc<Shared>,
+}
+
+impl QueueWriter {
+    /// Push without blocking; what doesn't fit is dropped and counted
+    pub fn write(&mut self, samples: &[f32]) {
+        let pushed = self.producer.push_slice(samples);
+        if pushed < samples.len() {
+            let counters = counters();
+            counters.overruns.fetch_add(1, Ordering::Relaxed);
+            counters
+                .dropped_samples
+                .fetch_add((samples.len() - pushed) as u64, Ordering::Relaxed);
+        }
+
+        let now = self.shared.start.elapsed().as_nanos() as u64;
+        self.shared.last_write_ns.store(now, Ordering::Relaxed);
+        self.shared
+            .written
+            .fetch_add(pushed as u64, Ordering::Release);
+        // Skipped while the reader is registering a new thread: it checks the
+        // ring before parking, and parks with a timeout
+        if let Ok(reader) = self.shared.reader.try_lock() {
+            if let Some(reader) = reader.as_ref() {
+                reader.unpark();
+            }
+        }
+    }
+}
+
+/// Worker side
+pub struct QueueReader {
+    consumer: HeapCons<f32>,
+    shared: Arc<Shared>,
+    /// Samples popped so far
+    read: u64,
+    /// Thread registered in `Shared::reader`
+    waiter: Option<ThreadId>,
+}
+
+impl QueueReader {
+    /// Wait until samples are queued; false (an underrun) if none came by `deadline`
+    pub fn wait_until(&mut self, deadline: Instant) -> bool {
+        let current = std::thread::current();
+        if self.waiter != Some(current.id()) {
+            self.waiter = Some(current.id());
+            *self.shared.reader.lock().unwrap() = Some(current);
+        }
+        while self.consumer.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                counters().underruns.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            std::thread::park_timeout(deadline - now);
+        }
+        true
+    }
+
+    /// Take everything queued, with the capture time of its last sample
+    ///
+    /// None when the queue is empty.
+    pub fn read(&mut self) -> Option<(Instant, Vec<f32>)> {
+        let mut samples = vec![0.0; self.consumer.occupied_len()];
+        if samples.is_empty() {
+            return None;
+        }
+        let popped = self.consumer.pop_slice(&mut samples);
+        samples.truncate(popped);
+        self.read += popped as u64;
+
+        // The latest push ended at `written`; samples before it are older by
+        // one sample period each
+        let written = self.shared.written.load(Ordering::Acquire);
+        let last_write = Duration::from_nanos(self.shared.last_write_ns.load(Ordering::Relaxed));
+        let behind = Duration::from_secs_f64(
+            written.saturating_sub(self.read) as f64 / self.shared.sample_rate as f64,
+        );
+        let captured = (self.shared.start + last_write)
+            .checked_sub(behind)
+            .unwrap_or(self.shared.start);
+        Some((captured, samples))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passes_samples_in_order() {
+        let (mut writer, mut reader) = channel(16000);
+        assert!(reader.read().is_none());
+        writer.write(&[0.1, 0.2, 0.3]);
+        writer.write(&[0.4]);
+        let (_, samples) = reader.read().unwrap();
+        assert_eq!(samples, [0.1, 0.2, 0.3, 0.4]);
+    }
+
+    #[test]
+    fn test_full_ring_drops_and_counts_instead_of_growing() {
+        let (mut writer, mut reader) = channel(1000); // 2000 samples
+        let before = stats();
+        writer.write(&[0.7; 1500]);
+        writer.write(&[0.8; 1500]);
+
+        let after = stats();
+        assert!(after.overruns > before.overruns);
+        assert!(after.dropped_samples >= before.dropped_samples + 1000);
+        let (_, samples) = reader.read().unwrap();
+        assert_eq!(samples.len(), 2000);
+        assert_eq!(samples[1999], 0.8);
+    }
+
+    #[test]
+    fn test_wait_wakes_on_write_and_times_out_as_underrun() {
+        let (mut writer, mut reader) = channel(16000);
+        let before = stats().underruns;
+        assert!(!reader.wait_until(Instant::now() + Duration::from_millis(20)));
+        assert!(stats().underruns > before);
+
+        let started = Instant::now();
+        let producer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(30));
+            writer.write(&[0.1; 160]);
+        });
+        assert!(reader.wait_until(Instant::now() + Duration::from_secs(5)));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn test_wait_wakes_reader_moved_to_another_thread() {
+        let (mut writer, mut reader) = channel(16000);
+        assert!(!reader.wait_until(Instant::now()));
+
+        let started = Instant::now();
+        let waiter =
+            std::thread::spawn(move || reader.wait_until(Instant::now() + Duration::from_secs(5)));
+        std::thread::sleep(Duration::from_millis(30));
+        writer.write(&[0.1; 160]);
+        assert!(waiter.join().unwrap());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_capture_time_accounts_for_samples_still_queued() {
+        let (mut writer, mut reader) = channel(1000);
+        writer.write(&[0.0; 500]);
+        let written_at = Instant::now();
+        let (captured, _) = reader.read().unwrap();
+        assert!(captured <= written_at);
+        assert!(written_at.duration_since(captured) < Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,487 @@
+//! Debug recordings of the live pipeline (`[audio.recorder]`, opt-in)
+//!
+//! For reports like "it triggered on nothing". With `enabled` set, the wake
+//! word capture keeps a clip of the processed audio around every detection
+//! and near miss; `continuous` also keeps the whole processed stream in
+//! rotating files, and `raw` the device audio before resampling and
+//! pre-processing.
+//!
+//! Everything is written to `<cache>/tmp_audio` (private to the user) and
+//! never leaves the machine. Files older than `retention_hours` are deleted,
+//! then the oldest ones until the directory is under `max_total_mb`.
+
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the directory is checked against the caps while recording
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Debug recorder settings (`[audio.recorder]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecorderConfig {
+    /// Explicit opt-in; nothing is recorded while false
+    pub enabled: bool,
+    /// Keep the whole processed stream, not just clips
+    pub continuous: bool,
+    /// Also keep device audio (device rate, after the channel mix)
+    pub raw: bool,
+    /// Length of each continuous file (10-3600 s)
+    pub segment_secs: u32,
+    /// Audio kept before a detection or near miss (0-10000 ms)
+    pub clip_pre_ms: u32,
+    /// Audio kept after it (0-10000 ms)
+    pub clip_post_ms: u32,
+    /// Cap on all recordings together (1-10000 MB)
+    pub max_total_mb: u32,
+    /// Recordings older than this are deleted (1-720 h)
+    pub retention_hours: u32,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            continuous: false,
+            raw: false,
+            segment_secs: 300,
+            clip_pre_ms: 3000,
+            clip_post_ms: 1000,
+            max_total_mb: 500,
+            retention_hours: 72,
+        }
+    }
+}
+
+/// Why a clip was saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipReason {
+    /// The wake word was reported
+    Detection,
+    /// Something came close without triggering
+    NearMiss,
+}
+
+impl ClipReason {
+    fn prefix(self) -> &'static str {
+        match self {
+            ClipReason::Detection => "detection",
+            ClipReason::NearMiss => "near_miss",
+        }
+    }
+}
+
+type Writer = WavWriter<BufWriter<File>>;
+
+/// A WAV file being written
+struct Recording {
+    path: PathBuf,
+    writer: Writer,
+    /// Samples still to write (clips) or until rotation (continuous files)
+    remaining: u64,
+}
+
+impl Recording {
+    fn create(path: PathBuf, rate: u32, float: bool, samples: u64) -> Result<Self> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: rate,
+            bits_per_sample: if float { 32 } else { 16 },
+            sample_format: if float {
+                SampleFormat::Float
+            } else {
+                SampleFormat::Int
+            },
+        };
+        Ok(Self {
+            writer: WavWriter::new(BufWriter::new(file), spec)?,
+            path,
+            remaining: samples,
+        })
+    }
+
+    /// Write up to `remaining` samples; true once the file is complete
+    fn write<S: hound::Sample + Copy>(&mut self, samples: &[S]) -> Result<bool> {
+        let take = samples.len().min(self.remaining as usize);
+        for &sample in &samples[..take] {
+            self.writer.write_sample(sample)?;
+        }
+        self.remaining -= take as u64;
+        Ok(self.remaining == 0)
+    }
+
+    fn finish(self) -> Result<()> {
+        self.writer
+            .finalize()
+            .with_context(|| format!("Failed to finish {}", self.path.display()))
+    }
+}
+
+/// Records one capture into `dir` as configured
+pub struct DebugRecorder {
+    config: RecorderConfig,
+    dir: PathBuf,
+    rate: u32,
+    raw_rate: u32,
+    /// The last `clip_pre_ms` of processed audio
+    history: VecDeque<i16>,
+    history_len: usize,
+    /// Clips still collecting their post-roll
+    clips: Vec<(ClipReason, Recording)>,
+    stream: Option<Recording>,
+    raw: Option<Recording>,
+    last_cleanup: Instant,
+}
+
+impl DebugRecorder {
+    /// Recorder for processed audio at `rate` and device audio at `raw_rate`
+    ///
+    /// Applies the retention and size caps to `dir` right away.
+    pub fn new(config: &RecorderConfig, dir: &Path, rate: u32, raw_rate: u32) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        enforce_limits(dir, config, &[])?;
+        log::info!(
+            "Debug recorder writing to {} (clips{}{})",
+            dir.display(),
+            if config.continuous {
+                ", continuous"
+            } else {
+                ""
+            },
+            if config.raw { ", raw" } else { "" }
+        );
+
+        let history_len = (rate as u64 * config.clip_pre_ms as u64 / 1000) as usize;
+        Ok(Self {
+            config: config.clone(),
+            dir: dir.to_path_buf(),
+            rate,
+            raw_rate,
+            history: VecDeque::with_capacity(history_len),
+            history_len,
+            clips: Vec::new(),
+            stream: None,
+            raw: None,
+            last_cleanup: Instant::now(),
+        })
+    }
+
+    /// Processed audio, in order
+    pub fn processed(&mut self, samples: &[i16]) -> Result<()> {
+        let mut finished = Vec::new();
+        for (i, (_, clip)) in self.clips.iter_mut().enumerate() {
+            if clip.write(samples)? {
+                finished.push(i);
+            }
+        }
+        for i in finished.into_iter().rev() {
+            let (_, clip) = self.clips.remove(i);
+            log::info!("Debug clip saved: {}", clip.path.display());
+            clip.finish()?;
+        }
+
+        self.history.extend(samples);
+        let excess = self.history.len().saturating_sub(self.history_len);
+        self.history.drain(..excess);
+
+        if self.config.continuous {
+            let (dir, rate) = (&self.dir, self.rate);
+            let segment = rate as u64 * self.config.segment_secs as u64;
+            let rotated = write_segmented(&mut self.stream, samples, || {
+                Recording::create(recording_path(dir, "stream", rate), rate, false, segment)
+            })?;
+            if rotated {
+                self.cleanup()?;
+            }
+        }
+
+        if self.last_cleanup.elapsed() >= CLEANUP_INTERVAL {
+            self.cleanup()?;
+        }
+        Ok(())
+    }
+
+    /// Device audio (mono, device rate), in order
+    pub fn raw(&mut self, samples: &[f32]) -> Result<()> {
+        if !self.config.raw {
+            return Ok(());
+        }
+        let (dir, rate) = (&self.dir, self.raw_rate);
+        let segment = rate as u64 * self.config.segment_secs as u64;
+        write_segmented(&mut self.raw, samples, || {
+            Recording::create(recording_path(dir, "raw", rate), rate, true, segment)
+        })?;
+        Ok(())
+    }
+
+    /// Save a clip from `clip_pre_ms` before now to `clip_post_ms` after
+    ///
+    /// Ignored while a clip for the same reason is still being recorded.
+    pub fn mark(&mut self, reason: ClipReason) -> Result<()> {
+        if self.clips.iter().any(|(open, _)| *open == reason) {
+            return Ok(());
+        }
+        let post = self.rate as u64 * self.config.clip_post_ms as u64 / 1000;
+        let history: Vec<i16> = self.history.iter().copied().collect();
+        let path = recording_path(&self.dir, reason.prefix(), self.rate);
+        let mut clip = Recording::create(path, self.rate, false, history.len() as u64 + post)?;
+        if clip.write(&history)? {
+            log::info!("Debug clip saved: {}", clip.path.display());
+            clip.finish()?;
+        } else {
+            self.clips.push((reason, clip));
+        }
+        self.cleanup()
+    }
+
+    /// Apply the caps, sparing the files being written
+    fn cleanup(&mut self) -> Result<()> {
+        self.last_cleanup = Instant::now();
+        let open: Vec<&Path> = self
+            .clips
+            .iter()
+            .map(|(_, clip)| clip)
+            .chain(&self.stream)
+            .chain(&self.raw)
+            .map(|recording| recording.path.as_path())
+            .collect();
+        enforce_limits(&self.dir, &self.config, &open)
+    }
+}
+
+/// `<prefix>-<local time>-<rate>hz.wav` in `dir`
+fn recording_path(dir: &Path, prefix: &str, rate: u32) -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+    dir.join(format!("{}-{}-{}hz.wav", prefix, stamp, rate))
+}
+
+/// Write to a continuous file, starting the next one whenever it fills up;
+/// true if a file was completed
+fn write_segmented<S: hound::Sample + Copy>(
+    slot: &mut Option<Recording>,
+    mut samples: &[S],
+    mut create: impl FnMut() -> Result<Recording>,
+) -> Result<bool> {
+    let mut rotated = false;
+    while !samples.is_empty() {
+        let recording = match slot {
+            Some(recording) => recording,
+            None => slot.insert(create()?),
+        };
+        let take = samples.len().min(recording.remaining as usize);
+        if recording.write(&samples[..take])? {
+            if let Some(done) = slot.take() {
+                done.finish()?;
+            }
+            rotated = true;
+        }
+        samples = &samples[take..];
+    }
+    Ok(rotated)
+}
+
+/// Delete recordings past the retention period, then the oldest until the
+/// rest fit in `max_total_mb`
+fn enforce_limits(dir: &Path, config: &RecorderConfig, open: &[&Path]) -> Result<()> {
+    let retention = Duration::from_secs(config.retention_hours as u64 * 3600);
+    let cap = config.max_total_mb as u64 * 1024 * 1024;
+    let now = SystemTime::now();
+
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to list {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "wav") {
+            continue;
+        }
+        let meta = fs::metadata(&path)?;
+        let modified = meta.modified().unwrap_or(now);
+        if open.contains(&path.as_path()) {
+            // Still growing; counts toward the cap but is never deleted
+            files.push((now, meta.len(), path));
+        } else if now.duration_since(modified).unwrap_or_default() > retention {
+            log::debug!("Deleting expired recording {}", path.display());
+            fs::remove_file(&path)?;
+        } else {
+            files.push((modified, meta.len(), path));
+        }
+    }
+
+    files.sort();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    for (_, len, path) in files {
+        if total <= cap {
+            break;
+        }
+        if open.contains(&path.as_path()) {
+            continue;
+        }
+        log::debug!("Deleting recording {} (size cap)", path.display());
+        fs::remove_file(&path)?;
+        total -= len;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ember-recorder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn wavs(dir: &Path, prefix: &str) -> Vec<Vec<i16>> {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.file_name().unwrap().to_string_lossy().starts_with(prefix))
+            .collect();
+        paths.sort();
+        paths
+            .iter()
+            .map(|p| {
+                hound::WavReader::open(p)
+                    .unwrap()
+                    .samples::<i16>()
+                    .map(Result::unwrap)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn config() -> RecorderConfig {
+        RecorderConfig {
+            enabled: true,
+            clip_pre_ms: 100,
+            clip_post_ms: 50,
+            ..RecorderConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_clip_spans_pre_and_post_roll() {
+        let dir = temp_dir("clip");
+        let mut recorder = DebugRecorder::new(&config(), &dir, RATE, 48000).unwrap();
+        let ramp: Vec<i16> = (0..8000).map(|i| i as i16).collect();
+        for chunk in ramp[..4000].chunks(160) {
+            recorder.processed(chunk).unwrap();
+        }
+        recorder.mark(ClipReason::NearMiss).unwrap();
+        recorder.processed(&ramp[4000..4160]).unwrap();
+        recorder.mark(ClipReason::NearMiss).unwrap();
+        for chunk in ramp[4160..].chunks(160) {
+            recorder.processed(chunk).unwrap();
+        }
+
+        let clips = wavs(&dir, "near_miss-");
+        assert_eq!(clips.len(), 1, "second mark fell inside the first clip");
+        // 100 ms before the mark, 50 ms after
+        let expected: Vec<i16> = (4000 - 1600..4000 + 800).map(|i| i as i16).collect();
+        assert_eq!(clips[0], expected);
+        assert!(wavs(&dir, "stream-").is_empty(), "continuous is off");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_continuous_stream_rotates() {
+        let dir = temp_dir("rotate");
+        let config = RecorderConfig {
+            continuous: true,
+            segment_secs: 1,
+            ..config()
+        };
+        let mut recorder = DebugRecorder::new(&config, &dir, RATE, RATE).unwrap();
+        // 30 ms chunks don't line up with the 1 s files
+        for k in 0..70 {
+            recorder.processed(&[k as i16; 480]).unwrap();
+            // Files are named by time; keep the rotations apart
+            std::thread::sleep(Duration::from_micros(100));
+        }
+        drop(recorder);
+
+        let segments = wavs(&dir, "stream-");
+        let lengths: Vec<usize> = segments.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [16000, 16000, 1600]);
+        // Chunk 33 straddles the first rotation and is split, not dropped
+        assert_eq!(&segments[0][15840..], &[33; 160][..]);
+        assert_eq!(&segments[1][..320], &[33; 320][..]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_size_cap_deletes_oldest_first() {
+        let dir = temp_dir("cap");
+        fs::create_dir_all(&dir).unwrap();
+        for (i, name) in ["a.wav", "b.wav", "c.wav"].iter().enumerate() {
+            fs::write(dir.join(name), vec![0u8; 600 * 1024]).unwrap();
+            let file = File::options().write(true).open(dir.join(name)).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(100 - i as u64 * 10))
+                .unwrap();
+        }
+        fs::write(dir.join("notes.txt"), vec![0u8; 4 * 1024 * 1024]).unwrap();
+
+        let config = RecorderConfig {
+            max_total_mb: 1,
+            ..config()
+        };
+        enforce_limits(&dir, &config, &[]).unwrap();
+        assert!(!dir.join("a.wav").exists());
+        assert!(!dir.join("b.wav").exists());
+        assert!(dir.join("c.wav").exists());
+        assert!(
+            dir.join("notes.txt").exists(),
+            "only recordings are managed"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retention_deletes_expired_but_not_open_files() {
+        let dir = temp_dir("retention");
+        fs::create_dir_all(&dir).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(3 * 3600);
+        for name in ["old.wav", "open.wav"] {
+            fs::write(dir.join(name), [0u8; 16]).unwrap();
+            File::options()
+                .write(true)
+                .open(dir.join(name))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+        }
+        fs::write(dir.join("new.wav"), [0u8; 16]).unwrap();
+
+        let config = RecorderConfig {
+            retention_hours: 1,
+            ..config()
+        };
+        let open = dir.join("open.wav");
+        enforce_limits(&dir, &config, &[open.as_path()]).unwrap();
+        assert!(!dir.join("old.wav").exists());
+        assert!(dir.join("open.wav").exists());
+        assert!(dir.join("new.wav").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,396 @@
+//! Device-rate to processing-rate conversion
+//!
+//! Takes the mono f32 signal at whatever rate the device runs (44.1, 22.05,
+//! 48 kHz, ...) in callback-sized pieces of any length and produces a
+//! continuous stream at the processing rate (`audio.resampler`):
+//!
+//! - `sinc`: band-limited sinc interpolation (default, best alias rejection)
+//! - `fft`: FFT-based synchronous resampling, cheaper for integer-ish ratios
+//! - `linear`: Sherpa-ONNX's streaming resampler (`kws_real` builds only;
+//!   other builds fall back to `sinc`)
+//!
+//! Input is buffered until the backend has a whole chunk, so any device rate
+//! works and the output count never drifts from `input * ratio` no matter how
+//! long the capture runs.
+
+use anyhow::Result;
+use rubato::{
+    calculate_cutoff, FftFixedInOut, Resampler, SincFixedIn, SincInterpolationParameters,
+    SincInterpolationType, WindowFunction,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Input per backend call (ms at the device rate)
+const CHUNK_MS: usize = 20;
+
+/// Sinc filter length (taps per side pair)
+const SINC_LEN: usize = 128;
+
+/// Resampler for devices that don't run at the processing rate (`audio.resampler`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResamplerKind {
+    /// Windowed sinc interpolation
+    #[default]
+    Sinc,
+    /// FFT overlap-add
+    Fft,
+    /// Sherpa-ONNX linear resampler
+    Linear,
+}
+
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Chunks dropped because the resampler failed, over all captures since startup
+pub fn errors() -> u64 {
+    ERRORS.load(Ordering::Relaxed)
+}
+
+enum Backend {
+    Sinc(SincFixedIn<f32>),
+    Fft(FftFixedInOut<f32>),
+    #[cfg(feature = "kws_real")]
+    Linear(sherpa::LinearResampler),
+}
+
+/// Streaming mono resampler accepting input of any length
+pub struct StreamResampler {
+    backend: Backend,
+    kind: ResamplerKind,
+    ratio: f64,
+    /// Input waiting for a whole chunk
+    pending: Vec<f32>,
+    /// Output of one chunk
+    scratch: Vec<f32>,
+}
+
+impl StreamResampler {
+    pub fn new(kind: ResamplerKind, from_rate: u32, to_rate: u32) -> Result<Self> {
+        let ratio = to_rate as f64 / from_rate as f64;
+        let chunk = (from_rate as usize * CHUNK_MS / 1000).max(1);
+
+        let (backend, kind) = match kind {
+            ResamplerKind::Fft => (
+                Backend::Fft(FftFixedInOut::new(
+                    from_rate as usize,
+                    to_rate as usize,
+                    chunk,
+                    1,
+                )?),
+                kind,
+            ),
+            #[cfg(feature = "kws_real")]
+            ResamplerKind::Linear => (
+                Backend::Linear(sherpa::LinearResampler::new(from_rate, to_rate)?),
+                kind,
+            ),
+            #[cfg(not(feature = "kws_real"))]
+            ResamplerKind::Linear => {
+                log::warn!("Linear resampler needs the kws_real build; using sinc");
+                (Self::sinc(ratio, chunk)?, ResamplerKind::Sinc)
+            }
+            ResamplerKind::Sinc => (Self::sinc(ratio, chunk)?, kind),
+        };
+
+        Ok(Self {
+            backend,
+            kind,
+            ratio,
+            pending: Vec::new(),
+            scratch: Vec::new(),
+        })
+    }
+
+    fn sinc(ratio: f64, chunk: usize) -> Result<Backend> {
+        let window = WindowFunction::BlackmanHarris2;
+        let parameters = SincInterpolationParameters {
+            sinc_len: SINC_LEN,
+            f_cutoff: calculate_cutoff(SINC_LEN, window),
+            oversampling_factor: 256,
+            interpolation: SincInterpolationType::Linear,
+            window,
+        };
+        Ok(Backend::Sinc(SincFixedIn::new(
+            ratio, 1.0, parameters, chunk, 1,
+        )?))
+    }
+
+    /// The backend in use (`sinc` when `linear` isn't available)
+    pub fn kind(&self) -> ResamplerKind {
+        self.kind
+    }
+
+    /// Input samples buffered but not yet resampled
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Resample `input`, appending whatever is ready to `output`
+    ///
+    /// On error the buffered input is dropped (counted in [`errors`]) and the
+    /// filter state reset, so the next call starts clean.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) -> Result<()> {
+        let result = self.run(input, output);
+        if result.is_err() {
+            ERRORS.fetch_add(1, Ordering::Relaxed);
+            self.pending.clear();
+            self.reset();
+        }
+        result
+    }
+
+    fn run(&mut self, input: &[f32], output: &mut Vec<f32>) -> Result<()> {
+        let resampler: &mut dyn ChunkResampler = match &mut self.backend {
+            Backend::Sinc(resampler) => resampler,
+            Backend::Fft(resampler) => resampler,
+            #[cfg(feature = "kws_real")]
+            Backend::Linear(resampler) => return resampler.process(input, output),
+        };
+
+        self.pending.extend_from_slice(input);
+        let mut start = 0;
+        while self.pending.len() - start >= resampler.input_frames_next() {
+            self.scratch.resize(resampler.output_frames_max(), 0.0);
+            let (used, written) =
+                resampler.process_chunk(&self.pending[start..], &mut self.scratch)?;
+            output.extend_from_slice(&self.scratch[..written]);
+            start += used;
+        }
+        self.pending.drain(..start);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        match &mut self.backend {
+            Backend::Sinc(resampler) => resampler.reset(),
+            Backend::Fft(resampler) => resampler.reset(),
+            #[cfg(feature = "kws_real")]
+            Backend::Linear(resampler) => resampler.reset(),
+        }
+    }
+
+    /// Output rate over input rate
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+}
+
+/// Mono view of the rubato resamplers (their trait has generic methods)
+trait ChunkResampler {
+    fn input_frames_next(&self) -> usize;
+    fn output_frames_max(&self) -> usize;
+    /// One chunk from the front of `input`: (input used, output written)
+    fn process_chunk(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize)>;
+}
+
+impl<R: Resampler<f32>> ChunkResampler for R {
+    fn input_frames_next(&self) -> usize {
+        Resampler::input_frames_next(self)
+    }
+
+    fn output_frames_max(&self) -> usize {
+        Resampler::output_frames_max(self)
+    }
+
+    fn process_chunk(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize)> {
+        let needed = Resampler::input_frames_next(self);
+        Ok(self.process_into_buffer(&[&input[..needed]], &mut [output], None)?)
+    }
+}
+
+#[cfg(feature = "kws_real")]
+mod sherpa {
+    use crate::ffi::sherpa_onnx_bindings::*;
+    use anyhow::{bail, Result};
+
+    /// Share of the lower Nyquist frequency the filter passes (as Sherpa's
+    /// own frontend configures it)
+    const CUTOFF: f32 = 0.99;
+
+    /// Zero crossings of the windowed sinc on each side
+    const NUM_ZEROS: i32 = 6;
+
+    /// Owned Sherpa-ONNX linear resampler (confined to the capture thread)
+    pub struct LinearResampler {
+        handle: *mut SherpaOnnxLinearResampler,
+    }
+
+    impl LinearResampler {
+        pub fn new(from_rate: u32, to_rate: u32) -> Result<Self> {
+            let cutoff = CUTOFF * 0.5 * from_rate.min(to_rate) as f32;
+            let handle = unsafe {
+                SherpaOnnxCreateLinearResampler(from_rate as i32, to_rate as i32, cutoff, NUM_ZEROS)
+            };
+            if handle.is_null() {
+                bail!(
+                    "Failed to create Sherpa-ONNX resampler {} -> {} Hz",
+                    from_rate,
+                    to_rate
+                );
+            }
+            Ok(Self { handle })
+        }
+
+        /// Streams without flushing; the resampler keeps its own history
+        pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) -> Result<()> {
+            if input.is_empty() {
+                return Ok(());
+            }
+            unsafe {
+                let result = SherpaOnnxLinearResamplerResample(
+                    self.handle,
+                    input.as_ptr(),
+                    input.len() as i32,
+                    0,
+                );
+                if result.is_null() {
+                    bail!("Sherpa-ONNX resampler returned no output");
+                }
+                let out = &*result;
+                if out.n > 0 && !out.samples.is_null() {
+                    output
+                        .extend_from_slice(std::slice::from_raw_parts(out.samples, out.n as usize));
+                }
+                SherpaOnnxLinearResamplerResampleFree(result);
+            }
+            Ok(())
+        }
+
+        pub fn reset(&mut self) {
+            unsafe { SherpaOnnxLinearResamplerReset(self.handle) };
+        }
+    }
+
+    impl Drop for LinearResampler {
+        fn drop(&mut self) {
+            unsafe { SherpaOnnxDestroyLinearResampler(self.handle) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const OUT_RATE: u32 = 16000;
+
+    fn sine(freq: f32, rate: u32, seconds: f32) -> Vec<f32> {
+        (0..(rate as f32 * seconds) as usize)
+            .map(|i| 0.5 * (2.0 * PI * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    /// Feed `input` in uneven callback-sized pieces, like a device would
+    fn stream(resampler: &mut StreamResampler, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::new();
+        let sizes = [441, 512, 97, 1024, 256, 1];
+        let mut rest = input;
+        for size in sizes.iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (piece, tail) = rest.split_at((*size).min(rest.len()));
+            resampler.process(piece, &mut output).unwrap();
+            rest = tail;
+        }
+        output
+    }
+
+    /// Amplitude of `freq` in `signal` (single-bin DFT)
+    fn amplitude(signal: &[f32], freq: f32, rate: u32) -> f32 {
+        let (mut re, mut im) = (0.0f64, 0.0f64);
+        for (i, &x) in signal.iter().enumerate() {
+            let phase = 2.0 * std::f64::consts::PI * freq as f64 * i as f64 / rate as f64;
+            re += x as f64 * phase.cos();
+            im += x as f64 * phase.sin();
+        }
+        (2.0 * (re * re + im * im).sqrt() / signal.len() as f64) as f32
+    }
+
+    /// Gain at `freq` through a `from` Hz -> 16 kHz resampler, in dB
+    fn gain_db(kind: ResamplerKind, from: u32, freq: f32) -> f32 {
+        let mut resampler = StreamResampler::new(kind, from, OUT_RATE).unwrap();
+        let output = stream(&mut resampler, &sine(freq, from, 1.0));
+        // Skip the filter's start-up transient
+        let settled = &output[OUT_RATE as usize / 4..];
+        // Aliases of tones above 8 kHz land on |freq - k * 16 kHz|
+        let alias = (freq - OUT_RATE as f32 * (freq / OUT_RATE as f32).round()).abs();
+        20.0 * (amplitude(settled, alias, OUT_RATE) / 0.5).log10()
+    }
+
+    #[test]
+    fn test_passband_tones_keep_their_level() {
+        for kind in [ResamplerKind::Sinc, ResamplerKind::Fft] {
+            for from in [44100, 22050, 48000, 8000] {
+                for freq in [300.0, 1000.0, 3400.0] {
+                    let gain = gain_db(kind, from, freq);
+                    assert!(
+                        gain.abs() < 0.5,
+                        "{:?} {} Hz: {} Hz at {:.2} dB",
+                        kind,
+                        from,
+                        freq,
+                        gain
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tones_above_output_nyquist_do_not_alias() {
+        for (kind, min_rejection) in [(ResamplerKind::Sinc, 60.0), (ResamplerKind::Fft, 40.0)] {
+            for (from, freq) in [(44100, 12000.0), (48000, 10000.0), (22050, 9500.0)] {
+                let gain = gain_db(kind, from, freq);
+                assert!(
+                    gain < -min_rejection,
+                    "{:?} {} Hz: {} Hz aliased at {:.1} dB",
+                    kind,
+                    from,
+                    freq,
+                    gain
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_count_does_not_drift_over_long_runs() {
+        for kind in [ResamplerKind::Sinc, ResamplerKind::Fft] {
+            for from in [44100, 22050] {
+                let mut resampler = StreamResampler::new(kind, from, OUT_RATE).unwrap();
+                let second = vec![0.0; from as usize];
+                let mut produced = 0usize;
+                let mut offset = None;
+                // Two minutes of capture; the filter delay is a constant offset
+                for seconds in 1..=120u64 {
+                    produced += stream(&mut resampler, &second).len();
+                    let expected = (seconds * OUT_RATE as u64) as f64
+                        - resampler.pending() as f64 * resampler.ratio();
+                    let error = produced as f64 - expected;
+                    let offset = *offset.get_or_insert(error);
+                    assert!(
+                        (error - offset).abs() <= 1.0,
+                        "{:?} {} Hz drifted {:.1} samples after {} s",
+                        kind,
+                        from,
+                        error - offset,
+                        seconds
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_linear_falls_back_without_sherpa() {
+        let resampler = StreamResampler::new(ResamplerKind::Linear, 44100, OUT_RATE).unwrap();
+        if cfg!(feature = "kws_real") {
+            assert_eq!(resampler.kind(), ResamplerKind::Linear);
+        } else {
+            assert_eq!(resampler.kind(), ResamplerKind::Sinc);
+        }
+    }
+}
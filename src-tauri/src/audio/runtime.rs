@@ -5,12 +5,14 @@
 //! requiring full application restart.
 
 use anyhow::Result;
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, Sender};
 
 use crate::audio::kws::KwsConfig;
 use crate::audio::kws::KwsWorker;
+use crate::audio::tagging::{TaggingConfig, TaggingInput, TaggingWorker};
 use crate::audio::vad::VadConfig;
-use crate::audio::AudioConfig;
+use crate::audio::{AudioConfig, TAP_CAPACITY};
+use crate::events::SharedEventSink;
 use crate::paths::AppPaths;
 
 /// Signal type for stopping the audio runtime
@@ -18,25 +20,57 @@ use crate::paths::AppPaths;
 pub struct StopSignal;
 
 /// Audio runtime that manages capture stream and KWS worker lifecycle
+///
+/// Dropping it (or [`stop`](Self::stop)) signals the workers first and then
+/// waits for the KWS thread, so the capture device is closed before a new
+/// runtime opens it.
 pub struct AudioRuntime {
-    pub kws_worker: Option<KwsWorker>,
+    // Declared first so it is dropped (disconnecting the workers' receivers)
+    // before the workers are joined
     stop_tx: Sender<StopSignal>,
+    pub kws_worker: Option<KwsWorker>,
+    pub tagging_worker: Option<TaggingWorker>,
 }
 
 impl AudioRuntime {
     /// Start the audio runtime with given configuration
     pub fn start(
-        app_handle: tauri::AppHandle,
+        sink: SharedEventSink,
         paths: AppPaths,
         audio_cfg: AudioConfig,
         kws_cfg: KwsConfig,
         vad_cfg: VadConfig,
-    ) -> Result<(Self, Receiver<StopSignal>)> {
+        tagging_cfg: TaggingConfig,
+    ) -> Result<Self> {
         log::info!("Starting audio runtime...");
 
         // Create stop channel
         let (stop_tx, stop_rx) = bounded::<StopSignal>(1);
 
+        // Tagging listens to the KWS capture rather than opening the device
+        // again; it only captures on its own while KWS is off
+        let (tap, tagging_input) = if kws_cfg.enabled {
+            let (tap, chunks) = bounded(TAP_CAPACITY);
+            (Some(tap), TaggingInput::Tap(chunks))
+        } else {
+            (None, TaggingInput::Capture(audio_cfg.clone()))
+        };
+
+        // Start audio tagging worker if enabled (optional, never fatal)
+        let tagging_worker = if tagging_cfg.enabled {
+            match TaggingWorker::start(sink.clone(), paths.clone(), tagging_cfg, tagging_input) {
+                Ok(worker) => Some(worker),
+                Err(e) => {
+                    log::warn!("Audio tagging not started: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        // Nothing listens without a tagger
+        let tap = tap.filter(|_| tagging_worker.is_some());
+
         // Start KWS worker if enabled
         let kws_worker = if kws_cfg.enabled {
             // Check mode: "real" or "stub"
@@ -46,12 +80,14 @@ impl AudioRuntime {
                     if let Some(ref model_id) = kws_cfg.model_id {
                         log::info!("Starting real KWS with model: {}", model_id);
                         match crate::audio::kws::real::KwsWorker::start(
-                            app_handle.clone(),
+                            sink.clone(),
                             paths.clone(),
                             kws_cfg.clone(),
                             vad_cfg.clone(),
                             audio_cfg.clone(),
                             model_id.clone(),
+                            tap.clone(),
+                            stop_rx.clone(),
                         ) {
                             Ok(worker) => {
                                 log::info!("✓ Audio runtime started with real KWS");
@@ -61,11 +97,13 @@ impl AudioRuntime {
                                 log::warn!("Real KWS failed, falling back to stub: {}", e);
                                 // Fall back to stub
                                 match KwsWorker::start_stub(
-                                    app_handle.clone(),
+                                    sink.clone(),
                                     paths,
                                     kws_cfg,
                                     vad_cfg,
                                     audio_cfg,
+                                    tap,
+                                    stop_rx,
                                 ) {
                                     Ok(stub_worker) => {
                                         log::info!(
@@ -83,11 +121,13 @@ impl AudioRuntime {
                     } else {
                         log::warn!("Real KWS mode requested but no model_id provided, using stub");
                         match KwsWorker::start_stub(
-                            app_handle.clone(),
+                            sink.clone(),
                             paths,
                             kws_cfg,
                             vad_cfg,
                             audio_cfg,
+                            tap,
+                            stop_rx,
                         ) {
                             Ok(stub_worker) => {
                                 log::info!("✓ Audio runtime started with stub KWS");
@@ -104,11 +144,13 @@ impl AudioRuntime {
                 {
                     log::warn!("Real KWS requested but feature not enabled, using stub");
                     match KwsWorker::start_stub(
-                        app_handle.clone(),
+                        sink.clone(),
                         paths,
                         kws_cfg,
                         vad_cfg,
                         audio_cfg,
+                        tap,
+                        stop_rx,
                     ) {
                         Ok(stub_worker) => {
                             log::info!("✓ Audio runtime started with stub KWS");
@@ -123,8 +165,15 @@ impl AudioRuntime {
             } else {
                 // Stub mode (default)
                 log::info!("Starting stub KWS");
-                match KwsWorker::start_stub(app_handle.clone(), paths, kws_cfg, vad_cfg, audio_cfg)
-                {
+                match KwsWorker::start_stub(
+                    sink.clone(),
+                    paths,
+                    kws_cfg,
+                    vad_cfg,
+                    audio_cfg,
+                    tap,
+                    stop_rx,
+                ) {
                     Ok(stub_worker) => {
                         log::info!("✓ Audio runtime started with stub KWS");
                         Some(stub_worker)
@@ -140,23 +189,25 @@ impl AudioRuntime {
             None
         };
 
-        let runtime = Self {
-            kws_worker,
+        Ok(Self {
             stop_tx,
-        };
-
-        Ok((runtime, stop_rx))
+            kws_worker,
+            tagging_worker,
+        })
     }
 
-    /// Stop the audio runtime gracefully
+    /// Stop the audio runtime gracefully, waiting for the KWS thread to exit
     pub fn stop(self) {
         log::info!("Stopping audio runtime...");
 
-        // Send stop signal (best effort)
-        let _ = self.stop_tx.send(StopSignal);
+        // Send stop signal (best effort); dropping the sender also wakes any
+        // other receiver
+        let _ = self.stop_tx.try_send(StopSignal);
+        drop(self.stop_tx);
 
-        // Drop worker to trigger cleanup
+        // Drop workers to trigger cleanup
         drop(self.kws_worker);
+        drop(self.tagging_worker);
 
         log::info!("✓ Audio runtime stopped");
     }
@@ -167,9 +218,3 @@ impl AudioRuntime {
         self.kws_worker.is_some()
     }
 }
-
-/// Helper to clone stop receiver for passing to worker threads
-#[allow(dead_code)]
-pub fn clone_stop_receiver(rx: &Receiver<StopSignal>) -> Receiver<StopSignal> {
-    rx.clone()
-}
@@ -0,0 +1,465 @@
+//! Audio event tagging (doorbell, glass break, alarm, ...)
+//!
+//! Runs a Sherpa-ONNX audio-tagging model over the capture stream at a low
+//! duty cycle and emits `audio:event_tagged` for a configurable allowlist of
+//! classes. Each label has its own refractory window, mirroring the KWS worker.
+//!
+//! While KWS runs, the tagger listens to the processed chunks of the KWS
+//! capture (see [`AudioCapture::with_tap`]) instead of opening the device a
+//! second time; it only captures on its own when KWS is off.
+//!
+//! Thread Safety:
+//! The tagger handle is created and used only inside the worker thread.
+
+use super::metrics::{PipelineMetrics, WorkerTicker};
+use super::{AudioCapture, AudioConfig, AudioSource, FRAME_TIMEOUT, TARGET_SAMPLE_RATE};
+use crate::events::{Event, SharedEventSink};
+use crate::paths::AppPaths;
+use anyhow::{bail, Result};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Audio tagging configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaggingConfig {
+    /// Run the tagging worker alongside KWS (opt-in)
+    pub enabled: bool,
+    /// Model directory name under `models/tagging/`
+    pub model_id: Option<String>,
+    /// Model architecture: "zipformer" or "ced"
+    pub model_type: String,
+    /// Class labels to report (case-insensitive, as named in the model's label file)
+    pub allowlist: Vec<String>,
+    /// Minimum probability for a label to be reported (0.0-1.0)
+    pub score_threshold: f32,
+    /// Length of the audio window fed to each inference (ms)
+    pub window_ms: u64,
+    /// Time between inferences (ms); controls the duty cycle
+    pub interval_ms: u64,
+    /// Suppress repeats of the same label within this window (ms)
+    pub refractory_ms: u64,
+    /// Number of top classes requested from the model per inference
+    pub top_k: usize,
+    /// ONNX Runtime provider (cpu, cuda, coreml, etc.)
+    pub provider: String,
+}
+
+impl Default for TaggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model_id: None,
+            model_type: "zipformer".to_string(),
+            allowlist: vec![
+                "Doorbell".to_string(),
+                "Glass".to_string(),
+                "Smoke detector, smoke alarm".to_string(),
+                "Fire alarm".to_string(),
+            ],
+            score_threshold: 0.5,
+            window_ms: 2000,
+            interval_ms: 1000,
+            refractory_ms: 5000,
+            top_k: 5,
+            provider: "cpu".to_string(),
+        }
+    }
+}
+
+/// Tagged audio event (`audio:event_tagged` payload)
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct AudioEventTag {
+    pub label: String,
+    pub score: f32,
+    /// Milliseconds since UNIX epoch
+    pub ts: u64,
+}
+
+/// Allowlist + threshold + per-label refractory filter for tagger output
+pub struct TagGate {
+    allowlist: HashSet<String>,
+    threshold: f32,
+    refractory: Duration,
+    last_emit: HashMap<String, Instant>,
+}
+
+impl TagGate {
+    pub fn new(config: &TaggingConfig) -> Self {
+        Self {
+            allowlist: config
+                .allowlist
+                .iter()
+                .map(|l| l.trim().to_lowercase())
+                .collect(),
+            threshold: config.score_threshold,
+            refractory: Duration::from_millis(config.refractory_ms),
+            last_emit: HashMap::new(),
+        }
+    }
+
+    /// Returns true if this label/score should be emitted now
+    pub fn accept(&mut self, label: &str, score: f32, now: Instant) -> bool {
+        let key = label.trim().to_lowercase();
+
+        if !self.allowlist.contains(&key) || score < self.threshold {
+            return false;
+        }
+
+        if let Some(last) = self.last_emit.get(&key) {
+            if now.duration_since(*last) < self.refractory {
+                return false;
+            }
+        }
+
+        self.last_emit.insert(key, now);
+        true
+    }
+}
+
+/// Where the tagging worker gets its audio
+pub enum TaggingInput {
+    /// Processed chunks tapped from the KWS capture
+    Tap(Receiver<Vec<i16>>),
+    /// A capture of its own, while KWS is off
+    Capture(AudioConfig),
+}
+
+/// [`TaggingInput`] once the worker has started
+enum Source {
+    Tap(Receiver<Vec<i16>>),
+    Capture(AudioCapture),
+}
+
+impl Source {
+    fn open(input: TaggingInput) -> Result<Self> {
+        Ok(match input {
+            TaggingInput::Tap(chunks) => Source::Tap(chunks),
+            TaggingInput::Capture(audio_config) => {
+                Source::Capture(AudioCapture::new(audio_config)?)
+            }
+        })
+    }
+
+    /// Next chunk, None if none came in time; an error once the tapped
+    /// KWS capture has stopped
+    fn next_chunk(&mut self) -> Result<Option<Vec<i16>>> {
+        match self {
+            Source::Capture(capture) => Ok(capture.next_frame(FRAME_TIMEOUT)),
+            Source::Tap(chunks) => match chunks.recv_timeout(FRAME_TIMEOUT) {
+                Ok(chunk) => Ok(Some(chunk)),
+                Err(RecvTimeoutError::Timeout) => Ok(None),
+                Err(RecvTimeoutError::Disconnected) => bail!("KWS capture stopped"),
+            },
+        }
+    }
+}
+
+/// Signal to stop the tagging worker
+#[derive(Debug, Clone, Copy)]
+struct StopTagging;
+
+/// Audio tagging worker that runs in a dedicated thread
+pub struct TaggingWorker {
+    stop_tx: Sender<StopTagging>,
+    thread_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl TaggingWorker {
+    /// Start the tagging worker thread
+    pub fn start(
+        sink: SharedEventSink,
+        paths: AppPaths,
+        config: TaggingConfig,
+        input: TaggingInput,
+    ) -> Result<Self> {
+        let model_id = match config.model_id.clone() {
+            Some(id) => id,
+            None => bail!("Audio tagging enabled but no model_id configured"),
+        };
+
+        let model_dir = paths.tagging_model_dir(&model_id);
+        if !model_dir.exists() {
+            bail!(
+                "Audio tagging model directory not found: {}",
+                model_dir.display()
+            );
+        }
+
+        if !cfg!(feature = "kws_real") {
+            bail!("Audio tagging requires kws_real feature. Build with --features kws_real");
+        }
+
+        log::info!("Starting audio tagging worker");
+        log::info!("  Model ID: {}", model_id);
+        log::info!("  Allowlist: {:?}", config.allowlist);
+
+        let (stop_tx, stop_rx) = bounded::<StopTagging>(1);
+
+        let handle = std::thread::spawn(move || {
+            if let Err(e) = run_tagging_worker(sink, config, input, model_dir, stop_rx) {
+                log::error!("Audio tagging worker error: {}", e);
+            }
+        });
+
+        Ok(Self {
+            stop_tx,
+            thread_handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for TaggingWorker {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.try_send(StopTagging);
+        // Its own capture (KWS off) is closed before the next one opens
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Tagging worker loop: keep a rolling window, run inference every `interval_ms`
+fn run_tagging_worker(
+    sink: SharedEventSink,
+    config: TaggingConfig,
+    input: TaggingInput,
+    model_dir: std::path::PathBuf,
+    stop_rx: Receiver<StopTagging>,
+) -> Result<()> {
+    let tagger = tagger::Tagger::new(&config, &model_dir)?;
+
+    let mut source = Source::open(input)?;
+    let sample_rate = TARGET_SAMPLE_RATE;
+    let window_samples = (sample_rate as u64 * config.window_ms / 1000) as usize;
+    let interval = Duration::from_millis(config.interval_ms);
+
+    let mut window: VecDeque<i16> = VecDeque::with_capacity(window_samples);
+    let mut gate = TagGate::new(&config);
+    let mut last_run = Instant::now();
+    let mut ticker = WorkerTicker::new(PipelineMetrics::global(), "tagging");
+
+    log::info!(
+        "Audio tagging worker loop started ({}ms window every {}ms)",
+        config.window_ms,
+        config.interval_ms
+    );
+
+    loop {
+        if stop_rx.try_recv().is_ok() {
+            log::info!("Audio tagging worker stopping");
+            return Ok(());
+        }
+        // Only the KWS worker publishes the metrics
+        ticker.tick();
+
+        let Some(samples) = source.next_chunk()? else {
+            continue;
+        };
+
+        window.extend(samples);
+        while window.len() > window_samples {
+            window.pop_front();
+        }
+
+        if window.len() < window_samples || last_run.elapsed() < interval {
+            continue;
+        }
+        last_run = Instant::now();
+
+        let samples_f32: Vec<f32> = window.iter().map(|&s| s as f32 / 32768.0).collect();
+        let events = match tagger.compute(sample_rate, &samples_f32, config.top_k) {
+            Ok(events) => events,
+            Err(e) => {
+                log::warn!("Audio tagging inference failed: {}", e);
+                continue;
+            }
+        };
+
+        let now = Instant::now();
+        for (label, score) in events {
+            if !gate.accept(&label, score, now) {
+                continue;
+            }
+
+            log::info!("Audio event tagged: '{}' score={:.3}", label, score);
+
+            let ts = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+
+            let event = AudioEventTag { label, score, ts };
+            if let Err(e) = sink.publish(Event::AudioEventTagged(event)) {
+                log::error!("Failed to emit audio:event_tagged: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "kws_real")]
+mod tagger {
+    use super::TaggingConfig;
+    use crate::ffi::sherpa_onnx_bindings::*;
+    use anyhow::{bail, Context, Result};
+    use std::ffi::{CStr, CString};
+    use std::path::Path;
+
+    /// Owned Sherpa-ONNX audio tagger (confined to the worker thread)
+    pub struct Tagger {
+        handle: *const SherpaOnnxAudioTagging,
+    }
+
+    impl Tagger {
+        pub fn new(config: &TaggingConfig, model_dir: &Path) -> Result<Self> {
+            let model_path = ["model.onnx", "model.int8.onnx"]
+                .iter()
+                .map(|f| model_dir.join(f))
+                .find(|p| p.exists())
+                .with_context(|| format!("No model.onnx found in {}", model_dir.display()))?;
+            let labels_path = model_dir.join("class_labels_indices.csv");
+
+            let model_cstr = CString::new(model_path.to_str().unwrap())?;
+            let labels_cstr = CString::new(labels_path.to_str().unwrap())?;
+            let provider_cstr = CString::new(config.provider.as_str())?;
+
+            let mut model_config = SherpaOnnxAudioTaggingModelConfig {
+                num_threads: 1,
+                provider: provider_cstr.as_ptr(),
+                ..Default::default()
+            };
+            if config.model_type == "ced" {
+                model_config.ced = model_cstr.as_ptr();
+            } else {
+                model_config.zipformer.model = model_cstr.as_ptr();
+            }
+
+            let tagging_config = SherpaOnnxAudioTaggingConfig {
+                model: model_config,
+                labels: labels_cstr.as_ptr(),
+                top_k: config.top_k as i32,
+            };
+
+            let handle = unsafe { SherpaOnnxCreateAudioTagging(&tagging_config) };
+            if handle.is_null() {
+                bail!("Failed to create Sherpa-ONNX audio tagger. Check model files.");
+            }
+
+            log::info!("Audio tagger initialized from {}", model_path.display());
+            Ok(Self { handle })
+        }
+
+        /// Run one inference; returns (label, probability) pairs
+        pub fn compute(
+            &self,
+            sample_rate: u32,
+            samples: &[f32],
+            top_k: usize,
+        ) -> Result<Vec<(String, f32)>> {
+            let stream = unsafe { SherpaOnnxAudioTaggingCreateOfflineStream(self.handle) };
+            if stream.is_null() {
+                bail!("Failed to create audio tagging stream");
+            }
+
+            let mut events = Vec::new();
+            unsafe {
+                SherpaOnnxAcceptWaveformOffline(
+                    stream,
+                    sample_rate as i32,
+                    samples.as_ptr(),
+                    samples.len() as i32,
+                );
+
+                let results = SherpaOnnxAudioTaggingCompute(self.handle, stream, top_k as i32);
+                if !results.is_null() {
+                    let mut i = 0;
+                    while !(*results.add(i)).is_null() {
+                        let event = &**results.add(i);
+                        if !event.name.is_null() {
+                            let name = CStr::from_ptr(event.name).to_string_lossy().into_owned();
+                            events.push((name, event.prob));
+                        }
+                        i += 1;
+                    }
+                    SherpaOnnxAudioTaggingFreeResults(results);
+                }
+
+                SherpaOnnxDestroyOfflineStream(stream);
+            }
+
+            Ok(events)
+        }
+    }
+
+    impl Drop for Tagger {
+        fn drop(&mut self) {
+            unsafe { SherpaOnnxDestroyAudioTagging(self.handle) };
+        }
+    }
+}
+
+#[cfg(not(feature = "kws_real"))]
+mod tagger {
+    use super::TaggingConfig;
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    /// Placeholder when kws_real feature is not enabled
+    pub struct Tagger;
+
+    impl Tagger {
+        pub fn new(_config: &TaggingConfig, _model_dir: &Path) -> Result<Self> {
+            bail!("Audio tagging requires kws_real feature. Build with --features kws_real")
+        }
+
+        pub fn compute(
+            &self,
+            _sample_rate: u32,
+            _samples: &[f32],
+            _top_k: usize,
+        ) -> Result<Vec<(String, f32)>> {
+            bail!("Audio tagging not available")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate_config() -> TaggingConfig {
+        TaggingConfig {
+            allowlist: vec!["Doorbell".to_string(), "Glass".to_string()],
+            score_threshold: 0.5,
+            refractory_ms: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_gate_allowlist_and_threshold() {
+        let mut gate = TagGate::new(&gate_config());
+        let now = Instant::now();
+
+        assert!(gate.accept("Doorbell", 0.9, now));
+        assert!(gate.accept("glass", 0.6, now), "Match is case-insensitive");
+        assert!(!gate.accept("Speech", 0.99, now), "Not in allowlist");
+
+        let mut gate = TagGate::new(&gate_config());
+        assert!(!gate.accept("Doorbell", 0.4, now), "Below threshold");
+    }
+
+    #[test]
+    fn test_gate_refractory_is_per_label() {
+        let mut gate = TagGate::new(&gate_config());
+        let t0 = Instant::now();
+
+        assert!(gate.accept("Doorbell", 0.9, t0));
+        assert!(!gate.accept("Doorbell", 0.9, t0 + Duration::from_millis(500)));
+        assert!(gate.accept("Glass", 0.9, t0 + Duration::from_millis(500)));
+        assert!(gate.accept("Doorbell", 0.9, t0 + Duration::from_millis(1000)));
+    }
+}
@@ -3,28 +3,25 @@
 //! Provides simple sine wave tone generation using CPAL for verifying
 //! output device configuration and audio pipeline functionality.
 
-use anyhow::{bail, Context, Result};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::SampleFormat;
+use super::aec::{FarEnd, FarEndWriter};
+use crate::error::CommandError;
+use anyhow::Result;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{Sample, SampleFormat};
 use std::f32::consts::PI;
 use std::time::Duration;
 
-/// Play a test tone on the specified output device with automatic format fallback
+/// Play a test tone on an output device with automatic format fallback
 ///
 /// # Arguments
-/// * `device_name` - Optional device name. If None, uses default output device
+/// * `device` - Output device (see `open_output_device`)
 /// * `freq_hz` - Frequency in Hz (clamped to 40-20000 Hz)
 /// * `duration_ms` - Duration in milliseconds (clamped to 50-10000 ms)
 /// * `volume` - Volume level 0.0-1.0 (clamped to 0.0-1.0)
 ///
 /// # Format Fallback
 /// Tries formats in order: F32 → I16 → U16, logging each attempt
-pub fn play_tone(
-    device_name: Option<String>,
-    freq_hz: f32,
-    duration_ms: u32,
-    volume: f32,
-) -> Result<()> {
+pub fn play_tone(device: &cpal::Device, freq_hz: f32, duration_ms: u32, volume: f32) -> Result<()> {
     // Clamp parameters to safe ranges
     let freq_hz = freq_hz.clamp(40.0, 20000.0);
     let duration_ms = duration_ms.clamp(50, 10_000);
@@ -37,18 +34,6 @@ pub fn play_tone(
         volume
     );
 
-    let host = cpal::default_host();
-
-    // Select output device
-    let device = if let Some(ref name) = device_name {
-        host.output_devices()?
-            .find(|d| d.name().map(|n| n == *name).unwrap_or(false))
-            .with_context(|| format!("Output device not found: {}", name))?
-    } else {
-        host.default_output_device()
-            .context("No default output device available")?
-    };
-
     let device_name_str = device.name()?;
     log::info!("Using output device: {}", device_name_str);
 
@@ -76,7 +61,7 @@ pub fn play_tone(
     for (format, format_name) in &formats_to_try {
         log::debug!("Attempting format: {}", format_name);
 
-        match try_build_stream(&device, *format, channels, sample_rate, freq_hz, volume) {
+        match try_build_stream(device, *format, channels, sample_rate, freq_hz, volume) {
             Ok(stream) => {
                 log::info!("✓ Test tone using {} format", format_name);
                 stream.play()?;
@@ -92,14 +77,12 @@ pub fn play_tone(
         }
     }
 
-    // All formats failed
+    // All formats failed; keep the last error so callers can tell busy from unsupported
     let (failed_format, err) = last_error.unwrap();
-    bail!(
-        "Test tone failed on device '{}': all formats (F32, I16, U16) failed. Last error ({}): {:?}",
-        device_name_str,
-        failed_format,
-        err
-    )
+    Err(err.context(format!(
+        "Test tone failed on device '{}': all formats (F32, I16, U16) failed, last tried {}",
+        device_name_str, failed_format
+    )))
 }
 
 /// Try to build a stream with a specific format
@@ -121,7 +104,7 @@ fn try_build_stream(
         SampleFormat::F32 => build_f32_stream_direct(device, config, freq_hz, sample_rate, volume),
         SampleFormat::I16 => build_i16_stream_direct(device, config, freq_hz, sample_rate, volume),
         SampleFormat::U16 => build_u16_stream_direct(device, config, freq_hz, sample_rate, volume),
-        _ => bail!("Unsupported format: {:?}", format),
+        _ => Err(CommandError::UnsupportedFormat(format!("{:?}", format)).into()),
     }
 }
 
@@ -135,6 +118,8 @@ fn build_f32_stream_direct(
 ) -> Result<cpal::Stream> {
     let channels = config.channels as usize;
     let mut sample_clock = 0f32;
+    // Played tones are the reference for echo cancellation
+    let mut reference = FarEndWriter::new(FarEnd::global(), sample_rate as u32, channels);
 
     let stream = device.build_output_stream(
         &config,
@@ -148,6 +133,7 @@ fn build_f32_stream_direct(
                     *sample = value;
                 }
             }
+            reference.write(data.iter().map(|&s| s.to_sample::<f32>()));
         },
         move |err| log::error!("Test tone stream error: {}", err),
         None,
@@ -166,6 +152,7 @@ fn build_i16_stream_direct(
 ) -> Result<cpal::Stream> {
     let channels = config.channels as usize;
     let mut sample_clock = 0f32;
+    let mut reference = FarEndWriter::new(FarEnd::global(), sample_rate as u32, channels);
 
     let stream = device.build_output_stream(
         &config,
@@ -181,6 +168,7 @@ fn build_i16_stream_direct(
                     *sample = sample_i16;
                 }
             }
+            reference.write(data.iter().map(|&s| s.to_sample::<f32>()));
         },
         move |err| log::error!("Test tone stream error: {}", err),
         None,
@@ -199,6 +187,7 @@ fn build_u16_stream_direct(
 ) -> Result<cpal::Stream> {
     let channels = config.channels as usize;
     let mut sample_clock = 0f32;
+    let mut reference = FarEndWriter::new(FarEnd::global(), sample_rate as u32, channels);
 
     let stream = device.build_output_stream(
         &config,
@@ -215,6 +204,7 @@ fn build_u16_stream_direct(
                     *sample = sample_u16;
                 }
             }
+            reference.write(data.iter().map(|&s| s.to_sample::<f32>()));
         },
         move |err| log::error!("Test tone stream error: {}", err),
         None,
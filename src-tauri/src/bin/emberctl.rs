@@ -0,0 +1,575 @@
+//! emberctl - command-line control for Emberleaf
+//!
+//! Works directly on the config, models and voiceprint profiles used by the
+//! desktop app and the headless daemon, without starting the audio pipeline.
+//! Config changes take effect the next time Emberleaf starts.
+//!
+//! See `docs/EMBERCTL.md`.
+
+use anyhow::{bail, Context, Result};
+use ember_lib::audio::{self, AudioDebugInfo, DeviceInfo};
+use ember_lib::config::AppConfig;
+use ember_lib::error::CommandError;
+use ember_lib::events::EventSink;
+use ember_lib::model_manager::ModelManager;
+use ember_lib::paths::AppPaths;
+use ember_lib::preflight::{self, CheckStatus};
+use ember_lib::validation;
+use ember_lib::voice;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: emberctl [--json] <command>
+
+Commands:
+  devices list              List audio input and output devices
+  kws status                Show wake word settings
+  kws enable <model>        Use a downloaded KWS model
+  kws disable               Return to the stub detector
+  models list               List registry models and install state
+  models download <model>   Download and verify a KWS model
+  models verify <model>     Check an installed model against the registry
+  models remove <model>     Delete an installed model
+  profiles list             List enrolled speaker profiles
+  profiles delete <user>    Delete a speaker profile
+  preflight                 Run system dependency checks
+  snapshot                  Print audio settings and devices
+
+Options:
+  --json                    Print machine-readable JSON
+  -h, --help                Show this help";
+
+/// Parsed subcommand
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    DevicesList,
+    KwsStatus,
+    KwsEnable(String),
+    KwsDisable,
+    ModelsList,
+    ModelsDownload(String),
+    ModelsVerify(String),
+    ModelsRemove(String),
+    ProfilesList,
+    ProfilesDelete(String),
+    Preflight,
+    Snapshot,
+}
+
+/// Command line after option parsing
+#[derive(Debug, PartialEq)]
+struct Cli {
+    json: bool,
+    command: Command,
+}
+
+/// Command result: JSON for `--json`, text otherwise
+struct Output {
+    json: Value,
+    text: String,
+    /// Exit with failure even though the command ran (e.g. failed preflight)
+    failed: bool,
+}
+
+impl Output {
+    fn new(json: Value, text: impl Into<String>) -> Self {
+        Self {
+            json,
+            text: text.into(),
+            failed: false,
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Cli> {
+    let mut json = false;
+    let mut words = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                return Ok(Cli {
+                    json,
+                    command: Command::Help,
+                })
+            }
+            flag if flag.starts_with('-') => bail!("Unknown option '{}'", flag),
+            word => words.push(word),
+        }
+    }
+
+    let command = match words.as_slice() {
+        [] | ["help"] => Command::Help,
+        ["devices", "list"] => Command::DevicesList,
+        ["kws", "status"] => Command::KwsStatus,
+        ["kws", "enable", model] => Command::KwsEnable(model.to_string()),
+        ["kws", "disable"] => Command::KwsDisable,
+        ["models", "list"] => Command::ModelsList,
+        ["models", "download", model] => Command::ModelsDownload(model.to_string()),
+        ["models", "verify", model] => Command::ModelsVerify(model.to_string()),
+        ["models", "remove", model] => Command::ModelsRemove(model.to_string()),
+        ["profiles", "list"] => Command::ProfilesList,
+        ["profiles", "delete", user] => Command::ProfilesDelete(user.to_string()),
+        ["preflight"] => Command::Preflight,
+        ["snapshot"] => Command::Snapshot,
+        _ => bail!("Unknown command '{}'", words.join(" ")),
+    };
+
+    Ok(Cli { json, command })
+}
+
+/// Prints model download progress to stderr; other events are ignored
+struct ProgressSink;
+
+impl EventSink for ProgressSink {
+    fn emit_value(&self, event: &str, payload: Value) -> Result<()> {
+        if event == "kws:model_download_progress" {
+            eprint!(
+                "\rDownloading {}: {:5.1}%",
+                payload["model_id"].as_str().unwrap_or_default(),
+                payload["percent"].as_f64().unwrap_or_default()
+            );
+            let _ = std::io::stderr().flush();
+        }
+        Ok(())
+    }
+}
+
+/// Load config.toml; repairs are reported as warnings on stderr
+fn load_config(paths: &AppPaths) -> AppConfig {
+    AppConfig::load(&paths.config_file()).config
+}
+
+fn load_model_manager(paths: &AppPaths) -> Result<ModelManager> {
+    let mut manager = ModelManager::new(paths.models_dir());
+    manager
+        .load_registry(&paths.kws_registry())
+        .context("Failed to load KWS registry")?;
+    Ok(manager)
+}
+
+fn format_devices(title: &str, devices: &[DeviceInfo]) -> String {
+    let mut text = format!("{}:\n", title);
+    if devices.is_empty() {
+        text.push_str("  (none)\n");
+    }
+    for device in devices {
+        text.push_str(&format!(
+            "  {} {} [{}, {} ch]\n",
+            if device.is_default { "*" } else { " " },
+            device.name,
+            device.host,
+            device.max_channels
+        ));
+    }
+    text
+}
+
+fn devices_list() -> Result<Output> {
+    let inputs = audio::list_input_devices()?;
+    let outputs = audio::list_output_devices()?;
+
+    let text =
+        format_devices("Input devices", &inputs) + &format_devices("Output devices", &outputs);
+    Ok(Output::new(
+        json!({ "input_devices": inputs, "output_devices": outputs }),
+        text.trim_end(),
+    ))
+}
+
+fn kws_status(paths: &AppPaths) -> Result<Output> {
+    let kws = load_config(paths).kws;
+
+    // Registry is optional here; status still works without it
+    let lang = kws.model_id.as_ref().and_then(|id| {
+        load_model_manager(paths)
+            .ok()
+            .and_then(|m| m.registry().ok()?.get_model(id).map(|e| e.lang.clone()))
+    });
+
+    let text = format!(
+        "Mode:    {}\nModel:   {}\nKeyword: {}\nEnabled: {}",
+        kws.mode,
+        match (&kws.model_id, &lang) {
+            (Some(id), Some(lang)) => format!("{} ({})", id, lang),
+            (Some(id), None) => id.clone(),
+            (None, _) => "-".to_string(),
+        },
+        kws.keyword,
+        kws.enabled
+    );
+
+    Ok(Output::new(
+        json!({
+            "mode": kws.mode,
+            "model_id": kws.model_id,
+            "keyword": kws.keyword,
+            "lang": lang,
+            "enabled": kws.enabled,
+        }),
+        text,
+    ))
+}
+
+fn kws_enable(paths: &AppPaths, model_id: &str) -> Result<Output> {
+    ModelManager::validate_model_id(model_id)?;
+
+    if !cfg!(feature = "kws_real") {
+        return Err(CommandError::Unavailable(
+            "Real KWS not available: emberctl was built without kws_real feature".to_string(),
+        )
+        .into());
+    }
+
+    let manager = load_model_manager(paths)?;
+    if manager.registry()?.get_model(model_id).is_none() {
+        return Err(CommandError::ModelMissing(model_id.to_string()).into());
+    }
+    if !manager.is_model_ready(model_id)? {
+        let error = anyhow::Error::new(CommandError::ModelMissing(model_id.to_string()));
+        return Err(error.context(format!("Run `emberctl models download {}` first", model_id)));
+    }
+
+    let config_path = paths.config_file();
+    let mut config = load_config(paths);
+    config.kws.model_id = Some(model_id.to_string());
+    config.kws.mode = "real".to_string();
+    config.kws.enabled = true;
+    config.save(&config_path)?;
+
+    Ok(Output::new(
+        json!({ "mode": "real", "model_id": model_id, "restart_required": true }),
+        format!(
+            "Real KWS set to model '{}' (restart Emberleaf to apply)",
+            model_id
+        ),
+    ))
+}
+
+fn kws_disable(paths: &AppPaths) -> Result<Output> {
+    let config_path = paths.config_file();
+    let mut config = load_config(paths);
+    config.kws.mode = "stub".to_string();
+    config.kws.model_id = None;
+    config.kws.enabled = true; // Same as the UI: keep KWS on, just use the stub
+    config.save(&config_path)?;
+
+    Ok(Output::new(
+        json!({ "mode": "stub", "model_id": null, "restart_required": true }),
+        "KWS returned to stub mode (restart Emberleaf to apply)",
+    ))
+}
+
+fn models_list(paths: &AppPaths) -> Result<Output> {
+    let manager = load_model_manager(paths)?;
+    let registry = manager.registry()?;
+
+    let mut ids: Vec<_> = registry.models.keys().cloned().collect();
+    ids.sort();
+
+    let mut entries = Vec::new();
+    let mut text = String::new();
+    for id in ids {
+        let entry = &registry.models[&id];
+        let installed = manager.is_model_ready(&id)?;
+        text.push_str(&format!(
+            "{} {:<40} {:<6} \"{}\" ({:.1} MB)\n",
+            if installed { "*" } else { " " },
+            id,
+            entry.lang,
+            entry.wakeword,
+            entry.size as f64 / 1_000_000.0
+        ));
+        entries.push(json!({
+            "id": id,
+            "lang": entry.lang,
+            "wakeword": entry.wakeword,
+            "size": entry.size,
+            "description": entry.description,
+            "installed": installed,
+        }));
+    }
+    if entries.is_empty() {
+        text.push_str("No models in registry");
+    }
+
+    Ok(Output::new(Value::Array(entries), text.trim_end()))
+}
+
+async fn models_download(paths: &AppPaths, model_id: &str) -> Result<Output> {
+    ModelManager::validate_model_id(model_id)?;
+    let manager = load_model_manager(paths)?;
+
+    if manager.is_model_ready(model_id)? {
+        return Ok(Output::new(
+            json!({ "model_id": model_id, "downloaded": false, "verified": true }),
+            format!("Model '{}' is already installed", model_id),
+        ));
+    }
+
+    let result = manager.download_model(&ProgressSink, model_id).await;
+    eprintln!();
+    result.context("Model download failed")?;
+
+    if !verify(&manager, model_id)? {
+        // Same as the app: never keep a model that failed verification
+        manager.remove_model(model_id).ok();
+        return Err(CommandError::VerificationFailed(model_id.to_string()).into());
+    }
+
+    Ok(Output::new(
+        json!({ "model_id": model_id, "downloaded": true, "verified": true }),
+        format!("Model '{}' downloaded and verified", model_id),
+    ))
+}
+
+/// Check an installed model against its registry checksum
+fn verify(manager: &ModelManager, model_id: &str) -> Result<bool> {
+    let entry = manager
+        .registry()?
+        .get_model(model_id)
+        .with_context(|| format!("Model '{}' not found in registry", model_id))?;
+    manager.verify_model(model_id, &entry.sha256)
+}
+
+fn models_verify(paths: &AppPaths, model_id: &str) -> Result<Output> {
+    ModelManager::validate_model_id(model_id)?;
+    let manager = load_model_manager(paths)?;
+
+    if !manager.model_dir(model_id).exists() {
+        bail!("Model '{}' is not installed", model_id);
+    }
+
+    let verified = verify(&manager, model_id)?;
+    Ok(Output {
+        json: json!({ "model_id": model_id, "verified": verified }),
+        text: if verified {
+            format!("Model '{}' verified", model_id)
+        } else {
+            format!("Model '{}' does not match the registry checksum", model_id)
+        },
+        failed: !verified,
+    })
+}
+
+fn models_remove(paths: &AppPaths, model_id: &str) -> Result<Output> {
+    ModelManager::validate_model_id(model_id)?;
+    let manager = ModelManager::new(paths.models_dir());
+
+    if !manager.model_dir(model_id).exists() {
+        bail!("Model '{}' is not installed", model_id);
+    }
+    manager.remove_model(model_id)?;
+
+    Ok(Output::new(
+        json!({ "model_id": model_id, "removed": true }),
+        format!("Model '{}' removed", model_id),
+    ))
+}
+
+fn profiles_list(paths: &AppPaths) -> Result<Output> {
+    let profiles_dir = paths.profiles_dir();
+    let mut users = if profiles_dir.exists() {
+        voice::list_profiles_in(&profiles_dir)?
+    } else {
+        Vec::new()
+    };
+    users.sort();
+
+    let text = if users.is_empty() {
+        "No enrolled profiles".to_string()
+    } else {
+        users.join("\n")
+    };
+    Ok(Output::new(json!(users), text))
+}
+
+fn profiles_delete(paths: &AppPaths, user: &str) -> Result<Output> {
+    let user = validation::validate_profile_name(user)?;
+    voice::delete_profile_in(&paths.profiles_dir(), &user)?;
+
+    Ok(Output::new(
+        json!({ "user": user, "deleted": true }),
+        format!("Deleted profile '{}'", user),
+    ))
+}
+
+fn run_preflight() -> Output {
+    let report = preflight::run_preflight(&ProgressSink);
+
+    let mut text = String::new();
+    for item in &report.items {
+        let status = match item.status {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        text.push_str(&format!("[{}] {}: {}\n", status, item.name, item.message));
+        if let Some(hint) = item
+            .fix_hint
+            .as_ref()
+            .filter(|_| item.status != CheckStatus::Pass)
+        {
+            text.push_str(&format!("       {}\n", hint));
+        }
+    }
+
+    Output {
+        json: json!(report),
+        text: text.trim_end().to_string(),
+        failed: !report.can_proceed,
+    }
+}
+
+fn snapshot(paths: &AppPaths) -> Result<Output> {
+    let config = load_config(paths);
+    let debug_info = AudioDebugInfo::from_config(&config.audio);
+
+    let input_devices = audio::list_input_devices().unwrap_or_else(|e| {
+        log::warn!("Failed to list input devices: {}", e);
+        Vec::new()
+    });
+    let output_devices = audio::list_output_devices().unwrap_or_else(|e| {
+        log::warn!("Failed to list output devices: {}", e);
+        Vec::new()
+    });
+
+    let text = format!(
+        "Processing: {} Hz, {} ch, frame {} ms, hop {} ms\nInput:  {}\nOutput: {}\n{}{}",
+        debug_info.processing_rate,
+        debug_info.processing_channels,
+        debug_info.frame_ms,
+        debug_info.hop_ms,
+        debug_info.input_device.as_deref().unwrap_or("(default)"),
+        debug_info.output_device.as_deref().unwrap_or("(default)"),
+        format_devices("Input devices", &input_devices),
+        format_devices("Output devices", &output_devices),
+    );
+
+    Ok(Output::new(
+        json!({
+            "debug_info": debug_info,
+            "input_devices": input_devices,
+            "output_devices": output_devices,
+            "selected_input_device": config.audio.device_name,
+            "selected_output_device": config.audio.output_device_name,
+        }),
+        text.trim_end(),
+    ))
+}
+
+async fn run(command: Command) -> Result<Output> {
+    let paths = AppPaths::new()?;
+    paths.ensure_directories()?;
+
+    match command {
+        Command::Help => Ok(Output::new(json!({ "usage": USAGE }), USAGE)),
+        Command::DevicesList => devices_list(),
+        Command::KwsStatus => kws_status(&paths),
+        Command::KwsEnable(model_id) => kws_enable(&paths, &model_id),
+        Command::KwsDisable => kws_disable(&paths),
+        Command::ModelsList => models_list(&paths),
+        Command::ModelsDownload(model_id) => models_download(&paths, &model_id).await,
+        Command::ModelsVerify(model_id) => models_verify(&paths, &model_id),
+        Command::ModelsRemove(model_id) => models_remove(&paths, &model_id),
+        Command::ProfilesList => profiles_list(&paths),
+        Command::ProfilesDelete(user) => profiles_delete(&paths, &user),
+        Command::Preflight => Ok(run_preflight()),
+        Command::Snapshot => snapshot(&paths),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    // Keep stdout clean for scripts; RUST_LOG=info shows library logs
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = match parse_args(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("emberctl: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if cli.command == Command::Help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(cli.command).await {
+        Ok(output) => {
+            if cli.json {
+                println!("{}", output.json);
+            } else {
+                println!("{}", output.text);
+            }
+            if output.failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => {
+            if cli.json {
+                let error = format!("{:#}", e);
+                let code = CommandError::from(e).code();
+                println!("{}", json!({ "error": error, "code": code }));
+            } else {
+                eprintln!("emberctl: {:#}", e);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Cli> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse("").unwrap().command, Command::Help);
+        assert_eq!(parse("devices list").unwrap().command, Command::DevicesList);
+        assert_eq!(
+            parse("kws enable hey_ember_en").unwrap().command,
+            Command::KwsEnable("hey_ember_en".to_string())
+        );
+        assert_eq!(
+            parse("profiles delete alice").unwrap().command,
+            Command::ProfilesDelete("alice".to_string())
+        );
+        assert_eq!(parse("snapshot").unwrap().command, Command::Snapshot);
+    }
+
+    #[test]
+    fn test_parse_json_flag_anywhere() {
+        assert_eq!(
+            parse("--json kws status").unwrap(),
+            Cli {
+                json: true,
+                command: Command::KwsStatus
+            }
+        );
+        assert!(parse("models list --json").unwrap().json);
+        assert!(!parse("models list").unwrap().json);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("kws enable").is_err());
+        assert!(parse("models download a b").is_err());
+        assert!(parse("devices").is_err());
+        assert!(parse("preflight --verbose").is_err());
+    }
+}
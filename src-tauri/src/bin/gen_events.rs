@@ -0,0 +1,25 @@
+//! Regenerate the event TypeScript types, JSON Schema and reference doc
+//!
+//! Run from anywhere with `npm run gen:events`. Paths are resolved relative
+//! to the repository root.
+
+use ember_lib::events::schema;
+use std::fs;
+use std::path::Path;
+
+fn main() -> anyhow::Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+
+    for (path, contents) in schema::generated_files() {
+        let target = root.join(path);
+        let current = fs::read_to_string(&target).unwrap_or_default();
+        if current == contents {
+            println!("unchanged  {}", path);
+        } else {
+            fs::write(&target, contents)?;
+            println!("written    {}", path);
+        }
+    }
+
+    Ok(())
+}
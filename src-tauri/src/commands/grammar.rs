@@ -0,0 +1,319 @@
+//! Declarative intent grammar (`commands.toml` in the config directory)
+//!
+//! ```toml
+//! min_confidence = 0.75
+//! fuzzy_threshold = 0.75
+//!
+//! [slots.room]
+//! values = ["kitchen", "living room", "bedroom"]
+//!
+//! [[intents]]
+//! name = "set_timer"
+//! phrases = ["set [a] timer for {duration}", "start a {duration} timer"]
+//!
+//! [[intents]]
+//! name = "lights_on"
+//! phrases = ["turn on the {room} lights"]
+//! ```
+//!
+//! `{name}` is a slot. Its type comes from `[intents.slots]` (`name = "type"`),
+//! otherwise from a built-in type or custom slot with the same name.
+//! `[word]` marks optional words.
+
+use super::matcher::{normalize, Element};
+use super::slots::SlotType;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Custom enum slot (`[slots.<name>]`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlotDef {
+    pub values: Vec<String>,
+}
+
+/// Intent definition (`[[intents]]`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct IntentDef {
+    pub name: String,
+    pub phrases: Vec<String>,
+    /// Slot name -> type name (built-in or custom)
+    #[serde(default)]
+    pub slots: HashMap<String, String>,
+}
+
+/// Raw grammar file
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrammarFile {
+    #[serde(default = "default_threshold")]
+    pub min_confidence: f32,
+    #[serde(default = "default_threshold")]
+    pub fuzzy_threshold: f32,
+    #[serde(default)]
+    pub slots: HashMap<String, SlotDef>,
+    #[serde(default)]
+    pub intents: Vec<IntentDef>,
+}
+
+fn default_threshold() -> f32 {
+    0.75
+}
+
+/// Compiled phrase
+#[derive(Debug, Clone)]
+pub struct Phrase {
+    /// Phrase as written in the grammar (reported in matches)
+    pub source: String,
+    pub elements: Vec<Element>,
+}
+
+/// Compiled intent
+#[derive(Debug, Clone)]
+pub struct Intent {
+    pub name: String,
+    pub phrases: Vec<Phrase>,
+}
+
+/// Compiled grammar ready for matching
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    /// Minimum confidence for a match to be reported (0.0-1.0)
+    pub min_confidence: f32,
+    /// Minimum similarity for a fuzzy word match (0.0-1.0)
+    pub fuzzy_threshold: f32,
+    pub intents: Vec<Intent>,
+}
+
+impl Default for Grammar {
+    fn default() -> Self {
+        Self {
+            min_confidence: default_threshold(),
+            fuzzy_threshold: default_threshold(),
+            intents: Vec::new(),
+        }
+    }
+}
+
+impl Grammar {
+    /// Load and compile a grammar file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read grammar: {}", path.display()))?;
+        let grammar = Self::from_toml_str(&content)
+            .with_context(|| format!("Invalid grammar: {}", path.display()))?;
+
+        log::info!("Command grammar loaded: {} intents", grammar.intents.len());
+        Ok(grammar)
+    }
+
+    /// Parse and compile grammar TOML
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let file: GrammarFile = toml::from_str(content).context("Failed to parse grammar TOML")?;
+        Self::compile(file)
+    }
+
+    fn compile(file: GrammarFile) -> Result<Self> {
+        for (field, value) in [
+            ("min_confidence", file.min_confidence),
+            ("fuzzy_threshold", file.fuzzy_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                bail!("{} must be between 0.0 and 1.0, got {}", field, value);
+            }
+        }
+
+        let mut custom = HashMap::new();
+        for (name, def) in &file.slots {
+            if SlotType::builtin(name).is_some() {
+                bail!("Custom slot '{}' shadows a built-in type", name);
+            }
+            let values: Vec<String> = def
+                .values
+                .iter()
+                .map(|v| normalize(v).join(" "))
+                .filter(|v| !v.is_empty())
+                .collect();
+            if values.is_empty() {
+                bail!("Custom slot '{}' has no values", name);
+            }
+            custom.insert(name.clone(), SlotType::Enum(values));
+        }
+
+        let mut seen = HashSet::new();
+        let mut intents = Vec::new();
+        for def in file.intents {
+            validate_intent_name(&def.name)?;
+            if !seen.insert(def.name.clone()) {
+                bail!("Duplicate intent '{}'", def.name);
+            }
+            if def.phrases.is_empty() {
+                bail!("Intent '{}' has no phrases", def.name);
+            }
+
+            let phrases = def
+                .phrases
+                .iter()
+                .map(|p| compile_phrase(p, &def.slots, &custom))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("In intent '{}'", def.name))?;
+
+            intents.push(Intent {
+                name: def.name,
+                phrases,
+            });
+        }
+
+        Ok(Self {
+            min_confidence: file.min_confidence,
+            fuzzy_threshold: file.fuzzy_threshold,
+            intents,
+        })
+    }
+}
+
+/// Intent names travel in events and handler lookups
+fn validate_intent_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 64 {
+        bail!("Intent name must be 1-64 characters");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        bail!("Intent name '{}' contains invalid characters", name);
+    }
+    Ok(())
+}
+
+fn resolve_slot_type(
+    name: &str,
+    declared: &HashMap<String, String>,
+    custom: &HashMap<String, SlotType>,
+) -> Result<SlotType> {
+    let type_name = declared.get(name).map(String::as_str).unwrap_or(name);
+    SlotType::builtin(type_name)
+        .or_else(|| custom.get(type_name).cloned())
+        .with_context(|| format!("Unknown type '{}' for slot '{}'", type_name, name))
+}
+
+fn compile_phrase(
+    source: &str,
+    declared: &HashMap<String, String>,
+    custom: &HashMap<String, SlotType>,
+) -> Result<Phrase> {
+    let mut elements = Vec::new();
+    let mut slot_names = HashSet::new();
+    let mut rest = source.trim();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .with_context(|| format!("Unclosed '{{' in phrase '{}'", source))?;
+            let name = after[..end].trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                bail!("Invalid slot name '{}' in phrase '{}'", name, source);
+            }
+            if !slot_names.insert(name.to_string()) {
+                bail!("Slot '{}' appears twice in phrase '{}'", name, source);
+            }
+            elements.push(Element::Slot {
+                name: name.to_string(),
+                ty: resolve_slot_type(name, declared, custom)?,
+            });
+            rest = after[end + 1..].trim_start();
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after
+                .find(']')
+                .with_context(|| format!("Unclosed '[' in phrase '{}'", source))?;
+            let words = normalize(&after[..end]);
+            if words.is_empty() {
+                bail!("Empty optional group in phrase '{}'", source);
+            }
+            elements.push(Element::Optional(words));
+            rest = after[end + 1..].trim_start();
+        } else {
+            let end = rest.find(['{', '[']).unwrap_or(rest.len());
+            elements.extend(normalize(&rest[..end]).into_iter().map(Element::Word));
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    if elements.is_empty() {
+        bail!("Empty phrase");
+    }
+
+    Ok(Phrase {
+        source: source.to_string(),
+        elements,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_phrase_elements() {
+        let grammar = Grammar::from_toml_str(
+            r#"
+            [slots.room]
+            values = ["Living Room", "kitchen"]
+
+            [[intents]]
+            name = "lights_on"
+            phrases = ["Turn on [the] {where} lights"]
+            slots = { where = "room" }
+            "#,
+        )
+        .unwrap();
+
+        let elements = &grammar.intents[0].phrases[0].elements;
+        assert_eq!(elements[0], Element::Word("turn".to_string()));
+        assert_eq!(elements[2], Element::Optional(vec!["the".to_string()]));
+        assert_eq!(
+            elements[3],
+            Element::Slot {
+                name: "where".to_string(),
+                ty: SlotType::Enum(vec!["living room".to_string(), "kitchen".to_string()]),
+            }
+        );
+        assert_eq!(elements[4], Element::Word("lights".to_string()));
+    }
+
+    #[test]
+    fn test_grammar_errors() {
+        let unknown_slot = r#"
+            [[intents]]
+            name = "x"
+            phrases = ["play {song}"]
+        "#;
+        assert!(Grammar::from_toml_str(unknown_slot).is_err());
+
+        let duplicate = r#"
+            [[intents]]
+            name = "x"
+            phrases = ["hello"]
+            [[intents]]
+            name = "x"
+            phrases = ["hi"]
+        "#;
+        assert!(Grammar::from_toml_str(duplicate).is_err());
+
+        let unclosed = r#"
+            [[intents]]
+            name = "x"
+            phrases = ["set timer {duration"]
+        "#;
+        assert!(Grammar::from_toml_str(unclosed).is_err());
+
+        let bad_name = r#"
+            [[intents]]
+            name = "../x"
+            phrases = ["hello"]
+        "#;
+        assert!(Grammar::from_toml_str(bad_name).is_err());
+    }
+}
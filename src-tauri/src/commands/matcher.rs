@@ -0,0 +1,237 @@
+//! Text normalization and fuzzy phrase alignment
+//!
+//! A phrase is aligned against the transcript tokens with a small dynamic
+//! program: literal words match fuzzily (edit-distance similarity), optional
+//! words may be skipped for free, slots consume a span their parser accepts,
+//! and missing or extra words cost a penalty. Confidence is the matched
+//! weight divided by the total weight of the alignment.
+
+use super::slots::{SlotType, SlotValue};
+use std::collections::HashMap;
+
+/// Phrase element after compilation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    /// Required literal word
+    Word(String),
+    /// Optional word group, written `[the]` or `[please]` in the grammar
+    Optional(Vec<String>),
+    /// Typed slot, written `{name}`
+    Slot { name: String, ty: SlotType },
+}
+
+/// Best alignment of one phrase against a transcript
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    pub confidence: f32,
+    pub slots: Vec<(String, SlotValue)>,
+}
+
+/// Lowercase, split on anything that is not a letter, digit or apostrophe,
+/// and keep decimal points between digits ("3.5").
+pub fn normalize(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut cleaned = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let decimal_point = c == '.'
+            && i > 0
+            && chars[i - 1].is_ascii_digit()
+            && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+        if c.is_alphanumeric() || c == '\'' || decimal_point {
+            cleaned.extend(c.to_lowercase());
+        } else {
+            cleaned.push(' ');
+        }
+    }
+
+    cleaned
+        .split_whitespace()
+        .map(|t| t.trim_matches('\'').to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Edit-distance similarity in 0.0-1.0 (1.0 = identical)
+pub fn similarity(a: &str, b: &str) -> f32 {
+    if a == b {
+        return 1.0;
+    }
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        curr[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    1.0 - prev[b.len()] as f32 / max_len as f32
+}
+
+/// Partial alignment from some (element, token) position to the end
+#[derive(Debug, Clone)]
+struct Path {
+    /// Objective: matched weight minus penalties
+    score: f32,
+    /// Matched weight (numerator of confidence)
+    gain: f32,
+    /// Total weight (denominator of confidence)
+    weight: f32,
+    slots: Vec<(String, SlotValue)>,
+}
+
+impl Path {
+    fn extend(mut self, score: f32, gain: f32, weight: f32) -> Self {
+        self.score += score;
+        self.gain += gain;
+        self.weight += weight;
+        self
+    }
+}
+
+struct Aligner<'a> {
+    elements: &'a [Element],
+    tokens: &'a [String],
+    fuzzy_threshold: f32,
+    memo: HashMap<(usize, usize), Option<Path>>,
+}
+
+impl Aligner<'_> {
+    fn best(&mut self, ei: usize, ti: usize) -> Option<Path> {
+        if let Some(cached) = self.memo.get(&(ei, ti)) {
+            return cached.clone();
+        }
+
+        let remaining = self.tokens.len() - ti;
+        let result = if ei == self.elements.len() {
+            // Trailing words the phrase does not account for
+            let extra = remaining as f32;
+            Some(Path {
+                score: -extra,
+                gain: 0.0,
+                weight: extra,
+                slots: Vec::new(),
+            })
+        } else {
+            let mut candidates = Vec::new();
+
+            // Skip an extra transcript word
+            if remaining > 0 {
+                if let Some(p) = self.best(ei, ti + 1) {
+                    candidates.push(p.extend(-1.0, 0.0, 1.0));
+                }
+            }
+
+            match &self.elements[ei] {
+                Element::Word(word) => {
+                    if remaining > 0 {
+                        let sim = similarity(word, &self.tokens[ti]);
+                        if sim >= self.fuzzy_threshold {
+                            if let Some(p) = self.best(ei + 1, ti + 1) {
+                                candidates.push(p.extend(sim, sim, 1.0));
+                            }
+                        }
+                    }
+                    // Missing required word
+                    if let Some(p) = self.best(ei + 1, ti) {
+                        candidates.push(p.extend(-1.0, 0.0, 1.0));
+                    }
+                }
+                Element::Optional(words) => {
+                    if remaining >= words.len() {
+                        let sims: Vec<f32> = words
+                            .iter()
+                            .zip(&self.tokens[ti..])
+                            .map(|(w, t)| similarity(w, t))
+                            .collect();
+                        if sims.iter().all(|s| *s >= self.fuzzy_threshold) {
+                            if let Some(p) = self.best(ei + 1, ti + words.len()) {
+                                let sum: f32 = sims.iter().sum();
+                                candidates.push(p.extend(sum, sum, words.len() as f32));
+                            }
+                        }
+                    }
+                    if let Some(p) = self.best(ei + 1, ti) {
+                        candidates.push(p);
+                    }
+                }
+                Element::Slot { name, ty } => {
+                    let max = ty.max_tokens().min(remaining);
+                    for len in 1..=max {
+                        let span = &self.tokens[ti..ti + len];
+                        if let Some((value, quality)) = ty.parse(span, self.fuzzy_threshold) {
+                            if let Some(mut p) = self.best(ei + 1, ti + len) {
+                                p.slots.insert(0, (name.clone(), value));
+                                candidates.push(p.extend(quality, quality, 1.0));
+                            }
+                        }
+                    }
+                }
+            }
+
+            candidates
+                .into_iter()
+                .max_by(|a, b| a.score.total_cmp(&b.score))
+        };
+
+        self.memo.insert((ei, ti), result.clone());
+        result
+    }
+}
+
+/// Align a compiled phrase against normalized transcript tokens
+pub fn align(elements: &[Element], tokens: &[String], fuzzy_threshold: f32) -> Option<Alignment> {
+    let mut aligner = Aligner {
+        elements,
+        tokens,
+        fuzzy_threshold,
+        memo: HashMap::new(),
+    };
+
+    let path = aligner.best(0, 0)?;
+    let confidence = if path.weight > 0.0 {
+        (path.gain / path.weight).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    Some(Alignment {
+        confidence,
+        slots: path.slots,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("Set a Timer, for 3.5 minutes!"),
+            vec!["set", "a", "timer", "for", "3.5", "minutes"]
+        );
+        assert_eq!(
+            normalize("What's the twenty-one?"),
+            vec!["what's", "the", "twenty", "one"]
+        );
+        assert_eq!(normalize("'quoted' end."), vec!["quoted", "end"]);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("timer", "timer"), 1.0);
+        assert!(similarity("timer", "timmer") > 0.8);
+        assert!(similarity("timer", "lights") < 0.5);
+    }
+}
@@ -0,0 +1,256 @@
+//! Intent/command engine
+//!
+//! Maps transcripts to named intents with typed slots using the declarative
+//! grammar in `commands.toml` (see [`grammar`]). Matching is text-only, so
+//! grammars can be unit-tested without audio.
+//!
+//! Matched intents are dispatched to registered [`IntentHandler`]s and
+//! emitted to the frontend as `intent:matched`.
+
+pub mod grammar;
+mod matcher;
+pub mod slots;
+
+pub use grammar::Grammar;
+pub use slots::SlotValue;
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Transcripts longer than this are not matched (bounds alignment cost)
+const MAX_TOKENS: usize = 64;
+
+/// Matched intent (`intent:matched` payload)
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct IntentMatch {
+    pub intent: String,
+    /// Alignment confidence (0.0-1.0)
+    pub confidence: f32,
+    pub slots: BTreeMap<String, SlotValue>,
+    /// Grammar phrase that matched
+    pub phrase: String,
+    /// Normalized transcript
+    pub text: String,
+}
+
+/// Handler invoked when its intent matches
+pub trait IntentHandler: Send + Sync {
+    /// Intent name this handler serves
+    fn intent(&self) -> &str;
+
+    fn handle(&self, matched: &IntentMatch) -> Result<()>;
+}
+
+/// Grammar + registered handlers
+#[derive(Default)]
+pub struct CommandEngine {
+    grammar: Grammar,
+    handlers: HashMap<String, Box<dyn IntentHandler>>,
+}
+
+impl CommandEngine {
+    pub fn new(grammar: Grammar) -> Self {
+        Self {
+            grammar,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Load the grammar file, falling back to an empty grammar if it is missing
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            log::info!("Command grammar not found at: {}", path.display());
+            return Ok(Self::default());
+        }
+        Ok(Self::new(Grammar::load(path)?))
+    }
+
+    /// Replace the grammar (handlers are kept)
+    pub fn set_grammar(&mut self, grammar: Grammar) {
+        self.grammar = grammar;
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    /// Register a handler, replacing any previous handler for the same intent
+    #[allow(dead_code)]
+    pub fn register_handler(&mut self, handler: Box<dyn IntentHandler>) {
+        self.handlers.insert(handler.intent().to_string(), handler);
+    }
+
+    /// All phrase matches, best first (includes matches below `min_confidence`)
+    pub fn rank(&self, text: &str) -> Vec<IntentMatch> {
+        let tokens = matcher::normalize(text);
+        if tokens.is_empty() || tokens.len() > MAX_TOKENS {
+            return Vec::new();
+        }
+        let normalized = tokens.join(" ");
+
+        let mut matches: Vec<IntentMatch> = Vec::new();
+        for intent in &self.grammar.intents {
+            // Keep only the best phrase per intent
+            let best = intent
+                .phrases
+                .iter()
+                .filter_map(|phrase| {
+                    matcher::align(&phrase.elements, &tokens, self.grammar.fuzzy_threshold)
+                        .map(|a| (phrase, a))
+                })
+                .max_by(|a, b| a.1.confidence.total_cmp(&b.1.confidence));
+
+            if let Some((phrase, alignment)) = best {
+                matches.push(IntentMatch {
+                    intent: intent.name.clone(),
+                    confidence: alignment.confidence,
+                    slots: alignment.slots.into_iter().collect(),
+                    phrase: phrase.source.clone(),
+                    text: normalized.clone(),
+                });
+            }
+        }
+
+        matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        matches
+    }
+
+    /// Best match at or above the grammar's `min_confidence`
+    pub fn match_text(&self, text: &str) -> Option<IntentMatch> {
+        self.rank(text)
+            .into_iter()
+            .next()
+            .filter(|m| m.confidence >= self.grammar.min_confidence)
+    }
+
+    /// Match and run the intent's handler (handler errors are logged, not returned)
+    pub fn dispatch(&self, text: &str) -> Option<IntentMatch> {
+        let matched = self.match_text(text)?;
+
+        if let Some(handler) = self.handlers.get(&matched.intent) {
+            if let Err(e) = handler.handle(&matched) {
+                log::warn!("Intent handler '{}' failed: {:#}", matched.intent, e);
+            }
+        }
+
+        Some(matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const GRAMMAR: &str = r#"
+        min_confidence = 0.75
+
+        [slots.room]
+        values = ["kitchen", "living room", "bedroom"]
+
+        [[intents]]
+        name = "set_timer"
+        phrases = ["set [a] timer for {duration}", "start a {duration} timer"]
+
+        [[intents]]
+        name = "lights_on"
+        phrases = ["turn on [the] {room} lights", "lights on in [the] {room}"]
+
+        [[intents]]
+        name = "set_volume"
+        phrases = ["set [the] volume to {level}"]
+        slots = { level = "integer" }
+
+        [[intents]]
+        name = "remind"
+        phrases = ["remind me to {text}"]
+    "#;
+
+    fn engine() -> CommandEngine {
+        CommandEngine::new(Grammar::from_toml_str(GRAMMAR).unwrap())
+    }
+
+    #[test]
+    fn test_match_with_typed_slots() {
+        let engine = engine();
+
+        let m = engine.match_text("Set a timer for five minutes.").unwrap();
+        assert_eq!(m.intent, "set_timer");
+        assert_eq!(m.slots["duration"], SlotValue::Duration { seconds: 300 });
+        assert!(m.confidence > 0.99);
+
+        let m = engine
+            .match_text("start a 1 hour 30 minutes timer")
+            .unwrap();
+        assert_eq!(m.slots["duration"], SlotValue::Duration { seconds: 5400 });
+
+        let m = engine.match_text("set volume to twenty").unwrap();
+        assert_eq!(m.intent, "set_volume");
+        assert_eq!(m.slots["level"], SlotValue::Integer(20));
+
+        let m = engine.match_text("remind me to buy more oat milk").unwrap();
+        assert_eq!(
+            m.slots["text"],
+            SlotValue::Text("buy more oat milk".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match_lowers_confidence() {
+        let engine = engine();
+
+        let m = engine.match_text("turn on the livin room light").unwrap();
+        assert_eq!(m.intent, "lights_on");
+        assert_eq!(m.slots["room"], SlotValue::Enum("living room".to_string()));
+        assert!(m.confidence < 1.0);
+
+        // Extra filler words are tolerated but cost confidence
+        let exact = engine.match_text("lights on in the kitchen").unwrap();
+        let filler = engine.match_text("um lights on in the kitchen").unwrap();
+        assert!(filler.confidence < exact.confidence);
+    }
+
+    #[test]
+    fn test_no_match() {
+        let engine = engine();
+        assert!(engine.match_text("what's the weather like").is_none());
+        // Slot must parse: "later" is not a duration
+        assert!(engine.match_text("set a timer for later").is_none());
+        assert!(engine.match_text("").is_none());
+    }
+
+    struct Recorder {
+        seen: Arc<Mutex<Vec<IntentMatch>>>,
+    }
+
+    impl IntentHandler for Recorder {
+        fn intent(&self) -> &str {
+            "set_timer"
+        }
+
+        fn handle(&self, matched: &IntentMatch) -> Result<()> {
+            self.seen.lock().unwrap().push(matched.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dispatch_to_handler() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = engine();
+        engine.register_handler(Box::new(Recorder { seen: seen.clone() }));
+
+        assert!(engine.dispatch("set timer for 10 seconds").is_some());
+        assert!(engine.dispatch("turn on the kitchen lights").is_some());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(
+            seen[0].slots["duration"],
+            SlotValue::Duration { seconds: 10 }
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Typed slot parsers (duration, number, integer, text, custom enums)
+//!
+//! Parsers work on normalized tokens, so "Five Minutes" and "5 minutes"
+//! arrive as `["five", "minutes"]` and `["5", "minutes"]`.
+
+use super::matcher::similarity;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Upper bound on tokens a bounded slot may consume
+const MAX_NUMBER_TOKENS: usize = 6;
+const MAX_DURATION_TOKENS: usize = 12;
+
+/// Slot type declared in the grammar
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotType {
+    /// "5 minutes", "an hour and a half", "1 hour 30 minutes"
+    Duration,
+    /// "3.5", "twenty one"
+    Number,
+    /// Whole numbers only
+    Integer,
+    /// Free text (one or more tokens)
+    Text,
+    /// One of a fixed set of values, matched fuzzily (values are normalized)
+    Enum(Vec<String>),
+}
+
+/// Parsed slot value (serialized as `{ "type": ..., "value": ... }`)
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SlotValue {
+    Duration { seconds: u64 },
+    Number(f64),
+    Integer(i64),
+    Text(String),
+    Enum(String),
+}
+
+impl SlotType {
+    /// Built-in type names usable in `[intents.slots]`
+    pub fn builtin(name: &str) -> Option<SlotType> {
+        match name {
+            "duration" => Some(SlotType::Duration),
+            "number" => Some(SlotType::Number),
+            "integer" => Some(SlotType::Integer),
+            "text" => Some(SlotType::Text),
+            _ => None,
+        }
+    }
+
+    /// Maximum number of tokens this slot may span
+    pub fn max_tokens(&self) -> usize {
+        match self {
+            SlotType::Duration => MAX_DURATION_TOKENS,
+            SlotType::Number | SlotType::Integer => MAX_NUMBER_TOKENS,
+            SlotType::Text => usize::MAX,
+            // Allow one stray token so "the living room" can still reach "living room"
+            SlotType::Enum(values) => {
+                values
+                    .iter()
+                    .map(|v| v.split(' ').count())
+                    .max()
+                    .unwrap_or(1)
+                    + 1
+            }
+        }
+    }
+
+    /// Parse a token span; returns the value and a match quality in 0.0-1.0
+    pub fn parse(&self, tokens: &[String], fuzzy_threshold: f32) -> Option<(SlotValue, f32)> {
+        if tokens.is_empty() {
+            return None;
+        }
+
+        match self {
+            SlotType::Duration => {
+                parse_duration(tokens).map(|seconds| (SlotValue::Duration { seconds }, 1.0))
+            }
+            SlotType::Number => parse_number(tokens).map(|n| (SlotValue::Number(n), 1.0)),
+            SlotType::Integer => parse_number(tokens)
+                .filter(|n| n.fract() == 0.0 && n.abs() < i64::MAX as f64)
+                .map(|n| (SlotValue::Integer(n as i64), 1.0)),
+            SlotType::Text => Some((SlotValue::Text(tokens.join(" ")), 1.0)),
+            SlotType::Enum(values) => {
+                let span = tokens.join(" ");
+                values
+                    .iter()
+                    .map(|v| (v, similarity(&span, v)))
+                    .filter(|(_, s)| *s >= fuzzy_threshold)
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(v, s)| (SlotValue::Enum(v.clone()), s))
+            }
+        }
+    }
+}
+
+fn small_number(word: &str) -> Option<f64> {
+    let n = match word {
+        "zero" | "oh" => 0,
+        "one" | "a" | "an" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        "thirteen" => 13,
+        "fourteen" => 14,
+        "fifteen" => 15,
+        "sixteen" => 16,
+        "seventeen" => 17,
+        "eighteen" => 18,
+        "nineteen" => 19,
+        "twenty" => 20,
+        "thirty" => 30,
+        "forty" => 40,
+        "fifty" => 50,
+        "sixty" => 60,
+        "seventy" => 70,
+        "eighty" => 80,
+        "ninety" => 90,
+        _ => return None,
+    };
+    Some(n as f64)
+}
+
+/// Parse digits ("42", "3.5") or English number words ("one hundred and five")
+pub fn parse_number(tokens: &[String]) -> Option<f64> {
+    if tokens.len() == 1 {
+        if let Ok(n) = tokens[0].parse::<f64>() {
+            return n.is_finite().then_some(n);
+        }
+    }
+
+    let mut total = 0.0;
+    let mut current = 0.0;
+    let mut seen_number = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            // "a"/"an" only count as one when they lead ("a hundred")
+            "a" | "an" if i > 0 => return None,
+            "and" if seen_number => continue,
+            "hundred" if seen_number => current *= 100.0,
+            "thousand" if seen_number => {
+                total += current * 1000.0;
+                current = 0.0;
+            }
+            word => {
+                let n = small_number(word)?;
+                current += n;
+                seen_number = true;
+            }
+        }
+    }
+
+    // A bare "a"/"an" is not a number
+    if !seen_number || (tokens.len() == 1 && matches!(tokens[0].as_str(), "a" | "an")) {
+        return None;
+    }
+    Some(total + current)
+}
+
+fn unit_seconds(word: &str) -> Option<u64> {
+    match word {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(3600),
+        "d" | "day" | "days" => Some(86_400),
+        _ => None,
+    }
+}
+
+/// Parse a spoken duration into seconds; every token must be consumed
+pub fn parse_duration(tokens: &[String]) -> Option<u64> {
+    let mut total = 0.0;
+    let mut last_unit = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let rest = &tokens[i..];
+
+        if rest[0] == "and" && last_unit.is_some() {
+            i += 1;
+            continue;
+        }
+
+        // "half an hour"
+        if rest.len() >= 3 && rest[0] == "half" && matches!(rest[1].as_str(), "a" | "an") {
+            if let Some(unit) = unit_seconds(&rest[2]) {
+                total += unit as f64 / 2.0;
+                last_unit = Some(unit);
+                i += 3;
+                continue;
+            }
+        }
+
+        // "... and a half" after a unit
+        if rest.len() >= 2 && rest[0] == "a" && rest[1] == "half" {
+            let unit = last_unit?;
+            total += unit as f64 / 2.0;
+            i += 2;
+            continue;
+        }
+
+        // "an hour", "a minute"
+        if rest.len() >= 2 && matches!(rest[0].as_str(), "a" | "an") {
+            if let Some(unit) = unit_seconds(&rest[1]) {
+                total += unit as f64;
+                last_unit = Some(unit);
+                i += 2;
+                continue;
+            }
+        }
+
+        // <number> <unit>, preferring the longest number span
+        let mut consumed = None;
+        for j in (1..rest.len().min(MAX_NUMBER_TOKENS + 1)).rev() {
+            if let (Some(n), Some(unit)) = (parse_number(&rest[..j]), unit_seconds(&rest[j])) {
+                consumed = Some((j + 1, n * unit as f64, unit));
+                break;
+            }
+        }
+        let (len, seconds, unit) = consumed?;
+        total += seconds;
+        last_unit = Some(unit);
+        i += len;
+    }
+
+    (last_unit.is_some() && total > 0.0).then_some(total.round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toks(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number(&toks("42")), Some(42.0));
+        assert_eq!(parse_number(&toks("3.5")), Some(3.5));
+        assert_eq!(parse_number(&toks("twenty one")), Some(21.0));
+        assert_eq!(parse_number(&toks("one hundred and five")), Some(105.0));
+        assert_eq!(parse_number(&toks("a hundred")), Some(100.0));
+        assert_eq!(parse_number(&toks("two thousand twenty")), Some(2020.0));
+        assert_eq!(parse_number(&toks("a")), None);
+        assert_eq!(parse_number(&toks("five apples")), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration(&toks("5 minutes")), Some(300));
+        assert_eq!(parse_duration(&toks("five minutes")), Some(300));
+        assert_eq!(parse_duration(&toks("an hour")), Some(3600));
+        assert_eq!(parse_duration(&toks("half an hour")), Some(1800));
+        assert_eq!(parse_duration(&toks("an hour and a half")), Some(5400));
+        assert_eq!(
+            parse_duration(&toks("1 hour 20 minutes and 30 seconds")),
+            Some(4830)
+        );
+        assert_eq!(parse_duration(&toks("5")), None);
+        assert_eq!(parse_duration(&toks("5 minutes please")), None);
+    }
+
+    #[test]
+    fn test_enum_slot_fuzzy() {
+        let ty = SlotType::Enum(vec!["kitchen".to_string(), "living room".to_string()]);
+        let (value, quality) = ty.parse(&toks("kitchn"), 0.75).unwrap();
+        assert_eq!(value, SlotValue::Enum("kitchen".to_string()));
+        assert!(quality < 1.0);
+        assert!(ty.parse(&toks("garage"), 0.75).is_none());
+    }
+}
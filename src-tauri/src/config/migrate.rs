@@ -0,0 +1,87 @@
+//! Upgrades of `config.toml` between schema versions
+//!
+//! Each step rewrites the raw TOML table from version N to N + 1, before serde
+//! sees it, so fields can be renamed or rescaled. Steps never fail: a value that
+//! cannot be carried over is reset and reported.
+
+use super::{ConfigWarning, CONFIG_VERSION};
+use toml::{Table, Value};
+
+type Step = fn(&mut Table, &mut Vec<ConfigWarning>);
+
+/// `STEPS[n - 1]` upgrades version n to n + 1
+const STEPS: &[Step] = &[v1_to_v2];
+
+/// Upgrade `table` from version `from` to [`CONFIG_VERSION`]
+pub(super) fn migrate(table: &mut Table, from: u32) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+    for version in from..CONFIG_VERSION {
+        STEPS[(version - 1) as usize](table, &mut warnings);
+    }
+    table.insert(
+        "config_version".to_string(),
+        Value::Integer(CONFIG_VERSION as i64),
+    );
+    warnings
+}
+
+/// Version 1 → 2
+/// - `vad.threshold` was an RMS energy level (100-2000); it is now a 0.0-1.0 probability
+/// - `vad.mode` was written in lowercase in the example file
+fn v1_to_v2(table: &mut Table, warnings: &mut Vec<ConfigWarning>) {
+    let Some(Value::Table(vad)) = table.get_mut("vad") else {
+        return;
+    };
+
+    let threshold = match vad.get("threshold") {
+        Some(Value::Float(v)) => Some(*v),
+        Some(Value::Integer(v)) => Some(*v as f64),
+        _ => None,
+    };
+    if let Some(old) = threshold.filter(|v| *v > 1.0) {
+        vad.insert("threshold".to_string(), Value::Float(0.5));
+        warnings.push(ConfigWarning::field(
+            "vad.threshold",
+            format!(
+                "Energy threshold {} replaced by speech probability 0.5 (VAD threshold is now 0.0-1.0)",
+                old
+            ),
+        ));
+    }
+
+    if let Some(Value::String(mode)) = vad.get_mut("mode") {
+        match mode.as_str() {
+            "silero" => *mode = "Silero".to_string(),
+            "disabled" => *mode = "Disabled".to_string(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_step_per_version() {
+        assert_eq!(STEPS.len(), (CONFIG_VERSION - 1) as usize);
+    }
+
+    #[test]
+    fn test_v1_vad_threshold() {
+        let mut table: Table = "[vad]\nenable = true\nmode = \"silero\"\nthreshold = 500"
+            .parse()
+            .unwrap();
+        let warnings = migrate(&mut table, 1);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(table["config_version"].as_integer(), Some(2));
+        assert_eq!(table["vad"]["threshold"].as_float(), Some(0.5));
+        assert_eq!(table["vad"]["mode"].as_str(), Some("Silero"));
+
+        // Probabilities are kept
+        let mut table: Table = "[vad]\nthreshold = 0.3".parse().unwrap();
+        assert!(migrate(&mut table, 1).is_empty());
+        assert_eq!(table["vad"]["threshold"].as_float(), Some(0.3));
+    }
+}
@@ -0,0 +1,513 @@
+//! Application configuration (`config.toml` in the config directory)
+//!
+//! The file carries a `config_version`. Loading never fails startup:
+//! - older files are migrated (see [`migrate`]) after a copy is kept as
+//!   `config.toml.v<N>.bak`
+//! - a section that no longer deserializes falls back to its defaults
+//! - every field is range checked and invalid values are reset (see [`validate`])
+//! - a file that is not valid TOML is kept as `config.toml.broken.bak` and
+//!   replaced by defaults
+//!
+//! Whatever was changed is returned as [`ConfigWarning`]s for the UI.
+//!
+//! While the app runs, [`ConfigStore`] owns the file: it writes it atomically
+//! and hot-reloads edits made outside the app.
+//!
+//! Named setups (devices, sensitivity, VAD threshold) are kept as
+//! [`ConfigProfile`]s next to it.
+
+mod migrate;
+mod profiles;
+mod store;
+mod validate;
+
+pub use profiles::{ConfigProfile, ConfigProfileInfo, ConfigProfiles, ProfileActivatedPayload};
+pub use store::{ConfigChangedPayload, ConfigStore};
+
+use crate::audio::kws::KwsConfig;
+use crate::audio::tagging::TaggingConfig;
+use crate::audio::vad::VadConfig;
+use crate::audio::AudioConfig;
+use crate::hooks::HooksConfig;
+use crate::ipc::IpcConfig;
+use crate::voice::{BiometricsConfig, PunctuationConfig};
+use anyhow::Context;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Current `config_version`; files without one are version 1
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Application configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    /// Schema version of the file (see [`CONFIG_VERSION`])
+    #[serde(default = "current_version")]
+    pub config_version: u32,
+    /// Name of the last activated [`ConfigProfile`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    pub audio: AudioConfig,
+    pub kws: KwsConfig,
+    pub vad: VadConfig,
+    pub biometrics: BiometricsConfig,
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub tagging: TaggingConfig,
+    #[serde(default)]
+    pub punctuation: PunctuationConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub ipc: IpcConfig,
+}
+
+fn current_version() -> u32 {
+    CONFIG_VERSION
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    pub focus_ring_contrast_min: f32,
+    pub min_touch_target_px: u32,
+    /// Remember mic monitor state across app restarts (default: false)
+    #[serde(default)]
+    pub persist_monitor_state: bool,
+    /// Last monitor state (used when persist_monitor_state is true)
+    #[serde(default)]
+    pub monitor_was_on: bool,
+    /// Mic monitor gain (0.0-0.5), used when the monitor is started without one
+    #[serde(default = "default_monitor_gain")]
+    pub monitor_gain: f32,
+}
+
+fn default_monitor_gain() -> f32 {
+    0.15
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            focus_ring_contrast_min: 3.0,
+            min_touch_target_px: 32,
+            persist_monitor_state: false,
+            monitor_was_on: false,
+            monitor_gain: default_monitor_gain(),
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            config_version: CONFIG_VERSION,
+            active_profile: None,
+            audio: AudioConfig::default(),
+            kws: KwsConfig::default(),
+            vad: VadConfig::default(),
+            biometrics: BiometricsConfig::default(),
+            ui: UiConfig::default(),
+            tagging: TaggingConfig::default(),
+            punctuation: PunctuationConfig::default(),
+            hooks: HooksConfig::default(),
+            ipc: IpcConfig::default(),
+        }
+    }
+}
+
+/// Something that was changed or ignored while loading the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigWarning {
+    /// Setting concerned (e.g. `vad.threshold`); absent for the whole file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl ConfigWarning {
+    fn field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.into()),
+            message: message.into(),
+        }
+    }
+
+    fn file(message: impl Into<String>) -> Self {
+        Self {
+            field: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Result of [`AppConfig::load`]
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: AppConfig,
+    /// Empty when the file was used as is
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl AppConfig {
+    /// Load config from file, creating, migrating or repairing it as needed
+    ///
+    /// Never fails: if the file cannot be read at all, defaults are used.
+    pub fn load(path: &Path) -> LoadedConfig {
+        let mut warnings = Vec::new();
+        let config = Self::load_inner(path, &mut warnings).unwrap_or_else(|e| {
+            warnings.push(ConfigWarning::file(format!(
+                "Configuration could not be loaded ({:#}); using defaults",
+                e
+            )));
+            Self::default()
+        });
+
+        for warning in &warnings {
+            log::warn!("Config: {}", warning);
+        }
+        LoadedConfig { config, warnings }
+    }
+
+    fn load_inner(path: &Path, warnings: &mut Vec<ConfigWarning>) -> anyhow::Result<Self> {
+        if !path.exists() {
+            let config = Self::default();
+            config.save(path)?;
+            log::info!("Default config created at: {}", path.display());
+            return Ok(config);
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let Decoded {
+            config,
+            version,
+            mut changes,
+        } = match Self::decode(&content) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                let backup = backup(path, "broken")?;
+                let config = Self::default();
+                config.save(path)?;
+                warnings.push(ConfigWarning::file(format!(
+                    "Configuration could not be read ({}); using defaults. The old file was saved as {}",
+                    e,
+                    backup.display()
+                )));
+                return Ok(config);
+            }
+        };
+
+        if version > CONFIG_VERSION {
+            warnings.push(newer_version_warning(version));
+        }
+
+        // Never rewrite a file from a newer version; it would lose its new settings
+        if version <= CONFIG_VERSION && (version < CONFIG_VERSION || !changes.is_empty()) {
+            let tag = if version < CONFIG_VERSION {
+                format!("v{}", version)
+            } else {
+                "invalid".to_string()
+            };
+            let backup = backup(path, &tag)?;
+            config.save(path)?;
+            if version < CONFIG_VERSION {
+                log::info!(
+                    "Config migrated from version {} to {} (previous file: {})",
+                    version,
+                    CONFIG_VERSION,
+                    backup.display()
+                );
+            }
+            if !changes.is_empty() {
+                changes.push(ConfigWarning::file(format!(
+                    "The previous configuration was saved as {}",
+                    backup.display()
+                )));
+            }
+        }
+
+        warnings.extend(changes);
+        log::info!("Config loaded from: {}", path.display());
+        Ok(config)
+    }
+
+    /// Parse config text as [`AppConfig::load`] does, without touching any file
+    ///
+    /// Fails only if the text is not TOML; invalid values are reset in the result.
+    pub fn from_toml(content: &str) -> anyhow::Result<LoadedConfig> {
+        let decoded = Self::decode(content)?;
+        let mut warnings = Vec::new();
+        if decoded.version > CONFIG_VERSION {
+            warnings.push(newer_version_warning(decoded.version));
+        }
+        warnings.extend(decoded.changes);
+        Ok(LoadedConfig {
+            config: decoded.config,
+            warnings,
+        })
+    }
+
+    /// Parse, migrate and validate in memory
+    fn decode(content: &str) -> anyhow::Result<Decoded> {
+        let (mut table, version) = Self::parse(content)?;
+
+        let mut changes = Vec::new();
+        if version < CONFIG_VERSION {
+            changes.extend(migrate::migrate(&mut table, version));
+        }
+
+        let mut config = Self::from_table(table, &mut changes);
+        config.config_version = version.max(CONFIG_VERSION);
+        changes.extend(config.repair());
+
+        Ok(Decoded {
+            config,
+            version,
+            changes,
+        })
+    }
+
+    /// Parse TOML and read its `config_version`
+    fn parse(content: &str) -> anyhow::Result<(toml::Table, u32)> {
+        let table: toml::Table = content.parse()?;
+        let version = match table.get("config_version") {
+            None => 1,
+            Some(toml::Value::Integer(v)) if *v >= 1 && *v <= u32::MAX as i64 => *v as u32,
+            Some(other) => {
+                anyhow::bail!("config_version must be a positive integer, got {}", other)
+            }
+        };
+        Ok((table, version))
+    }
+
+    /// Deserialize section by section so one bad section does not discard the rest
+    fn from_table(mut table: toml::Table, warnings: &mut Vec<ConfigWarning>) -> Self {
+        let mut config = Self::default();
+
+        match table.remove("active_profile") {
+            None => {}
+            Some(toml::Value::String(name)) => config.active_profile = Some(name),
+            Some(other) => warnings.push(ConfigWarning::field(
+                "active_profile",
+                format!("Expected a profile name, got {}; ignored", other),
+            )),
+        }
+
+        macro_rules! section {
+            ($($name:ident),+) => {$(
+                if let Some(value) = table.remove(stringify!($name)) {
+                    match value.try_into() {
+                        Ok(section) => config.$name = section,
+                        Err(e) => warnings.push(ConfigWarning::field(
+                            stringify!($name),
+                            format!("Section could not be read ({}); using defaults", e.message()),
+                        )),
+                    }
+                }
+            )+};
+        }
+
+        section!(
+            audio,
+            kws,
+            vad,
+            biometrics,
+            ui,
+            tagging,
+            punctuation,
+            hooks,
+            ipc
+        );
+        config
+    }
+
+    /// Write config to file (atomically, owner-only permissions)
+    ///
+    /// Inside the app, go through [`ConfigStore`] instead so writes are
+    /// debounced and not mistaken for external edits.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let toml_str = toml::to_string_pretty(self)?;
+        store::write_atomic(path, &toml_str)?;
+        log::info!("Config saved to: {}", path.display());
+        Ok(())
+    }
+}
+
+/// Result of [`AppConfig::decode`]
+struct Decoded {
+    config: AppConfig,
+    /// Version of the text before migration
+    version: u32,
+    /// Migrations and repairs applied
+    changes: Vec<ConfigWarning>,
+}
+
+fn newer_version_warning(version: u32) -> ConfigWarning {
+    ConfigWarning::file(format!(
+        "Configuration was written by a newer Emberleaf (version {}); settings this version does not know are ignored",
+        version
+    ))
+}
+
+/// Copy `path` to `<file>.<tag>.bak` (or `<file>.<tag>.<n>.bak` if taken)
+fn backup(path: &Path, tag: &str) -> anyhow::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "config.toml".to_string());
+
+    let mut target = path.with_file_name(format!("{}.{}.bak", file_name, tag));
+    let mut n = 1;
+    while target.exists() {
+        target = path.with_file_name(format!("{}.{}.{}.bak", file_name, tag, n));
+        n += 1;
+    }
+
+    fs::copy(path, &target)
+        .with_context(|| format!("Failed to back up config to {}", target.display()))?;
+    store::restrict_permissions(&target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config(name: &str, content: Option<&str>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ember-config-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        if let Some(content) = content {
+            fs::write(&path, content).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_creates_default_without_warnings() {
+        let path = temp_config("create", None);
+        let loaded = AppConfig::load(&path);
+        assert!(loaded.warnings.is_empty());
+        assert!(path.exists());
+
+        // A current, valid file loads as is
+        let again = AppConfig::load(&path);
+        assert!(again.warnings.is_empty());
+        assert_eq!(again.config.config_version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_v1_file_is_migrated_with_backup() {
+        let v1 = r#"
+[audio]
+sample_rate_hz = 16000
+frame_ms = 20
+hop_ms = 10
+
+[vad]
+enable = true
+mode = "silero"
+threshold = 500.0
+"#;
+        let path = temp_config("migrate", Some(v1));
+        let loaded = AppConfig::load(&path);
+
+        assert_eq!(loaded.config.vad.threshold, 0.5);
+        assert_eq!(loaded.config.config_version, CONFIG_VERSION);
+        assert!(loaded
+            .warnings
+            .iter()
+            .any(|w| w.field.as_deref() == Some("vad.threshold")));
+
+        let backup = path.with_file_name("config.toml.v1.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), v1);
+
+        // Rewritten file is current and clean
+        let again = AppConfig::load(&path);
+        assert!(again.warnings.is_empty(), "{:?}", again.warnings);
+    }
+
+    #[test]
+    fn test_invalid_values_reset_to_defaults() {
+        let mut config = AppConfig::default();
+        config.kws.score_threshold = 7.0;
+        config.audio.frame_ms = 0;
+        let path = temp_config("invalid", Some(&toml::to_string_pretty(&config).unwrap()));
+
+        let loaded = AppConfig::load(&path);
+        let defaults = AppConfig::default();
+        assert_eq!(
+            loaded.config.kws.score_threshold,
+            defaults.kws.score_threshold
+        );
+        assert_eq!(loaded.config.audio.frame_ms, defaults.audio.frame_ms);
+        assert!(path.with_file_name("config.toml.invalid.bak").exists());
+    }
+
+    #[test]
+    fn test_bad_section_keeps_the_rest() {
+        let mut config = AppConfig::default();
+        config.kws.keyword = "hola ember".to_string();
+        let mut content = toml::to_string_pretty(&config).unwrap();
+        content = content.replace("enroll_utterances_min", "enroll_utterances");
+        let path = temp_config("section", Some(&content));
+
+        let loaded = AppConfig::load(&path);
+        assert_eq!(loaded.config.kws.keyword, "hola ember");
+        assert!(loaded
+            .warnings
+            .iter()
+            .any(|w| w.field.as_deref() == Some("biometrics")));
+    }
+
+    #[test]
+    fn test_unparseable_file_recovers_to_defaults() {
+        let path = temp_config("broken", Some("[audio\nframe_ms = "));
+        let loaded = AppConfig::load(&path);
+
+        assert_eq!(loaded.warnings.len(), 1);
+        assert!(loaded.warnings[0].field.is_none());
+        assert_eq!(
+            fs::read_to_string(path.with_file_name("config.toml.broken.bak")).unwrap(),
+            "[audio\nframe_ms = "
+        );
+        assert!(AppConfig::load(&path).warnings.is_empty());
+    }
+
+    #[test]
+    fn test_example_file_is_current_and_valid() {
+        let example = include_str!("../../../config.toml.example");
+        let path = temp_config("example", Some(example));
+        let loaded = AppConfig::load(&path);
+        assert!(loaded.warnings.is_empty(), "{:?}", loaded.warnings);
+        assert_eq!(fs::read_to_string(&path).unwrap(), example);
+    }
+
+    #[test]
+    fn test_newer_version_is_not_rewritten() {
+        let config = AppConfig {
+            config_version: CONFIG_VERSION + 1,
+            ..AppConfig::default()
+        };
+        let content = toml::to_string_pretty(&config).unwrap();
+        let path = temp_config("newer", Some(&content));
+
+        let loaded = AppConfig::load(&path);
+        assert_eq!(loaded.warnings.len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+}
@@ -0,0 +1,465 @@
+//! Named configuration profiles (`profiles/<name>.toml` in the config directory)
+//!
+//! A profile holds what differs between setups ("desk", "living room",
+//! "headset"): the input and output devices, the KWS sensitivity
+//! (`kws.score_threshold`), the VAD threshold and the mic monitor gain.
+//! Activating one replaces exactly those settings in one step, so the audio
+//! runtime restarts once with all of them; everything else, including echo
+//! cancellation, DSP and the recorder opt-in, is left alone. A profile may hold
+//! only some of them; the ones it leaves out keep their current values.
+//!
+//! With `auto_activate = true`, a profile is activated when its input device
+//! (`audio.stable_input_id`) is plugged in while the app runs.
+
+use super::{store, AppConfig, ConfigWarning};
+use crate::audio::{identity, DeviceId};
+use crate::error::CommandError;
+use crate::validation::validate_config_profile_name;
+use anyhow::Context;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Settings of one named setup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigProfile {
+    /// Activate when the input device (`audio.stable_input_id`) is plugged in
+    #[serde(default)]
+    pub auto_activate: bool,
+    /// Mic monitor gain (0.0-0.5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_gain: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<ProfileDevices>,
+    #[serde(default)]
+    pub kws: ProfileKws,
+    #[serde(default)]
+    pub vad: ProfileVad,
+}
+
+/// `[audio]` of a profile: both devices, applied together
+///
+/// A device left out is the system default, as in `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileDevices {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_device_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_input_id: Option<DeviceId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_output_id: Option<DeviceId>,
+}
+
+/// `[kws]` of a profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileKws {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_threshold: Option<f32>,
+}
+
+/// `[vad]` of a profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileVad {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f32>,
+}
+
+impl ConfigProfile {
+    /// Capture the current setup
+    pub fn from_config(config: &AppConfig, auto_activate: bool) -> Self {
+        Self {
+            auto_activate,
+            monitor_gain: Some(config.ui.monitor_gain),
+            audio: Some(ProfileDevices {
+                device_name: config.audio.device_name.clone(),
+                output_device_name: config.audio.output_device_name.clone(),
+                stable_input_id: config.audio.stable_input_id.clone(),
+                stable_output_id: config.audio.stable_output_id.clone(),
+            }),
+            kws: ProfileKws {
+                score_threshold: Some(config.kws.score_threshold),
+            },
+            vad: ProfileVad {
+                threshold: Some(config.vad.threshold),
+            },
+        }
+    }
+
+    /// Replace the settings the profile holds in `config` and mark it active
+    ///
+    /// Values are checked like `config.toml`; invalid ones are reset to their
+    /// defaults and returned as warnings.
+    pub fn apply(&self, name: &str, config: &mut AppConfig) -> Vec<ConfigWarning> {
+        let mut applied = config.clone();
+        if let Some(devices) = &self.audio {
+            applied.audio.device_name = devices.device_name.clone();
+            applied.audio.output_device_name = devices.output_device_name.clone();
+            applied.audio.stable_input_id = devices.stable_input_id.clone();
+            applied.audio.stable_output_id = devices.stable_output_id.clone();
+        }
+        if let Some(threshold) = self.kws.score_threshold {
+            applied.kws.score_threshold = threshold;
+        }
+        if let Some(threshold) = self.vad.threshold {
+            applied.vad.threshold = threshold;
+        }
+        if let Some(gain) = self.monitor_gain {
+            applied.ui.monitor_gain = gain;
+        }
+        applied.active_profile = Some(name.to_string());
+
+        let warnings = applied.repair();
+        *config = applied;
+        warnings
+    }
+}
+
+/// Profile as listed to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigProfileInfo {
+    pub name: String,
+    pub auto_activate: bool,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    /// Last activated profile
+    pub active: bool,
+}
+
+/// A configuration profile was activated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProfileActivatedPayload {
+    pub name: String,
+    /// Activated because its input device was plugged in
+    pub auto: bool,
+    /// Profile values that were invalid and reset to their defaults
+    pub warnings: Vec<ConfigWarning>,
+}
+
+/// The `profiles/` directory
+#[derive(Debug, Clone)]
+pub struct ConfigProfiles {
+    dir: PathBuf,
+}
+
+impl ConfigProfiles {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, CommandError> {
+        validate_config_profile_name(name).map_err(|e| CommandError::validation("name", e))?;
+        Ok(self.dir.join(format!("{}.toml", name)))
+    }
+
+    fn not_found(name: &str) -> CommandError {
+        CommandError::validation("name", format!("No profile named '{}'", name))
+    }
+
+    /// All readable profiles, sorted by name
+    ///
+    /// Files that do not parse are skipped with a warning in the log.
+    pub fn list(&self, active: Option<&str>) -> anyhow::Result<Vec<ConfigProfileInfo>> {
+        let mut infos = Vec::new();
+        for (name, profile) in self.load_all()? {
+            let devices = profile.audio.unwrap_or_default();
+            infos.push(ConfigProfileInfo {
+                active: active == Some(name.as_str()),
+                auto_activate: profile.auto_activate,
+                input_device: devices.device_name,
+                output_device: devices.output_device_name,
+                name,
+            });
+        }
+        Ok(infos)
+    }
+
+    fn load_all(&self) -> anyhow::Result<Vec<(String, ConfigProfile)>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.dir.display()))
+            }
+        };
+
+        let mut profiles = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if validate_config_profile_name(name).is_err() {
+                continue;
+            }
+            match self.load(name) {
+                Ok(profile) => profiles.push((name.to_string(), profile)),
+                Err(e) => log::warn!("Skipping profile {}: {:#}", path.display(), e),
+            }
+        }
+        profiles.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(profiles)
+    }
+
+    pub fn load(&self, name: &str) -> anyhow::Result<ConfigProfile> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(Self::not_found(name).into());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).map_err(|e| {
+            CommandError::InvalidState(format!(
+                "Profile '{}' could not be read: {}",
+                name,
+                e.message()
+            ))
+            .into()
+        })
+    }
+
+    /// Save a new profile; an existing one with the same name is not replaced
+    pub fn create(&self, name: &str, profile: &ConfigProfile) -> anyhow::Result<()> {
+        let path = self.path(name)?;
+        if path.exists() {
+            return Err(CommandError::InvalidState(format!(
+                "A profile named '{}' already exists",
+                name
+            ))
+            .into());
+        }
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        store::write_atomic(&path, &toml::to_string_pretty(profile)?)?;
+        log::info!("Profile '{}' saved to: {}", name, path.display());
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> anyhow::Result<()> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(Self::not_found(name).into());
+        }
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))?;
+        log::info!("Profile '{}' deleted", name);
+        Ok(())
+    }
+
+    /// Profile to auto-activate after the input devices changed from `before` to `now`
+    ///
+    /// Only a device that was just plugged in counts, so a profile the user
+    /// switched away from is not re-activated while its device stays connected.
+    /// Devices are matched like the capture device (see [`identity`]), so other
+    /// devices changing index do not count. Ties go to the first name in
+    /// alphabetical order.
+    pub fn plugged_in(
+        &self,
+        before: &[DeviceId],
+        now: &[DeviceId],
+        active: Option<&str>,
+    ) -> Option<String> {
+        if now.len() <= before.len() && now.iter().all(|id| before.contains(id)) {
+            return None;
+        }
+        let profiles = self.load_all().unwrap_or_else(|e| {
+            log::warn!("Failed to read profiles: {:#}", e);
+            Vec::new()
+        });
+        profiles
+            .into_iter()
+            .find(|(name, profile)| {
+                profile.auto_activate
+                    && active != Some(name.as_str())
+                    && profile
+                        .audio
+                        .as_ref()
+                        .and_then(|devices| devices.stable_input_id.as_ref())
+                        .is_some_and(|id| {
+                            identity::best_match(id, now).is_some()
+                                && identity::best_match(id, before).is_none()
+                        })
+            })
+            .map(|(name, _)| name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_profiles(name: &str) -> ConfigProfiles {
+        let dir = std::env::temp_dir().join(format!("ember-profiles-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        ConfigProfiles::new(dir)
+    }
+
+    fn headset() -> DeviceId {
+        DeviceId {
+            host_api: "ALSA".to_string(),
+            index: 3,
+            name: "hw:CARD=Headset,DEV=0".to_string(),
+            bus_path: Some("pci0000:00/0000:00:14.0/usb1/1-3/1-3:1.0".to_string()),
+            usb_id: Some("0d8c:0014".to_string()),
+            serial: None,
+        }
+    }
+
+    #[test]
+    fn test_create_list_delete() {
+        let profiles = temp_profiles("crud");
+        assert!(profiles.list(None).unwrap().is_empty());
+
+        let mut config = AppConfig::default();
+        config.audio.device_name = Some("USB Headset".to_string());
+        profiles
+            .create("living room", &ConfigProfile::from_config(&config, false))
+            .unwrap();
+        profiles
+            .create(
+                "desk",
+                &ConfigProfile::from_config(&AppConfig::default(), false),
+            )
+            .unwrap();
+        assert!(profiles
+            .create("desk", &ConfigProfile::from_config(&config, false))
+            .is_err());
+
+        let list = profiles.list(Some("desk")).unwrap();
+        let names: Vec<_> = list.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["desk", "living room"]);
+        assert!(list[0].active);
+        assert_eq!(list[1].input_device.as_deref(), Some("USB Headset"));
+
+        profiles.delete("desk").unwrap();
+        assert!(profiles.delete("desk").is_err());
+        assert!(profiles.load("../config").is_err());
+        assert_eq!(profiles.list(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_replaces_only_profile_sections() {
+        let mut source = AppConfig::default();
+        source.audio.device_name = Some("USB Headset".to_string());
+        source.kws.score_threshold = 0.8;
+        source.vad.threshold = 7.0; // edited by hand to an invalid value
+        source.ui.monitor_gain = 0.3;
+        let profile = ConfigProfile::from_config(&source, false);
+
+        let mut config = AppConfig::default();
+        config.hooks.timeout_ms = 1234;
+        let warnings = profile.apply("headset", &mut config);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field.as_deref(), Some("vad.threshold"));
+        assert_eq!(config.audio.device_name.as_deref(), Some("USB Headset"));
+        assert_eq!(config.kws.score_threshold, 0.8);
+        assert_eq!(config.vad.threshold, 0.5);
+        assert_eq!(config.ui.monitor_gain, 0.3);
+        assert_eq!(config.hooks.timeout_ms, 1234);
+        assert_eq!(config.active_profile.as_deref(), Some("headset"));
+    }
+
+    #[test]
+    fn test_apply_leaves_other_audio_settings_alone() {
+        let profile = ConfigProfile::from_config(&AppConfig::default(), false);
+
+        let mut config = AppConfig::default();
+        config.audio.recorder.enabled = true;
+        config.audio.aec.enabled = false;
+        config.audio.aec.tail_ms = 250;
+        config.audio.hop_ms = 20;
+        config.kws.keyword = "hey desk".to_string();
+        assert!(profile.apply("default", &mut config).is_empty());
+
+        assert!(config.audio.recorder.enabled);
+        assert!(!config.audio.aec.enabled);
+        assert_eq!(config.audio.aec.tail_ms, 250);
+        assert_eq!(config.audio.hop_ms, 20);
+        assert_eq!(config.kws.keyword, "hey desk");
+    }
+
+    #[test]
+    fn test_partial_profile_overrides_only_its_settings() {
+        let profiles = temp_profiles("partial");
+        fs::create_dir_all(&profiles.dir).unwrap();
+        fs::write(
+            profiles.dir.join("kws-only.toml"),
+            "auto_activate = false\n\n[kws]\nscore_threshold = 0.8\n",
+        )
+        .unwrap();
+        // Older profiles held whole sections; only the profile settings count
+        fs::write(
+            profiles.dir.join("old.toml"),
+            "monitor_gain = 0.2\n\n[audio]\ndevice_name = \"USB Headset\"\n\
+             frame_ms = 30\n\n[audio.recorder]\nenabled = false\n",
+        )
+        .unwrap();
+
+        let profile = profiles.load("kws-only").unwrap();
+        assert!(profile.audio.is_none() && profile.vad.threshold.is_none());
+        assert!(profile.monitor_gain.is_none());
+
+        let mut config = AppConfig::default();
+        config.audio.device_name = Some("Desk Mic".to_string());
+        config.vad.threshold = 0.7;
+        config.ui.monitor_gain = 0.3;
+        assert!(profile.apply("kws-only", &mut config).is_empty());
+
+        assert_eq!(config.kws.score_threshold, 0.8);
+        assert_eq!(config.audio.device_name.as_deref(), Some("Desk Mic"));
+        assert_eq!(config.vad.threshold, 0.7);
+        assert_eq!(config.ui.monitor_gain, 0.3);
+        assert_eq!(config.active_profile.as_deref(), Some("kws-only"));
+
+        config.audio.recorder.enabled = true;
+        let warnings = profiles.load("old").unwrap().apply("old", &mut config);
+        assert!(warnings.is_empty());
+        assert_eq!(config.audio.device_name.as_deref(), Some("USB Headset"));
+        assert_eq!(config.audio.frame_ms, 20);
+        assert!(config.audio.recorder.enabled);
+        assert_eq!(config.ui.monitor_gain, 0.2);
+
+        let list = profiles.list(None).unwrap();
+        assert_eq!(list[0].input_device, None);
+        assert_eq!(list[1].input_device.as_deref(), Some("USB Headset"));
+    }
+
+    #[test]
+    fn test_plugged_in() {
+        let profiles = temp_profiles("auto");
+        let mut config = AppConfig::default();
+        config.audio.stable_input_id = Some(headset());
+        profiles
+            .create("headset", &ConfigProfile::from_config(&config, true))
+            .unwrap();
+        profiles
+            .create("manual", &ConfigProfile::from_config(&config, false))
+            .unwrap();
+
+        let before = Vec::new();
+        let now = vec![headset()];
+        assert_eq!(
+            profiles.plugged_in(&before, &now, None).as_deref(),
+            Some("headset")
+        );
+        // Already active, or the device was already there
+        assert_eq!(profiles.plugged_in(&before, &now, Some("headset")), None);
+        assert_eq!(profiles.plugged_in(&now, &now, None), None);
+
+        // Another device plugged in ahead of it shifts its index
+        let mut shifted = headset();
+        shifted.index = 4;
+        let webcam = DeviceId {
+            name: "hw:CARD=Webcam,DEV=0".to_string(),
+            bus_path: None,
+            usb_id: None,
+            ..headset()
+        };
+        assert_eq!(profiles.plugged_in(&now, &[webcam, shifted], None), None);
+    }
+}
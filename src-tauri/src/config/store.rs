@@ -0,0 +1,462 @@
+//! Single writer for `config.toml`
+//!
+//! Commands change the shared in-memory [`AppConfig`] and call
+//! [`ConfigStore::save`]; saves within [`DEBOUNCE`] of each other become one
+//! write. Every write goes to a temporary file that is fsynced and renamed over
+//! `config.toml`, so a crash leaves either the old or the new file, never a
+//! partial one. The file is owner-only (0600) on Unix.
+//!
+//! The worker thread also polls the file. An edit made outside the app (users
+//! hand-edit it while the app runs) replaces the in-memory config and is
+//! announced as `config:changed`; handlers registered with
+//! [`ConfigStore::on_reload`] apply it (the app restarts the audio runtime
+//! when a section it was started from changed). Invalid values are reset in memory only; the
+//! file stays as the user wrote it. Text that is not valid TOML is ignored
+//! until it is fixed. If the file was edited after a pending in-app change, the
+//! edit wins and the in-app change is dropped.
+
+use super::{AppConfig, ConfigWarning};
+use crate::error::CommandError;
+use crate::events::{Event, SharedEventSink};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Quiet period before pending changes are written
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How often the file is checked for external edits
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sections the audio runtime (capture, KWS, VAD, tagging) is started from
+const RUNTIME_SECTIONS: &[&str] = &["audio", "kws", "vad", "tagging"];
+
+/// Called on the store thread after an external edit was reloaded
+type ReloadHandler = Box<dyn Fn(&ConfigChangedPayload) + Send + Sync>;
+
+/// `config.toml` was edited outside the app and reloaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigChangedPayload {
+    /// Top-level sections whose values changed (e.g. `["kws", "vad"]`)
+    pub sections: Vec<String>,
+    /// Invalid values that were reset to their defaults (the file is left as is)
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl ConfigChangedPayload {
+    /// The running audio runtime was started from a section that changed
+    pub fn needs_audio_restart(&self) -> bool {
+        self.sections
+            .iter()
+            .any(|section| RUNTIME_SECTIONS.contains(&section.as_str()))
+    }
+}
+
+/// Owns `config.toml` while the app runs (see module docs)
+pub struct ConfigStore {
+    path: PathBuf,
+    config: Arc<Mutex<AppConfig>>,
+    state: Mutex<StoreState>,
+    wake: Condvar,
+    handlers: Mutex<Vec<ReloadHandler>>,
+}
+
+struct StoreState {
+    /// When the pending debounced write is due
+    pending: Option<Instant>,
+    /// File content last written or reloaded; anything else on disk is an external edit
+    known: Option<String>,
+    /// Unparseable content already reported, to log it once
+    rejected: Option<String>,
+    stopped: bool,
+}
+
+impl ConfigStore {
+    /// Manage `path`, whose content was loaded into `config`
+    pub fn new(path: PathBuf, config: Arc<Mutex<AppConfig>>) -> Arc<Self> {
+        let known = fs::read_to_string(&path).ok();
+        Arc::new(Self {
+            path,
+            config,
+            state: Mutex::new(StoreState {
+                pending: None,
+                known,
+                rejected: None,
+                stopped: false,
+            }),
+            wake: Condvar::new(),
+            handlers: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Write the in-memory config after [`DEBOUNCE`] without further saves
+    ///
+    /// Only takes effect once the worker runs (see [`ConfigStore::start`]).
+    pub fn save(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.pending = Some(Instant::now() + DEBOUNCE);
+        self.wake.notify_all();
+    }
+
+    /// Change the in-memory config and schedule a save
+    pub fn update(&self, change: impl FnOnce(&mut AppConfig)) {
+        change(&mut self.config.lock().unwrap());
+        self.save();
+    }
+
+    /// Write the in-memory config now, reporting failures
+    pub fn flush(&self) -> anyhow::Result<()> {
+        // Snapshot first: callers may hold the config lock while calling save()
+        let snapshot = self.config.lock().unwrap().clone();
+        let content = toml::to_string_pretty(&snapshot)?;
+
+        let mut state = self.state.lock().unwrap();
+        state.pending = None;
+        if state.known.as_deref() == Some(content.as_str()) {
+            return Ok(());
+        }
+
+        let on_disk = fs::read_to_string(&self.path).ok();
+        if on_disk.is_some() && on_disk != state.known {
+            return Err(CommandError::InvalidState(format!(
+                "{} was edited outside the app; it will be reloaded instead of overwritten",
+                self.path.display()
+            ))
+            .into());
+        }
+
+        write_atomic(&self.path, &content)?;
+        state.known = Some(content);
+        log::info!("Config saved to: {}", self.path.display());
+        Ok(())
+    }
+
+    /// Run `handler` after every reload of an external edit, once the new
+    /// config is in place
+    pub fn on_reload(&self, handler: impl Fn(&ConfigChangedPayload) + Send + Sync + 'static) {
+        self.handlers.lock().unwrap().push(Box::new(handler));
+    }
+
+    /// Start the worker that performs debounced writes and watches the file
+    pub fn start(self: &Arc<Self>, sink: SharedEventSink) -> JoinHandle<()> {
+        let store = Arc::clone(self);
+        std::thread::Builder::new()
+            .name("config-store".to_string())
+            .spawn(move || store.run(sink))
+            .expect("Failed to spawn config store thread")
+    }
+
+    /// Write pending changes and stop the worker
+    pub fn stop(&self) {
+        let pending = {
+            let mut state = self.state.lock().unwrap();
+            state.stopped = true;
+            self.wake.notify_all();
+            state.pending.is_some()
+        };
+        if pending {
+            if let Err(e) = self.flush() {
+                log::error!("Failed to save config: {:#}", e);
+            }
+        }
+    }
+
+    fn run(&self, sink: SharedEventSink) {
+        let mut next_poll = Instant::now() + POLL_INTERVAL;
+        loop {
+            let due = {
+                let state = self.state.lock().unwrap();
+                if state.stopped {
+                    break;
+                }
+                let now = Instant::now();
+                let deadline = state.pending.map_or(next_poll, |p| p.min(next_poll));
+                if deadline > now {
+                    let _ = self.wake.wait_timeout(state, deadline - now).unwrap();
+                    continue;
+                }
+                state.pending.is_some_and(|p| p <= now)
+            };
+
+            // Edits on disk are picked up before writing over them
+            if Instant::now() >= next_poll || due {
+                if let Some(payload) = self.reload_if_changed() {
+                    for handler in self.handlers.lock().unwrap().iter() {
+                        handler(&payload);
+                    }
+                    if let Err(e) = sink.publish(Event::ConfigChanged(payload)) {
+                        log::error!("Failed to emit config:changed: {}", e);
+                    }
+                }
+                next_poll = Instant::now() + POLL_INTERVAL;
+            }
+
+            if due {
+                if let Err(e) = self.flush() {
+                    log::error!("Failed to save config: {:#}", e);
+                }
+            }
+        }
+    }
+
+    /// Reload the file if it differs from what the app last wrote or read
+    fn reload_if_changed(&self) -> Option<ConfigChangedPayload> {
+        // A missing file is recreated by the next save
+        let content = fs::read_to_string(&self.path).ok()?;
+
+        let mut state = self.state.lock().unwrap();
+        if state.known.as_deref() == Some(content.as_str()) {
+            return None;
+        }
+
+        let loaded = match AppConfig::from_toml(&content) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                if state.rejected.as_deref() != Some(content.as_str()) {
+                    log::warn!(
+                        "Ignoring edit to {} until it is fixed: {}",
+                        self.path.display(),
+                        e
+                    );
+                    state.rejected = Some(content);
+                }
+                return None;
+            }
+        };
+
+        // The edit replaces any change still waiting to be written
+        if state.pending.take().is_some() {
+            log::warn!("Config edited outside the app; unsaved in-app change dropped");
+        }
+        state.known = Some(content);
+        state.rejected = None;
+        drop(state);
+
+        let sections = {
+            let mut config = self.config.lock().unwrap();
+            let sections = changed_sections(&config, &loaded.config);
+            *config = loaded.config;
+            sections
+        };
+
+        for warning in &loaded.warnings {
+            log::warn!("Config: {}", warning);
+        }
+        log::info!("Config reloaded from disk (changed: {:?})", sections);
+
+        Some(ConfigChangedPayload {
+            sections,
+            warnings: loaded.warnings,
+        })
+    }
+}
+
+/// Top-level sections that differ between two configs
+fn changed_sections(old: &AppConfig, new: &AppConfig) -> Vec<String> {
+    let (Ok(toml::Value::Table(old)), Ok(toml::Value::Table(new))) =
+        (toml::Value::try_from(old), toml::Value::try_from(new))
+    else {
+        return Vec::new();
+    };
+
+    let mut sections: Vec<String> = new
+        .iter()
+        .filter(|(name, value)| old.get(*name) != Some(*value))
+        .map(|(name, _)| name.clone())
+        .chain(old.keys().filter(|name| !new.contains_key(*name)).cloned())
+        .collect();
+    sections.sort();
+    sections
+}
+
+/// Replace `path` with `contents`: temp file in the same directory, fsync, rename
+pub(super) fn write_atomic(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid config path: {}", path.display()))?;
+    let tmp = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    // The temp file may predate this write with other permissions
+    restrict_permissions(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+
+    // Persist the rename itself
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Make a config file owner-only (0600); no-op on other platforms
+pub(super) fn restrict_permissions(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventSink;
+
+    /// Records published event names
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<(String, serde_json::Value)>>);
+
+    impl EventSink for RecordingSink {
+        fn emit_value(&self, event: &str, payload: serde_json::Value) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push((event.to_string(), payload));
+            Ok(())
+        }
+    }
+
+    fn temp_store(name: &str) -> (Arc<ConfigStore>, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("ember-store-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let config = AppConfig::load(&path).config;
+        let store = ConfigStore::new(path.clone(), Arc::new(Mutex::new(config)));
+        (store, path)
+    }
+
+    #[test]
+    fn test_flush_writes_atomically_with_owner_only_permissions() {
+        let (store, path) = temp_store("flush");
+        store.update(|c| c.kws.keyword = "hola ember".to_string());
+        store.flush().unwrap();
+
+        let saved = AppConfig::from_toml(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.config.kws.keyword, "hola ember");
+        assert!(!path.with_file_name(".config.toml.tmp").exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_debounced_saves_and_external_reload() {
+        let (store, path) = temp_store("watch");
+        let sink = Arc::new(RecordingSink::default());
+        let worker = store.start(sink.clone());
+
+        // A burst of saves is written once the debounce period passes
+        for threshold in [0.3, 0.4, 0.7] {
+            store.update(|c| c.vad.threshold = threshold);
+        }
+        std::thread::sleep(DEBOUNCE + Duration::from_millis(300));
+        let saved = AppConfig::from_toml(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.config.vad.threshold, 0.7);
+
+        // Our own write is not reported as an external change
+        std::thread::sleep(POLL_INTERVAL + Duration::from_millis(300));
+        assert!(sink.0.lock().unwrap().is_empty());
+
+        // A hand edit is reloaded and announced, invalid values reset in memory only
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("keyword = \"hey ember\"", "keyword = \"oye ember\"")
+            .replace("max_clients = 8", "max_clients = 0");
+        fs::write(&path, &edited).unwrap();
+        std::thread::sleep(POLL_INTERVAL + Duration::from_millis(500));
+
+        let events = sink.0.lock().unwrap().clone();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "config:changed");
+        // ipc.max_clients was reset to its current value, so only kws changed
+        assert_eq!(events[0].1["sections"], serde_json::json!(["kws"]));
+        assert_eq!(events[0].1["warnings"][0]["field"], "ipc.max_clients");
+
+        let config = store.config.lock().unwrap().clone();
+        assert_eq!(config.kws.keyword, "oye ember");
+        assert_eq!(config.ipc.max_clients, 8);
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+
+        store.stop();
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_external_kws_edit_reaches_restarted_worker() {
+        let (store, path) = temp_store("restart");
+        store.flush().unwrap();
+
+        // Stands in for the audio runtime: restarted with the latest config
+        let running = Arc::new(Mutex::new(store.config.lock().unwrap().kws.clone()));
+        let restarts = Arc::new(Mutex::new(0));
+        {
+            let config = Arc::clone(&store.config);
+            let running = Arc::clone(&running);
+            let restarts = Arc::clone(&restarts);
+            store.on_reload(move |change| {
+                if change.needs_audio_restart() {
+                    *running.lock().unwrap() = config.lock().unwrap().kws.clone();
+                    *restarts.lock().unwrap() += 1;
+                }
+            });
+        }
+        let worker = store.start(Arc::new(RecordingSink::default()));
+
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("score_threshold = 0.6", "score_threshold = 0.8");
+        assert_ne!(edited, fs::read_to_string(&path).unwrap());
+        fs::write(&path, &edited).unwrap();
+        std::thread::sleep(POLL_INTERVAL + Duration::from_millis(500));
+        assert_eq!(running.lock().unwrap().score_threshold, 0.8);
+        assert_eq!(*restarts.lock().unwrap(), 1);
+
+        // Sections the runtime does not use apply without a restart
+        let edited = edited.replace("max_clients = 8", "max_clients = 4");
+        fs::write(&path, &edited).unwrap();
+        std::thread::sleep(POLL_INTERVAL + Duration::from_millis(500));
+        assert_eq!(store.config.lock().unwrap().ipc.max_clients, 4);
+        assert_eq!(*restarts.lock().unwrap(), 1);
+
+        store.stop();
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_flush_refuses_to_overwrite_unread_edit() {
+        let (store, path) = temp_store("conflict");
+        fs::write(&path, "# edited by hand\n").unwrap();
+        store.update(|c| c.kws.enabled = false);
+        assert!(store.flush().is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# edited by hand\n");
+    }
+}
@@ -0,0 +1,274 @@
+//! Range checks for every config field
+//!
+//! Uses the same validators as command inputs (see [`crate::validation`]).
+//! An invalid value is reset to its default rather than rejecting the file.
+
+use super::{AppConfig, ConfigWarning};
+use crate::audio::channels::{ChannelMix, MAX_CHANNELS, MAX_MIC_OFFSET_MM};
+use crate::audio::DeviceId;
+use crate::model_manager::ModelManager;
+use crate::validation::{
+    validate_choice, validate_config_profile_name, validate_device_id, validate_device_name,
+    validate_keyword, validate_lang_code, validate_range, validate_vad_threshold, ValidationError,
+};
+use std::collections::HashMap;
+
+/// ONNX Runtime execution providers accepted by Sherpa-ONNX
+const PROVIDERS: &[&str] = &[
+    "cpu", "cuda", "coreml", "directml", "xnnpack", "nnapi", "trt",
+];
+
+fn provider(value: &str) -> Result<String, ValidationError> {
+    validate_choice("provider", value, PROVIDERS)
+}
+
+fn optional_model_id(value: &Option<String>) -> anyhow::Result<()> {
+    match value {
+        Some(id) => ModelManager::validate_model_id(id),
+        None => Ok(()),
+    }
+}
+
+fn optional_device_name(value: &Option<String>) -> Result<(), ValidationError> {
+    match value {
+        Some(name) => validate_device_name(name).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+fn optional_device_id(value: &Option<DeviceId>) -> Result<(), ValidationError> {
+    let Some(id) = value else {
+        return Ok(());
+    };
+    validate_device_id(
+        &id.host_api,
+        i32::try_from(id.index).unwrap_or(-1),
+        &id.name,
+    )?;
+    // Hardware properties follow the same rules as names (read from sysfs)
+    [&id.bus_path, &id.usb_id, &id.serial]
+        .into_iter()
+        .flatten()
+        .try_for_each(|value| validate_device_name(value).map(drop))
+}
+
+/// A duration in ms that is a whole number of samples at `rate`
+fn whole_samples(rate: u32) -> impl Fn(&u32) -> Result<(), ValidationError> {
+    move |ms| {
+        if (rate as u64 * *ms as u64).is_multiple_of(1000) {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidRange(format!(
+                "{} ms is not a whole number of samples at {} Hz",
+                ms, rate
+            )))
+        }
+    }
+}
+
+fn channel_mix(value: &ChannelMix) -> Result<(), ValidationError> {
+    let channel = |c: &u16| validate_range("Channel", *c, 0, MAX_CHANNELS - 1).map(drop);
+    match value {
+        ChannelMix::Average => Ok(()),
+        ChannelMix::Channel { channel: c } => channel(c),
+        ChannelMix::Subset { channels } => {
+            if channels.is_empty() {
+                return Err(ValidationError::InvalidFormat(
+                    "Channel subset is empty".to_string(),
+                ));
+            }
+            channels.iter().try_for_each(channel)
+        }
+        ChannelMix::Beam {
+            mic_positions_mm,
+            steer_deg,
+        } => {
+            validate_range(
+                "Mic count",
+                mic_positions_mm.len(),
+                2,
+                MAX_CHANNELS as usize,
+            )?;
+            mic_positions_mm.iter().flatten().try_for_each(|v| {
+                validate_range("Mic position", *v, -MAX_MIC_OFFSET_MM, MAX_MIC_OFFSET_MM).map(drop)
+            })?;
+            validate_range("Steering angle", *steer_deg, -360.0, 360.0).map(drop)
+        }
+    }
+}
+
+fn optional_profile_name(value: &Option<String>) -> Result<(), ValidationError> {
+    match value {
+        Some(name) => validate_config_profile_name(name).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+impl AppConfig {
+    /// Reset every invalid field to its default, returning one warning per field
+    pub(super) fn repair(&mut self) -> Vec<ConfigWarning> {
+        let defaults = AppConfig::default();
+        let mut warnings = Vec::new();
+
+        // check!(section.field, validator): the validator gets a reference to the value
+        macro_rules! check {
+            ($($field:ident).+, $validate:expr) => {
+                let result = ($validate)(&self.$($field).+);
+                if let Err(e) = result {
+                    warnings.push(ConfigWarning::field(
+                        [$(stringify!($field)),+].join("."),
+                        format!("{}; reset to default", e),
+                    ));
+                    self.$($field).+ = defaults.$($field).+.clone();
+                }
+            };
+        }
+
+        // range!(section.field, min, max): inclusive numeric range
+        macro_rules! range {
+            ($($field:ident).+, $min:expr, $max:expr) => {
+                check!($($field).+, |v: &_| validate_range("Value", *v, $min, $max))
+            };
+        }
+
+        check!(active_profile, optional_profile_name);
+
+        range!(audio.sample_rate_hz, 8000, 48000);
+        range!(audio.frame_ms, 10, 100);
+        check!(audio.frame_ms, whole_samples(self.audio.sample_rate_hz));
+        range!(audio.hop_ms, 5, self.audio.frame_ms);
+        check!(audio.hop_ms, whole_samples(self.audio.sample_rate_hz));
+        check!(audio.device_name, optional_device_name);
+        check!(audio.output_device_name, optional_device_name);
+        check!(audio.stable_input_id, optional_device_id);
+        check!(audio.stable_output_id, optional_device_id);
+        range!(audio.aec.tail_ms, 32, 500);
+        range!(audio.dsp.high_pass_hz, 20.0, 400.0);
+        range!(audio.dsp.noise_suppression_db, 0.0, 40.0);
+        range!(audio.dsp.agc_target_dbfs, -40.0, -6.0);
+        range!(audio.dsp.agc_max_gain_db, 0.0, 40.0);
+        check!(audio.channels, channel_mix);
+        range!(audio.recorder.segment_secs, 10, 3600);
+        range!(audio.recorder.clip_pre_ms, 0, 10_000);
+        range!(audio.recorder.clip_post_ms, 0, 10_000);
+        range!(audio.recorder.max_total_mb, 1, 10_000);
+        range!(audio.recorder.retention_hours, 1, 720);
+
+        check!(kws.keyword, |v: &String| validate_keyword(v));
+        range!(kws.score_threshold, 0.0, 1.0);
+        range!(kws.refractory_ms, 0, 60_000);
+        range!(kws.endpoint_ms, 50, 5000);
+        check!(kws.provider, |v: &String| provider(v));
+        range!(kws.max_active_paths, 1, 32);
+        check!(kws.model_id, optional_model_id);
+        check!(kws.mode, |v: &String| validate_choice(
+            "mode",
+            v,
+            &["stub", "real"]
+        ));
+
+        check!(vad.threshold, |v: &f32| validate_vad_threshold(*v));
+
+        range!(biometrics.enroll_utterances_min, 1, 20);
+        range!(biometrics.utterance_min_ms, 500, 10_000);
+        range!(biometrics.verify_threshold, 0.0, 1.0);
+        range!(biometrics.max_verify_ms, 1000, 30_000);
+
+        range!(ui.focus_ring_contrast_min, 1.0, 21.0);
+        range!(ui.min_touch_target_px, 16, 128);
+        range!(ui.monitor_gain, 0.0, 0.5);
+
+        check!(tagging.model_id, optional_model_id);
+        check!(tagging.model_type, |v: &String| {
+            validate_choice("model type", v, &["zipformer", "ced"])
+        });
+        check!(tagging.allowlist, |v: &Vec<String>| {
+            v.iter()
+                .try_for_each(|label| validate_range("Label length", label.len(), 1, 128).map(drop))
+        });
+        range!(tagging.score_threshold, 0.0, 1.0);
+        range!(tagging.window_ms, 100, 10_000);
+        range!(tagging.interval_ms, 100, 60_000);
+        range!(tagging.refractory_ms, 0, 600_000);
+        range!(tagging.top_k, 1, 50);
+        check!(tagging.provider, |v: &String| provider(v));
+
+        check!(punctuation.default_lang, |v: &String| validate_lang_code(v));
+        check!(punctuation.models, |v: &HashMap<String, String>| {
+            v.iter().try_for_each(|(lang, id)| {
+                validate_lang_code(lang)?;
+                ModelManager::validate_model_id(id)
+            })
+        });
+        range!(punctuation.num_threads, 1, 16);
+        check!(punctuation.provider, |v: &String| provider(v));
+
+        // Hook actions are checked by HookRunner::check_config when hooks start
+        range!(hooks.max_concurrent, 1, 16);
+        range!(hooks.timeout_ms, 100, 60_000);
+
+        range!(ipc.max_clients, 1, 64);
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_are_valid() {
+        assert!(AppConfig::default().repair().is_empty());
+    }
+
+    #[test]
+    fn test_repair_resets_only_invalid_fields() {
+        let mut config = AppConfig::default();
+        config.vad.threshold = 500.0;
+        config.kws.keyword = "hola ember".to_string();
+        config.kws.provider = "gpu".to_string();
+        config.audio.hop_ms = 50; // longer than the 20 ms frame
+        config.audio.channels = ChannelMix::Beam {
+            mic_positions_mm: vec![[0.0, 0.0]], // one mic is no array
+            steer_deg: 0.0,
+        };
+        config
+            .punctuation
+            .models
+            .insert("en".into(), "../evil".into());
+
+        let warnings = config.repair();
+        let fields: Vec<_> = warnings.iter().filter_map(|w| w.field.as_deref()).collect();
+        assert_eq!(
+            fields,
+            [
+                "audio.hop_ms",
+                "audio.channels",
+                "kws.provider",
+                "vad.threshold",
+                "punctuation.models"
+            ]
+        );
+
+        assert_eq!(config.vad.threshold, 0.5);
+        assert_eq!(config.kws.provider, "cpu");
+        assert_eq!(config.audio.hop_ms, 10);
+        assert_eq!(config.audio.channels, ChannelMix::Average);
+        assert!(config.punctuation.models.is_empty());
+        assert_eq!(config.kws.keyword, "hola ember");
+    }
+
+    #[test]
+    fn test_frame_and_hop_must_be_whole_samples() {
+        let mut config = AppConfig::default();
+        config.audio.sample_rate_hz = 44100;
+        config.audio.frame_ms = 30; // 1323 samples
+        config.audio.hop_ms = 15; // 661.5 samples
+        let warnings = config.repair();
+        let fields: Vec<_> = warnings.iter().filter_map(|w| w.field.as_deref()).collect();
+        assert_eq!(fields, ["audio.hop_ms"]);
+        assert_eq!(config.audio.frame_ms, 30);
+        assert_eq!(config.audio.hop_ms, 10);
+    }
+}
@@ -0,0 +1,518 @@
+//! Bug report bundles (`create_diagnostic_bundle`)
+//!
+//! Support needs logs, config and device state together. A bundle is one
+//! `emberleaf-diagnostics-<time>.tar.gz` in `<cache>/diagnostics` (never
+//! replacing an earlier one) holding:
+//! - `manifest.json`: app version, creation time and what was left out
+//! - the JSON reports added by the caller (audio snapshot, preflight, config,
+//!   model verification)
+//! - `logs/`: the end of each log file (see [`crate::logging`])
+//! - `clips/`: debug recordings (see [`crate::audio::recorder`]), only when
+//!   the user asks for them
+//!
+//! The home directory is replaced by `~` in every report and log, and hook
+//! arguments and URLs are removed from the config ([`redact_config`]).
+//! Voiceprints and their encryption key are never read: anything in the
+//! voiceprints directory, `.key` and `*.voiceprint` files are refused, also
+//! when reached through a symlink.
+//!
+//! Nothing is uploaded; the user attaches the file to a report.
+
+use crate::config::AppConfig;
+use crate::paths::AppPaths;
+use crate::registry::{verify_onnx_set, VerificationState};
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Tail kept of each log file
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Clips beyond this total are left out, oldest first
+const MAX_CLIP_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Replacement for removed config values
+const REDACTED: &str = "[redacted]";
+
+/// `manifest.json`
+#[derive(Debug, Serialize)]
+struct Manifest {
+    app_version: &'static str,
+    created: String,
+    /// Archive entries, in order
+    entries: Vec<String>,
+    /// Files left out, with the reason
+    skipped: Vec<String>,
+}
+
+/// Verification of one installed KWS model
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ModelVerification {
+    /// State per model file
+    Checked {
+        files: BTreeMap<String, VerificationState>,
+    },
+    /// Model files missing or unreadable
+    Failed { error: String },
+}
+
+/// `.tar.gz` being written
+pub struct DiagnosticBundle {
+    path: PathBuf,
+    /// Top-level directory inside the archive
+    root: String,
+    archive: tar::Builder<GzEncoder<File>>,
+    voiceprints: PathBuf,
+    home: Option<String>,
+    mtime: u64,
+    manifest: Manifest,
+}
+
+impl DiagnosticBundle {
+    /// Start a new bundle in `<cache>/diagnostics`
+    pub fn create(paths: &AppPaths) -> Result<Self> {
+        let dir = paths.diagnostics_dir();
+        fs::create_dir_all(&dir).context("Failed to create diagnostics directory")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+                .context("Failed to set diagnostics directory permissions")?;
+        }
+
+        // A bundle started in the same millisecond gets a suffix instead of
+        // replacing (or writing into) this one
+        let now = chrono::Local::now();
+        let stamp = format!("emberleaf-diagnostics-{}", now.format("%Y%m%d-%H%M%S%.3f"));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut attempt = 0;
+        let (root, path, file) = loop {
+            let root = match attempt {
+                0 => stamp.clone(),
+                n => format!("{}-{}", stamp, n),
+            };
+            let path = dir.join(format!("{}.tar.gz", root));
+            match options.open(&path) {
+                Ok(file) => break (root, path, file),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", path.display()))
+                }
+            }
+        };
+
+        let voiceprints = paths.voiceprints_dir();
+        Ok(Self {
+            path,
+            root,
+            archive: tar::Builder::new(GzEncoder::new(file, Compression::default())),
+            voiceprints: fs::canonicalize(&voiceprints).unwrap_or(voiceprints),
+            home: directories::BaseDirs::new()
+                .map(|dirs| dirs.home_dir().to_string_lossy().into_owned()),
+            mtime: now.timestamp().max(0) as u64,
+            manifest: Manifest {
+                app_version: env!("CARGO_PKG_VERSION"),
+                created: now.to_rfc3339(),
+                entries: Vec::new(),
+                skipped: Vec::new(),
+            },
+        })
+    }
+
+    /// Add a report as pretty-printed JSON, with the home directory redacted
+    pub fn add_json(&mut self, name: &str, value: &impl Serialize) -> Result<()> {
+        let json = serde_json::to_string_pretty(value)?;
+        let json = redact_home(&json, self.home.as_deref());
+        self.append(name, json.as_bytes())
+    }
+
+    /// Add the end of every `*.log` file in `dir` under `logs/`
+    pub fn add_logs(&mut self, dir: &Path) -> Result<()> {
+        for path in files_with_extension(dir, "log")? {
+            let Some(data) = self.read_allowed(&path, MAX_LOG_BYTES)? else {
+                continue;
+            };
+            let text = String::from_utf8_lossy(&data);
+            let text = redact_home(&text, self.home.as_deref());
+            self.append(&format!("logs/{}", file_name(&path)), text.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Add the newest `*.wav` recordings in `dir` under `clips/`, up to
+    /// [`MAX_CLIP_BYTES`]
+    pub fn add_clips(&mut self, dir: &Path) -> Result<()> {
+        let mut clips: Vec<(std::time::SystemTime, u64, PathBuf)> =
+            files_with_extension(dir, "wav")?
+                .into_iter()
+                .filter_map(|path| {
+                    let meta = fs::metadata(&path).ok()?;
+                    Some((meta.modified().ok()?, meta.len(), path))
+                })
+                .collect();
+        clips.sort_by_key(|clip| std::cmp::Reverse(clip.0));
+
+        let mut total = 0;
+        for (_, len, path) in clips {
+            if total + len > MAX_CLIP_BYTES {
+                self.skip(&path, "clip size limit");
+                continue;
+            }
+            let Some(data) = self.read_allowed(&path, len)? else {
+                continue;
+            };
+            total += len;
+            self.append(&format!("clips/{}", file_name(&path)), &data)?;
+        }
+        Ok(())
+    }
+
+    /// Write the manifest and close the archive
+    pub fn finish(mut self) -> Result<PathBuf> {
+        let manifest = serde_json::to_string_pretty(&self.manifest)?;
+        self.append("manifest.json", manifest.as_bytes())?;
+        let file = self
+            .archive
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .context("Failed to finish diagnostic bundle")?;
+        file.sync_all()?;
+        Ok(self.path)
+    }
+
+    fn append(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(self.mtime);
+        header.set_cksum();
+        self.archive
+            .append_data(&mut header, format!("{}/{}", self.root, name), data)
+            .with_context(|| format!("Failed to add {} to diagnostic bundle", name))?;
+        self.manifest.entries.push(name.to_string());
+        Ok(())
+    }
+
+    /// Last `max` bytes of a file, or None if it must not be bundled
+    fn read_allowed(&mut self, path: &Path, max: u64) -> Result<Option<Vec<u8>>> {
+        if is_secret(path, &self.voiceprints) {
+            log::warn!("Diagnostic bundle: refused {}", path.display());
+            self.skip(path, "refused");
+            return Ok(None);
+        }
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let len = file.metadata()?.len();
+        let mut data = Vec::new();
+        if len > max {
+            file.seek(SeekFrom::Start(len - max))?;
+            file.take(max).read_to_end(&mut data)?;
+            // Start at a line boundary
+            if let Some(newline) = data.iter().position(|&b| b == b'\n') {
+                data.drain(..=newline);
+            }
+        } else {
+            file.read_to_end(&mut data)?;
+        }
+        Ok(Some(data))
+    }
+
+    fn skip(&mut self, path: &Path, reason: &str) {
+        self.manifest
+            .skipped
+            .push(format!("{} ({})", file_name(path), reason));
+    }
+}
+
+/// Voiceprint data, wherever the path (or a symlink in it) leads
+fn is_secret(path: &Path, voiceprints: &Path) -> bool {
+    let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    [path, resolved.as_path()].iter().any(|p| {
+        p.starts_with(voiceprints)
+            || p.file_name().is_some_and(|name| name == ".key")
+            || p.extension().is_some_and(|ext| ext == "voiceprint")
+    })
+}
+
+/// Regular files (or symlinks to them) in `dir` with `extension`, by name;
+/// a missing directory has none
+fn files_with_extension(dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Replace the home directory with `~`
+fn redact_home(text: &str, home: Option<&str>) -> String {
+    match home {
+        Some(home) if home.len() > 1 => text.replace(home, "~"),
+        _ => text.to_string(),
+    }
+}
+
+/// Config as JSON without hook arguments and URL paths
+///
+/// Arguments and webhook URLs are where tokens end up; only the origin of a
+/// URL is kept.
+pub fn redact_config(config: &AppConfig) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(config)?;
+    let actions = value
+        .pointer_mut("/hooks/actions")
+        .and_then(|actions| actions.as_array_mut());
+    for action in actions.into_iter().flatten() {
+        let Some(action) = action.get_mut("action") else {
+            continue;
+        };
+        if let Some(args) = action.get_mut("args").and_then(|args| args.as_array_mut()) {
+            for arg in args {
+                *arg = REDACTED.into();
+            }
+        }
+        if let Some(url) = action.get_mut("url") {
+            let origin = url
+                .as_str()
+                .and_then(|u| url::Url::parse(u).ok())
+                .map(|u| format!("{}/{}", u.origin().ascii_serialization(), REDACTED))
+                .unwrap_or_else(|| REDACTED.to_string());
+            *url = origin.into();
+        }
+    }
+    Ok(value)
+}
+
+/// Verify every installed KWS model (hashes all model files, slow)
+pub fn verify_models(paths: &AppPaths) -> BTreeMap<String, ModelVerification> {
+    let Ok(entries) = fs::read_dir(paths.kws_models_root()) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .map(|dir| {
+            let state = match verify_onnx_set(&dir) {
+                Ok(files) => ModelVerification::Checked {
+                    files: files.into_iter().collect(),
+                },
+                Err(e) => ModelVerification::Failed {
+                    error: format!("{:#}", e),
+                },
+            };
+            (file_name(&dir), state)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::{HookAction, HookConfig};
+    use flate2::read::GzDecoder;
+
+    fn temp_paths(name: &str) -> AppPaths {
+        let root =
+            std::env::temp_dir().join(format!("ember-diagnostics-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        AppPaths {
+            config: root.join("config"),
+            data: root.join("data"),
+            cache: root.join("cache"),
+            runtime: root.join("run"),
+        }
+    }
+
+    /// Archive entries (without the top-level directory) and their contents
+    fn unpack(path: &Path) -> BTreeMap<String, Vec<u8>> {
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(path).unwrap()));
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().into_owned();
+                let (root, name) = name.split_once('/').unwrap();
+                assert!(root.starts_with("emberleaf-diagnostics-"));
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (name.to_string(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bundle_contains_reports_logs_and_opted_in_clips() {
+        let paths = temp_paths("contents");
+        fs::create_dir_all(paths.logs_dir()).unwrap();
+        fs::create_dir_all(paths.tmp_audio_dir()).unwrap();
+        fs::write(paths.logs_dir().join("ember.log"), "started\n").unwrap();
+        fs::write(paths.logs_dir().join("notes.txt"), "not a log").unwrap();
+        fs::write(paths.tmp_audio_dir().join("detection-1.wav"), b"RIFF").unwrap();
+
+        let mut bundle = DiagnosticBundle::create(&paths).unwrap();
+        bundle
+            .add_json("config.json", &serde_json::json!({"a": 1}))
+            .unwrap();
+        bundle.add_logs(&paths.logs_dir()).unwrap();
+        let path = bundle.finish().unwrap();
+        assert!(path.starts_with(paths.diagnostics_dir()));
+
+        let entries = unpack(&path);
+        let names: Vec<&str> = entries.keys().map(String::as_str).collect();
+        assert_eq!(names, ["config.json", "logs/ember.log", "manifest.json"]);
+        assert_eq!(entries["logs/ember.log"], b"started\n");
+
+        let mut bundle = DiagnosticBundle::create(&paths).unwrap();
+        bundle.add_clips(&paths.tmp_audio_dir()).unwrap();
+        let entries = unpack(&bundle.finish().unwrap());
+        assert_eq!(entries["clips/detection-1.wav"], b"RIFF");
+
+        fs::remove_dir_all(paths.cache.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_bundles_started_together_get_their_own_files() {
+        let paths = temp_paths("names");
+        let mut first = DiagnosticBundle::create(&paths).unwrap();
+        let mut second = DiagnosticBundle::create(&paths).unwrap();
+        assert_ne!(first.path, second.path);
+
+        first.add_json("first.json", &1).unwrap();
+        second.add_json("second.json", &2).unwrap();
+        let second = unpack(&second.finish().unwrap());
+        let first = unpack(&first.finish().unwrap());
+        assert!(first.contains_key("first.json") && !first.contains_key("second.json"));
+        assert!(second.contains_key("second.json") && !second.contains_key("first.json"));
+
+        fs::remove_dir_all(paths.cache.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_voiceprints_and_key_are_never_bundled() {
+        let paths = temp_paths("secrets");
+        let voiceprints = paths.voiceprints_dir();
+        fs::create_dir_all(&voiceprints).unwrap();
+        fs::create_dir_all(paths.tmp_audio_dir()).unwrap();
+        fs::create_dir_all(paths.logs_dir()).unwrap();
+        fs::write(voiceprints.join("alice.voiceprint"), "secret").unwrap();
+        fs::write(voiceprints.join(".key"), "secret").unwrap();
+
+        // Symlinks into the voiceprints directory, named like clips and logs
+        let clips = paths.tmp_audio_dir();
+        std::os::unix::fs::symlink(voiceprints.join(".key"), clips.join("key.wav")).unwrap();
+        std::os::unix::fs::symlink(voiceprints.join("alice.voiceprint"), clips.join("a.wav"))
+            .unwrap();
+        fs::write(clips.join("stream-1.wav"), b"RIFF").unwrap();
+        std::os::unix::fs::symlink(voiceprints.join(".key"), paths.logs_dir().join("key.log"))
+            .unwrap();
+
+        let mut bundle = DiagnosticBundle::create(&paths).unwrap();
+        bundle.add_clips(&clips).unwrap();
+        bundle.add_logs(&paths.logs_dir()).unwrap();
+        let entries = unpack(&bundle.finish().unwrap());
+
+        let names: Vec<&str> = entries.keys().map(String::as_str).collect();
+        assert_eq!(names, ["clips/stream-1.wav", "manifest.json"]);
+        assert!(entries
+            .values()
+            .all(|data| !String::from_utf8_lossy(data).contains("secret")));
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&entries["manifest.json"]).unwrap();
+        assert_eq!(manifest["skipped"].as_array().unwrap().len(), 3);
+
+        fs::remove_dir_all(paths.cache.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_log_tail_starts_at_a_line() {
+        let paths = temp_paths("tail");
+        fs::create_dir_all(paths.logs_dir()).unwrap();
+        let line = "x".repeat(99) + "\n";
+        let log = line.repeat(30_000) + "last line\n"; // ~3 MB
+        fs::write(paths.logs_dir().join("ember.1.log"), &log).unwrap();
+
+        let mut bundle = DiagnosticBundle::create(&paths).unwrap();
+        bundle.add_logs(&paths.logs_dir()).unwrap();
+        let entries = unpack(&bundle.finish().unwrap());
+
+        let tail = &entries["logs/ember.1.log"];
+        assert!(tail.len() as u64 <= MAX_LOG_BYTES);
+        assert!(tail.starts_with(line.as_bytes()));
+        assert!(tail.ends_with(b"last line\n"));
+
+        fs::remove_dir_all(paths.cache.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_redaction() {
+        assert_eq!(
+            redact_home("/home/sam/.cache/Emberleaf/tmp_audio", Some("/home/sam")),
+            "~/.cache/Emberleaf/tmp_audio"
+        );
+        assert_eq!(redact_home("/x", Some("/")), "/x");
+
+        let mut config = AppConfig::default();
+        config.hooks.actions = vec![
+            HookConfig {
+                name: "lights".into(),
+                events: vec!["wakeword::detected".into()],
+                intents: Vec::new(),
+                labels: Vec::new(),
+                action: HookAction::Http {
+                    url: "http://127.0.0.1:8123/api/webhook/s3cr3t?token=abc".into(),
+                },
+                timeout_ms: None,
+            },
+            HookConfig {
+                name: "notify".into(),
+                events: vec!["wakeword::detected".into()],
+                intents: Vec::new(),
+                labels: Vec::new(),
+                action: HookAction::Exec {
+                    path: "notify.sh".into(),
+                    args: vec!["--token".into(), "s3cr3t".into()],
+                },
+                timeout_ms: None,
+            },
+        ];
+
+        let value = redact_config(&config).unwrap();
+        let actions = &value["hooks"]["actions"];
+        assert_eq!(
+            actions[0]["action"]["url"],
+            "http://127.0.0.1:8123/[redacted]"
+        );
+        assert_eq!(
+            actions[1]["action"]["args"],
+            serde_json::json!(["[redacted]", "[redacted]"])
+        );
+        assert_eq!(actions[1]["action"]["path"], "notify.sh");
+        assert!(!value.to_string().contains("s3cr3t"));
+        assert_eq!(value["kws"], serde_json::to_value(&config.kws).unwrap());
+    }
+}
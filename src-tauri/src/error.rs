@@ -0,0 +1,383 @@
+//! Errors returned by commands
+//!
+//! Tauri commands and IPC calls fail with a [`CommandError`], serialized as
+//! `{ code, message, detail?, field?, retryable }`. `code` is stable and is
+//! what the frontend matches on to pick a localized message; `message` is the
+//! English fallback and `detail` the technical cause for logs and bug reports.
+//!
+//! Library code keeps returning `anyhow::Result` and raises a `CommandError`
+//! where the cause is known (device missing, model not installed...). The
+//! `From<anyhow::Error>` conversion finds it in the error chain, or classifies
+//! known CPAL, I/O and HTTP errors by type.
+
+use crate::events::AudioErrorPayload;
+use crate::validation::ValidationError;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use thiserror::Error;
+
+/// Failure of a command, with a stable code
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CommandError {
+    /// Another application holds the audio device
+    #[error("Audio device is busy: {0}")]
+    DeviceBusy(String),
+
+    /// The selected device disappeared or was never there
+    #[error("Audio device not found: {0}")]
+    DeviceNotFound(String),
+
+    /// No device of the requested kind at all
+    #[error("No audio device available: {0}")]
+    NoDevice(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// Device does not support any sample format/config we can use
+    #[error("Unsupported audio format: {0}")]
+    UnsupportedFormat(String),
+
+    /// Model is not in the registry or not installed (payload: model ID)
+    #[error("Model '{0}' is not available")]
+    ModelMissing(String),
+
+    #[error("Download failed: {0}")]
+    DownloadFailed(String),
+
+    /// Downloaded model did not match its checksum (payload: model ID)
+    #[error("Model '{0}' failed verification")]
+    VerificationFailed(String),
+
+    /// Rejected command input
+    #[error("{message}")]
+    Validation {
+        field: Option<String>,
+        message: String,
+    },
+
+    /// No voiceprint for this user
+    #[error("No voiceprint found for user: {0}")]
+    ProfileNotFound(String),
+
+    /// Operation not allowed right now (e.g. no enrollment in progress)
+    #[error("{0}")]
+    InvalidState(String),
+
+    /// Feature not built in or not initialized
+    #[error("{0}")]
+    Unavailable(String),
+
+    /// Reading or writing a file failed
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl CommandError {
+    /// Validation failure for one input field
+    pub fn validation(field: &str, message: impl fmt::Display) -> Self {
+        Self::Validation {
+            field: Some(field.to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn internal(error: impl fmt::Display) -> Self {
+        Self::Internal(error.to_string())
+    }
+
+    /// Stable machine-readable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DeviceBusy(_) => "device_busy",
+            Self::DeviceNotFound(_) => "device_not_found",
+            Self::NoDevice(_) => "no_device",
+            Self::PermissionDenied(_) => "permission_denied",
+            Self::Timeout(_) => "timeout",
+            Self::UnsupportedFormat(_) => "unsupported_format",
+            Self::ModelMissing(_) => "model_missing",
+            Self::DownloadFailed(_) => "download_failed",
+            Self::VerificationFailed(_) => "verification_failed",
+            Self::Validation { .. } => "validation",
+            Self::ProfileNotFound(_) => "profile_not_found",
+            Self::InvalidState(_) => "invalid_state",
+            Self::Unavailable(_) => "unavailable",
+            Self::Io(_) => "io_error",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// English message for the user (the frontend localizes by `code`)
+    pub fn message(&self) -> String {
+        match self {
+            Self::DeviceBusy(_) => {
+                "Another app is using this audio device. Close other audio apps and try again."
+                    .to_string()
+            }
+            Self::DeviceNotFound(_) => {
+                "Audio device was unplugged or is no longer available. Please reconnect it."
+                    .to_string()
+            }
+            Self::NoDevice(_) => "No audio device found. Please connect one.".to_string(),
+            Self::PermissionDenied(_) => {
+                "Permission denied. Check your system's microphone settings.".to_string()
+            }
+            Self::Timeout(_) => "The operation timed out. Please try again.".to_string(),
+            Self::UnsupportedFormat(_) => "This audio device is not supported.".to_string(),
+            Self::ModelMissing(id) => format!("Model '{}' is not available.", id),
+            Self::DownloadFailed(_) => {
+                "Download failed. Check your internet connection and try again.".to_string()
+            }
+            Self::VerificationFailed(id) => {
+                format!("Model '{}' was corrupted and has been removed.", id)
+            }
+            Self::Validation { message, .. } => message.clone(),
+            Self::ProfileNotFound(user) => format!("No voice profile for '{}'.", user),
+            Self::InvalidState(message) | Self::Unavailable(message) => message.clone(),
+            Self::Io(_) => "Could not read or write a file.".to_string(),
+            Self::Internal(_) => "An unexpected error occurred.".to_string(),
+        }
+    }
+
+    /// Technical cause, if it adds anything to [`message`](Self::message)
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            Self::DeviceBusy(d)
+            | Self::DeviceNotFound(d)
+            | Self::NoDevice(d)
+            | Self::PermissionDenied(d)
+            | Self::Timeout(d)
+            | Self::UnsupportedFormat(d)
+            | Self::DownloadFailed(d)
+            | Self::Io(d)
+            | Self::Internal(d) => Some(d),
+            Self::ModelMissing(_)
+            | Self::VerificationFailed(_)
+            | Self::Validation { .. }
+            | Self::ProfileNotFound(_)
+            | Self::InvalidState(_)
+            | Self::Unavailable(_) => None,
+        }
+    }
+
+    /// Whether trying again (possibly after reconnecting a device) may succeed
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::DeviceBusy(_)
+                | Self::DeviceNotFound(_)
+                | Self::Timeout(_)
+                | Self::DownloadFailed(_)
+                | Self::VerificationFailed(_)
+        )
+    }
+
+    fn field(&self) -> Option<&str> {
+        match self {
+            Self::Validation { field, .. } => field.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CommandErrorBody<'a> {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'a str>,
+    retryable: bool,
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CommandErrorBody {
+            code: self.code(),
+            message: self.message(),
+            detail: self.detail(),
+            field: self.field(),
+            retryable: self.retryable(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl From<&CommandError> for AudioErrorPayload {
+    fn from(error: &CommandError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.message(),
+            field: error.field().map(str::to_string),
+            value: None,
+        }
+    }
+}
+
+impl From<ValidationError> for CommandError {
+    fn from(error: ValidationError) -> Self {
+        Self::Validation {
+            field: None,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        classify_io(&error, error.to_string())
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(error: anyhow::Error) -> Self {
+        let detail = format!("{:#}", error);
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<CommandError>() {
+                return e.clone();
+            }
+            if let Some(e) = cause.downcast_ref::<ValidationError>() {
+                return Self::Validation {
+                    field: None,
+                    message: e.to_string(),
+                };
+            }
+            if let Some(e) = classify_cpal(cause, &detail) {
+                return e;
+            }
+            if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+                return classify_io(e, detail);
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return if e.is_timeout() {
+                    Self::Timeout(detail)
+                } else {
+                    Self::DownloadFailed(detail)
+                };
+            }
+        }
+        Self::Internal(detail)
+    }
+}
+
+fn classify_io(error: &std::io::Error, detail: String) -> CommandError {
+    use std::io::ErrorKind;
+    match error.kind() {
+        ErrorKind::PermissionDenied => CommandError::PermissionDenied(detail),
+        ErrorKind::TimedOut => CommandError::Timeout(detail),
+        _ => CommandError::Io(detail),
+    }
+}
+
+/// CPAL reports both "busy" (EBUSY) and "gone" as `DeviceNotAvailable`. When
+/// building or starting a stream the device was just enumerated, so it is
+/// almost always busy; when querying its configs it has usually vanished.
+fn classify_cpal(cause: &(dyn std::error::Error + 'static), detail: &str) -> Option<CommandError> {
+    use cpal::{
+        BuildStreamError, DefaultStreamConfigError, PlayStreamError, SupportedStreamConfigsError,
+    };
+
+    let detail = detail.to_string();
+    if let Some(e) = cause.downcast_ref::<BuildStreamError>() {
+        return Some(match e {
+            BuildStreamError::DeviceNotAvailable => CommandError::DeviceBusy(detail),
+            BuildStreamError::StreamConfigNotSupported | BuildStreamError::InvalidArgument => {
+                CommandError::UnsupportedFormat(detail)
+            }
+            _ => CommandError::Internal(detail),
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<PlayStreamError>() {
+        return Some(match e {
+            PlayStreamError::DeviceNotAvailable => CommandError::DeviceBusy(detail),
+            _ => CommandError::Internal(detail),
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<DefaultStreamConfigError>() {
+        return Some(match e {
+            DefaultStreamConfigError::DeviceNotAvailable => CommandError::DeviceNotFound(detail),
+            DefaultStreamConfigError::StreamTypeNotSupported => {
+                CommandError::UnsupportedFormat(detail)
+            }
+            _ => CommandError::Internal(detail),
+        });
+    }
+    if let Some(e) = cause.downcast_ref::<SupportedStreamConfigsError>() {
+        return Some(match e {
+            SupportedStreamConfigsError::DeviceNotAvailable => CommandError::DeviceNotFound(detail),
+            _ => CommandError::Internal(detail),
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialized_shape() {
+        let error = CommandError::validation("gain", "Gain must be between 0.0 and 0.5");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "validation",
+                "message": "Gain must be between 0.0 and 0.5",
+                "field": "gain",
+                "retryable": false,
+            })
+        );
+
+        let value = serde_json::to_value(CommandError::DeviceBusy("EBUSY".into())).unwrap();
+        assert_eq!(value["code"], "device_busy");
+        assert_eq!(value["detail"], "EBUSY");
+        assert_eq!(value["retryable"], true);
+    }
+
+    #[test]
+    fn test_from_anyhow_keeps_command_error() {
+        let error: anyhow::Result<()> = Err(CommandError::ModelMissing("hey_ember".into()).into());
+        let error = error.context("Failed to enable KWS").unwrap_err();
+        assert_eq!(
+            CommandError::from(error),
+            CommandError::ModelMissing("hey_ember".into())
+        );
+    }
+
+    #[test]
+    fn test_from_anyhow_classifies_by_type() {
+        let error = anyhow::Error::new(cpal::BuildStreamError::DeviceNotAvailable)
+            .context("Failed to start capture");
+        let error = CommandError::from(error);
+        assert_eq!(error.code(), "device_busy");
+        assert!(error
+            .detail()
+            .unwrap()
+            .starts_with("Failed to start capture: "));
+
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "config.toml");
+        assert_eq!(
+            CommandError::from(anyhow::Error::new(io)).code(),
+            "permission_denied"
+        );
+
+        let validation = ValidationError::InvalidRange("out of range".into());
+        assert_eq!(
+            CommandError::from(anyhow::Error::new(validation)).code(),
+            "validation"
+        );
+
+        // Plain messages are not guessed at
+        let error = CommandError::from(anyhow::anyhow!("device busy"));
+        assert_eq!(error, CommandError::Internal("device busy".into()));
+    }
+}
@@ -0,0 +1,278 @@
+//! Catalog of backend events
+//!
+//! Every event the backend emits is a variant of [`Event`], declared once in
+//! the `events!` table below with its wire name and payload type. Payloads
+//! that belong to a subsystem (wake word, hooks, preflight...) are defined
+//! there; the small ones used by the device and restart commands live here.
+//!
+//! Bump [`EVENT_SCHEMA_VERSION`] whenever an event is removed or renamed or a
+//! payload changes shape, then regenerate the TypeScript and JSON Schema
+//! files (`npm run gen:events`).
+
+use crate::audio::kws::WakeWordEvent;
+use crate::audio::metrics::PipelineMetricsSnapshot;
+use crate::audio::tagging::AudioEventTag;
+use crate::audio::DeviceId;
+use crate::commands::IntentMatch;
+use crate::config::{ConfigChangedPayload, ProfileActivatedPayload};
+use crate::hooks::HookResult;
+use crate::model_manager::ModelDownloadProgress;
+use crate::preflight::{PreflightItem, PreflightReport};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Version of the event names and payload shapes
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Audio failure shown to the user
+///
+/// `code` selects a localized message in the frontend; `message` is the
+/// fallback. Validation failures also name the rejected `field` and `value`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AudioErrorPayload {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+}
+
+impl AudioErrorPayload {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            field: None,
+            value: None,
+        }
+    }
+}
+
+/// Audio capture restarted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RestartOkPayload {
+    /// Input device name ("default" for the system default)
+    pub device: String,
+    pub elapsed_ms: u64,
+    /// Why the restart happened, if not requested by the user (e.g. "device_fallback")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TestTonePayload {
+    pub device: String,
+    pub frequency_hz: f32,
+    pub duration_ms: u32,
+    pub volume: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProbeSuggestionPayload {
+    pub device: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DeviceChangedPayload {
+    /// "input" or "output"
+    pub kind: String,
+    pub device: DeviceId,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DeviceLostPayload {
+    /// "input" or "output"
+    pub kind: String,
+    pub previous: DeviceId,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FallbackOkPayload {
+    /// "input" or "output"
+    pub kind: String,
+    pub new_device: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FallbackFailedPayload {
+    /// "input" or "output"
+    pub kind: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MonitorGuardedPayload {
+    /// e.g. "feedback_risk" when input and output are the same device
+    pub reason: String,
+}
+
+/// Real-KWS detection, used by the QA-019 test window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct KwsDetectionPayload {
+    pub model_id: String,
+    pub keyword: String,
+    /// Milliseconds since UNIX epoch
+    pub ts: u64,
+}
+
+/// Name, description and payload schema of one event
+#[derive(Debug, Clone)]
+pub struct EventSpec {
+    pub name: &'static str,
+    pub description: String,
+    pub payload: Schema,
+}
+
+macro_rules! events {
+    ($( $(#[doc = $doc:literal])* $variant:ident($payload:ty) = $name:literal, )*) => {
+        /// Every event emitted by the backend, with its payload
+        #[derive(Debug, Clone)]
+        pub enum Event {
+            $( $(#[doc = $doc])* $variant($payload), )*
+        }
+
+        /// Wire names of all events, in catalog order
+        pub const EVENT_NAMES: &[&str] = &[$($name),*];
+
+        impl Event {
+            /// Wire name (e.g. `audio:rms`)
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( Event::$variant(_) => $name, )*
+                }
+            }
+
+            /// Wire name and serialized payload
+            pub fn into_parts(self) -> serde_json::Result<(&'static str, serde_json::Value)> {
+                match self {
+                    $( Event::$variant(payload) => Ok(($name, serde_json::to_value(payload)?)), )*
+                }
+            }
+        }
+
+        /// Specs of all events; payload types are added to `gen`'s definitions
+        pub fn catalog(gen: &mut SchemaGenerator) -> Vec<EventSpec> {
+            vec![
+                $(
+                    EventSpec {
+                        name: $name,
+                        description: {
+                            let lines: &[&str] = &[$($doc),*];
+                            lines.iter().map(|l| l.trim()).collect::<Vec<_>>().join(" ")
+                        },
+                        payload: gen.subschema_for::<$payload>(),
+                    },
+                )*
+            ]
+        }
+    };
+}
+
+events! {
+    /// Input level (normalized RMS 0.0-1.0), ~20-50 times per second
+    AudioRms(f32) = "audio:rms",
+    /// Audio failure or rejected command input
+    AudioError(AudioErrorPayload) = "audio:error",
+    /// Audio capture restarted
+    AudioRestartOk(RestartOkPayload) = "audio:restart_ok",
+    /// Restart ignored because the previous one was too recent
+    AudioRestartBlocked(()) = "audio:restart_blocked",
+    /// Test tone finished playing
+    AudioTestTonePlayed(TestTonePayload) = "audio:test_tone_played",
+    /// Input device probe started
+    AudioAutoProbeStarted(()) = "audio:auto_probe_started",
+    /// Probe found a better input device
+    AudioAutoProbeSuggestion(ProbeSuggestionPayload) = "audio:auto_probe_suggestion",
+    /// Audio device plugged in (desktop app only)
+    AudioDeviceAdded(DeviceChangedPayload) = "audio:device_added",
+    /// Audio device unplugged (desktop app only)
+    AudioDeviceRemoved(DeviceChangedPayload) = "audio:device_removed",
+    /// Configured device disappeared (desktop app only)
+    AudioDeviceLost(DeviceLostPayload) = "audio:device_lost",
+    /// Switched to the default device after a device was lost
+    AudioDeviceFallbackOk(FallbackOkPayload) = "audio:device_fallback_ok",
+    /// Could not switch to the default device
+    AudioDeviceFallbackFailed(FallbackFailedPayload) = "audio:device_fallback_failed",
+    /// Switched back to the configured device after it was plugged in again
+    AudioDeviceRestored(DeviceChangedPayload) = "audio:device_restored",
+    /// Mic monitor not resumed to avoid feedback
+    AudioMonitorGuarded(MonitorGuardedPayload) = "audio:monitor_guarded",
+    /// Sound event recognized by the audio tagger
+    AudioEventTagged(AudioEventTag) = "audio:event_tagged",
+    /// Pipeline latency, CPU and drop counts, every 5 seconds while KWS runs
+    AudioMetrics(PipelineMetricsSnapshot) = "audio:metrics",
+    /// Wake word detected
+    WakewordDetected(WakeWordEvent) = "wakeword::detected",
+    /// KWS model download progress
+    KwsModelDownloadProgress(ModelDownloadProgress) = "kws:model_download_progress",
+    /// Downloaded model matches its registry checksum (payload: model ID)
+    KwsModelVerified(String) = "kws:model_verified",
+    /// Downloaded model failed verification and was removed (payload: model ID)
+    KwsModelVerifyFailed(String) = "kws:model_verify_failed",
+    /// Real KWS enabled (payload: model ID)
+    KwsEnabled(String) = "kws:enabled",
+    /// Returned to the stub detector
+    KwsDisabled(()) = "kws:disabled",
+    /// Wake word detected while the QA test window was armed
+    KwsWakeTestPass(KwsDetectionPayload) = "kws:wake_test_pass",
+    /// Internal: real-KWS detection for the test window listener
+    KwsInternalDetection(KwsDetectionPayload) = "_kws_internal_detection",
+    /// Preflight checks started
+    PreflightStarted(()) = "preflight:started",
+    /// One preflight check finished
+    PreflightItem(PreflightItem) = "preflight:item",
+    /// All preflight checks finished
+    PreflightDone(PreflightReport) = "preflight:done",
+    /// Transcript matched a voice command intent
+    IntentMatched(IntentMatch) = "intent:matched",
+    /// Automation hook finished
+    HookResult(HookResult) = "hook:result",
+    /// `config.toml` was edited outside the app and reloaded
+    ConfigChanged(ConfigChangedPayload) = "config:changed",
+    /// A configuration profile was applied and audio capture restarted with it
+    ConfigProfileActivated(ProfileActivatedPayload) = "config:profile_activated",
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_event_names_are_unique() {
+        let unique: HashSet<_> = EVENT_NAMES.iter().collect();
+        assert_eq!(unique.len(), EVENT_NAMES.len());
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let (name, payload) = Event::AudioRestartOk(RestartOkPayload {
+            device: "default".to_string(),
+            elapsed_ms: 12,
+            reason: None,
+        })
+        .into_parts()
+        .unwrap();
+        assert_eq!(name, "audio:restart_ok");
+        assert_eq!(
+            payload,
+            serde_json::json!({"device": "default", "elapsed_ms": 12})
+        );
+
+        let (name, payload) = Event::KwsDisabled(()).into_parts().unwrap();
+        assert_eq!(name, "kws:disabled");
+        assert!(payload.is_null());
+    }
+
+    #[test]
+    fn test_catalog_covers_all_events() {
+        let mut gen = SchemaGenerator::default();
+        let names: Vec<_> = catalog(&mut gen).iter().map(|spec| spec.name).collect();
+        assert_eq!(names, EVENT_NAMES);
+        assert!(gen.definitions().contains_key("AudioErrorPayload"));
+    }
+}
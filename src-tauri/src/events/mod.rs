@@ -0,0 +1,105 @@
+//! Event sink abstraction
+//!
+//! Background workers (audio runtime, KWS, tagging, hooks) publish events
+//! through [`EventSink`] instead of holding a `tauri::AppHandle`, so the same
+//! pipeline runs inside the desktop app and in headless mode.
+//!
+//! - Desktop: `tauri::AppHandle` implements [`EventSink`] and forwards to the webview
+//! - Headless: [`BusSink`] broadcasts events to in-process subscribers
+//!
+//! Events are published as typed [`Event`] values from the [`catalog`];
+//! [`schema`] generates the matching TypeScript and JSON Schema files.
+
+pub mod catalog;
+pub mod schema;
+
+pub use catalog::*;
+
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Destination for pipeline events (event name + JSON payload)
+pub trait EventSink: Send + Sync {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<()>;
+
+    /// Publish a typed event
+    fn publish(&self, event: Event) -> Result<()> {
+        let (name, payload) = event.into_parts()?;
+        self.emit_value(name, payload)
+    }
+}
+
+/// Buffered events per bus subscriber before it starts lagging
+pub const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Shared handle passed to worker threads
+pub type SharedEventSink = Arc<dyn EventSink>;
+
+impl<R: tauri::Runtime> EventSink for tauri::AppHandle<R> {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<()> {
+        tauri::Emitter::emit(self, event, payload)?;
+        Ok(())
+    }
+}
+
+/// Event published on the in-process bus
+#[derive(Debug, Clone, Serialize)]
+pub struct BusEvent {
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+/// Sink backed by a broadcast channel (headless mode)
+///
+/// Slow subscribers lag and drop events rather than blocking audio threads.
+pub struct BusSink {
+    tx: broadcast::Sender<BusEvent>,
+}
+
+impl BusSink {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BusEvent> {
+        self.tx.subscribe()
+    }
+}
+
+impl EventSink for BusSink {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<()> {
+        // No subscribers is not an error
+        let _ = self.tx.send(BusEvent {
+            event: event.to_string(),
+            payload,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_sink_delivers_serialized_payload() {
+        let bus = Arc::new(BusSink::new(8));
+        let mut rx = bus.subscribe();
+
+        let sink: SharedEventSink = bus.clone();
+        sink.publish(Event::AudioRms(0.5)).unwrap();
+
+        let received = rx.try_recv().unwrap();
+        assert_eq!(received.event, "audio:rms");
+        assert_eq!(received.payload, serde_json::json!(0.5));
+    }
+
+    #[test]
+    fn test_bus_sink_without_subscribers() {
+        let sink: SharedEventSink = Arc::new(BusSink::new(8));
+        assert!(sink.publish(Event::KwsDisabled(())).is_ok());
+    }
+}
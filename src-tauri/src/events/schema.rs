@@ -0,0 +1,443 @@
+//! Generated event definitions for the frontend and external clients
+//!
+//! Produces, from the [`catalog`](super::catalog):
+//!
+//! - `src/lib/events.ts`: TypeScript payload types and an event name map
+//! - `docs/events.schema.json`: JSON Schema of the `{event, payload}` envelope
+//! - `docs/events.md`: event reference
+//!
+//! `npm run gen:events` (the `gen-events` binary) rewrites the files, and a
+//! test fails when the committed copies are out of date.
+
+use super::catalog::{catalog, EventSpec, EVENT_SCHEMA_VERSION};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+
+/// Output paths, relative to the repository root
+pub const TYPESCRIPT_PATH: &str = "src/lib/events.ts";
+pub const JSON_SCHEMA_PATH: &str = "docs/events.schema.json";
+pub const MARKDOWN_PATH: &str = "docs/events.md";
+
+const GENERATED_NOTICE: &str = "Generated by `npm run gen:events` from \
+`src-tauri/src/events/catalog.rs`. Do not edit.";
+
+/// Event specs plus the named payload types they reference, as JSON values
+struct Catalog {
+    events: Vec<(EventSpec, Value)>,
+    definitions: Map<String, Value>,
+}
+
+fn load() -> Catalog {
+    let mut gen = SchemaGenerator::new(SchemaSettings::draft07());
+    let events = catalog(&mut gen)
+        .into_iter()
+        .map(|spec| {
+            let payload = serde_json::to_value(&spec.payload).expect("schema serializes");
+            (spec, payload)
+        })
+        .collect();
+    let definitions = gen
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| {
+            let schema = serde_json::to_value(schema).expect("schema serializes");
+            (name, schema)
+        })
+        .collect();
+
+    Catalog {
+        events,
+        definitions,
+    }
+}
+
+/// JSON Schema (draft-07) for one `{event, payload}` envelope
+pub fn json_schema() -> Value {
+    let catalog = load();
+
+    let variants: Vec<Value> = catalog
+        .events
+        .iter()
+        .map(|(spec, payload)| {
+            json!({
+                "description": spec.description,
+                "type": "object",
+                "properties": {
+                    "event": { "const": spec.name },
+                    "payload": payload,
+                },
+                "required": ["event", "payload"],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "EmberleafEvent",
+        "description": format!(
+            "Backend event envelope (schema version {})",
+            EVENT_SCHEMA_VERSION
+        ),
+        "schemaVersion": EVENT_SCHEMA_VERSION,
+        "oneOf": variants,
+        "definitions": catalog.definitions,
+    })
+}
+
+/// TypeScript type for a JSON Schema
+fn ts_type(schema: &Value) -> String {
+    let obj = match schema {
+        Value::Bool(true) => return "unknown".to_string(),
+        Value::Bool(false) => return "never".to_string(),
+        Value::Object(obj) => obj,
+        _ => return "unknown".to_string(),
+    };
+
+    if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+    if let Some(value) = obj.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = obj.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(Value::to_string));
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(members) = obj.get(key).and_then(Value::as_array) {
+            return union(members.iter().map(ts_type));
+        }
+    }
+    if let Some([single]) = obj
+        .get("allOf")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+    {
+        return ts_type(single);
+    }
+
+    match obj.get("type") {
+        Some(Value::String(ty)) => ts_instance_type(ty, obj),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|ty| ts_instance_type(ty, obj)),
+        ),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn ts_instance_type(ty: &str, obj: &Map<String, Value>) -> String {
+    match ty {
+        "string" => "string".to_string(),
+        "number" | "integer" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let item = obj.get("items").map(ts_type).unwrap_or("unknown".into());
+            if item.contains(' ') {
+                format!("({})[]", item)
+            } else {
+                format!("{}[]", item)
+            }
+        }
+        "object" => match obj.get("properties").and_then(Value::as_object) {
+            Some(properties) => {
+                let fields: Vec<String> = properties
+                    .iter()
+                    .map(|(name, schema)| {
+                        format!(
+                            "{}{}: {}",
+                            name,
+                            optional_marker(obj, name),
+                            ts_type(schema)
+                        )
+                    })
+                    .collect();
+                format!("{{ {} }}", fields.join("; "))
+            }
+            None => {
+                let value = obj
+                    .get("additionalProperties")
+                    .map(ts_type)
+                    .unwrap_or("unknown".into());
+                format!("Record<string, {}>", value)
+            }
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+fn union(members: impl Iterator<Item = String>) -> String {
+    let mut seen: Vec<String> = Vec::new();
+    for member in members {
+        if !seen.contains(&member) {
+            seen.push(member);
+        }
+    }
+    if seen.is_empty() {
+        "never".to_string()
+    } else {
+        seen.join(" | ")
+    }
+}
+
+fn optional_marker(obj: &Map<String, Value>, property: &str) -> &'static str {
+    let required = obj
+        .get("required")
+        .and_then(Value::as_array)
+        .is_some_and(|r| r.iter().any(|p| p == property));
+    if required {
+        ""
+    } else {
+        "?"
+    }
+}
+
+/// JSDoc from the schema's title and description (Rust doc comments)
+fn doc_comment(out: &mut String, indent: &str, schema: &Value) {
+    let text: Vec<&str> = ["title", "description"]
+        .iter()
+        .filter_map(|key| schema.get(*key).and_then(Value::as_str))
+        .collect();
+    let text = text.join("\n\n");
+
+    let lines: Vec<&str> = text.lines().collect();
+    match lines.as_slice() {
+        [] => {}
+        [line] => {
+            let _ = writeln!(out, "{}/** {} */", indent, line);
+        }
+        lines => {
+            let _ = writeln!(out, "{}/**", indent);
+            for line in lines {
+                let _ = writeln!(
+                    out,
+                    "{} *{}{}",
+                    indent,
+                    if line.is_empty() { "" } else { " " },
+                    line
+                );
+            }
+            let _ = writeln!(out, "{} */", indent);
+        }
+    }
+}
+
+/// `export interface` / `export type` for a named payload type
+fn ts_definition(out: &mut String, name: &str, schema: &Value) {
+    doc_comment(out, "", schema);
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    match (properties, schema.as_object()) {
+        (Some(properties), Some(obj)) => {
+            let _ = writeln!(out, "export interface {} {{", name);
+            for (property, property_schema) in properties {
+                doc_comment(out, "  ", property_schema);
+                let _ = writeln!(
+                    out,
+                    "  {}{}: {};",
+                    property,
+                    optional_marker(obj, property),
+                    ts_type(property_schema)
+                );
+            }
+            let _ = writeln!(out, "}}");
+        }
+        _ => {
+            let ty = ts_type(schema);
+            let members = schema
+                .get("oneOf")
+                .or_else(|| schema.get("anyOf"))
+                .and_then(Value::as_array);
+            match members {
+                // One member per line for long unions
+                Some(members) if ty.len() > 80 => {
+                    let _ = write!(out, "export type {} =", name);
+                    for member in members {
+                        let _ = write!(out, "\n  | {}", ts_type(member));
+                    }
+                    let _ = writeln!(out, ";");
+                }
+                _ => {
+                    let _ = writeln!(out, "export type {} = {};", name, ts_type(schema));
+                }
+            }
+        }
+    }
+}
+
+/// TypeScript definitions (`src/lib/events.ts`)
+pub fn typescript() -> String {
+    let catalog = load();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "// {}\n", GENERATED_NOTICE);
+    let _ = writeln!(
+        out,
+        "/** Bumped when event names or payload shapes change */"
+    );
+    let _ = writeln!(
+        out,
+        "export const EVENT_SCHEMA_VERSION = {};\n",
+        EVENT_SCHEMA_VERSION
+    );
+
+    for (name, schema) in &catalog.definitions {
+        ts_definition(&mut out, name, schema);
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "/** Payload type of every backend event, by name */");
+    let _ = writeln!(out, "export interface EventPayloads {{");
+    for (spec, payload) in &catalog.events {
+        let _ = writeln!(out, "  /** {} */", spec.description);
+        let _ = writeln!(out, "  \"{}\": {};", spec.name, ts_type(payload));
+    }
+    let _ = writeln!(out, "}}\n");
+    let _ = writeln!(out, "export type EventName = keyof EventPayloads;");
+
+    out
+}
+
+/// Event reference (`docs/events.md`)
+pub fn markdown() -> String {
+    let catalog = load();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# Backend Events\n");
+    let _ = writeln!(out, "<!-- {} -->\n", GENERATED_NOTICE);
+    let _ = writeln!(
+        out,
+        "Schema version: **{}**. Payload types are in [`src/lib/events.ts`](../src/lib/events.ts) \
+         and [`events.schema.json`](events.schema.json).\n",
+        EVENT_SCHEMA_VERSION
+    );
+    let _ = writeln!(
+        out,
+        "The frontend receives these as Tauri events. Hooks and IPC clients get the same payloads \
+         (see [HOOKS.md](HOOKS.md) and [IPC.md](IPC.md)).\n"
+    );
+
+    let _ = writeln!(out, "## Events\n");
+    let _ = writeln!(out, "| Event | Payload | Description |");
+    let _ = writeln!(out, "|-------|---------|-------------|");
+    for (spec, payload) in &catalog.events {
+        let _ = writeln!(
+            out,
+            "| `{}` | `{}` | {} |",
+            spec.name,
+            ts_type(payload).replace('|', "\\|"),
+            spec.description
+        );
+    }
+
+    let _ = writeln!(out, "\n## Payload Types\n");
+    let _ = writeln!(out, "```typescript");
+    let mut first = true;
+    for (name, schema) in &catalog.definitions {
+        if !first {
+            out.push('\n');
+        }
+        first = false;
+        ts_definition(&mut out, name, schema);
+    }
+    let _ = writeln!(out, "```");
+
+    out
+}
+
+/// All generated files as (path relative to the repository root, contents)
+pub fn generated_files() -> Vec<(&'static str, String)> {
+    let mut json = serde_json::to_string_pretty(&json_schema()).expect("schema serializes");
+    json.push('\n');
+
+    vec![
+        (TYPESCRIPT_PATH, typescript()),
+        (JSON_SCHEMA_PATH, json),
+        (MARKDOWN_PATH, markdown()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_ts_types() {
+        assert_eq!(
+            ts_type(&json!({"type": "number", "format": "float"})),
+            "number"
+        );
+        assert_eq!(
+            ts_type(&json!({"type": ["string", "null"]})),
+            "string | null"
+        );
+        assert_eq!(
+            ts_type(&json!({"$ref": "#/definitions/DeviceId"})),
+            "DeviceId"
+        );
+        assert_eq!(
+            ts_type(&json!({"type": "string", "enum": ["ok", "failed"]})),
+            "\"ok\" | \"failed\""
+        );
+        assert_eq!(
+            ts_type(&json!({"type": "array", "items": {"type": ["string", "null"]}})),
+            "(string | null)[]"
+        );
+        assert_eq!(
+            ts_type(&json!({
+                "type": "object",
+                "properties": {"seconds": {"type": "integer"}, "note": {"type": "string"}},
+                "required": ["seconds"]
+            })),
+            "{ note?: string; seconds: number }"
+        );
+        assert_eq!(
+            ts_type(&json!({"type": "object", "additionalProperties": {"type": "boolean"}})),
+            "Record<string, boolean>"
+        );
+        assert_eq!(ts_type(&json!(true)), "unknown");
+    }
+
+    #[test]
+    fn test_schema_lists_every_event() {
+        let schema = json_schema();
+        let names: Vec<&str> = schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["properties"]["event"]["const"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, super::super::EVENT_NAMES);
+        assert_eq!(schema["schemaVersion"], json!(EVENT_SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_typescript_payload_map() {
+        let ts = typescript();
+        assert!(ts.contains("  \"audio:rms\": number;"));
+        assert!(ts.contains("  \"audio:error\": AudioErrorPayload;"));
+        assert!(ts.contains("  \"kws:disabled\": null;"));
+        assert!(ts.contains("export type CheckStatus = \"pass\" | \"warn\" | \"fail\";"));
+    }
+
+    #[test]
+    fn test_generated_files_are_up_to_date() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        for (path, expected) in generated_files() {
+            let actual = std::fs::read_to_string(root.join(path)).unwrap_or_default();
+            assert!(
+                actual == expected,
+                "{} is out of date; run `npm run gen:events`",
+                path
+            );
+        }
+    }
+}
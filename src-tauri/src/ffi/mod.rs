@@ -11,6 +11,7 @@
     non_snake_case,
     non_camel_case_types,
     clippy::upper_case_acronyms,
+    clippy::missing_safety_doc,
     dead_code
 )]
 
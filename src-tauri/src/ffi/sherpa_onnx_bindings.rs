@@ -2056,6 +2056,15 @@ extern "C" {
 extern "C" {
     pub fn SherpaOnnxDestroyOfflinePunctuation(punct: *const SherpaOnnxOfflinePunctuation);
 }
+extern "C" {
+    pub fn SherpaOfflinePunctuationAddPunct(
+        punct: *const SherpaOnnxOfflinePunctuation,
+        text: *const ::std::os::raw::c_char,
+    ) -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn SherpaOfflinePunctuationFreeText(text: *const ::std::os::raw::c_char);
+}
 #[repr(C)]
 #[derive(Debug)]
 pub struct SherpaOnnxLinearResampler {
@@ -0,0 +1,476 @@
+//! Headless daemon mode (`ember --headless` or `EMBER_HEADLESS=1`)
+//!
+//! Runs the audio runtime (KWS, VAD, audio tagging), speaker biometrics and
+//! automation hooks without creating a Tauri window, for machines without a
+//! display. Workers emit into a [`BusSink`]; hookable events are forwarded to
+//! the hook runner, and the local IPC socket (if enabled) serves clients.
+//!
+//! The daemon stops on Ctrl-C or SIGTERM. See `docs/HEADLESS.md`.
+
+use crate::KwsStatus;
+use anyhow::{Context, Result};
+use ember_lib::audio::runtime::AudioRuntime;
+use ember_lib::config::{AppConfig, ConfigStore};
+use ember_lib::error::CommandError;
+use ember_lib::events::{
+    BusSink, DeviceChangedPayload, DeviceLostPayload, Event, FallbackFailedPayload,
+    FallbackOkPayload, SharedEventSink, EVENT_BUS_CAPACITY,
+};
+use ember_lib::hooks::{HookRunner, HOOKABLE_EVENTS};
+use ember_lib::ipc::{self, IpcBackend, IpcMethod};
+use ember_lib::model_manager::ModelManager;
+use ember_lib::paths::AppPaths;
+use ember_lib::validation;
+use ember_lib::voice::{SpeakerBiometrics, VerificationResult};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+
+/// True if headless mode was requested on the command line or environment
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--headless")
+        || std::env::var("EMBER_HEADLESS").is_ok_and(|v| v == "1")
+}
+
+/// Pipeline state owned by the daemon (the headless counterpart of `AppState`)
+struct Daemon {
+    paths: AppPaths,
+    config: Arc<Mutex<AppConfig>>,
+    config_store: Arc<ConfigStore>,
+    sink: SharedEventSink,
+    audio_runtime: Mutex<Option<AudioRuntime>>,
+    speaker_biometrics: Mutex<Option<SpeakerBiometrics>>,
+    model_manager: tokio::sync::Mutex<ModelManager>,
+}
+
+impl Daemon {
+    /// Stop the current audio runtime and start one with the latest config
+    ///
+    /// Blocks while the device closes and opens. The runtime stays locked
+    /// from stop to start, so concurrent restarts (IPC, config reload, device
+    /// watcher) run one after the other and the last one sees the latest
+    /// config.
+    fn restart_audio(&self) -> Result<(), CommandError> {
+        let mut audio_runtime = self.audio_runtime.lock().unwrap();
+        if let Some(runtime) = audio_runtime.take() {
+            runtime.stop();
+        }
+
+        let config = self.config.lock().unwrap().clone();
+        *audio_runtime = Some(AudioRuntime::start(
+            self.sink.clone(),
+            self.paths.clone(),
+            config.audio,
+            config.kws,
+            config.vad,
+            config.tagging,
+        )?);
+        Ok(())
+    }
+
+    /// [`restart_audio`](Self::restart_audio) off the async runtime
+    async fn restart_audio_blocking(self: &Arc<Self>) -> Result<(), CommandError> {
+        let daemon = Arc::clone(self);
+        tokio::task::spawn_blocking(move || daemon.restart_audio())
+            .await
+            .map_err(blocking_task_failed)?
+    }
+
+    async fn kws_status(&self) -> Result<KwsStatus, CommandError> {
+        let kws = self.config.lock().unwrap().kws.clone();
+
+        let lang = match kws.model_id {
+            Some(ref model_id) => {
+                let manager = self.model_manager.lock().await;
+                manager
+                    .registry()
+                    .ok()
+                    .and_then(|r| r.get_model(model_id).map(|e| e.lang.clone()))
+            }
+            None => None,
+        };
+
+        Ok(KwsStatus {
+            mode: kws.mode,
+            model_id: kws.model_id,
+            keyword: kws.keyword,
+            lang,
+            enabled: kws.enabled,
+        })
+    }
+
+    #[cfg_attr(not(feature = "kws_real"), allow(unused_variables))]
+    async fn kws_enable(self: &Arc<Self>, model_id: String) -> Result<String, CommandError> {
+        ModelManager::validate_model_id(&model_id)?;
+
+        #[cfg(not(feature = "kws_real"))]
+        {
+            Err(CommandError::Unavailable(
+                "Real KWS not available: app was built without kws_real feature".to_string(),
+            ))
+        }
+
+        #[cfg(feature = "kws_real")]
+        {
+            {
+                let manager = self.model_manager.lock().await;
+                if !manager.is_model_ready(&model_id)? {
+                    log::info!("Model '{}' not found, downloading...", model_id);
+                    manager
+                        .download_model(self.sink.as_ref(), &model_id)
+                        .await?;
+
+                    let registry = manager.registry()?;
+                    let entry = registry
+                        .get_model(&model_id)
+                        .ok_or_else(|| CommandError::ModelMissing(model_id.clone()))?;
+                    if !manager.verify_model(&model_id, &entry.sha256)? {
+                        manager.remove_model(&model_id).ok();
+                        return Err(CommandError::VerificationFailed(model_id));
+                    }
+                }
+            }
+
+            {
+                let mut config = self.config.lock().unwrap();
+                config.kws.model_id = Some(model_id.clone());
+                config.kws.mode = "real".to_string();
+                config.kws.enabled = true;
+            }
+
+            self.restart_audio_blocking().await?;
+
+            log::info!("Real KWS enabled with model: {}", model_id);
+            let _ = self.sink.publish(Event::KwsEnabled(model_id.clone()));
+
+            Ok(format!("Real KWS enabled with model '{}'", model_id))
+        }
+    }
+
+    /// Unlike the desktop command, the new device is applied immediately
+    /// (blocking, see [`restart_audio`](Self::restart_audio))
+    fn set_input_device(&self, name: String, persist: bool) -> Result<String, CommandError> {
+        if !name.is_empty() {
+            validation::validate_device_name(&name)
+                .map_err(|e| CommandError::validation("device_name", e))?;
+        }
+
+        let stable_id = if !name.is_empty() {
+            ember_lib::audio::list_input_devices()
+                .ok()
+                .and_then(|devices| {
+                    devices
+                        .into_iter()
+                        .find(|d| d.name == name)
+                        .and_then(|d| d.stable_id)
+                })
+        } else {
+            None
+        };
+
+        {
+            let mut config = self.config.lock().unwrap();
+            config.audio.device_name = if name.is_empty() {
+                None
+            } else {
+                Some(name.clone())
+            };
+            config.audio.stable_input_id = stable_id;
+        }
+
+        if persist {
+            self.config_store.flush()?;
+        }
+
+        self.restart_audio()?;
+
+        Ok(format!(
+            "Device set to '{}'",
+            if name.is_empty() { "default" } else { &name }
+        ))
+    }
+
+    fn verify_speaker(
+        &self,
+        user: &str,
+        samples: &[f32],
+    ) -> Result<VerificationResult, CommandError> {
+        let biometrics = self.speaker_biometrics.lock().unwrap();
+        let biometrics = biometrics.as_ref().ok_or_else(|| {
+            CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+        })?;
+
+        Ok(biometrics.verify(user, samples)?)
+    }
+
+    fn list_profiles(&self) -> Result<Vec<String>, CommandError> {
+        let biometrics = self.speaker_biometrics.lock().unwrap();
+        let biometrics = biometrics.as_ref().ok_or_else(|| {
+            CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+        })?;
+
+        Ok(biometrics.list_profiles()?)
+    }
+}
+
+fn blocking_task_failed(e: tokio::task::JoinError) -> CommandError {
+    CommandError::Internal(format!("Audio restart task failed: {}", e))
+}
+
+/// IPC backend operating on the daemon state
+struct DaemonIpcBackend(Arc<Daemon>);
+
+impl IpcBackend for DaemonIpcBackend {
+    fn call(&self, method: IpcMethod) -> ipc::BoxFuture<Result<serde_json::Value, CommandError>> {
+        let daemon = Arc::clone(&self.0);
+        Box::pin(async move {
+            match method {
+                IpcMethod::KwsStatus => ipc::to_result(daemon.kws_status().await),
+                IpcMethod::KwsEnable { model_id } => {
+                    ipc::to_result(daemon.kws_enable(model_id).await)
+                }
+                IpcMethod::SetInputDevice { name, persist } => {
+                    let result =
+                        tokio::task::spawn_blocking(move || daemon.set_input_device(name, persist))
+                            .await;
+                    ipc::to_result(result.unwrap_or_else(|e| Err(blocking_task_failed(e))))
+                }
+                IpcMethod::VerifySpeaker { user, samples } => {
+                    ipc::to_result(daemon.verify_speaker(&user, &samples))
+                }
+                IpcMethod::ListProfiles => ipc::to_result(daemon.list_profiles()),
+            }
+        })
+    }
+}
+
+/// Run the voice pipeline until a shutdown signal arrives
+pub async fn run(paths: AppPaths, config: AppConfig) -> Result<()> {
+    log::info!("Starting headless daemon (no window)");
+
+    let bus = Arc::new(BusSink::new(EVENT_BUS_CAPACITY));
+    let sink: SharedEventSink = bus.clone();
+
+    let hooks = Arc::new(HookRunner::new(
+        paths.hooks_dir(),
+        config.hooks.max_concurrent,
+        tokio::runtime::Handle::current(),
+    ));
+    if config.hooks.enabled {
+        hooks.check_config(&config.hooks);
+    }
+
+    // Biometrics are optional, as in the desktop app
+    let biometrics = match crate::initialize_biometrics(&paths, &config) {
+        Ok(biometrics) => biometrics,
+        Err(e) => {
+            log::error!("Failed to initialize speaker biometrics: {}", e);
+            None
+        }
+    };
+
+    // Without audio there is nothing to serve, so this is fatal here
+    let runtime = crate::initialize_audio_runtime(&paths, &config, sink.clone())
+        .context("Failed to start audio runtime")?;
+
+    // Debounced config writes and hot reload of external edits (see below)
+    let shared_config = Arc::new(Mutex::new(config.clone()));
+    let config_store = ConfigStore::new(paths.config_file(), Arc::clone(&shared_config));
+    config_store.start(sink.clone());
+
+    let daemon = Arc::new(Daemon {
+        paths: paths.clone(),
+        config: shared_config,
+        config_store,
+        sink: sink.clone(),
+        audio_runtime: Mutex::new(runtime),
+        speaker_biometrics: Mutex::new(biometrics),
+        model_manager: tokio::sync::Mutex::new(crate::load_model_manager(&paths)),
+    });
+
+    // Edits to config.toml that the audio runtime was started from restart it
+    let reload_daemon = Arc::downgrade(&daemon);
+    daemon.config_store.on_reload(move |change| {
+        let Some(daemon) = reload_daemon.upgrade() else {
+            return;
+        };
+        if change.needs_audio_restart() {
+            if let Err(e) = daemon.restart_audio() {
+                log::error!("Failed to apply edited config: {}", e);
+            }
+        }
+    });
+
+    // Without a window nothing else notices an unplugged input device
+    let watcher = tokio::spawn(device_watcher(Arc::clone(&daemon)));
+
+    let ipc_task = if config.ipc.enabled {
+        let backend = Arc::new(DaemonIpcBackend(Arc::clone(&daemon)));
+        match ipc::start(&paths.ipc_socket(), &config.ipc, backend, bus.clone()) {
+            Ok(task) => Some(task),
+            Err(e) => {
+                log::error!("Failed to start IPC server: {:#}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut rx = bus.subscribe();
+    let forwarder_daemon = Arc::clone(&daemon);
+    let forwarder = tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(bus_event) => {
+                    log::trace!("Event {}: {}", bus_event.event, bus_event.payload);
+                    // Read per event so edits to config.toml apply without a restart
+                    let hooks_config = forwarder_daemon.config.lock().unwrap().hooks.clone();
+                    if hooks_config.enabled && HOOKABLE_EVENTS.contains(&bus_event.event.as_str()) {
+                        hooks.dispatch(&sink, &hooks_config, &bus_event.event, bus_event.payload);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Event forwarder lagged, {} events dropped", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    log::info!("✓ Headless daemon running");
+    shutdown_signal().await;
+    log::info!("Shutdown signal received");
+
+    forwarder.abort();
+    watcher.abort();
+    if let Some(task) = ipc_task {
+        // Dropping the server removes the socket file
+        task.abort();
+        let _ = task.await;
+    }
+    if let Some(runtime) = daemon.audio_runtime.lock().unwrap().take() {
+        runtime.stop();
+    }
+    daemon.config_store.stop();
+
+    log::info!("Headless daemon stopped");
+    Ok(())
+}
+
+/// Device watcher: falls back to the default input device when the configured
+/// one is unplugged and switches back when it returns, like the desktop app's
+async fn device_watcher(daemon: Arc<Daemon>) {
+    use ember_lib::audio::hotplug::{self, DeviceKind, SystemSource, Transition};
+
+    log::info!("Device watcher started");
+
+    let mut changes = hotplug::spawn(SystemSource::new());
+    while let Some(change) = changes.recv().await {
+        for (kind, device) in &change.removed {
+            let _ = daemon
+                .sink
+                .publish(Event::AudioDeviceRemoved(DeviceChangedPayload {
+                    kind: kind.as_str().to_string(),
+                    device: device.clone(),
+                }));
+        }
+        for (kind, device) in &change.added {
+            let _ = daemon
+                .sink
+                .publish(Event::AudioDeviceAdded(DeviceChangedPayload {
+                    kind: kind.as_str().to_string(),
+                    device: device.clone(),
+                }));
+        }
+
+        let audio = daemon.config.lock().unwrap().audio.clone();
+
+        // Tones and WAVs pick their device each time they play
+        if let Some(Transition::Lost(previous)) = change.preferred(
+            DeviceKind::Output,
+            audio.stable_output_id.as_ref(),
+            audio.output_device_name.as_deref(),
+        ) {
+            log::warn!(
+                "Output device '{}' unplugged, falling back to the default device",
+                previous.name
+            );
+            let _ = daemon
+                .sink
+                .publish(Event::AudioDeviceLost(DeviceLostPayload {
+                    kind: "output".to_string(),
+                    previous,
+                }));
+        }
+
+        match change.preferred(
+            DeviceKind::Input,
+            audio.stable_input_id.as_ref(),
+            audio.device_name.as_deref(),
+        ) {
+            Some(Transition::Lost(previous)) => {
+                log::warn!(
+                    "Input device '{}' unplugged, falling back to the default device",
+                    previous.name
+                );
+                let _ = daemon
+                    .sink
+                    .publish(Event::AudioDeviceLost(DeviceLostPayload {
+                        kind: "input".to_string(),
+                        previous,
+                    }));
+
+                let event = match daemon.restart_audio_blocking().await {
+                    Ok(()) => Event::AudioDeviceFallbackOk(FallbackOkPayload {
+                        kind: "input".to_string(),
+                        new_device: "default".to_string(),
+                    }),
+                    Err(e) => Event::AudioDeviceFallbackFailed(FallbackFailedPayload {
+                        kind: "input".to_string(),
+                        reason: e.to_string(),
+                    }),
+                };
+                let _ = daemon.sink.publish(event);
+            }
+            Some(Transition::Returned(device)) => {
+                log::info!("Input device '{}' is back, switching to it", device.name);
+                match daemon.restart_audio_blocking().await {
+                    Ok(()) => {
+                        let _ =
+                            daemon
+                                .sink
+                                .publish(Event::AudioDeviceRestored(DeviceChangedPayload {
+                                    kind: "input".to_string(),
+                                    device,
+                                }));
+                    }
+                    Err(e) => log::error!("Failed to switch back to '{}': {}", device.name, e),
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Wait for Ctrl-C, or SIGTERM on Unix (service managers)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => log::warn!("Failed to install SIGTERM handler: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        log::error!("Failed to wait for Ctrl-C: {}", e);
+    }
+}
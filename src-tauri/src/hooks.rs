@@ -0,0 +1,660 @@
+//! Local automation hooks
+//!
+//! Runs user-configured actions when voice events fire (`wakeword::detected`,
+//! `intent:matched`, `audio:event_tagged`, ...):
+//! - `exec`: run an executable from `<config>/hooks/` with the event JSON on stdin
+//! - `http`: POST the event JSON to a loopback URL
+//! - `pipe`: write the event JSON as one line to a FIFO in `<config>/hooks/`
+//!
+//! Every invocation has a timeout and runs under a global concurrency limit;
+//! invocations beyond the limit are skipped rather than queued. Invocations
+//! run on the tokio runtime the runner was created with, so the headless
+//! daemon never starts Tauri's. Outcomes are emitted as `hook:result`.
+//!
+//! Security:
+//! Executables and pipes are resolved with `validation::validate_path` against
+//! the hooks directory, so symlinks and `..` cannot escape it. Executables run
+//! without a shell and with a cleared environment. URLs must be loopback and
+//! redirects are not followed.
+
+use crate::events::{Event, SharedEventSink};
+use crate::validation::{validate_localhost_url, validate_path, ValidationError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+/// Events hooks may subscribe to (`hook:result` is excluded to avoid loops)
+pub const HOOKABLE_EVENTS: &[&str] = &[
+    "wakeword::detected",
+    "intent:matched",
+    "audio:event_tagged",
+    "audio:device_added",
+    "audio:device_removed",
+    "audio:device_lost",
+    "audio:device_fallback_ok",
+];
+
+/// Captured stdout/response body is truncated to this many bytes
+const MAX_OUTPUT_BYTES: usize = 1024;
+
+const MIN_TIMEOUT_MS: u64 = 100;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+const MAX_CONCURRENT: usize = 16;
+
+/// Hooks configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Master switch (hooks never run unless set)
+    pub enabled: bool,
+    /// Maximum hook invocations in flight (1-16, applied at startup)
+    pub max_concurrent: usize,
+    /// Default timeout per invocation (ms)
+    pub timeout_ms: u64,
+    pub actions: Vec<HookConfig>,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: 4,
+            timeout_ms: 5000,
+            actions: Vec::new(),
+        }
+    }
+}
+
+/// A single hook (`[[hooks.actions]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    pub name: String,
+    /// Event names that trigger this hook (see `HOOKABLE_EVENTS`)
+    pub events: Vec<String>,
+    /// Only run for these intents (`intent:matched`; empty = all)
+    #[serde(default)]
+    pub intents: Vec<String>,
+    /// Only run for these labels (`audio:event_tagged`; empty = all)
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub action: HookAction,
+    /// Overrides `hooks.timeout_ms`
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// What a hook does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookAction {
+    /// Executable path, relative to the hooks directory
+    Exec {
+        path: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Loopback URL receiving a JSON POST
+    Http { url: String },
+    /// FIFO path, relative to the hooks directory
+    Pipe { path: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStatus {
+    Ok,
+    Failed,
+    Timeout,
+    /// Concurrency limit reached or configuration rejected
+    Skipped,
+}
+
+/// Outcome of one invocation (`hook:result` payload)
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct HookResult {
+    pub hook: String,
+    pub event: String,
+    pub status: HookStatus,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// Truncated stdout or response body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Milliseconds since UNIX epoch
+    pub ts: u64,
+}
+
+/// JSON document handed to every hook
+#[derive(Debug, Clone, Serialize)]
+struct HookInput<'a> {
+    event: &'a str,
+    hook: &'a str,
+    ts: u64,
+    payload: &'a serde_json::Value,
+}
+
+/// Action after path/URL validation
+#[derive(Debug, Clone, PartialEq)]
+enum ResolvedAction {
+    Exec { program: PathBuf, args: Vec<String> },
+    Http { url: url::Url },
+    Pipe { path: PathBuf },
+}
+
+impl HookConfig {
+    /// Does this hook fire for `event` with `payload`?
+    pub fn matches(&self, event: &str, payload: &serde_json::Value) -> bool {
+        if !self.events.iter().any(|e| e == event) {
+            return false;
+        }
+
+        let field_allowed = |filter: &[String], field: &str| {
+            filter.is_empty()
+                || payload
+                    .get(field)
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|v| filter.iter().any(|f| f.eq_ignore_ascii_case(v)))
+        };
+
+        match event {
+            "intent:matched" => field_allowed(&self.intents, "intent"),
+            "audio:event_tagged" => field_allowed(&self.labels, "label"),
+            _ => true,
+        }
+    }
+}
+
+/// Hooks whose event list names something that cannot be hooked
+pub fn unknown_events(config: &HooksConfig) -> Vec<(String, String)> {
+    config
+        .actions
+        .iter()
+        .flat_map(|h| {
+            h.events
+                .iter()
+                .filter(|e| !HOOKABLE_EVENTS.contains(&e.as_str()))
+                .map(|e| (h.name.clone(), e.clone()))
+        })
+        .collect()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn truncate_output(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut end = text.len().min(MAX_OUTPUT_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(text[..end].to_string())
+}
+
+/// Executes hooks under a shared concurrency limit
+pub struct HookRunner {
+    hooks_dir: PathBuf,
+    semaphore: Arc<Semaphore>,
+    client: reqwest::Client,
+    /// Runtime the dispatched hooks are spawned on
+    runtime: Handle,
+}
+
+impl HookRunner {
+    pub fn new(hooks_dir: PathBuf, max_concurrent: usize, runtime: Handle) -> Self {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .no_proxy()
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            hooks_dir,
+            semaphore: Arc::new(Semaphore::new(max_concurrent.clamp(1, MAX_CONCURRENT))),
+            client,
+            runtime,
+        }
+    }
+
+    /// Resolve a hooks-directory path, rejecting anything outside it
+    fn resolve_path(&self, path: &str) -> Result<PathBuf, ValidationError> {
+        let base = self
+            .hooks_dir
+            .canonicalize()
+            .map_err(|e| ValidationError::InvalidPath(format!("Hooks directory: {}", e)))?;
+        let candidate = if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            base.join(path)
+        };
+        validate_path(&candidate.to_string_lossy(), &base)
+    }
+
+    fn resolve(&self, action: &HookAction) -> Result<ResolvedAction, ValidationError> {
+        match action {
+            HookAction::Exec { path, args } => {
+                let program = self.resolve_path(path)?;
+                let metadata = program
+                    .metadata()
+                    .map_err(|e| ValidationError::InvalidPath(e.to_string()))?;
+                if !metadata.is_file() {
+                    return Err(ValidationError::InvalidPath(format!(
+                        "{} is not a file",
+                        program.display()
+                    )));
+                }
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = metadata.permissions().mode();
+                    if mode & 0o111 == 0 {
+                        return Err(ValidationError::InvalidPath(format!(
+                            "{} is not executable",
+                            program.display()
+                        )));
+                    }
+                    if mode & 0o002 != 0 {
+                        return Err(ValidationError::InvalidPath(format!(
+                            "{} is world-writable",
+                            program.display()
+                        )));
+                    }
+                }
+                Ok(ResolvedAction::Exec {
+                    program,
+                    args: args.clone(),
+                })
+            }
+            HookAction::Http { url } => Ok(ResolvedAction::Http {
+                url: validate_localhost_url(url)?,
+            }),
+            HookAction::Pipe { path } => Ok(ResolvedAction::Pipe {
+                path: self.resolve_path(path)?,
+            }),
+        }
+    }
+
+    /// Log configuration problems once (at startup or after a config change)
+    pub fn check_config(&self, config: &HooksConfig) {
+        for (hook, event) in unknown_events(config) {
+            log::warn!("Hook '{}': event '{}' cannot be hooked", hook, event);
+        }
+        for hook in &config.actions {
+            if let Err(e) = self.resolve(&hook.action) {
+                log::warn!("Hook '{}' rejected: {}", hook.name, e);
+            }
+        }
+    }
+
+    /// Spawn every hook matching `event` and emit a `hook:result` for each
+    pub fn dispatch(
+        self: &Arc<Self>,
+        sink: &SharedEventSink,
+        config: &HooksConfig,
+        event: &str,
+        payload: serde_json::Value,
+    ) {
+        if !config.enabled {
+            return;
+        }
+
+        let payload = Arc::new(payload);
+        for hook in config.actions.iter().filter(|h| h.matches(event, &payload)) {
+            let runner = Arc::clone(self);
+            let sink = Arc::clone(sink);
+            let hook = hook.clone();
+            let event = event.to_string();
+            let payload = Arc::clone(&payload);
+            let timeout_ms = hook.timeout_ms.unwrap_or(config.timeout_ms);
+
+            self.runtime.spawn(async move {
+                let result = runner.run(&hook, &event, &payload, timeout_ms).await;
+                match result.status {
+                    HookStatus::Ok => log::debug!("Hook '{}' ok", result.hook),
+                    _ => log::warn!(
+                        "Hook '{}' {:?}: {}",
+                        result.hook,
+                        result.status,
+                        result.error.as_deref().unwrap_or("")
+                    ),
+                }
+                let _ = sink.publish(Event::HookResult(result));
+            });
+        }
+    }
+
+    /// Run one hook to completion (bounded by its timeout)
+    pub async fn run(
+        &self,
+        hook: &HookConfig,
+        event: &str,
+        payload: &serde_json::Value,
+        timeout_ms: u64,
+    ) -> HookResult {
+        let started = Instant::now();
+        let mut result = HookResult {
+            hook: hook.name.clone(),
+            event: event.to_string(),
+            status: HookStatus::Skipped,
+            duration_ms: 0,
+            exit_code: None,
+            http_status: None,
+            output: None,
+            error: None,
+            ts: now_ms(),
+        };
+
+        let Ok(_permit) = self.semaphore.clone().try_acquire_owned() else {
+            result.error = Some("Concurrency limit reached".to_string());
+            return result;
+        };
+
+        let action = match self.resolve(&hook.action) {
+            Ok(action) => action,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        };
+
+        let input = HookInput {
+            event,
+            hook: &hook.name,
+            ts: result.ts,
+            payload,
+        };
+        let body = match serde_json::to_vec(&input) {
+            Ok(body) => body,
+            Err(e) => {
+                result.status = HookStatus::Failed;
+                result.error = Some(e.to_string());
+                return result;
+            }
+        };
+
+        let timeout = Duration::from_millis(timeout_ms.clamp(MIN_TIMEOUT_MS, MAX_TIMEOUT_MS));
+        let outcome = tokio::time::timeout(timeout, async {
+            match action {
+                ResolvedAction::Exec { program, args } => {
+                    self.run_exec(&program, &args, event, &hook.name, &body, &mut result)
+                        .await
+                }
+                ResolvedAction::Http { url } => self.run_http(url, body, &mut result).await,
+                ResolvedAction::Pipe { path } => Self::run_pipe(&path, body).await,
+            }
+        })
+        .await;
+
+        match outcome {
+            Ok(Ok(())) => result.status = HookStatus::Ok,
+            Ok(Err(e)) => {
+                result.status = HookStatus::Failed;
+                result.error = Some(format!("{:#}", e));
+            }
+            Err(_) => {
+                result.status = HookStatus::Timeout;
+                result.error = Some(format!("Timed out after {}ms", timeout.as_millis()));
+            }
+        }
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        result
+    }
+
+    async fn run_exec(
+        &self,
+        program: &Path,
+        args: &[String],
+        event: &str,
+        hook_name: &str,
+        body: &[u8],
+        result: &mut HookResult,
+    ) -> anyhow::Result<()> {
+        // No shell, minimal environment; the child is killed if the timeout drops us
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .current_dir(&self.hooks_dir)
+            .env_clear()
+            .env("PATH", "/usr/local/bin:/usr/bin:/bin")
+            .env("EMBER_EVENT", event)
+            .env("EMBER_HOOK", hook_name)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        // Stdin is written while the output is read: a hook that prints more
+        // than a pipe buffer before reading its input would otherwise block us
+        let stdin = child.stdin.take();
+        let write_stdin = async move {
+            if let Some(mut stdin) = stdin {
+                // A hook that ignores stdin may close it early; that is not an error
+                let _ = stdin.write_all(body).await;
+            }
+        };
+        let ((), output) = tokio::join!(write_stdin, child.wait_with_output());
+        let output = output?;
+        result.exit_code = output.status.code();
+        result.output = truncate_output(&output.stdout);
+
+        if !output.status.success() {
+            let stderr = truncate_output(&output.stderr).unwrap_or_default();
+            anyhow::bail!("Exited with {}: {}", output.status, stderr);
+        }
+        Ok(())
+    }
+
+    async fn run_http(
+        &self,
+        url: url::Url,
+        body: Vec<u8>,
+        result: &mut HookResult,
+    ) -> anyhow::Result<()> {
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        result.http_status = Some(status.as_u16());
+        result.output = truncate_output(&response.bytes().await.unwrap_or_default());
+
+        if !status.is_success() {
+            anyhow::bail!("HTTP {}", status);
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    async fn run_pipe(path: &Path, mut body: Vec<u8>) -> anyhow::Result<()> {
+        // Non-blocking open: fails with ENXIO when nobody is reading the FIFO
+        let mut sender = tokio::net::unix::pipe::OpenOptions::new().open_sender(path)?;
+        body.push(b'\n');
+        sender.write_all(&body).await?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    async fn run_pipe(_path: &Path, _body: Vec<u8>) -> anyhow::Result<()> {
+        anyhow::bail!("Named pipe hooks are only supported on Unix")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hook(events: &[&str], action: HookAction) -> HookConfig {
+        HookConfig {
+            name: "test".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            intents: Vec::new(),
+            labels: Vec::new(),
+            action,
+            timeout_ms: None,
+        }
+    }
+
+    fn hooks_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ember-hooks-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_hook_matching_filters() {
+        let mut h = hook(
+            &["intent:matched", "wakeword::detected"],
+            HookAction::Http {
+                url: "http://localhost/".to_string(),
+            },
+        );
+        h.intents = vec!["lights_on".to_string()];
+
+        assert!(h.matches("intent:matched", &json!({ "intent": "lights_on" })));
+        assert!(!h.matches("intent:matched", &json!({ "intent": "set_timer" })));
+        assert!(h.matches("wakeword::detected", &json!({ "keyword": "hey ember" })));
+        assert!(!h.matches("audio:event_tagged", &json!({ "label": "Doorbell" })));
+    }
+
+    #[test]
+    fn test_unknown_events() {
+        let config = HooksConfig {
+            actions: vec![hook(
+                &["wakeword::detected", "hook:result"],
+                HookAction::Pipe {
+                    path: "fifo".to_string(),
+                },
+            )],
+            ..Default::default()
+        };
+        assert_eq!(
+            unknown_events(&config),
+            vec![("test".to_string(), "hook:result".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_resolve_rejects_escapes() {
+        let dir = hooks_dir("resolve");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let runner = HookRunner::new(dir.clone(), 1, runtime.handle().clone());
+
+        let escape = HookAction::Exec {
+            path: "../../bin/sh".to_string(),
+            args: Vec::new(),
+        };
+        assert!(runner.resolve(&escape).is_err());
+
+        let absolute = HookAction::Exec {
+            path: "/bin/sh".to_string(),
+            args: Vec::new(),
+        };
+        assert!(runner.resolve(&absolute).is_err());
+
+        let remote = HookAction::Http {
+            url: "http://example.com/hook".to_string(),
+        };
+        assert!(runner.resolve(&remote).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exec_hook_stdin_and_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = hooks_dir("exec");
+        let script = dir.join("echo.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nif [ \"$1\" = slow ]; then sleep 5; fi\ncat\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let runner = HookRunner::new(dir.clone(), 2, Handle::current());
+        let payload = json!({ "keyword": "hey ember" });
+
+        let echo = hook(
+            &["wakeword::detected"],
+            HookAction::Exec {
+                path: "echo.sh".to_string(),
+                args: Vec::new(),
+            },
+        );
+        let result = runner
+            .run(&echo, "wakeword::detected", &payload, 5000)
+            .await;
+        assert_eq!(result.status, HookStatus::Ok);
+        assert_eq!(result.exit_code, Some(0));
+        assert!(result.output.unwrap().contains("\"keyword\":\"hey ember\""));
+
+        let slow = hook(
+            &["wakeword::detected"],
+            HookAction::Exec {
+                path: "echo.sh".to_string(),
+                args: vec!["slow".to_string()],
+            },
+        );
+        let result = runner.run(&slow, "wakeword::detected", &payload, 200).await;
+        assert_eq!(result.status, HookStatus::Timeout);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exec_hook_writing_before_reading_stdin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Fills the stdout pipe before it reads the (also oversized) payload
+        let dir = hooks_dir("chatty");
+        let script = dir.join("chatty.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nhead -c 200000 /dev/zero | tr '\\0' x\ncat > /dev/null\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let runner = HookRunner::new(dir.clone(), 1, Handle::current());
+        let payload = json!({ "text": "x".repeat(200_000) });
+        let chatty = hook(
+            &["intent:matched"],
+            HookAction::Exec {
+                path: "chatty.sh".to_string(),
+                args: Vec::new(),
+            },
+        );
+        let result = runner.run(&chatty, "intent:matched", &payload, 5000).await;
+        assert_eq!(result.status, HookStatus::Ok, "{:?}", result.error);
+        assert!(result.output.unwrap().starts_with("xxx"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,87 @@
+//! Local IPC API for third-party clients
+//!
+//! Newline-delimited JSON-RPC 2.0 over a Unix domain socket in the runtime
+//! directory (`<runtime>/ember.sock`, mode 0600). Exposes the same operations
+//! as the matching Tauri commands plus an event subscription stream, so
+//! scripts and other apps can drive the assistant without the GUI.
+//!
+//! The server only parses and routes requests; operations are carried out by
+//! an [`IpcBackend`] (Tauri state in the desktop app, daemon state in
+//! headless mode). See `docs/IPC.md` for the wire format.
+
+pub mod protocol;
+#[cfg(unix)]
+pub mod server;
+
+pub use protocol::IpcMethod;
+
+use crate::error::CommandError;
+use crate::events::BusSink;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Events clients may subscribe to
+pub const IPC_EVENTS: &[&str] = &["wakeword::detected", "audio:rms", "audio:device_lost"];
+
+/// IPC configuration (`[ipc]` in config.toml)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    /// Listen on the local socket (default: false)
+    pub enabled: bool,
+    /// Maximum simultaneous client connections
+    pub max_clients: usize,
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_clients: 8,
+        }
+    }
+}
+
+/// Boxed future returned by [`IpcBackend::call`]
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Executes IPC operations against application state
+pub trait IpcBackend: Send + Sync + 'static {
+    /// Run one operation; the result is serialized as the JSON-RPC `result`
+    fn call(&self, method: IpcMethod) -> BoxFuture<Result<serde_json::Value, CommandError>>;
+}
+
+/// Serialize a command result for [`IpcBackend::call`]
+pub fn to_result<T: Serialize>(
+    result: Result<T, CommandError>,
+) -> Result<serde_json::Value, CommandError> {
+    result.and_then(|value| serde_json::to_value(value).map_err(CommandError::internal))
+}
+
+/// Bind the socket and serve clients on a background task
+///
+/// `events` carries the [`IPC_EVENTS`] to stream to subscribers. Aborting the
+/// returned task closes the socket and removes the socket file.
+#[cfg(unix)]
+pub fn start(
+    socket: &Path,
+    config: &IpcConfig,
+    backend: Arc<dyn IpcBackend>,
+    events: Arc<BusSink>,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    let server = server::IpcServer::bind(socket, backend, events, config.max_clients)?;
+    Ok(tokio::spawn(server.serve()))
+}
+
+#[cfg(not(unix))]
+pub fn start(
+    _socket: &Path,
+    _config: &IpcConfig,
+    _backend: Arc<dyn IpcBackend>,
+    _events: Arc<BusSink>,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    anyhow::bail!("Local IPC socket is only available on Unix")
+}
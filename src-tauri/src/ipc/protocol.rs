@@ -0,0 +1,314 @@
+//! JSON-RPC 2.0 wire types
+//!
+//! One JSON object per line in both directions. Requests without an `id` are
+//! treated as having `id: null` (a response is still sent).
+
+use super::IPC_EVENTS;
+use crate::error::CommandError;
+use crate::events::BusEvent;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+/// Operation failed (`data` carries the command error)
+pub const SERVER_ERROR: i32 = -32000;
+
+/// Incoming request
+#[derive(Debug, Deserialize)]
+struct RawRequest {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// JSON-RPC error object
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<&CommandError> for RpcError {
+    fn from(error: &CommandError) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: error.message(),
+            data: serde_json::to_value(error).ok(),
+        }
+    }
+}
+
+/// Outgoing response
+#[derive(Debug, Serialize)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn ok(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn err(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Event notification (`method: "event"`, no id)
+#[derive(Debug, Serialize)]
+pub struct RpcNotification<'a> {
+    pub jsonrpc: &'static str,
+    pub method: &'static str,
+    pub params: &'a BusEvent,
+}
+
+impl<'a> RpcNotification<'a> {
+    pub fn event(event: &'a BusEvent) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            method: "event",
+            params: event,
+        }
+    }
+}
+
+/// Operation forwarded to the [`IpcBackend`](super::IpcBackend)
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpcMethod {
+    KwsStatus,
+    KwsEnable { model_id: String },
+    SetInputDevice { name: String, persist: bool },
+    VerifySpeaker { user: String, samples: Vec<f32> },
+    ListProfiles,
+}
+
+/// Parsed request
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Call(IpcMethod),
+    /// Start receiving the listed events (see [`IPC_EVENTS`])
+    Subscribe(Vec<String>),
+    /// Stop receiving the listed events (empty = all)
+    Unsubscribe(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KwsEnableParams {
+    model_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SetInputDeviceParams {
+    name: String,
+    #[serde(default)]
+    persist: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifySpeakerParams {
+    user: String,
+    samples: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EventsParams {
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoParams {}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Omitted params are the same as `{}`
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn subscribable(events: Vec<String>) -> Result<Vec<String>, RpcError> {
+    if let Some(unknown) = events.iter().find(|e| !IPC_EVENTS.contains(&e.as_str())) {
+        return Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("Event '{}' is not available over IPC", unknown),
+        ));
+    }
+    Ok(events)
+}
+
+/// Parse one request line. Errors carry the request id when it could be read.
+pub fn parse_request(line: &[u8]) -> Result<(Value, Request), (Value, RpcError)> {
+    let value: Value = serde_json::from_slice(line)
+        .map_err(|e| (Value::Null, RpcError::new(PARSE_ERROR, e.to_string())))?;
+    let raw: RawRequest = serde_json::from_value(value)
+        .map_err(|e| (Value::Null, RpcError::new(INVALID_REQUEST, e.to_string())))?;
+
+    let id = raw.id;
+    if raw.jsonrpc != "2.0" {
+        return Err((
+            id,
+            RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+        ));
+    }
+
+    let request = match raw.method.as_str() {
+        "kws_status" => params::<NoParams>(raw.params).map(|_| Request::Call(IpcMethod::KwsStatus)),
+        "kws_enable" => params::<KwsEnableParams>(raw.params).map(|p| {
+            Request::Call(IpcMethod::KwsEnable {
+                model_id: p.model_id,
+            })
+        }),
+        "set_input_device" => params::<SetInputDeviceParams>(raw.params).map(|p| {
+            Request::Call(IpcMethod::SetInputDevice {
+                name: p.name,
+                persist: p.persist,
+            })
+        }),
+        "verify_speaker" => params::<VerifySpeakerParams>(raw.params).map(|p| {
+            Request::Call(IpcMethod::VerifySpeaker {
+                user: p.user,
+                samples: p.samples,
+            })
+        }),
+        "list_profiles" => {
+            params::<NoParams>(raw.params).map(|_| Request::Call(IpcMethod::ListProfiles))
+        }
+        "subscribe" => params::<EventsParams>(raw.params)
+            .and_then(|p| subscribable(p.events))
+            .map(Request::Subscribe),
+        "unsubscribe" => params::<EventsParams>(raw.params).map(|p| Request::Unsubscribe(p.events)),
+        other => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", other),
+        )),
+    };
+
+    request.map(|r| (id.clone(), r)).map_err(|e| (id, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: Value) -> Result<Request, RpcError> {
+        parse_request(value.to_string().as_bytes())
+            .map(|(_, r)| r)
+            .map_err(|(_, e)| e)
+    }
+
+    #[test]
+    fn test_parse_methods() {
+        assert_eq!(
+            parse(json!({"jsonrpc": "2.0", "id": 1, "method": "kws_status"})),
+            Ok(Request::Call(IpcMethod::KwsStatus))
+        );
+        assert_eq!(
+            parse(json!({
+                "jsonrpc": "2.0", "id": 2, "method": "set_input_device",
+                "params": {"name": "USB Mic"}
+            })),
+            Ok(Request::Call(IpcMethod::SetInputDevice {
+                name: "USB Mic".to_string(),
+                persist: false,
+            }))
+        );
+        assert_eq!(
+            parse(json!({
+                "jsonrpc": "2.0", "id": 3, "method": "subscribe",
+                "params": {"events": ["wakeword::detected", "audio:rms"]}
+            })),
+            Ok(Request::Subscribe(vec![
+                "wakeword::detected".to_string(),
+                "audio:rms".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let code = |v: Value| parse(v).unwrap_err().code;
+
+        assert_eq!(parse_request(b"{not json").unwrap_err().1.code, PARSE_ERROR);
+        assert_eq!(
+            code(json!({"id": 1, "method": "kws_status"})),
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            code(json!({"jsonrpc": "1.0", "id": 1, "method": "kws_status"})),
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            code(json!({"jsonrpc": "2.0", "id": 1, "method": "enroll_start"})),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            code(json!({"jsonrpc": "2.0", "id": 1, "method": "kws_enable", "params": {}})),
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            code(json!({
+                "jsonrpc": "2.0", "id": 1, "method": "kws_status",
+                "params": {"extra": true}
+            })),
+            INVALID_PARAMS
+        );
+        // Only the mirrored events can be subscribed to
+        assert_eq!(
+            code(json!({
+                "jsonrpc": "2.0", "id": 1, "method": "subscribe",
+                "params": {"events": ["hook:result"]}
+            })),
+            INVALID_PARAMS
+        );
+    }
+
+    #[test]
+    fn test_error_keeps_request_id() {
+        let line = json!({"jsonrpc": "2.0", "id": "abc", "method": "nope"}).to_string();
+        let (id, _) = parse_request(line.as_bytes()).unwrap_err();
+        assert_eq!(id, json!("abc"));
+    }
+}
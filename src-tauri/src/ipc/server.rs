@@ -0,0 +1,332 @@
+//! Unix domain socket server
+//!
+//! Security:
+//! The socket lives in a 0700 directory and is chmod 0600 after binding.
+//! Connections from other users are rejected using peer credentials, and
+//! at most `max_clients` connections are served at once.
+
+use super::protocol::{parse_request, Request, RpcError, RpcNotification, RpcResponse};
+use super::protocol::{INVALID_REQUEST, SERVER_ERROR};
+use super::IpcBackend;
+use crate::events::{BusEvent, BusSink};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Semaphore;
+
+/// Longest accepted request line (`verify_speaker` carries raw samples)
+const MAX_LINE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Bound socket ready to serve
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+    owner_uid: u32,
+    backend: Arc<dyn IpcBackend>,
+    events: Arc<BusSink>,
+    clients: Arc<Semaphore>,
+}
+
+impl IpcServer {
+    /// Bind `path`, replacing a stale socket left by a previous run
+    pub fn bind(
+        path: &Path,
+        backend: Arc<dyn IpcBackend>,
+        events: Arc<BusSink>,
+        max_clients: usize,
+    ) -> Result<Self> {
+        let dir = path.parent().context("IPC socket path has no parent")?;
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create runtime directory: {}", dir.display()))?;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .context("Failed to set runtime directory permissions")?;
+
+        if let Ok(meta) = fs::symlink_metadata(path) {
+            if !meta.file_type().is_socket() {
+                bail!("{} exists and is not a socket", path.display());
+            }
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                bail!(
+                    "IPC socket {} is in use (another instance running?)",
+                    path.display()
+                );
+            }
+            fs::remove_file(path).context("Failed to remove stale IPC socket")?;
+        }
+
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind IPC socket: {}", path.display()))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .context("Failed to set IPC socket permissions")?;
+        let owner_uid = fs::metadata(path)?.uid();
+
+        log::info!("IPC socket listening at {}", path.display());
+
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+            owner_uid,
+            backend,
+            events,
+            clients: Arc::new(Semaphore::new(max_clients.max(1))),
+        })
+    }
+
+    /// Accept clients until the task is dropped
+    pub async fn serve(self) {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!("IPC accept failed: {}", e);
+                    continue;
+                }
+            };
+
+            match stream.peer_cred() {
+                Ok(cred) if cred.uid() == self.owner_uid => {}
+                Ok(cred) => {
+                    log::warn!("IPC connection from uid {} rejected", cred.uid());
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("IPC peer credentials unavailable, rejecting: {}", e);
+                    continue;
+                }
+            }
+
+            let Ok(permit) = Arc::clone(&self.clients).try_acquire_owned() else {
+                log::warn!("IPC client limit reached, rejecting connection");
+                let (_, mut writer) = stream.into_split();
+                let error = RpcError::new(SERVER_ERROR, "Too many IPC clients");
+                let _ = write_line(&mut writer, &RpcResponse::err(Value::Null, error)).await;
+                continue;
+            };
+
+            let backend = Arc::clone(&self.backend);
+            let events = Arc::clone(&self.events);
+            tokio::spawn(async move {
+                if let Err(e) = handle_client(stream, backend, events).await {
+                    log::debug!("IPC client disconnected: {}", e);
+                }
+                drop(permit);
+            });
+        }
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+async fn write_line<T: Serialize>(writer: &mut OwnedWriteHalf, message: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    Ok(())
+}
+
+/// Next bus event, or never if the client has not subscribed yet
+async fn next_event(rx: &mut Option<broadcast::Receiver<BusEvent>>) -> Result<BusEvent, RecvError> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Serve one connection: requests are answered in order, events interleave
+async fn handle_client(
+    stream: UnixStream,
+    backend: Arc<dyn IpcBackend>,
+    events: Arc<BusSink>,
+) -> Result<()> {
+    let (read_half, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(read_half);
+    let mut buf = Vec::new();
+    let mut subscribed: BTreeSet<String> = BTreeSet::new();
+    let mut events_rx = None;
+
+    loop {
+        tokio::select! {
+            read = async {
+                // Partial reads stay in `buf`, so cancelling this branch is safe
+                let limit = (MAX_LINE_BYTES + 1).saturating_sub(buf.len()) as u64;
+                (&mut reader).take(limit).read_until(b'\n', &mut buf).await
+            } => {
+                if read? == 0 {
+                    return Ok(());
+                }
+                if buf.last() != Some(&b'\n') {
+                    if buf.len() > MAX_LINE_BYTES {
+                        let error = RpcError::new(INVALID_REQUEST, "Request line too long");
+                        write_line(&mut writer, &RpcResponse::err(Value::Null, error)).await?;
+                        bail!("request line too long");
+                    }
+                    continue;
+                }
+
+                let line = std::mem::take(&mut buf);
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+
+                let response = match parse_request(&line) {
+                    Ok((id, Request::Call(method))) => match backend.call(method).await {
+                        Ok(result) => RpcResponse::ok(id, result),
+                        Err(error) => RpcResponse::err(id, RpcError::from(&error)),
+                    },
+                    Ok((id, Request::Subscribe(names))) => {
+                        if events_rx.is_none() {
+                            events_rx = Some(events.subscribe());
+                        }
+                        subscribed.extend(names);
+                        RpcResponse::ok(id, json!({ "subscribed": subscribed }))
+                    }
+                    Ok((id, Request::Unsubscribe(names))) => {
+                        if names.is_empty() {
+                            subscribed.clear();
+                        } else {
+                            for name in &names {
+                                subscribed.remove(name);
+                            }
+                        }
+                        RpcResponse::ok(id, json!({ "subscribed": subscribed }))
+                    }
+                    Err((id, error)) => RpcResponse::err(id, error),
+                };
+                write_line(&mut writer, &response).await?;
+            }
+            event = next_event(&mut events_rx) => match event {
+                Ok(event) if subscribed.contains(&event.event) => {
+                    write_line(&mut writer, &RpcNotification::event(&event)).await?;
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    log::debug!("IPC client lagged, {} events dropped", skipped);
+                }
+                Err(RecvError::Closed) => events_rx = None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::kws::WakeWordEvent;
+    use crate::error::CommandError;
+    use crate::events::{Event, SharedEventSink};
+    use crate::ipc::{BoxFuture, IpcMethod};
+
+    struct MockBackend;
+
+    impl IpcBackend for MockBackend {
+        fn call(&self, method: IpcMethod) -> BoxFuture<Result<Value, CommandError>> {
+            Box::pin(async move {
+                match method {
+                    IpcMethod::ListProfiles => Ok(json!(["alice"])),
+                    _ => Err(CommandError::Unavailable(
+                        "Speaker biometrics not initialized".to_string(),
+                    )),
+                }
+            })
+        }
+    }
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("ember_ipc_test_{}_{}", name, std::process::id()))
+            .join("ember.sock")
+    }
+
+    #[tokio::test]
+    async fn test_requests_and_event_stream() {
+        let path = socket_path("stream");
+        let bus = Arc::new(BusSink::new(16));
+        let server = IpcServer::bind(&path, Arc::new(MockBackend), Arc::clone(&bus), 2).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let server_task = tokio::spawn(server.serve());
+
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (read_half, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(read_half).lines();
+        let request = |line: Value| {
+            let mut bytes = line.to_string().into_bytes();
+            bytes.push(b'\n');
+            bytes
+        };
+
+        writer
+            .write_all(&request(
+                json!({"jsonrpc": "2.0", "id": 1, "method": "list_profiles"}),
+            ))
+            .await
+            .unwrap();
+        let reply: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"], json!(["alice"]));
+
+        writer
+            .write_all(&request(json!({"jsonrpc": "2.0", "id": 2, "method": "verify_speaker", "params": {"user": "bob", "samples": []}})))
+            .await
+            .unwrap();
+        let reply: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(reply["error"]["code"], SERVER_ERROR);
+        assert_eq!(reply["error"]["data"]["code"], "unavailable");
+
+        writer
+            .write_all(&request(json!({"jsonrpc": "2.0", "id": 3, "method": "subscribe", "params": {"events": ["wakeword::detected"]}})))
+            .await
+            .unwrap();
+        let reply: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(reply["result"]["subscribed"], json!(["wakeword::detected"]));
+
+        // Only subscribed events are forwarded
+        let sink: SharedEventSink = bus.clone();
+        sink.publish(Event::AudioRms(0.3)).unwrap();
+        sink.publish(Event::WakewordDetected(WakeWordEvent {
+            keyword: "hey ember".to_string(),
+            score: 1.0,
+        }))
+        .unwrap();
+        let note: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(note["method"], "event");
+        assert_eq!(note["params"]["event"], "wakeword::detected");
+        assert_eq!(note["params"]["payload"]["keyword"], "hey ember");
+
+        server_task.abort();
+        let _ = server_task.await;
+        assert!(!path.exists(), "socket should be removed on shutdown");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_bind_refuses_non_socket() {
+        let path = socket_path("nonsocket");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, b"not a socket").unwrap();
+
+        let bus = Arc::new(BusSink::new(4));
+        assert!(IpcServer::bind(&path, Arc::new(MockBackend), bus, 1).is_err());
+        assert!(path.exists());
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
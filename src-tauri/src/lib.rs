@@ -1,5 +1,25 @@
 // Library exports for Tauri
 // This file is required for cdylib/staticlib builds
+//
+// The voice pipeline lives here so that every binary (`ember`, `emberctl`)
+// shares it. Tauri setup, app state and commands stay in main.rs.
+
+pub mod audio;
+pub mod commands;
+pub mod config;
+pub mod diagnostics;
+pub mod error;
+pub mod events;
+pub mod ffi;
+pub mod hooks;
+pub mod ipc;
+pub mod logging;
+pub mod model_manager;
+pub mod paths;
+pub mod preflight;
+pub mod registry;
+pub mod validation;
+pub mod voice;
 
 // Link Sherpa-ONNX native libraries when enabled
 #[cfg(feature = "kws_real")]
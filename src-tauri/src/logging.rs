@@ -0,0 +1,172 @@
+//! Log output: stderr and `<cache>/logs/ember.log`
+//!
+//! Every run starts a new `ember.log`; the previous runs are kept as
+//! `ember.1.log` and `ember.2.log`. A file that reaches [`MAX_FILE_BYTES`]
+//! is rotated the same way, so the directory never holds more than
+//! [`KEPT_FILES`] × [`MAX_FILE_BYTES`]. The files are private to the user
+//! (they can contain transcripts and device names) and are only read back
+//! for diagnostic bundles (see [`crate::diagnostics`]).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Log of the current run
+pub const LOG_FILE: &str = "ember.log";
+
+/// Size at which the current file is rotated
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// `ember.log` plus the rotated files
+const KEPT_FILES: usize = 3;
+
+/// Log to stderr and, if the directory is writable, to `logs_dir`
+///
+/// `RUST_LOG` overrides `default_filter` as usual.
+pub fn init(logs_dir: &Path, default_filter: &str) {
+    let file = LogFile::open(logs_dir);
+    let file_error = file.as_ref().err().map(|e| format!("{:#}", e));
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .target(env_logger::Target::Pipe(Box::new(Tee { file: file.ok() })))
+        .init();
+    if let Some(e) = file_error {
+        log::warn!("Logging to stderr only: {}", e);
+    }
+}
+
+/// Rotated file name (`ember.log`, `ember.1.log`, ...)
+fn rotated_name(index: usize) -> String {
+    match index {
+        0 => LOG_FILE.to_string(),
+        n => format!("ember.{}.log", n),
+    }
+}
+
+/// Size-capped log file
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl LogFile {
+    /// Rotate the previous run's log and start a new one
+    fn open(dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        rotate(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file: create(&dir.join(LOG_FILE))?,
+            written: 0,
+        })
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_FILE_BYTES {
+            self.file.flush()?;
+            rotate(&self.dir)?;
+            self.file = create(&self.dir.join(LOG_FILE))?;
+            self.written = 0;
+        }
+        self.file.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+}
+
+/// Shift `ember.log` → `ember.1.log` → ..., dropping the oldest
+fn rotate(dir: &Path) -> io::Result<()> {
+    for index in (0..KEPT_FILES - 1).rev() {
+        let from = dir.join(rotated_name(index));
+        if from.exists() {
+            fs::rename(&from, dir.join(rotated_name(index + 1)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Create (truncate) a log file, readable by the user only
+fn create(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// env_logger target writing every record to stderr and the log file
+struct Tee {
+    /// None once writing failed (a full disk must not take logging down)
+    file: Option<LogFile>,
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        if let Some(file) = self.file.as_mut() {
+            if let Err(e) = file.write_all(buf) {
+                let _ = writeln!(io::stderr(), "Log file disabled: {}", e);
+                self.file = None;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.file.flush()?;
+        }
+        io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ember-logging-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_each_run_keeps_previous_logs() {
+        let dir = temp_dir("runs");
+        for run in 0..4 {
+            let mut log = LogFile::open(&dir).unwrap();
+            log.write_all(format!("run {}\n", run).as_bytes()).unwrap();
+        }
+
+        let read = |index| fs::read_to_string(dir.join(rotated_name(index))).unwrap();
+        assert_eq!(read(0), "run 3\n");
+        assert_eq!(read(1), "run 2\n");
+        assert_eq!(read(2), "run 1\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), KEPT_FILES);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_full_file_is_rotated() {
+        let dir = temp_dir("size");
+        let mut log = LogFile::open(&dir).unwrap();
+        let line = vec![b'x'; 1024 * 1024];
+        for _ in 0..6 {
+            log.write_all(&line).unwrap();
+        }
+
+        let size = |index| fs::metadata(dir.join(rotated_name(index))).unwrap().len();
+        assert_eq!(size(0), 1024 * 1024);
+        assert_eq!(size(1), 5 * 1024 * 1024);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,98 +1,50 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod audio;
 mod display_backend;
-mod ffi;
-mod model_manager;
-mod paths;
-mod preflight;
-mod registry;
-mod validation;
-mod voice;
-
-use audio::kws::{KwsConfig, Sensitivity};
+mod headless;
+
+use ember_lib::{
+    audio, commands, diagnostics, error, events, hooks, ipc, logging, model_manager, paths,
+    preflight, validation, voice,
+};
+
+use audio::kws::Sensitivity;
 use audio::monitor::MicMonitor;
 use audio::runtime::AudioRuntime;
-use audio::vad::VadConfig;
-use audio::AudioConfig;
-use paths::AppPaths;
+use commands::{CommandEngine, Grammar, IntentMatch};
+use ember_lib::config::{
+    AppConfig, ConfigProfile, ConfigProfileInfo, ConfigProfiles, ConfigStore, ConfigWarning,
+    LoadedConfig, ProfileActivatedPayload,
+};
 #[cfg(feature = "kws_real")]
-use registry::verify_onnx_set;
+use ember_lib::registry::{self, verify_onnx_set};
+use error::CommandError;
+use events::{
+    AudioErrorPayload, BusSink, DeviceChangedPayload, DeviceLostPayload, Event, EventSink,
+    FallbackFailedPayload, FallbackOkPayload, KwsDetectionPayload, MonitorGuardedPayload,
+    ProbeSuggestionPayload, RestartOkPayload, SharedEventSink, TestTonePayload,
+};
+use hooks::HookRunner;
+use ipc::{IpcConfig, IpcMethod};
+use paths::AppPaths;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 use voice::{
-    BiometricsConfig, EnrollmentProgress, ProfileInfo, SpeakerBiometrics, VerificationResult,
+    EnrollmentProgress, ProfileInfo, PunctRegistry, PunctuationEngine, PunctuationResult,
+    SpeakerBiometrics, VerificationResult,
 };
 
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Manager, State};
 
 // SEC-001B: Import validators for command input validation
 use validation::{
     emit_validation_error, validate_device_name, validate_duration_ms, validate_frequency_hz_f32,
-    validate_gain,
+    validate_gain, validate_lang_code, validate_transcript_text,
 };
 
-/// Application configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppConfig {
-    pub audio: AudioConfig,
-    pub kws: KwsConfig,
-    pub vad: VadConfig,
-    pub biometrics: BiometricsConfig,
-    pub ui: UiConfig,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UiConfig {
-    pub focus_ring_contrast_min: f32,
-    pub min_touch_target_px: u32,
-    /// Remember mic monitor state across app restarts (default: false)
-    #[serde(default)]
-    pub persist_monitor_state: bool,
-    /// Last monitor state (used when persist_monitor_state is true)
-    #[serde(default)]
-    pub monitor_was_on: bool,
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            audio: AudioConfig::default(),
-            kws: KwsConfig::default(),
-            vad: VadConfig::default(),
-            biometrics: BiometricsConfig::default(),
-            ui: UiConfig {
-                focus_ring_contrast_min: 3.0,
-                min_touch_target_px: 32,
-                persist_monitor_state: false,
-                monitor_was_on: false,
-            },
-        }
-    }
-}
-
-impl AppConfig {
-    /// Load config from file or create default
-    pub fn load_or_create(path: &std::path::Path) -> anyhow::Result<Self> {
-        if path.exists() {
-            let content = fs::read_to_string(path)?;
-            let config: AppConfig = toml::from_str(&content)?;
-            log::info!("Config loaded from: {}", path.display());
-            Ok(config)
-        } else {
-            let config = Self::default();
-            let toml_str = toml::to_string_pretty(&config)?;
-            fs::write(path, toml_str)?;
-            log::info!("Default config created at: {}", path.display());
-            Ok(config)
-        }
-    }
-}
-
 /// KWS test window state (for QA-019 wake word testing)
 #[derive(Debug, Default)]
 struct KwsTestWindow {
@@ -124,6 +76,10 @@ impl KwsTestWindow {
 struct AppState {
     paths: AppPaths,
     config: Arc<Mutex<AppConfig>>,
+    /// Persists `config` (debounced, atomic) and reloads external edits
+    config_store: Arc<ConfigStore>,
+    /// Named setups in the config directory
+    profiles: ConfigProfiles,
     audio_runtime: Arc<Mutex<Option<AudioRuntime>>>,
     speaker_biometrics: Arc<Mutex<Option<SpeakerBiometrics>>>,
     mic_monitor: Arc<Mutex<Option<MicMonitor>>>,
@@ -136,13 +92,43 @@ struct AppState {
     last_restart_ms: Arc<Mutex<u64>>,
     /// KWS test window for QA-019 automated testing
     kws_test_window: Arc<Mutex<KwsTestWindow>>,
+    /// Punctuation/casing post-processor (models loaded on first use)
+    punctuation: Arc<Mutex<PunctuationEngine>>,
+    /// Intent grammar and handlers
+    commands: Arc<Mutex<CommandEngine>>,
+    /// Automation hook executor
+    hooks: Arc<HookRunner>,
+    /// What was reset or migrated while loading config.toml
+    config_warnings: Vec<ConfigWarning>,
+}
+
+/// Tauri command: Run preflight checks
+#[tauri::command]
+async fn run_preflight_checks(app: AppHandle) -> Result<preflight::PreflightReport, CommandError> {
+    Ok(preflight::run_preflight(&app))
+}
+
+/// Tauri command: Event schema version (frontend compares it to its generated types)
+#[tauri::command]
+fn event_schema_version() -> u32 {
+    events::EVENT_SCHEMA_VERSION
+}
+
+/// Tauri command: Config problems fixed at startup (frontend shows them once)
+#[tauri::command]
+fn get_config_warnings(state: State<'_, AppState>) -> Vec<ConfigWarning> {
+    state.config_warnings.clone()
 }
 
 /// Tauri command: Set KWS sensitivity (runtime only, not persisted)
 #[tauri::command]
-async fn kws_set_sensitivity(level: String, state: State<'_, AppState>) -> Result<String, String> {
-    let sensitivity = Sensitivity::from_str(&level)
-        .ok_or_else(|| format!("Invalid sensitivity level: {}", level))?;
+async fn kws_set_sensitivity(
+    level: String,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let sensitivity = Sensitivity::from_str(&level).ok_or_else(|| {
+        CommandError::validation("level", format!("Invalid sensitivity level: {}", level))
+    })?;
 
     // Update config in memory (not saved to disk)
     {
@@ -162,10 +148,13 @@ async fn kws_set_sensitivity(level: String, state: State<'_, AppState>) -> Resul
 
 /// Tauri command: Set VAD threshold (runtime only, not persisted)
 #[tauri::command]
-async fn vad_set_threshold(threshold: f32, state: State<'_, AppState>) -> Result<String, String> {
+async fn vad_set_threshold(
+    threshold: f32,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
     // SEC-001: Validate input range
-    let validated_threshold =
-        validation::validate_vad_threshold(threshold).map_err(|e| e.to_string())?;
+    let validated_threshold = validation::validate_vad_threshold(threshold)
+        .map_err(|e| CommandError::validation("threshold", e))?;
 
     // Update config in memory
     {
@@ -185,24 +174,11 @@ async fn vad_set_threshold(threshold: f32, state: State<'_, AppState>) -> Result
 
 /// Tauri command: Save current configuration to disk
 #[tauri::command]
-async fn save_preferences(state: State<'_, AppState>) -> Result<String, String> {
-    let config = state.config.lock().unwrap().clone();
-    let config_path = state.paths.config_file();
-    let toml_str = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-
-    // SEC-001: Write config file with secure permissions (owner-only: 0600)
-    fs::write(&config_path, toml_str).map_err(|e| e.to_string())?;
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&config_path)
-            .map_err(|e| e.to_string())?
-            .permissions();
-        perms.set_mode(0o600); // Owner read/write only
-        fs::set_permissions(&config_path, perms).map_err(|e| e.to_string())?;
-    }
+async fn save_preferences(state: State<'_, AppState>) -> Result<String, CommandError> {
+    // SEC-001: The store writes atomically with owner-only permissions (0600)
+    state.config_store.flush()?;
 
+    let config_path = state.paths.config_file();
     log::info!(
         "Preferences saved to: {} (secure perms)",
         config_path.display()
@@ -212,7 +188,7 @@ async fn save_preferences(state: State<'_, AppState>) -> Result<String, String>
 
 /// Tauri command: Check if KWS is enabled
 #[tauri::command]
-async fn kws_enabled(state: State<'_, AppState>) -> Result<bool, String> {
+async fn kws_enabled(state: State<'_, AppState>) -> Result<bool, CommandError> {
     let config = state.config.lock().unwrap();
     Ok(config.kws.enabled)
 }
@@ -223,7 +199,7 @@ async fn kws_enabled(state: State<'_, AppState>) -> Result<bool, String> {
 async fn restart_audio_capture_internal(
     app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     // Reuse existing restart logic but return simpler Result
     restart_audio_capture(state, app_handle).await.map(|_| ())
 }
@@ -240,7 +216,7 @@ struct KwsStatus {
 
 /// Tauri command: Get KWS status
 #[tauri::command]
-async fn kws_status(state: State<'_, AppState>) -> Result<KwsStatus, String> {
+async fn kws_status(state: State<'_, AppState>) -> Result<KwsStatus, CommandError> {
     // Clone config data first (don't hold lock across await)
     let (mode, model_id_opt, keyword, enabled) = {
         let config = state.config.lock().unwrap();
@@ -277,9 +253,9 @@ async fn kws_status(state: State<'_, AppState>) -> Result<KwsStatus, String> {
 #[tauri::command]
 async fn kws_list_models(
     state: State<'_, AppState>,
-) -> Result<Vec<model_manager::KwsModelEntry>, String> {
+) -> Result<Vec<model_manager::KwsModelEntry>, CommandError> {
     let manager = state.model_manager.lock().await;
-    let registry = manager.registry().map_err(|e| e.to_string())?;
+    let registry = manager.registry()?;
 
     let mut models: Vec<_> = registry
         .models
@@ -303,16 +279,13 @@ async fn kws_download_model(
     model_id: String,
     app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // Validate model_id
-    model_manager::ModelManager::validate_model_id(&model_id).map_err(|e| e.to_string())?;
+    model_manager::ModelManager::validate_model_id(&model_id)?;
 
     // Check if already downloaded
     let manager = state.model_manager.lock().await;
-    if manager
-        .is_model_ready(&model_id)
-        .map_err(|e| e.to_string())?
-    {
+    if manager.is_model_ready(&model_id)? {
         return Ok(format!(
             "Model '{}' is already downloaded and verified",
             model_id
@@ -326,34 +299,32 @@ async fn kws_download_model(
         .download_model(&app_handle, &model_id)
         .await
         .map_err(|e| {
-            let error_msg = format!("Model download failed: {}", e);
-            log::error!("{}", error_msg);
-            let _ = app_handle.emit("audio:error", error_msg.clone());
-            error_msg
+            log::error!("Model download failed: {:#}", e);
+            let error = CommandError::from(e);
+            let _ = app_handle.publish(Event::AudioError(AudioErrorPayload::from(&error)));
+            error
         })?;
 
     // Verify model
-    let registry = manager.registry().map_err(|e| e.to_string())?;
+    let registry = manager.registry()?;
     let entry = registry
         .get_model(&model_id)
-        .ok_or_else(|| format!("Model '{}' not found in registry", model_id))?;
+        .ok_or_else(|| CommandError::ModelMissing(model_id.clone()))?;
 
-    let is_valid = manager
-        .verify_model(&model_id, &entry.sha256)
-        .map_err(|e| e.to_string())?;
+    let is_valid = manager.verify_model(&model_id, &entry.sha256)?;
 
     if !is_valid {
         // Verification failed - remove corrupted model
         manager.remove_model(&model_id).ok();
-        let error_msg = format!("Model verification failed for '{}'", model_id);
-        log::error!("{}", error_msg);
-        let _ = app_handle.emit("kws:model_verify_failed", &model_id);
-        let _ = app_handle.emit("audio:error", error_msg.clone());
-        return Err(error_msg);
+        log::error!("Model verification failed for '{}'", model_id);
+        let error = CommandError::VerificationFailed(model_id.clone());
+        let _ = app_handle.publish(Event::KwsModelVerifyFailed(model_id));
+        let _ = app_handle.publish(Event::AudioError(AudioErrorPayload::from(&error)));
+        return Err(error);
     }
 
     log::info!("Model '{}' verified successfully", model_id);
-    let _ = app_handle.emit("kws:model_verified", &model_id);
+    let _ = app_handle.publish(Event::KwsModelVerified(model_id.clone()));
 
     Ok(format!("Model '{}' downloaded and verified", model_id))
 }
@@ -365,13 +336,15 @@ async fn kws_enable(
     model_id: String,
     app_handle: AppHandle,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // Validate model_id
-    model_manager::ModelManager::validate_model_id(&model_id).map_err(|e| e.to_string())?;
+    model_manager::ModelManager::validate_model_id(&model_id)?;
 
     #[cfg(not(feature = "kws_real"))]
     {
-        Err("Real KWS not available: app was built without kws_real feature".to_string())
+        Err(CommandError::Unavailable(
+            "Real KWS not available: app was built without kws_real feature".to_string(),
+        ))
     }
 
     #[cfg(feature = "kws_real")]
@@ -379,10 +352,7 @@ async fn kws_enable(
         // Check if model is ready, download if needed
         {
             let manager = state.model_manager.lock().await;
-            if !manager
-                .is_model_ready(&model_id)
-                .map_err(|e| e.to_string())?
-            {
+            if !manager.is_model_ready(&model_id)? {
                 log::info!("Model '{}' not found, downloading...", model_id);
                 drop(manager);
 
@@ -403,7 +373,7 @@ async fn kws_enable(
         restart_audio_capture_internal(app_handle.clone(), state.clone()).await?;
 
         log::info!("Real KWS enabled with model: {}", model_id);
-        let _ = app_handle.emit("kws:enabled", &model_id);
+        let _ = app_handle.publish(Event::KwsEnabled(model_id.clone()));
 
         Ok(format!("Real KWS enabled with model '{}'", model_id))
     }
@@ -411,7 +381,10 @@ async fn kws_enable(
 
 /// Tauri command: Disable real KWS and return to stub
 #[tauri::command]
-async fn kws_disable(app_handle: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+async fn kws_disable(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
     // Update config to use stub
     {
         let mut config = state.config.lock().unwrap();
@@ -424,7 +397,7 @@ async fn kws_disable(app_handle: AppHandle, state: State<'_, AppState>) -> Resul
     restart_audio_capture_internal(app_handle.clone(), state.clone()).await?;
 
     log::info!("KWS disabled, returned to stub mode");
-    let _ = app_handle.emit("kws:disabled", ());
+    let _ = app_handle.publish(Event::KwsDisabled(()));
 
     Ok("KWS disabled, returned to stub mode".to_string())
 }
@@ -437,10 +410,13 @@ async fn kws_disable(app_handle: AppHandle, state: State<'_, AppState>) -> Resul
 async fn kws_arm_test_window(
     duration_ms: u32,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // Validate duration (max 30 seconds for safety)
     if duration_ms > 30000 {
-        return Err("Test window duration cannot exceed 30000ms".to_string());
+        return Err(CommandError::validation(
+            "duration_ms",
+            "Test window duration cannot exceed 30000ms",
+        ));
     }
 
     let mut test_window = state.kws_test_window.lock().unwrap();
@@ -454,7 +430,7 @@ async fn kws_arm_test_window(
 /// Returns true if we detect PipeWire audio server with loopback capability.
 /// This is used by the test UI to decide whether to play a sample or prompt user speech.
 #[tauri::command]
-async fn is_pipewire_loopback() -> Result<bool, String> {
+async fn is_pipewire_loopback() -> Result<bool, CommandError> {
     #[cfg(target_os = "linux")]
     {
         // Check if PipeWire is running by looking for pw-cli or pipewire process
@@ -483,7 +459,7 @@ async fn is_pipewire_loopback() -> Result<bool, String> {
 
 /// Tauri command: Get current configuration
 #[tauri::command]
-async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
+async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, CommandError> {
     let config = state.config.lock().unwrap().clone();
     Ok(config)
 }
@@ -492,13 +468,13 @@ async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
 
 /// Tauri command: List all available input devices
 #[tauri::command]
-async fn list_input_devices() -> Result<Vec<audio::DeviceInfo>, String> {
-    audio::list_input_devices().map_err(|e| e.to_string())
+async fn list_input_devices() -> Result<Vec<audio::DeviceInfo>, CommandError> {
+    Ok(audio::list_input_devices()?)
 }
 
 /// Tauri command: Get the current input device name
 #[tauri::command]
-async fn current_input_device(state: State<'_, AppState>) -> Result<Option<String>, String> {
+async fn current_input_device(state: State<'_, AppState>) -> Result<Option<String>, CommandError> {
     let config = state.config.lock().unwrap();
     Ok(config.audio.device_name.clone())
 }
@@ -508,30 +484,37 @@ async fn current_input_device(state: State<'_, AppState>) -> Result<Option<Strin
 async fn set_input_device(
     name: String,
     persist: bool,
+    stable_id: Option<audio::DeviceId>,
     app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // SEC-001B: Validate device name if provided
     if !name.is_empty() {
         if let Err(e) = validate_device_name(&name) {
-            emit_validation_error(
+            return Err(emit_validation_error(
                 &app,
                 "invalid_device_name",
                 "device_name",
                 &e.to_string(),
                 Some(serde_json::json!(name)),
-            );
-            return Err(e.to_string());
+            ));
         }
     }
 
-    // Look up stable_id for the device
+    // Look up stable_id for the device; the one picked in the UI tells devices
+    // with the same name apart
     let stable_id = if !name.is_empty() {
         audio::list_input_devices().ok().and_then(|devices| {
-            devices
+            let ids: Vec<_> = devices
                 .into_iter()
-                .find(|d| d.name == name)
-                .and_then(|d| d.stable_id)
+                .filter_map(|d| d.stable_id)
+                .filter(|id| id.name == name)
+                .collect();
+            let position = stable_id
+                .as_ref()
+                .and_then(|picked| audio::identity::best_match(picked, &ids))
+                .unwrap_or(0);
+            ids.into_iter().nth(position)
         })
     } else {
         None
@@ -550,11 +533,7 @@ async fn set_input_device(
 
     // Save to disk if requested
     if persist {
-        let config = state.config.lock().unwrap().clone();
-        let config_path = state.paths.config_file();
-        let toml_str = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-        fs::write(&config_path, toml_str).map_err(|e| e.to_string())?;
-        log::info!("Device config saved to: {}", config_path.display());
+        state.config_store.flush()?;
     }
 
     Ok(format!(
@@ -569,18 +548,17 @@ async fn set_input_device(
 
 /// Tauri command: Get audio pipeline debug information
 #[tauri::command]
-async fn get_audio_debug(state: State<'_, AppState>) -> Result<audio::AudioDebugInfo, String> {
+async fn get_audio_debug(
+    state: State<'_, AppState>,
+) -> Result<audio::AudioDebugInfo, CommandError> {
     let config = state.config.lock().unwrap();
-    Ok(audio::AudioDebugInfo {
-        processing_rate: config.audio.sample_rate_hz,
-        processing_channels: audio::TARGET_CHANNELS,
-        frame_ms: config.audio.frame_ms,
-        hop_ms: config.audio.hop_ms,
-        samples_per_frame: config.audio.samples_per_frame(),
-        samples_per_hop: config.audio.samples_per_hop(),
-        input_device: config.audio.device_name.clone(),
-        output_device: config.audio.output_device_name.clone(),
-    })
+    Ok(audio::AudioDebugInfo::from_config(&config.audio))
+}
+
+/// Tauri command: Get pipeline latency, CPU and drop metrics
+#[tauri::command]
+async fn get_pipeline_metrics() -> Result<audio::metrics::PipelineMetricsSnapshot, CommandError> {
+    Ok(audio::metrics::PipelineMetrics::global().snapshot())
 }
 
 /// Comprehensive audio snapshot for diagnostics
@@ -591,26 +569,28 @@ pub struct AudioSnapshot {
     pub output_devices: Vec<audio::DeviceInfo>,
     pub selected_input_device: Option<String>,
     pub selected_output_device: Option<String>,
+    /// The selected input device is connected (false: the default is used)
+    pub input_device_available: bool,
+    /// The selected output device is connected (false: the default is used)
+    pub output_device_available: bool,
     pub monitor_active: bool,
+    /// Overruns and underruns of the capture queue since startup
+    pub capture_queue: audio::queue::QueueStats,
     pub last_restart_ms: u64,
     pub timestamp_ms: u64,
 }
 
 /// Tauri command: Get comprehensive audio snapshot for diagnostics
 #[tauri::command]
-async fn get_audio_snapshot(state: State<'_, AppState>) -> Result<AudioSnapshot, String> {
+async fn get_audio_snapshot(state: State<'_, AppState>) -> Result<AudioSnapshot, CommandError> {
+    Ok(audio_snapshot(&state))
+}
+
+/// Current audio state (shared by `get_audio_snapshot` and diagnostic bundles)
+fn audio_snapshot(state: &AppState) -> AudioSnapshot {
     // Get current debug info
     let config = state.config.lock().unwrap();
-    let debug_info = audio::AudioDebugInfo {
-        processing_rate: config.audio.sample_rate_hz,
-        processing_channels: audio::TARGET_CHANNELS,
-        frame_ms: config.audio.frame_ms,
-        hop_ms: config.audio.hop_ms,
-        samples_per_frame: config.audio.samples_per_frame(),
-        samples_per_hop: config.audio.samples_per_hop(),
-        input_device: config.audio.device_name.clone(),
-        output_device: config.audio.output_device_name.clone(),
-    };
+    let debug_info = audio::AudioDebugInfo::from_config(&config.audio);
     drop(config);
 
     // Get device lists
@@ -627,8 +607,23 @@ async fn get_audio_snapshot(state: State<'_, AppState>) -> Result<AudioSnapshot,
     let config = state.config.lock().unwrap();
     let selected_input_device = config.audio.device_name.clone();
     let selected_output_device = config.audio.output_device_name.clone();
+    let audio_config = config.audio.clone();
     drop(config);
 
+    // Health check (the default device stands in for a missing one)
+    let input_device_available = (audio_config.stable_input_id.is_none()
+        && audio_config.device_name.is_none())
+        || audio::check_input_device_exists(
+            audio_config.stable_input_id.as_ref(),
+            audio_config.device_name.as_deref(),
+        );
+    let output_device_available = (audio_config.stable_output_id.is_none()
+        && audio_config.output_device_name.is_none())
+        || audio::check_output_device_exists(
+            audio_config.stable_output_id.as_ref(),
+            audio_config.output_device_name.as_deref(),
+        );
+
     // Get monitor state
     let monitor_active = state.mic_monitor.lock().unwrap().is_some();
 
@@ -641,27 +636,70 @@ async fn get_audio_snapshot(state: State<'_, AppState>) -> Result<AudioSnapshot,
         .unwrap()
         .as_millis() as u64;
 
-    Ok(AudioSnapshot {
+    AudioSnapshot {
         debug_info,
         input_devices,
         output_devices,
         selected_input_device,
         selected_output_device,
+        input_device_available,
+        output_device_available,
         monitor_active,
+        capture_queue: audio::queue::stats(),
         last_restart_ms,
         timestamp_ms,
+    }
+}
+
+/// Tauri command: Write a bug report bundle to `<cache>/diagnostics`, returns its path
+///
+/// Debug recordings are only included with `include_clips`; voiceprints and
+/// their key never are (see `diagnostics.rs`).
+#[tauri::command]
+async fn create_diagnostic_bundle(
+    include_clips: bool,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let snapshot = audio_snapshot(&state);
+    let config = state.config.lock().unwrap().clone();
+    let paths = state.paths.clone();
+
+    // Preflight probes devices and the bundle compresses logs and clips; keep
+    // both off the async runtime
+    let path = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let preflight = preflight::run_preflight(&app);
+        let mut bundle = diagnostics::DiagnosticBundle::create(&paths)?;
+        bundle.add_json("audio_snapshot.json", &snapshot)?;
+        bundle.add_json("preflight.json", &preflight)?;
+        bundle.add_json("config.json", &diagnostics::redact_config(&config)?)?;
+        bundle.add_json("models.json", &diagnostics::verify_models(&paths))?;
+        bundle.add_json(
+            "pipeline_metrics.json",
+            &audio::metrics::PipelineMetrics::global().snapshot(),
+        )?;
+        bundle.add_logs(&paths.logs_dir())?;
+        if include_clips {
+            bundle.add_clips(&paths.tmp_audio_dir())?;
+        }
+        bundle.finish()
     })
+    .await
+    .map_err(|e| CommandError::Internal(format!("Diagnostic bundle task failed: {}", e)))??;
+
+    log::info!("Diagnostic bundle written to {}", path.display());
+    Ok(path.display().to_string())
 }
 
 /// Tauri command: List all available output devices
 #[tauri::command]
-async fn list_output_devices() -> Result<Vec<audio::DeviceInfo>, String> {
-    audio::list_output_devices().map_err(|e| e.to_string())
+async fn list_output_devices() -> Result<Vec<audio::DeviceInfo>, CommandError> {
+    Ok(audio::list_output_devices()?)
 }
 
 /// Tauri command: Get the current output device name
 #[tauri::command]
-async fn current_output_device(state: State<'_, AppState>) -> Result<Option<String>, String> {
+async fn current_output_device(state: State<'_, AppState>) -> Result<Option<String>, CommandError> {
     let config = state.config.lock().unwrap();
     Ok(config.audio.output_device_name.clone())
 }
@@ -671,30 +709,37 @@ async fn current_output_device(state: State<'_, AppState>) -> Result<Option<Stri
 async fn set_output_device(
     name: String,
     persist: bool,
+    stable_id: Option<audio::DeviceId>,
     app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // SEC-001B: Validate device name if provided
     if !name.is_empty() {
         if let Err(e) = validate_device_name(&name) {
-            emit_validation_error(
+            return Err(emit_validation_error(
                 &app,
                 "invalid_device_name",
                 "device_name",
                 &e.to_string(),
                 Some(serde_json::json!(name)),
-            );
-            return Err(e.to_string());
+            ));
         }
     }
 
-    // Look up stable_id for the device
+    // Look up stable_id for the device; the one picked in the UI tells devices
+    // with the same name apart
     let stable_id = if !name.is_empty() {
         audio::list_output_devices().ok().and_then(|devices| {
-            devices
+            let ids: Vec<_> = devices
                 .into_iter()
-                .find(|d| d.name == name)
-                .and_then(|d| d.stable_id)
+                .filter_map(|d| d.stable_id)
+                .filter(|id| id.name == name)
+                .collect();
+            let position = stable_id
+                .as_ref()
+                .and_then(|picked| audio::identity::best_match(picked, &ids))
+                .unwrap_or(0);
+            ids.into_iter().nth(position)
         })
     } else {
         None
@@ -713,11 +758,12 @@ async fn set_output_device(
 
     // Save to disk if requested
     if persist {
-        let config = state.config.lock().unwrap().clone();
-        let config_path = state.paths.config_file();
-        let toml_str = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-        fs::write(&config_path, toml_str).map_err(|e| e.to_string())?;
-        log::info!("Output device config saved to: {}", config_path.display());
+        state.config_store.flush()?;
+    }
+
+    // A running mic monitor moves to the new device
+    if let Err(e) = restart_mic_monitor(&state) {
+        log::warn!("Failed to move mic monitor to the new output device: {}", e);
     }
 
     Ok(format!(
@@ -747,7 +793,16 @@ struct RestartResponse {
 async fn restart_audio_capture(
     state: State<'_, AppState>,
     app_handle: AppHandle,
-) -> Result<RestartResponse, String> {
+) -> Result<RestartResponse, CommandError> {
+    restart_audio(state, app_handle, None).await
+}
+
+/// Restart audio capture, reporting `reason` (e.g. "device_fallback") with the result
+async fn restart_audio(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+    reason: Option<&str>,
+) -> Result<RestartResponse, CommandError> {
     let start_time = std::time::Instant::now();
 
     // Reentrancy guard: check if restart is already in progress
@@ -759,7 +814,7 @@ async fn restart_audio_capture(
         log::warn!("Restart already in progress, ignoring duplicate call");
 
         // Emit blocked event
-        let _ = app_handle.emit("audio:restart_blocked", ());
+        let _ = app_handle.publish(Event::AudioRestartBlocked(()));
 
         return Ok(RestartResponse {
             ok: false,
@@ -785,7 +840,9 @@ async fn restart_audio_capture(
     }
 
     // 2. Stop current runtime
-    if let Some(runtime) = state.audio_runtime.lock().unwrap().take() {
+    // Not under the lock: stopping waits for the KWS thread
+    let runtime = state.audio_runtime.lock().unwrap().take();
+    if let Some(runtime) = runtime {
         runtime.stop();
     }
 
@@ -795,13 +852,14 @@ async fn restart_audio_capture(
 
     // 4. Start fresh runtime
     let result = match audio::runtime::AudioRuntime::start(
-        app_handle.clone(),
+        Arc::new(app_handle.clone()),
         paths,
         config.audio.clone(),
         config.kws.clone(),
         config.vad.clone(),
+        config.tagging.clone(),
     ) {
-        Ok((runtime, _stop_rx)) => {
+        Ok(runtime) => {
             *state.audio_runtime.lock().unwrap() = Some(runtime);
 
             // Update restart timestamp
@@ -821,45 +879,26 @@ async fn restart_audio_capture(
             log::info!("✓ Audio restarted successfully in {}ms", elapsed_ms);
 
             // Emit success event
-            #[derive(serde::Serialize, Clone)]
-            struct RestartOkPayload {
-                device: String,
-                elapsed_ms: u64,
-            }
-            let _ = app_handle.emit(
-                "audio:restart_ok",
-                RestartOkPayload {
-                    device: device_name.clone(),
-                    elapsed_ms,
-                },
-            );
+            let _ = app_handle.publish(Event::AudioRestartOk(RestartOkPayload {
+                device: device_name.clone(),
+                elapsed_ms,
+                reason: reason.map(String::from),
+            }));
 
             Ok(RestartResponse {
                 ok: true,
                 message: format!("Reconnected to {}", device_name),
                 elapsed_ms: Some(elapsed_ms),
-                reason: None,
+                reason: reason.map(String::from),
             })
         }
         Err(e) => {
-            log::error!("Failed to restart audio: {}", e);
-
-            // Emit friendly error event
-            let friendly = audio::friendly_audio_error(&e);
-            #[derive(serde::Serialize, Clone)]
-            struct AudioErrorPayload {
-                code: String,
-                message: String,
-            }
-            let _ = app_handle.emit(
-                "audio:error",
-                AudioErrorPayload {
-                    code: friendly.code,
-                    message: friendly.message.clone(),
-                },
-            );
+            log::error!("Failed to restart audio: {:#}", e);
+
+            let error = CommandError::from(e);
+            let _ = app_handle.publish(Event::AudioError(AudioErrorPayload::from(&error)));
 
-            Err(format!("Audio restart failed: {}", friendly.message))
+            Err(error)
         }
     };
 
@@ -873,9 +912,15 @@ async fn restart_audio_capture(
         if input_device == output_device && input_device.is_some() {
             log::warn!("Cannot resume monitor: input and output are the same device");
             *state.monitor_was_active.lock().unwrap() = false;
+            let _ = app_handle.publish(Event::AudioMonitorGuarded(MonitorGuardedPayload {
+                reason: "feedback_risk".to_string(),
+            }));
         } else {
-            match audio::monitor::MicMonitor::start(input_device, output_device, 0.15) {
+            match audio::monitor::MicMonitor::start(&config.audio, config.ui.monitor_gain) {
                 Ok(monitor) => {
+                    if monitor.output_fallback() {
+                        publish_output_fallback(&app_handle, "default");
+                    }
                     *state.mic_monitor.lock().unwrap() = Some(monitor);
                     log::info!("✓ Mic monitor resumed");
                 }
@@ -890,6 +935,30 @@ async fn restart_audio_capture(
     result
 }
 
+/// How often a queued restart checks whether the one in flight has finished
+const RESTART_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Restart audio capture once any restart in flight has finished
+///
+/// For restarts that apply a config change (profile, hotplug, external edit):
+/// the one in flight may have read the config before the change, so this one
+/// must not be dropped as a duplicate.
+async fn restart_audio_queued(
+    app_handle: &AppHandle,
+    reason: Option<&str>,
+) -> Result<RestartResponse, CommandError> {
+    loop {
+        let state: State<AppState> = app_handle.state();
+        while state.restart_in_progress.load(Ordering::SeqCst) {
+            tokio::time::sleep(RESTART_POLL).await;
+        }
+        let response = restart_audio(state, app_handle.clone(), reason).await?;
+        if response.reason.as_deref() != Some("in_progress") {
+            return Ok(response);
+        }
+    }
+}
+
 /// RAII guard to reset restart flag on scope exit
 struct ResetOnDrop<'a>(&'a AtomicBool);
 impl Drop for ResetOnDrop<'_> {
@@ -899,6 +968,9 @@ impl Drop for ResetOnDrop<'_> {
 }
 
 /// Tauri command: Play a test tone on the output device
+///
+/// Plays on `device_name` if given, else on the configured output device; a
+/// missing device is replaced by the default (reported as a fallback).
 #[tauri::command]
 async fn play_test_tone(
     device_name: Option<String>,
@@ -907,45 +979,43 @@ async fn play_test_tone(
     volume: Option<f32>,
     simple_mode: Option<bool>,
     app: AppHandle,
-) -> Result<String, String> {
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
     // SEC-001B: Validate optional device name
     if let Some(name) = device_name.as_ref() {
         if let Err(e) = validate_device_name(name) {
-            emit_validation_error(
+            return Err(emit_validation_error(
                 &app,
                 "invalid_device_name",
                 "device_name",
                 &e.to_string(),
                 Some(serde_json::json!(name)),
-            );
-            return Err(e.to_string());
+            ));
         }
     }
 
     // SEC-001B: Validate frequency (default 440Hz if not provided)
     let freq = frequency_hz.unwrap_or(440.0);
     if let Err(e) = validate_frequency_hz_f32(freq) {
-        emit_validation_error(
+        return Err(emit_validation_error(
             &app,
             "invalid_frequency",
             "frequency_hz",
             &e.to_string(),
             Some(serde_json::json!(freq)),
-        );
-        return Err(e.to_string());
+        ));
     }
 
     // SEC-001B: Validate duration (default 500ms if not provided)
     let mut dur = duration_ms.unwrap_or(500);
     if let Err(e) = validate_duration_ms(dur) {
-        emit_validation_error(
+        return Err(emit_validation_error(
             &app,
             "invalid_duration",
             "duration_ms",
             &e.to_string(),
             Some(serde_json::json!(dur)),
-        );
-        return Err(e.to_string());
+        ));
     }
 
     let mut vol = volume.unwrap_or(0.2);
@@ -965,27 +1035,24 @@ async fn play_test_tone(
         is_simple
     );
 
-    let result = audio::test_tone::play_tone(device_name.clone(), freq, dur, vol);
+    let output = configured_output(&state, device_name).map_err(|e| {
+        log::error!("Test tone failed: {:#}", e);
+        CommandError::from(e)
+    })?;
+    if output.fallback {
+        publish_output_fallback(&app, &output.name);
+    }
+
+    let result = audio::test_tone::play_tone(&output.device, freq, dur, vol);
 
     if result.is_ok() {
         // Emit test tone event
-        #[derive(serde::Serialize, Clone)]
-        struct TestTonePayload {
-            device: String,
-            frequency_hz: f32,
-            duration_ms: u32,
-            volume: f32,
-        }
-
-        let _ = app.emit(
-            "audio:test_tone_played",
-            TestTonePayload {
-                device: device_name.clone().unwrap_or_else(|| "default".to_string()),
-                frequency_hz: freq,
-                duration_ms: dur,
-                volume: vol,
-            },
-        );
+        let _ = app.publish(Event::AudioTestTonePlayed(TestTonePayload {
+            device: output.name.clone(),
+            frequency_hz: freq,
+            duration_ms: dur,
+            volume: vol,
+        }));
     }
 
     result
@@ -995,10 +1062,38 @@ async fn play_test_tone(
                 freq,
                 dur,
                 vol * 100.0,
-                device_name.unwrap_or_else(|| "default device".to_string())
+                output.name
             )
         })
-        .map_err(|e| format!("Test tone failed: {:#}", e))
+        .map_err(|e| {
+            log::error!("Test tone failed: {:#}", e);
+            CommandError::from(e)
+        })
+}
+
+/// Output device for playback: `device_name` if given, else the configured one
+fn configured_output(
+    state: &AppState,
+    device_name: Option<String>,
+) -> anyhow::Result<audio::OutputDevice> {
+    match device_name {
+        Some(name) => audio::open_output_device(None, Some(&name)),
+        None => {
+            let config = state.config.lock().unwrap();
+            audio::open_output_device(
+                config.audio.stable_output_id.as_ref(),
+                config.audio.output_device_name.as_deref(),
+            )
+        }
+    }
+}
+
+/// Tell the UI that playback moved to the default output device
+fn publish_output_fallback(app: &AppHandle, device: &str) {
+    let _ = app.publish(Event::AudioDeviceFallbackOk(FallbackOkPayload {
+        kind: "output".to_string(),
+        new_device: device.to_string(),
+    }));
 }
 
 /// Tauri command: Play a WAV asset file (QA-019 test harness)
@@ -1009,15 +1104,22 @@ async fn play_test_tone(
 async fn play_wav_asset_once(
     name: String,
     amplitude: Option<f32>,
-    _app: AppHandle,
-) -> Result<String, String> {
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
     // SEC-001B: Validate asset name (prevent path traversal)
     if name.contains("..") || name.contains('/') || name.contains('\\') {
-        return Err("Invalid asset name: path traversal not allowed".to_string());
+        return Err(CommandError::validation(
+            "name",
+            "Invalid asset name: path traversal not allowed",
+        ));
     }
 
     if !name.ends_with(".wav") {
-        return Err("Asset must be a .wav file".to_string());
+        return Err(CommandError::validation(
+            "name",
+            "Asset must be a .wav file",
+        ));
     }
 
     // Cap amplitude at -12 dBFS (0.25) for safety
@@ -1031,12 +1133,15 @@ async fn play_wav_asset_once(
     let asset_path = std::path::PathBuf::from("assets/audio").join(&name);
 
     if !asset_path.exists() {
-        return Err(format!("Asset not found: {}", asset_path.display()));
+        return Err(CommandError::validation(
+            "name",
+            format!("Asset not found: {}", asset_path.display()),
+        ));
     }
 
     // Decode WAV file
     let mut reader =
-        hound::WavReader::open(&asset_path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+        hound::WavReader::open(&asset_path).map_err(|e| CommandError::Io(e.to_string()))?;
 
     let spec = reader.spec();
     log::info!(
@@ -1051,9 +1156,12 @@ async fn play_wav_asset_once(
         reader
             .samples::<i16>()
             .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| format!("Failed to read samples: {}", e))?
+            .map_err(|e| CommandError::Io(e.to_string()))?
     } else {
-        return Err(format!("Unsupported bit depth: {}", spec.bits_per_sample));
+        return Err(CommandError::UnsupportedFormat(format!(
+            "{}-bit WAV",
+            spec.bits_per_sample
+        )));
     };
 
     // Convert to f32 with amplitude scaling
@@ -1062,9 +1170,12 @@ async fn play_wav_asset_once(
         .map(|&s| (s as f32 / 32768.0) * amp)
         .collect();
 
-    // Play via CPAL using a similar approach to test_tone
-    play_wav_samples_internal(None, samples_f32, spec.sample_rate, spec.channels)
-        .map_err(|e| format!("Playback failed: {}", e))?;
+    // Play via CPAL on the configured output device, like test tones
+    let output = configured_output(&state, None)?;
+    if output.fallback {
+        publish_output_fallback(&app, &output.name);
+    }
+    play_wav_samples_internal(&output.device, samples_f32, spec.sample_rate, spec.channels)?;
 
     let duration_s = samples.len() as f32 / spec.sample_rate as f32 / spec.channels as f32;
 
@@ -1078,24 +1189,12 @@ async fn play_wav_asset_once(
 
 /// Internal helper: Play PCM samples via CPAL
 fn play_wav_samples_internal(
-    device_name: Option<String>,
+    device: &cpal::Device,
     samples: Vec<f32>,
     sample_rate: u32,
     channels: u16,
 ) -> anyhow::Result<()> {
-    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-
-    let host = cpal::default_host();
-
-    // Select output device
-    let device = if let Some(ref name) = device_name {
-        host.output_devices()?
-            .find(|d| d.name().map(|n| n == *name).unwrap_or(false))
-            .ok_or_else(|| anyhow::anyhow!("Output device not found: {}", name))?
-    } else {
-        host.default_output_device()
-            .ok_or_else(|| anyhow::anyhow!("No default output device available"))?
-    };
+    use cpal::traits::{DeviceTrait, StreamTrait};
 
     let config = cpal::StreamConfig {
         channels,
@@ -1107,6 +1206,8 @@ fn play_wav_samples_internal(
     let buffer = std::sync::Arc::new(std::sync::Mutex::new(samples));
     let buffer_clone = buffer.clone();
     let mut position = 0usize;
+    let mut reference =
+        audio::aec::FarEndWriter::new(audio::aec::FarEnd::global(), sample_rate, channels as usize);
 
     let stream = device.build_output_stream(
         &config,
@@ -1121,6 +1222,7 @@ fn play_wav_samples_internal(
                     0.0 // Silence after buffer ends
                 };
             }
+            reference.write(data.iter().copied());
         },
         move |err| log::error!("WAV playback stream error: {}", err),
         None,
@@ -1141,18 +1243,17 @@ async fn start_mic_monitor(
     app: AppHandle,
     state: State<'_, AppState>,
     gain: Option<f32>,
-) -> Result<String, String> {
-    // SEC-001B: Validate gain (default 0.15 if not provided)
-    let gain = gain.unwrap_or(0.15);
+) -> Result<String, CommandError> {
+    // SEC-001B: Validate gain (default: last gain used, see ui.monitor_gain)
+    let gain = gain.unwrap_or_else(|| state.config.lock().unwrap().ui.monitor_gain);
     if let Err(e) = validate_gain(gain) {
-        emit_validation_error(
+        return Err(emit_validation_error(
             &app,
             "invalid_gain",
             "gain",
             &e.to_string(),
             Some(serde_json::json!(gain)),
-        );
-        return Err(e.to_string());
+        ));
     }
 
     // Stop existing monitor if any
@@ -1160,62 +1261,60 @@ async fn start_mic_monitor(
         monitor.stop();
     }
 
-    let config = state.config.lock().unwrap();
+    let config = state.config.lock().unwrap().clone();
     let input_device = config.audio.device_name.clone();
     let output_device = config.audio.output_device_name.clone();
     let persist_enabled = config.ui.persist_monitor_state;
-    drop(config);
 
     log::info!("Starting mic monitor with gain={:.2}", gain);
 
-    match audio::monitor::MicMonitor::start(input_device.clone(), output_device.clone(), gain) {
+    match audio::monitor::MicMonitor::start(&config.audio, gain) {
         Ok(monitor) => {
+            let output_fallback = monitor.output_fallback();
+            if output_fallback {
+                publish_output_fallback(&app, "default");
+            }
             *state.mic_monitor.lock().unwrap() = Some(monitor);
 
-            // Persist monitor state if enabled
+            // Persist monitor state if enabled; the gain is always kept (profiles capture it)
+            state.config_store.update(|config| {
+                config.ui.monitor_gain = gain;
+                if persist_enabled {
+                    config.ui.monitor_was_on = true;
+                }
+            });
             if persist_enabled {
-                let mut config = state.config.lock().unwrap();
-                config.ui.monitor_was_on = true;
-                drop(config);
-
-                let config = state.config.lock().unwrap().clone();
-                let config_path = state.paths.config_file();
-                let toml_str = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-                let _ = fs::write(&config_path, toml_str);
                 log::debug!("Monitor state persisted: ON");
             }
 
             Ok(format!(
                 "✓ Mic monitor active: {} → {} (gain={:.0}%)",
                 input_device.unwrap_or_else(|| "default".to_string()),
-                output_device.unwrap_or_else(|| "default".to_string()),
+                output_device
+                    .filter(|_| !output_fallback)
+                    .unwrap_or_else(|| "default".to_string()),
                 gain * 100.0
             ))
         }
         Err(e) => {
-            log::error!("Failed to start mic monitor: {}", e);
-            Err(format!("Mic monitor failed: {:#}", e))
+            log::error!("Failed to start mic monitor: {:#}", e);
+            Err(e.into())
         }
     }
 }
 
 /// Tauri command: Stop microphone monitoring
 #[tauri::command]
-async fn stop_mic_monitor(state: State<'_, AppState>) -> Result<String, String> {
+async fn stop_mic_monitor(state: State<'_, AppState>) -> Result<String, CommandError> {
     if let Some(monitor) = state.mic_monitor.lock().unwrap().take() {
         monitor.stop();
 
         // Persist monitor state if enabled
         let persist_enabled = state.config.lock().unwrap().ui.persist_monitor_state;
         if persist_enabled {
-            let mut config = state.config.lock().unwrap();
-            config.ui.monitor_was_on = false;
-            drop(config);
-
-            let config = state.config.lock().unwrap().clone();
-            let config_path = state.paths.config_file();
-            let toml_str = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-            let _ = fs::write(&config_path, toml_str);
+            state
+                .config_store
+                .update(|config| config.ui.monitor_was_on = false);
             log::debug!("Monitor state persisted: OFF");
         }
 
@@ -1230,16 +1329,10 @@ async fn stop_mic_monitor(state: State<'_, AppState>) -> Result<String, String>
 async fn set_persist_monitor_state(
     state: State<'_, AppState>,
     enabled: bool,
-) -> Result<String, String> {
-    let mut config = state.config.lock().unwrap();
-    config.ui.persist_monitor_state = enabled;
-    drop(config);
-
-    // Save to disk
-    let config = state.config.lock().unwrap().clone();
-    let config_path = state.paths.config_file();
-    let toml_str = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
-    fs::write(&config_path, toml_str).map_err(|e| e.to_string())?;
+) -> Result<String, CommandError> {
+    state
+        .config_store
+        .update(|config| config.ui.persist_monitor_state = enabled);
 
     log::info!("persist_monitor_state set to: {}", enabled);
     Ok(format!(
@@ -1248,16 +1341,97 @@ async fn set_persist_monitor_state(
     ))
 }
 
+/// Tauri command: List named configuration profiles
+#[tauri::command]
+fn list_config_profiles(
+    state: State<'_, AppState>,
+) -> Result<Vec<ConfigProfileInfo>, CommandError> {
+    let active = state.config.lock().unwrap().active_profile.clone();
+    Ok(state.profiles.list(active.as_deref())?)
+}
+
+/// Tauri command: Save the current devices, KWS, VAD and monitor gain as a profile
+#[tauri::command]
+fn create_config_profile(
+    name: String,
+    auto_activate: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let profile = ConfigProfile::from_config(
+        &state.config.lock().unwrap(),
+        auto_activate.unwrap_or(false),
+    );
+    state.profiles.create(&name, &profile)?;
+    Ok(())
+}
+
+/// Tauri command: Apply a profile and restart audio capture with it
+#[tauri::command]
+async fn activate_config_profile(
+    name: String,
+    app_handle: AppHandle,
+) -> Result<RestartResponse, CommandError> {
+    activate_profile(&app_handle, &name, false).await
+}
+
+/// Tauri command: Delete a profile (the current settings are kept)
+#[tauri::command]
+fn delete_config_profile(name: String, state: State<'_, AppState>) -> Result<(), CommandError> {
+    state.profiles.delete(&name)?;
+    state.config_store.update(|config| {
+        if config.active_profile.as_deref() == Some(name.as_str()) {
+            config.active_profile = None;
+        }
+    });
+    Ok(())
+}
+
+/// Apply a profile's audio, KWS and VAD settings together, then restart audio once
+async fn activate_profile(
+    app_handle: &AppHandle,
+    name: &str,
+    auto: bool,
+) -> Result<RestartResponse, CommandError> {
+    let state: State<AppState> = app_handle.state();
+    let profile = state.profiles.load(name)?;
+
+    // Single config lock: the restart never sees half a profile
+    let mut warnings = Vec::new();
+    state
+        .config_store
+        .update(|config| warnings = profile.apply(name, config));
+
+    // Announced once capture runs with the profile's devices
+    let response = restart_audio_queued(app_handle, Some("profile")).await?;
+    if response.ok {
+        log::info!(
+            "Profile '{}' activated{}",
+            name,
+            if auto {
+                " (input device plugged in)"
+            } else {
+                ""
+            }
+        );
+        let _ = app_handle.publish(Event::ConfigProfileActivated(ProfileActivatedPayload {
+            name: name.to_string(),
+            auto,
+            warnings,
+        }));
+    }
+    Ok(response)
+}
+
 /// Tauri command: Auto-probe and suggest best input device
 #[tauri::command]
 async fn suggest_input_device(
     state: State<'_, AppState>,
     app: AppHandle,
-) -> Result<audio::probe::ProbeResult, String> {
+) -> Result<audio::probe::ProbeResult, CommandError> {
     log::info!("Starting auto-probe for input device suggestion...");
 
     // Emit start event
-    let _ = app.emit("audio:auto_probe_started", ());
+    let _ = app.publish(Event::AudioAutoProbeStarted(()));
 
     let config = state.config.lock().unwrap();
     let current_device = config.audio.device_name.clone();
@@ -1269,26 +1443,17 @@ async fn suggest_input_device(
 
             // Emit suggestion event if we have one
             if result.suggested.is_some() {
-                #[derive(serde::Serialize, Clone)]
-                struct SuggestionPayload {
-                    device: String,
-                    reason: String,
-                }
-
-                let _ = app.emit(
-                    "audio:auto_probe_suggestion",
-                    SuggestionPayload {
-                        device: result.suggested.clone().unwrap(),
-                        reason: result.reason.clone(),
-                    },
-                );
+                let _ = app.publish(Event::AudioAutoProbeSuggestion(ProbeSuggestionPayload {
+                    device: result.suggested.clone().unwrap(),
+                    reason: result.reason.clone(),
+                }));
             }
 
             Ok(result)
         }
         Err(e) => {
-            log::error!("Auto-probe failed: {}", e);
-            Err(format!("Auto-probe failed: {:#}", e))
+            log::error!("Auto-probe failed: {:#}", e);
+            Err(e.into())
         }
     }
 }
@@ -1297,13 +1462,13 @@ async fn suggest_input_device(
 
 /// Tauri command: Start enrollment for a user
 #[tauri::command]
-async fn enroll_start(user: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn enroll_start(user: String, state: State<'_, AppState>) -> Result<(), CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
-    biometrics.enroll_start(user).map_err(|e| e.to_string())
+    Ok(biometrics.enroll_start(user)?)
 }
 
 /// Tauri command: Add an enrollment sample (from audio buffer)
@@ -1312,35 +1477,33 @@ async fn enroll_start(user: String, state: State<'_, AppState>) -> Result<(), St
 async fn enroll_add_sample(
     samples: Vec<f32>,
     state: State<'_, AppState>,
-) -> Result<EnrollmentProgress, String> {
+) -> Result<EnrollmentProgress, CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
-    biometrics
-        .enroll_add_sample(&samples)
-        .map_err(|e| e.to_string())
+    Ok(biometrics.enroll_add_sample(&samples)?)
 }
 
 /// Tauri command: Finalize enrollment and save voiceprint
 #[tauri::command]
-async fn enroll_finalize(state: State<'_, AppState>) -> Result<ProfileInfo, String> {
+async fn enroll_finalize(state: State<'_, AppState>) -> Result<ProfileInfo, CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
-    biometrics.enroll_finalize().map_err(|e| e.to_string())
+    Ok(biometrics.enroll_finalize()?)
 }
 
 /// Tauri command: Cancel ongoing enrollment
 #[tauri::command]
-async fn enroll_cancel(state: State<'_, AppState>) -> Result<(), String> {
+async fn enroll_cancel(state: State<'_, AppState>) -> Result<(), CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
     biometrics.enroll_cancel();
     Ok(())
@@ -1352,48 +1515,144 @@ async fn verify_speaker(
     user: String,
     samples: Vec<f32>,
     state: State<'_, AppState>,
-) -> Result<VerificationResult, String> {
+) -> Result<VerificationResult, CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
-    biometrics
-        .verify(&user, &samples)
-        .map_err(|e| e.to_string())
+    Ok(biometrics.verify(&user, &samples)?)
 }
 
 /// Tauri command: Check if a profile exists
 #[tauri::command]
-async fn profile_exists(user: String, state: State<'_, AppState>) -> Result<bool, String> {
+async fn profile_exists(user: String, state: State<'_, AppState>) -> Result<bool, CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
     Ok(biometrics.profile_exists(&user))
 }
 
 /// Tauri command: Delete a user profile
 #[tauri::command]
-async fn delete_profile(user: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn delete_profile(user: String, state: State<'_, AppState>) -> Result<(), CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
-    biometrics.delete_profile(&user).map_err(|e| e.to_string())
+    Ok(biometrics.delete_profile(&user)?)
 }
 
 /// Tauri command: List all enrolled users
 #[tauri::command]
-async fn list_profiles(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+async fn list_profiles(state: State<'_, AppState>) -> Result<Vec<String>, CommandError> {
     let biometrics = state.speaker_biometrics.lock().unwrap();
-    let biometrics = biometrics
-        .as_ref()
-        .ok_or_else(|| "Speaker biometrics not initialized".to_string())?;
+    let biometrics = biometrics.as_ref().ok_or_else(|| {
+        CommandError::Unavailable("Speaker biometrics not initialized".to_string())
+    })?;
 
-    biometrics.list_profiles().map_err(|e| e.to_string())
+    Ok(biometrics.list_profiles()?)
+}
+
+// ===== TEXT POST-PROCESSING COMMANDS =====
+
+/// Tauri command: Restore punctuation and casing in recognized text
+#[tauri::command]
+async fn punctuate_text(
+    text: String,
+    lang: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PunctuationResult, CommandError> {
+    if let Err(e) = validate_transcript_text(&text) {
+        return Err(emit_validation_error(
+            &app,
+            "invalid_text",
+            "text",
+            &e.to_string(),
+            None,
+        ));
+    }
+    if let Some(ref lang) = lang {
+        if let Err(e) = validate_lang_code(lang) {
+            return Err(emit_validation_error(
+                &app,
+                "invalid_lang",
+                "lang",
+                &e.to_string(),
+                Some(serde_json::json!(lang)),
+            ));
+        }
+    }
+
+    let config = state.config.lock().unwrap().punctuation.clone();
+    let mut engine = state.punctuation.lock().unwrap();
+    Ok(engine.punctuate(&config, &text, lang.as_deref()))
+}
+
+/// Tauri command: Match text against the intent grammar (emits `intent:matched`)
+#[tauri::command]
+async fn match_intent(
+    text: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<IntentMatch>, CommandError> {
+    if let Err(e) = validate_transcript_text(&text) {
+        return Err(emit_validation_error(
+            &app,
+            "invalid_text",
+            "text",
+            &e.to_string(),
+            None,
+        ));
+    }
+
+    let matched = state.commands.lock().unwrap().dispatch(&text);
+    if let Some(ref m) = matched {
+        log::info!(
+            "Intent matched: {} (confidence {:.2})",
+            m.intent,
+            m.confidence
+        );
+        let _ = app.publish(Event::IntentMatched(m.clone()));
+    }
+    Ok(matched)
+}
+
+/// Tauri command: Reload commands.toml from the config directory
+#[tauri::command]
+async fn reload_commands(state: State<'_, AppState>) -> Result<usize, CommandError> {
+    let path = state.paths.commands_file();
+    let grammar = if path.exists() {
+        Grammar::load(&path)?
+    } else {
+        Grammar::default()
+    };
+
+    let mut engine = state.commands.lock().unwrap();
+    engine.set_grammar(grammar);
+    Ok(engine.grammar().intents.len())
+}
+
+/// Initialize model manager (registry is optional)
+fn load_model_manager(paths: &AppPaths) -> model_manager::ModelManager {
+    let mut model_manager = model_manager::ModelManager::new(paths.models_dir());
+
+    // Try to load registry (non-fatal if missing)
+    let registry_path = paths.kws_registry();
+    if registry_path.exists() {
+        match model_manager.load_registry(&registry_path) {
+            Ok(()) => log::info!("KWS registry loaded successfully"),
+            Err(e) => log::warn!("Failed to load KWS registry: {}", e),
+        }
+    } else {
+        log::warn!("KWS registry not found at: {}", registry_path.display());
+    }
+
+    model_manager
 }
 
 /// Initialize speaker biometrics
@@ -1431,7 +1690,7 @@ fn initialize_biometrics(
 fn initialize_audio_runtime(
     paths: &AppPaths,
     config: &AppConfig,
-    app_handle: AppHandle,
+    sink: SharedEventSink,
 ) -> anyhow::Result<Option<AudioRuntime>> {
     log::info!("Initializing audio runtime...");
 
@@ -1482,13 +1741,14 @@ fn initialize_audio_runtime(
 
     // Start audio runtime
     match audio::runtime::AudioRuntime::start(
-        app_handle,
+        sink,
         paths.clone(),
         config.audio.clone(),
         config.kws.clone(),
         config.vad.clone(),
+        config.tagging.clone(),
     ) {
-        Ok((runtime, _stop_rx)) => {
+        Ok(runtime) => {
             if config.kws.enabled {
                 log::info!(
                     "✓ Audio runtime started with wake-word: '{}'",
@@ -1506,6 +1766,78 @@ fn initialize_audio_runtime(
     }
 }
 
+/// IPC backend that runs operations through the Tauri command handlers
+struct TauriIpcBackend {
+    app_handle: AppHandle,
+}
+
+impl ipc::IpcBackend for TauriIpcBackend {
+    fn call(&self, method: IpcMethod) -> ipc::BoxFuture<Result<serde_json::Value, CommandError>> {
+        let app_handle = self.app_handle.clone();
+        Box::pin(async move {
+            let state: State<AppState> = app_handle.state();
+            match method {
+                IpcMethod::KwsStatus => ipc::to_result(kws_status(state).await),
+                IpcMethod::KwsEnable { model_id } => {
+                    ipc::to_result(kws_enable(model_id, app_handle.clone(), state).await)
+                }
+                IpcMethod::SetInputDevice { name, persist } => ipc::to_result(
+                    set_input_device(name, persist, None, app_handle.clone(), state).await,
+                ),
+                IpcMethod::VerifySpeaker { user, samples } => {
+                    ipc::to_result(verify_speaker(user, samples, state).await)
+                }
+                IpcMethod::ListProfiles => ipc::to_result(list_profiles(state).await),
+            }
+        })
+    }
+}
+
+/// Serve the local IPC socket and mirror subscribable events into it
+fn setup_ipc_server(app_handle: AppHandle, paths: &AppPaths, config: &IpcConfig) {
+    use tauri::Listener;
+
+    let bus = Arc::new(BusSink::new(events::EVENT_BUS_CAPACITY));
+    for event_name in ipc::IPC_EVENTS {
+        let bus = Arc::clone(&bus);
+        let _ = app_handle.listen(*event_name, move |event| {
+            let payload = serde_json::from_str(event.payload()).unwrap_or(serde_json::Value::Null);
+            let _ = bus.emit_value(event_name, payload);
+        });
+    }
+
+    let backend = Arc::new(TauriIpcBackend { app_handle });
+    match ipc::start(&paths.ipc_socket(), config, backend, bus) {
+        Ok(_) => log::info!("IPC server started"),
+        Err(e) => log::error!("Failed to start IPC server: {:#}", e),
+    }
+}
+
+/// Forward hookable voice events to the automation hook runner
+///
+/// The hooks config is read per event, so edits apply without re-registering.
+fn setup_hook_listeners(app_handle: AppHandle) {
+    use tauri::Listener;
+
+    let sink: SharedEventSink = Arc::new(app_handle.clone());
+    for event_name in hooks::HOOKABLE_EVENTS {
+        let app_handle_clone = app_handle.clone();
+        let sink = Arc::clone(&sink);
+        let _ = app_handle.listen(*event_name, move |event| {
+            let state: State<AppState> = app_handle_clone.state();
+            let config = state.config.lock().unwrap().hooks.clone();
+            if !config.enabled {
+                return;
+            }
+
+            let payload = serde_json::from_str(event.payload()).unwrap_or(serde_json::Value::Null);
+            state.hooks.dispatch(&sink, &config, event_name, payload);
+        });
+    }
+
+    log::info!("Hook listeners registered");
+}
+
 /// QA-019: Set up test window event listener
 ///
 /// Listens for internal wake word detection events from the KWS worker.
@@ -1513,16 +1845,9 @@ fn initialize_audio_runtime(
 fn setup_test_window_listener(app_handle: AppHandle) {
     use tauri::Listener;
 
-    #[derive(serde::Deserialize, Clone)]
-    struct TestDetectionPayload {
-        model_id: String,
-        keyword: String,
-        ts: u64,
-    }
-
     let app_handle_clone = app_handle.clone();
     let _ = app_handle.listen("_kws_internal_detection", move |event| {
-        if let Ok(payload) = serde_json::from_str::<TestDetectionPayload>(event.payload()) {
+        if let Ok(payload) = serde_json::from_str::<KwsDetectionPayload>(event.payload()) {
             let state: State<AppState> = app_handle_clone.state();
 
             // Check if test window is armed
@@ -1536,21 +1861,8 @@ fn setup_test_window_listener(app_handle: AppHandle) {
                     "✓ KWS test window armed, wake word detected - emitting test pass event"
                 );
 
-                // Emit test pass event
-                #[derive(serde::Serialize, Clone)]
-                struct TestPassPayload {
-                    model_id: String,
-                    keyword: String,
-                    ts: u64,
-                }
-
-                let test_pass = TestPassPayload {
-                    model_id: payload.model_id.clone(),
-                    keyword: payload.keyword.clone(),
-                    ts: payload.ts,
-                };
-
-                if let Err(e) = app_handle_clone.emit("kws:wake_test_pass", &test_pass) {
+                // Emit test pass event (same payload as the detection)
+                if let Err(e) = app_handle_clone.publish(Event::KwsWakeTestPass(payload)) {
                     log::error!("Failed to emit kws:wake_test_pass event: {}", e);
                 }
 
@@ -1565,219 +1877,240 @@ fn setup_test_window_listener(app_handle: AppHandle) {
     log::info!("KWS test window event listener installed");
 }
 
-/// Device health watcher - monitors configured devices and handles loss/fallback
+/// Device watcher: follows hotplug notifications to fall back to the default
+/// device when a configured one is unplugged and to switch back when it
+/// returns, and auto-activates profiles whose input device was plugged in
 async fn device_health_watcher(app_handle: AppHandle) {
-    use tokio::time::{sleep, Duration};
+    use audio::hotplug::{self, DeviceKind, SystemSource};
 
-    log::info!("Device health watcher started");
+    log::info!("Device watcher started");
 
-    loop {
-        sleep(Duration::from_secs(2)).await;
+    let mut changes = hotplug::spawn(SystemSource::new());
+    while let Some(change) = changes.recv().await {
+        for (kind, device) in &change.removed {
+            let _ = app_handle.publish(Event::AudioDeviceRemoved(DeviceChangedPayload {
+                kind: kind.as_str().to_string(),
+                device: device.clone(),
+            }));
+        }
+        for (kind, device) in &change.added {
+            let _ = app_handle.publish(Event::AudioDeviceAdded(DeviceChangedPayload {
+                kind: kind.as_str().to_string(),
+                device: device.clone(),
+            }));
+        }
 
         let state: State<AppState> = app_handle.state();
         let config = state.config.lock().unwrap().clone();
 
-        // Check input device
-        if let Some(ref device_name) = config.audio.device_name {
-            let stable_id = config.audio.stable_input_id.as_ref();
-            let exists = audio::check_input_device_exists(stable_id, Some(device_name));
+        // Handled first: a profile activated below does not cover an output
+        // device that changed in the same notification
+        if let Some(transition) = change.preferred(
+            DeviceKind::Output,
+            config.audio.stable_output_id.as_ref(),
+            config.audio.output_device_name.as_deref(),
+        ) {
+            output_device_changed(&app_handle, transition);
+        }
 
-            if !exists {
-                log::warn!(
-                    "Input device '{}' no longer available, attempting fallback",
-                    device_name
-                );
+        // A profile for the plugged-in device replaces the configured devices
+        if let Some(name) = state.profiles.plugged_in(
+            &change.before.inputs,
+            &change.now.inputs,
+            config.active_profile.as_deref(),
+        ) {
+            match activate_profile(&app_handle, &name, true).await {
+                Ok(response) if response.ok => {}
+                Ok(response) => log::warn!(
+                    "Profile '{}' not auto-activated: {}",
+                    name,
+                    response.message
+                ),
+                Err(e) => log::error!("Failed to auto-activate profile '{}': {}", name, e),
+            }
+            continue;
+        }
 
-                // Emit device lost event
-                if let Some(ref sid) = config.audio.stable_input_id {
-                    #[derive(serde::Serialize, Clone)]
-                    struct DeviceLostPayload {
-                        kind: String,
-                        previous: audio::DeviceId,
-                    }
-                    let _ = app_handle.emit(
-                        "audio:device_lost",
-                        DeviceLostPayload {
+        if let Some(transition) = change.preferred(
+            DeviceKind::Input,
+            config.audio.stable_input_id.as_ref(),
+            config.audio.device_name.as_deref(),
+        ) {
+            input_device_changed(&app_handle, transition).await;
+        }
+    }
+}
+
+/// Restart capture after the configured input device was unplugged or plugged back in
+///
+/// The preference is kept: capture resolves it to the default device while it
+/// is missing and to the device itself once it is back.
+async fn input_device_changed(app_handle: &AppHandle, transition: audio::hotplug::Transition) {
+    use audio::hotplug::Transition;
+
+    match transition {
+        Transition::Lost(previous) => {
+            log::warn!(
+                "Input device '{}' unplugged, falling back to the default device",
+                previous.name
+            );
+            let _ = app_handle.publish(Event::AudioDeviceLost(DeviceLostPayload {
+                kind: "input".to_string(),
+                previous,
+            }));
+
+            match restart_audio_queued(app_handle, Some("device_fallback")).await {
+                Ok(response) if response.ok => {
+                    let _ = app_handle.publish(Event::AudioDeviceFallbackOk(FallbackOkPayload {
+                        kind: "input".to_string(),
+                        new_device: "default".to_string(),
+                    }));
+                }
+                Ok(response) => log::warn!("Fallback restart skipped: {}", response.message),
+                Err(e) => {
+                    let _ = app_handle.publish(Event::AudioDeviceFallbackFailed(
+                        FallbackFailedPayload {
                             kind: "input".to_string(),
-                            previous: sid.clone(),
+                            reason: e.to_string(),
                         },
-                    );
+                    ));
                 }
-
-                // Attempt fallback by clearing device preference and restarting
-                {
-                    let mut cfg = state.config.lock().unwrap();
-                    cfg.audio.device_name = None;
-                    cfg.audio.stable_input_id = None;
+            }
+        }
+        Transition::Returned(device) => {
+            log::info!("Input device '{}' is back, switching to it", device.name);
+            match restart_audio_queued(app_handle, Some("device_restored")).await {
+                Ok(response) if response.ok => {
+                    let _ = app_handle.publish(Event::AudioDeviceRestored(DeviceChangedPayload {
+                        kind: "input".to_string(),
+                        device,
+                    }));
                 }
+                Ok(response) => log::warn!("Restore restart skipped: {}", response.message),
+                Err(e) => log::error!("Failed to switch back to '{}': {}", device.name, e),
+            }
+        }
+    }
+}
 
-                // Trigger restart via internal restart logic
-                match audio::runtime::AudioRuntime::start(
-                    app_handle.clone(),
-                    state.paths.clone(),
-                    state.config.lock().unwrap().audio.clone(),
-                    state.config.lock().unwrap().kws.clone(),
-                    state.config.lock().unwrap().vad.clone(),
-                ) {
-                    Ok((runtime, _stop_rx)) => {
-                        // Check if monitor was active before fallback
-                        let monitor_was_active = state.mic_monitor.lock().unwrap().is_some();
-
-                        // Stop old runtime and monitor
-                        if let Some(old_runtime) = state.audio_runtime.lock().unwrap().take() {
-                            old_runtime.stop();
-                        }
-                        if let Some(monitor) = state.mic_monitor.lock().unwrap().take() {
-                            monitor.stop();
-                        }
-
-                        // Install new runtime
-                        *state.audio_runtime.lock().unwrap() = Some(runtime);
-
-                        log::info!("✓ Successfully fell back to default input device");
-
-                        // Emit fallback success event
-                        #[derive(serde::Serialize, Clone)]
-                        struct FallbackOkPayload {
-                            kind: String,
-                            new_device: String,
-                        }
-                        let _ = app_handle.emit(
-                            "audio:device_fallback_ok",
-                            FallbackOkPayload {
-                                kind: "input".to_string(),
-                                new_device: "default".to_string(),
-                            },
-                        );
-
-                        // Emit restart ok with fallback reason
-                        #[derive(serde::Serialize, Clone)]
-                        struct RestartOkPayload {
-                            device: String,
-                            elapsed_ms: u64,
-                            reason: String,
-                        }
-                        let _ = app_handle.emit(
-                            "audio:restart_ok",
-                            RestartOkPayload {
-                                device: "default".to_string(),
-                                elapsed_ms: 0,
-                                reason: "device_fallback".to_string(),
-                            },
-                        );
-
-                        // Attempt to resume monitor if it was active, but only if safe
-                        if monitor_was_active {
-                            let cfg = state.config.lock().unwrap().clone();
-                            let input_device = cfg.audio.device_name.clone();
-                            let output_device = cfg.audio.output_device_name.clone();
-
-                            // Safety check: prevent feedback loop
-                            if input_device == output_device && input_device.is_some() {
-                                log::warn!("Cannot resume monitor after fallback: input and output are the same device (feedback prevention)");
-
-                                // Emit monitor guarded event
-                                #[derive(serde::Serialize, Clone)]
-                                struct MonitorGuardedPayload {
-                                    reason: String,
-                                }
-                                let _ = app_handle.emit(
-                                    "audio:monitor_guarded",
-                                    MonitorGuardedPayload {
-                                        reason: "feedback_risk".to_string(),
-                                    },
-                                );
-                            } else {
-                                // Safe to resume monitor
-                                match audio::monitor::MicMonitor::start(
-                                    input_device,
-                                    output_device,
-                                    0.15,
-                                ) {
-                                    Ok(monitor) => {
-                                        *state.mic_monitor.lock().unwrap() = Some(monitor);
-                                        log::info!("✓ Mic monitor resumed after fallback");
-                                    }
-                                    Err(e) => {
-                                        log::warn!(
-                                            "Failed to resume monitor after fallback: {}",
-                                            e
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to fallback after device loss: {}", e);
-
-                        // Emit fallback failed event
-                        #[derive(serde::Serialize, Clone)]
-                        struct FallbackFailedPayload {
-                            kind: String,
-                            reason: String,
-                        }
-                        let _ = app_handle.emit(
-                            "audio:device_fallback_failed",
-                            FallbackFailedPayload {
-                                kind: "input".to_string(),
-                                reason: format!("{}", e),
-                            },
-                        );
-
-                        // Emit friendly error
-                        let friendly = audio::friendly_audio_error(&e);
-                        #[derive(serde::Serialize, Clone)]
-                        struct AudioErrorPayload {
-                            code: String,
-                            message: String,
-                        }
-                        let _ = app_handle.emit(
-                            "audio:error",
-                            AudioErrorPayload {
-                                code: friendly.code,
-                                message: friendly.message,
-                            },
-                        );
-                    }
+/// Move a running mic monitor after the configured output device was unplugged
+/// or plugged back in (tones and WAVs pick their device each time they play)
+fn output_device_changed(app_handle: &AppHandle, transition: audio::hotplug::Transition) {
+    use audio::hotplug::Transition;
+
+    let state: State<AppState> = app_handle.state();
+    match transition {
+        Transition::Lost(previous) => {
+            log::warn!(
+                "Output device '{}' unplugged, falling back to the default device",
+                previous.name
+            );
+            let _ = app_handle.publish(Event::AudioDeviceLost(DeviceLostPayload {
+                kind: "output".to_string(),
+                previous,
+            }));
+
+            match restart_mic_monitor(&state) {
+                Ok(true) => {
+                    let _ = app_handle.publish(Event::AudioDeviceFallbackOk(FallbackOkPayload {
+                        kind: "output".to_string(),
+                        new_device: "default".to_string(),
+                    }));
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    let _ = app_handle.publish(Event::AudioDeviceFallbackFailed(
+                        FallbackFailedPayload {
+                            kind: "output".to_string(),
+                            reason: e.to_string(),
+                        },
+                    ));
+                }
+            }
+        }
+        Transition::Returned(device) => {
+            log::info!("Output device '{}' is back, switching to it", device.name);
+            match restart_mic_monitor(&state) {
+                Ok(true) => {
+                    let _ = app_handle.publish(Event::AudioDeviceRestored(DeviceChangedPayload {
+                        kind: "output".to_string(),
+                        device,
+                    }));
                 }
+                Ok(false) => {}
+                Err(e) => log::error!("Failed to switch back to '{}': {}", device.name, e),
             }
         }
     }
 }
 
+/// Restart the mic monitor on the configured devices; false if it was not running
+fn restart_mic_monitor(state: &AppState) -> Result<bool, CommandError> {
+    let Some(monitor) = state.mic_monitor.lock().unwrap().take() else {
+        return Ok(false);
+    };
+    monitor.stop();
+
+    let config = state.config.lock().unwrap().clone();
+    let monitor = audio::monitor::MicMonitor::start(&config.audio, config.ui.monitor_gain)?;
+    *state.mic_monitor.lock().unwrap() = Some(monitor);
+    Ok(true)
+}
+
 #[tokio::main]
 async fn main() {
-    // Initialize logging
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Initialize paths
+    let paths = AppPaths::new().expect("Failed to initialize application paths");
+
+    // Initialize logging (stderr and <cache>/logs)
+    logging::init(&paths.logs_dir(), "info");
 
     log::info!("Emberleaf starting...");
 
-    // Configure display backend EARLY (Linux only)
+    let headless = headless::requested();
+
+    // Configure display backend EARLY (Linux only, not needed without a window)
     #[cfg(target_os = "linux")]
-    {
+    if !headless {
         display_backend::apply_env(display_backend::DisplayBackend::Auto);
         display_backend::check_linux_dependencies();
     }
 
-    // Initialize paths
-    let paths = AppPaths::new().expect("Failed to initialize application paths");
     paths
         .ensure_directories()
         .expect("Failed to create application directories");
 
-    // Load configuration
+    // Load configuration (invalid or outdated files are repaired, never fatal)
     let config_path = paths.config_file();
-    let config = AppConfig::load_or_create(&config_path).expect("Failed to load configuration");
+    let LoadedConfig {
+        config,
+        warnings: config_warnings,
+    } = AppConfig::load(&config_path);
 
     log::info!("Configuration loaded");
 
+    if headless {
+        if let Err(e) = headless::run(paths, config).await {
+            log::error!("Headless daemon failed: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Clone for the setup closure
     let paths_for_setup = paths.clone();
     let config_for_setup = config.clone();
 
     // Attempt to run app with crash-resilient fallback
-    if let Err(e) = run_app(paths, config, paths_for_setup, config_for_setup).await {
+    if let Err(e) = run_app(
+        paths,
+        config,
+        config_warnings,
+        paths_for_setup,
+        config_for_setup,
+    )
+    .await
+    {
         log::error!("Application failed to start: {}", e);
         std::process::exit(1);
     }
@@ -1787,28 +2120,53 @@ async fn main() {
 async fn run_app(
     paths: AppPaths,
     config: AppConfig,
+    config_warnings: Vec<ConfigWarning>,
     paths_for_setup: AppPaths,
     config_for_setup: AppConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize model manager
-    let mut model_manager = model_manager::ModelManager::new(paths.models_dir());
-
-    // Try to load registry (non-fatal if missing)
-    let registry_path = paths.kws_registry();
-    if registry_path.exists() {
-        match model_manager.load_registry(&registry_path) {
-            Ok(()) => log::info!("KWS registry loaded successfully"),
-            Err(e) => log::warn!("Failed to load KWS registry: {}", e),
-        }
+    let model_manager = load_model_manager(&paths);
+
+    // Punctuation registry is optional; without it only config overrides select models
+    let punct_registry_path = paths.punct_registry();
+    let punct_registry = if punct_registry_path.exists() {
+        PunctRegistry::load(&punct_registry_path).unwrap_or_else(|e| {
+            log::warn!("Failed to load punctuation registry: {}", e);
+            PunctRegistry::default()
+        })
     } else {
-        log::warn!("KWS registry not found at: {}", registry_path.display());
+        log::info!(
+            "Punctuation registry not found at: {}",
+            punct_registry_path.display()
+        );
+        PunctRegistry::default()
+    };
+    let punctuation = PunctuationEngine::new(paths.punct_models_root(), punct_registry);
+
+    let hooks = Arc::new(HookRunner::new(
+        paths.hooks_dir(),
+        config.hooks.max_concurrent,
+        tokio::runtime::Handle::current(),
+    ));
+    if config.hooks.enabled {
+        hooks.check_config(&config.hooks);
     }
 
+    // A broken grammar should not keep the app from starting
+    let commands = CommandEngine::load(&paths.commands_file()).unwrap_or_else(|e| {
+        log::warn!("Failed to load command grammar: {:#}", e);
+        CommandEngine::default()
+    });
+
+    let config = Arc::new(Mutex::new(config));
+    let config_store = ConfigStore::new(paths.config_file(), Arc::clone(&config));
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .manage(AppState {
             paths: paths.clone(),
-            config: Arc::new(Mutex::new(config)),
+            config,
+            config_store,
+            profiles: ConfigProfiles::new(paths.config_profiles_dir()),
             audio_runtime: Arc::new(Mutex::new(None)),
             speaker_biometrics: Arc::new(Mutex::new(None)),
             mic_monitor: Arc::new(Mutex::new(None)),
@@ -1817,12 +2175,37 @@ async fn run_app(
             monitor_was_active: Arc::new(Mutex::new(false)),
             last_restart_ms: Arc::new(Mutex::new(0)),
             kws_test_window: Arc::new(Mutex::new(KwsTestWindow::default())),
+            punctuation: Arc::new(Mutex::new(punctuation)),
+            commands: Arc::new(Mutex::new(commands)),
+            hooks,
+            config_warnings,
         })
         .setup(move |app| {
             let app_handle = app.handle().clone();
             let paths_clone = paths_for_setup.clone();
             let config_clone = config_for_setup.clone();
 
+            // Debounced config writes and hot reload of external edits; edits to
+            // the sections the audio runtime uses restart it, like a profile does
+            let reload_handle = app_handle.clone();
+            let config_store = &app.state::<AppState>().config_store;
+            config_store.on_reload(move |change| {
+                if !change.needs_audio_restart() {
+                    return;
+                }
+                let app_handle = reload_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    match restart_audio_queued(&app_handle, Some("config_reload")).await {
+                        Ok(response) if response.ok => {}
+                        Ok(response) => {
+                            log::warn!("Edited config not applied: {}", response.message)
+                        }
+                        Err(e) => log::error!("Failed to apply edited config: {}", e),
+                    }
+                });
+            });
+            config_store.start(Arc::new(app_handle.clone()));
+
             // Initialize biometrics and KWS asynchronously (state is now available)
             tokio::spawn(async move {
                 // Initialize speaker biometrics (pass paths directly, no state access)
@@ -1843,7 +2226,7 @@ async fn run_app(
                 }
 
                 // Initialize audio runtime
-                match initialize_audio_runtime(&paths_clone, &config_clone, app_handle.clone()) {
+                match initialize_audio_runtime(&paths_clone, &config_clone, Arc::new(app_handle.clone())) {
                     Ok(Some(runtime)) => {
                         let state: State<AppState> = app_handle.state();
                         let mut audio_runtime = state.audio_runtime.lock().unwrap();
@@ -1855,14 +2238,14 @@ async fn run_app(
                         if config_clone.ui.persist_monitor_state && config_clone.ui.monitor_was_on {
                             log::info!("Attempting to auto-resume mic monitor...");
 
-                            let input_device = config_clone.audio.device_name.clone();
-                            let output_device = config_clone.audio.output_device_name.clone();
+                            let input_device = &config_clone.audio.device_name;
+                            let output_device = &config_clone.audio.output_device_name;
 
                             // Safety check: prevent feedback
                             if input_device == output_device && input_device.is_some() {
                                 log::warn!("Cannot auto-resume monitor: input and output are the same device (feedback prevention)");
                             } else {
-                                match audio::monitor::MicMonitor::start(input_device, output_device, 0.15) {
+                                match audio::monitor::MicMonitor::start(&config_clone.audio, config_clone.ui.monitor_gain) {
                                     Ok(monitor) => {
                                         *state.mic_monitor.lock().unwrap() = Some(monitor);
                                         log::info!("✓ Mic monitor auto-resumed from persisted state");
@@ -1889,12 +2272,22 @@ async fn run_app(
                 // QA-019: Set up test window event listener
                 // Listens for internal wake word detections and emits test pass events if test window is armed
                 setup_test_window_listener(app_handle.clone());
+
+                // Forward hookable voice events to automation hooks
+                setup_hook_listeners(app_handle.clone());
+
+                // Local IPC API for scripts and other apps
+                if config_clone.ipc.enabled {
+                    setup_ipc_server(app_handle.clone(), &paths_clone, &config_clone.ipc);
+                }
             });
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            preflight::run_preflight_checks,
+            run_preflight_checks,
+            event_schema_version,
+            get_config_warnings,
             kws_set_sensitivity,
             kws_enabled,
             kws_status,
@@ -1912,6 +2305,8 @@ async fn run_app(
             set_input_device,
             get_audio_debug,
             get_audio_snapshot,
+            get_pipeline_metrics,
+            create_diagnostic_bundle,
             list_output_devices,
             current_output_device,
             set_output_device,
@@ -1921,6 +2316,10 @@ async fn run_app(
             start_mic_monitor,
             stop_mic_monitor,
             set_persist_monitor_state,
+            list_config_profiles,
+            create_config_profile,
+            activate_config_profile,
+            delete_config_profile,
             suggest_input_device,
             enroll_start,
             enroll_add_sample,
@@ -1929,8 +2328,18 @@ async fn run_app(
             verify_speaker,
             profile_exists,
             delete_profile,
-            list_profiles
+            list_profiles,
+            punctuate_text,
+            match_intent,
+            reload_commands
         ])
-        .run(tauri::generate_context!())
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+        .build(tauri::generate_context!())
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?
+        .run(|app_handle, event| {
+            // Write config changes still inside their debounce period
+            if let tauri::RunEvent::Exit = event {
+                app_handle.state::<AppState>().config_store.stop();
+            }
+        });
+    Ok(())
 }
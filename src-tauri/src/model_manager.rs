@@ -6,14 +6,16 @@
 //! - SHA256 verification
 //! - Model storage management
 
+use crate::error::CommandError;
+use crate::events::{Event, EventSink};
 use anyhow::{anyhow, bail, Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use tauri::{AppHandle, Emitter};
 
 const ALLOWED_HOSTS: &[&str] = &["github.com", "huggingface.co"];
 
@@ -37,7 +39,7 @@ pub struct KwsRegistry {
 }
 
 /// Model download progress event
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct ModelDownloadProgress {
     pub model_id: String,
     pub downloaded: u64,
@@ -93,22 +95,28 @@ impl ModelManager {
 
     /// Get the registry (must be loaded first)
     pub fn registry(&self) -> Result<&KwsRegistry> {
-        self.registry
-            .as_ref()
-            .ok_or_else(|| anyhow!("Registry not loaded"))
+        self.registry.as_ref().ok_or_else(|| {
+            CommandError::Unavailable("KWS model registry not loaded".to_string()).into()
+        })
     }
 
     /// Validate model ID (alphanumeric, _ - only, max 64 chars)
     pub fn validate_model_id(model_id: &str) -> Result<()> {
         if model_id.is_empty() || model_id.len() > 64 {
-            bail!("Model ID must be 1-64 characters");
+            return Err(
+                CommandError::validation("model_id", "Model ID must be 1-64 characters").into(),
+            );
         }
 
         if !model_id
             .chars()
             .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
         {
-            bail!("Model ID can only contain alphanumeric, underscore, and hyphen");
+            return Err(CommandError::validation(
+                "model_id",
+                "Model ID can only contain alphanumeric, underscore, and hyphen",
+            )
+            .into());
         }
 
         Ok(())
@@ -216,13 +224,13 @@ impl ModelManager {
     }
 
     /// Download model with progress tracking
-    pub async fn download_model(&self, app_handle: &AppHandle, model_id: &str) -> Result<()> {
+    pub async fn download_model(&self, sink: &dyn EventSink, model_id: &str) -> Result<()> {
         Self::validate_model_id(model_id)?;
 
         let registry = self.registry()?;
         let entry = registry
             .get_model(model_id)
-            .ok_or_else(|| anyhow!("Model '{}' not found in registry", model_id))?;
+            .ok_or_else(|| CommandError::ModelMissing(model_id.to_string()))?;
 
         Self::validate_url(&entry.url)?;
 
@@ -242,7 +250,7 @@ impl ModelManager {
             .context("Failed to start download")?;
 
         if !response.status().is_success() {
-            bail!("Download failed with status: {}", response.status());
+            return Err(CommandError::DownloadFailed(format!("HTTP {}", response.status())).into());
         }
 
         let total_size = entry.size;
@@ -264,7 +272,7 @@ impl ModelManager {
                     percent,
                 };
 
-                let _ = app_handle.emit("kws:model_download_progress", &progress);
+                let _ = sink.publish(Event::KwsModelDownloadProgress(progress));
             }
         }
 
@@ -332,6 +340,16 @@ mod tests {
         assert!(ModelManager::validate_model_id("a".repeat(65).as_str()).is_err());
         assert!(ModelManager::validate_model_id("model/path").is_err());
         assert!(ModelManager::validate_model_id("model:name").is_err());
+
+        let error = CommandError::from(ModelManager::validate_model_id("").unwrap_err());
+        assert_eq!(error.code(), "validation");
+    }
+
+    #[test]
+    fn test_missing_registry_is_unavailable() {
+        let manager = ModelManager::new(PathBuf::from("/nonexistent"));
+        let error = CommandError::from(manager.registry().unwrap_err());
+        assert_eq!(error.code(), "unavailable");
     }
 
     #[test]
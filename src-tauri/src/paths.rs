@@ -12,6 +12,8 @@ pub struct AppPaths {
     pub data: PathBuf,
     /// Cache directory (temp audio, logs)
     pub cache: PathBuf,
+    /// Runtime directory (IPC socket)
+    pub runtime: PathBuf,
 }
 
 impl AppPaths {
@@ -22,7 +24,11 @@ impl AppPaths {
     /// ## Linux
     /// - Config: `~/.config/Emberleaf/`
     /// - Data: `~/.local/share/Emberleaf/` → models/, voiceprints/, sync/, state.toml
-    /// - Cache: `~/.cache/Emberleaf/` → tmp_audio/, logs/
+    /// - Cache: `~/.cache/Emberleaf/` → tmp_audio/, logs/, diagnostics/
+    /// - Runtime: `$XDG_RUNTIME_DIR/emberleaf/` → ember.sock
+    ///
+    /// Without `XDG_RUNTIME_DIR` (and on macOS/Windows) the runtime directory
+    /// is `<cache>/run/`.
     ///
     /// ## macOS
     /// - Config: `~/Library/Preferences/Emberleaf/`
@@ -37,10 +43,17 @@ impl AppPaths {
         let proj_dirs = ProjectDirs::from("com", "LotusEmberLabs", "Emberleaf")
             .context("Failed to determine project directories")?;
 
+        let cache = proj_dirs.cache_dir().to_path_buf();
+        let runtime = proj_dirs
+            .runtime_dir()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_else(|| cache.join("run"));
+
         let paths = Self {
             config: proj_dirs.config_dir().to_path_buf(),
             data: proj_dirs.data_dir().to_path_buf(),
-            cache: proj_dirs.cache_dir().to_path_buf(),
+            cache,
+            runtime,
         };
 
         Ok(paths)
@@ -50,6 +63,16 @@ impl AppPaths {
     pub fn ensure_directories(&self) -> Result<()> {
         // Config directory
         fs::create_dir_all(&self.config).context("Failed to create config directory")?;
+        fs::create_dir_all(self.hooks_dir()).context("Failed to create hooks directory")?;
+        fs::create_dir_all(self.config_profiles_dir())
+            .context("Failed to create config profiles directory")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            // Hooks run with the user's privileges; keep the directory private
+            fs::set_permissions(self.hooks_dir(), fs::Permissions::from_mode(0o700))
+                .context("Failed to set hooks directory permissions")?;
+        }
 
         // Data directory and subdirectories
         fs::create_dir_all(&self.data).context("Failed to create data directory")?;
@@ -61,9 +84,15 @@ impl AppPaths {
 
         // Cache directory and subdirectories
         fs::create_dir_all(&self.cache).context("Failed to create cache directory")?;
-        fs::create_dir_all(self.cache.join("tmp_audio"))
-            .context("Failed to create tmp_audio directory")?;
-        fs::create_dir_all(self.cache.join("logs")).context("Failed to create logs directory")?;
+        fs::create_dir_all(self.tmp_audio_dir()).context("Failed to create tmp_audio directory")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            // Debug recordings contain whatever the microphone heard
+            fs::set_permissions(self.tmp_audio_dir(), fs::Permissions::from_mode(0o700))
+                .context("Failed to set tmp_audio directory permissions")?;
+        }
+        fs::create_dir_all(self.logs_dir()).context("Failed to create logs directory")?;
 
         log::info!("Application directories initialized");
         log::debug!("  Config: {}", self.config.display());
@@ -78,6 +107,26 @@ impl AppPaths {
         self.config.join("config.toml")
     }
 
+    /// Get path to intent grammar file
+    pub fn commands_file(&self) -> PathBuf {
+        self.config.join("commands.toml")
+    }
+
+    /// Get path to automation hooks directory (executables and FIFOs)
+    pub fn hooks_dir(&self) -> PathBuf {
+        self.config.join("hooks")
+    }
+
+    /// Get path to named configuration profiles (`<name>.toml`)
+    pub fn config_profiles_dir(&self) -> PathBuf {
+        self.config.join("profiles")
+    }
+
+    /// Get path to the local IPC socket
+    pub fn ipc_socket(&self) -> PathBuf {
+        self.runtime.join("ember.sock")
+    }
+
     /// Get path to state file
     #[allow(dead_code)]
     pub fn state_file(&self) -> PathBuf {
@@ -113,6 +162,31 @@ impl AppPaths {
         self.kws_models_root().join(model_id)
     }
 
+    /// Get path to audio tagging models root directory
+    pub fn tagging_models_root(&self) -> PathBuf {
+        self.models_dir().join("tagging")
+    }
+
+    /// Get path to a specific audio tagging model directory by model_id
+    pub fn tagging_model_dir(&self, model_id: &str) -> PathBuf {
+        self.tagging_models_root().join(model_id)
+    }
+
+    /// Get path to punctuation models root directory
+    pub fn punct_models_root(&self) -> PathBuf {
+        self.models_dir().join("punct")
+    }
+
+    /// Get path to a specific punctuation model directory by model_id
+    pub fn punct_model_dir(&self, model_id: &str) -> PathBuf {
+        self.punct_models_root().join(model_id)
+    }
+
+    /// Get path to punctuation registry file
+    pub fn punct_registry(&self) -> PathBuf {
+        self.models_dir().join("punct_registry.json")
+    }
+
     /// Get path to KWS registry file
     pub fn kws_registry(&self) -> PathBuf {
         self.models_dir().join("kws_registry.json")
@@ -136,11 +210,20 @@ impl AppPaths {
         self.voiceprints_dir()
     }
 
-    /// Get path to logs directory
-    #[allow(dead_code)]
+    /// Get path to debug recordings directory (see `audio/recorder.rs`)
+    pub fn tmp_audio_dir(&self) -> PathBuf {
+        self.cache.join("tmp_audio")
+    }
+
+    /// Get path to logs directory (see `logging.rs`)
     pub fn logs_dir(&self) -> PathBuf {
         self.cache.join("logs")
     }
+
+    /// Get path to diagnostic bundles directory (see `diagnostics.rs`)
+    pub fn diagnostics_dir(&self) -> PathBuf {
+        self.cache.join("diagnostics")
+    }
 }
 
 #[cfg(test)]
@@ -155,5 +238,6 @@ mod tests {
         assert!(!paths.config.as_os_str().is_empty());
         assert!(!paths.data.as_os_str().is_empty());
         assert!(!paths.cache.as_os_str().is_empty());
+        assert!(!paths.runtime.as_os_str().is_empty());
     }
 }
@@ -4,12 +4,13 @@
  * Verifies audio stack, webkit, portals, and mic access before onboarding.
  * Emits events for UI feedback and returns structured report.
  */
+use crate::events::{Event, EventSink};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::process::Command;
-use tauri::{AppHandle, Emitter};
 
 /// Status of an individual preflight check
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum CheckStatus {
     Pass,
@@ -18,7 +19,7 @@ pub enum CheckStatus {
 }
 
 /// Individual preflight check result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PreflightItem {
     pub name: String,
     pub status: CheckStatus,
@@ -27,7 +28,7 @@ pub struct PreflightItem {
 }
 
 /// Complete preflight report
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PreflightReport {
     pub items: Vec<PreflightItem>,
     pub overall: CheckStatus,
@@ -53,31 +54,31 @@ impl PreflightReport {
 }
 
 /// Run all preflight checks and emit events
-pub fn run_preflight(app: &AppHandle) -> PreflightReport {
+pub fn run_preflight(sink: &dyn EventSink) -> PreflightReport {
     log::info!("Starting preflight checks...");
 
-    let _ = app.emit("preflight:started", ());
+    let _ = sink.publish(Event::PreflightStarted(()));
 
     let mut items = Vec::new();
 
     // Check 1: Audio stack (PipeWire or PulseAudio)
     let audio_check = check_audio_stack();
-    let _ = app.emit("preflight:item", &audio_check);
+    let _ = sink.publish(Event::PreflightItem(audio_check.clone()));
     items.push(audio_check);
 
     // Check 2: WebKit2GTK minimum version
     let webkit_check = check_webkit();
-    let _ = app.emit("preflight:item", &webkit_check);
+    let _ = sink.publish(Event::PreflightItem(webkit_check.clone()));
     items.push(webkit_check);
 
     // Check 3: XDG Desktop Portal
     let portal_check = check_portal();
-    let _ = app.emit("preflight:item", &portal_check);
+    let _ = sink.publish(Event::PreflightItem(portal_check.clone()));
     items.push(portal_check);
 
     // Check 4: Microphone permissions/access
     let mic_check = check_mic_access();
-    let _ = app.emit("preflight:item", &mic_check);
+    let _ = sink.publish(Event::PreflightItem(mic_check.clone()));
     items.push(mic_check);
 
     let overall = PreflightReport::compute_overall(&items);
@@ -89,7 +90,7 @@ pub fn run_preflight(app: &AppHandle) -> PreflightReport {
         can_proceed,
     };
 
-    let _ = app.emit("preflight:done", &report);
+    let _ = sink.publish(Event::PreflightDone(report.clone()));
 
     log::info!("Preflight complete: {:?}", overall);
     report
@@ -255,9 +256,3 @@ fn check_mic_access() -> PreflightItem {
         },
     }
 }
-
-/// Tauri command: Run preflight checks
-#[tauri::command]
-pub async fn run_preflight_checks(app: AppHandle) -> Result<PreflightReport, String> {
-    Ok(run_preflight(&app))
-}
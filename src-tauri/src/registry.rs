@@ -16,7 +16,8 @@ const REGISTRY_PUBLIC_KEY: &str =
     "0000000000000000000000000000000000000000000000000000000000000000";
 
 /// Model integrity verification state
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
 pub enum VerificationState {
     /// Hash matches signed registry
     Verified,
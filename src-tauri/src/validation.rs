@@ -6,8 +6,9 @@
 //! Note: Contains defensive API functions reserved for future use
 #![allow(dead_code)]
 
+use crate::error::CommandError;
+use crate::events::{AudioErrorPayload, Event, EventSink};
 use std::path::{Path, PathBuf};
-use tauri::Emitter;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -178,6 +179,40 @@ pub fn validate_profile_name(name: &str) -> Result<String, ValidationError> {
     Ok(name.to_string())
 }
 
+/// Validate configuration profile name (used as file name: letters, digits, spaces, `-`, `_`)
+pub fn validate_config_profile_name(name: &str) -> Result<String, ValidationError> {
+    if name.trim().is_empty() {
+        return Err(ValidationError::InvalidFormat(
+            "Profile name cannot be empty".to_string(),
+        ));
+    }
+
+    if name.len() > 64 {
+        return Err(ValidationError::ValueTooLong {
+            max: 64,
+            actual: name.len(),
+        });
+    }
+
+    if name.trim() != name {
+        return Err(ValidationError::InvalidFormat(
+            "Profile name cannot start or end with a space".to_string(),
+        ));
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '_' || c == '-')
+    {
+        return Err(ValidationError::InvalidFormat(
+            "Profile name can only contain letters, numbers, spaces, underscores, and hyphens"
+                .to_string(),
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
 /// Validate KWS sensitivity (0.0 to 1.0)
 pub fn validate_sensitivity(sensitivity: f32) -> Result<f32, ValidationError> {
     if !(0.0..=1.0).contains(&sensitivity) {
@@ -262,32 +297,155 @@ pub fn validate_vad_mode(mode: &str) -> Result<String, ValidationError> {
     }
 }
 
-// ========== Validation Error Emission ==========
+/// Validate text submitted for post-processing (max 8192 bytes, no NUL bytes)
+pub fn validate_transcript_text(text: &str) -> Result<String, ValidationError> {
+    if text.trim().is_empty() {
+        return Err(ValidationError::InvalidFormat(
+            "Text cannot be empty".to_string(),
+        ));
+    }
+
+    if text.len() > 8192 {
+        return Err(ValidationError::ValueTooLong {
+            max: 8192,
+            actual: text.len(),
+        });
+    }
+
+    // NUL bytes cannot cross the C API boundary
+    if text.contains('\0') {
+        return Err(ValidationError::InvalidFormat(
+            "Text contains NUL bytes".to_string(),
+        ));
+    }
+
+    Ok(text.to_string())
+}
+
+/// Validate a language code (e.g. "en", "zh", "pt-BR")
+pub fn validate_lang_code(lang: &str) -> Result<String, ValidationError> {
+    if lang.is_empty() || lang.len() > 16 {
+        return Err(ValidationError::InvalidFormat(
+            "Language code must be 1-16 characters".to_string(),
+        ));
+    }
+
+    if !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(ValidationError::InvalidFormat(format!(
+            "Invalid language code '{}'",
+            lang
+        )));
+    }
+
+    Ok(lang.to_string())
+}
+
+/// Validate a numeric setting against an inclusive range (NaN is rejected)
+pub fn validate_range<T>(name: &str, value: T, min: T, max: T) -> Result<T, ValidationError>
+where
+    T: PartialOrd + std::fmt::Display,
+{
+    // Written so that NaN fails both comparisons
+    if !(value >= min && value <= max) {
+        return Err(ValidationError::InvalidRange(format!(
+            "{} must be between {} and {}, got {}",
+            name, min, max, value
+        )));
+    }
+    Ok(value)
+}
+
+/// Validate a setting that must be one of a fixed set of values
+pub fn validate_choice(
+    name: &str,
+    value: &str,
+    allowed: &[&str],
+) -> Result<String, ValidationError> {
+    if !allowed.contains(&value) {
+        return Err(ValidationError::InvalidFormat(format!(
+            "Invalid {} '{}', must be one of: {}",
+            name,
+            value,
+            allowed.join(", ")
+        )));
+    }
+    Ok(value.to_string())
+}
 
-#[derive(serde::Serialize, Debug, Clone)]
-pub struct ValidationErrorPayload<'a> {
-    pub code: &'a str,    // e.g., "invalid_gain", "invalid_device_name"
-    pub message: &'a str, // short, user-friendly
-    pub field: &'a str,   // e.g., "gain", "device_name"
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub value: Option<serde_json::Value>,
+/// Validate a wake-word keyword (1-64 characters, no control characters)
+pub fn validate_keyword(keyword: &str) -> Result<String, ValidationError> {
+    if keyword.trim().is_empty() {
+        return Err(ValidationError::InvalidFormat(
+            "Keyword cannot be empty".to_string(),
+        ));
+    }
+
+    if keyword.len() > 64 {
+        return Err(ValidationError::ValueTooLong {
+            max: 64,
+            actual: keyword.len(),
+        });
+    }
+
+    if keyword.chars().any(|c| c.is_control()) {
+        return Err(ValidationError::InvalidFormat(
+            "Keyword contains control characters".to_string(),
+        ));
+    }
+
+    Ok(keyword.to_string())
 }
 
-/// Emit a validation error event to the frontend
+/// Validate a hook URL (http/https on the loopback interface only)
+pub fn validate_localhost_url(url: &str) -> Result<url::Url, ValidationError> {
+    let parsed = url::Url::parse(url)
+        .map_err(|e| ValidationError::InvalidFormat(format!("Invalid URL: {}", e)))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(ValidationError::InvalidFormat(format!(
+            "URL scheme must be http or https, got '{}'",
+            parsed.scheme()
+        )));
+    }
+
+    let is_loopback = match parsed.host() {
+        Some(url::Host::Domain(host)) => host.eq_ignore_ascii_case("localhost"),
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    };
+    if !is_loopback {
+        return Err(ValidationError::InvalidFormat(
+            "URL host must be localhost, 127.0.0.1 or ::1".to_string(),
+        ));
+    }
+
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err(ValidationError::InvalidFormat(
+            "URL must not contain credentials".to_string(),
+        ));
+    }
+
+    Ok(parsed)
+}
+
+// ========== Validation Error Emission ==========
+
+/// Emit a validation error as `audio:error`, returning the command error to fail with
 pub fn emit_validation_error(
-    app: &tauri::AppHandle,
-    code: &str,
-    field: &str,
-    message: &str,
+    sink: &dyn EventSink,
+    code: &str,    // e.g., "invalid_gain", "invalid_device_name"
+    field: &str,   // e.g., "gain", "device_name"
+    message: &str, // short, user-friendly
     value: Option<serde_json::Value>,
-) {
-    let payload = ValidationErrorPayload {
-        code,
-        field,
-        message,
+) -> CommandError {
+    let _ = sink.publish(Event::AudioError(AudioErrorPayload {
+        code: code.to_string(),
+        message: message.to_string(),
+        field: Some(field.to_string()),
         value,
-    };
-    let _ = app.emit("audio:error", &payload);
+    }));
+    CommandError::validation(field, message)
 }
 
 #[cfg(test)]
@@ -360,6 +518,17 @@ mod tests {
         assert!(validate_profile_name("user/path").is_err()); // Slash
     }
 
+    #[test]
+    fn test_config_profile_name() {
+        assert!(validate_config_profile_name("living room").is_ok());
+        assert!(validate_config_profile_name("sala-2").is_ok());
+        assert!(validate_config_profile_name("").is_err());
+        assert!(validate_config_profile_name(" desk").is_err());
+        assert!(validate_config_profile_name("../config").is_err());
+        assert!(validate_config_profile_name("desk.toml").is_err());
+        assert!(validate_config_profile_name(&"a".repeat(65)).is_err());
+    }
+
     #[test]
     fn test_sensitivity_valid() {
         assert!(validate_sensitivity(0.0).is_ok());
@@ -442,6 +611,70 @@ mod tests {
         assert!(validate_vad_mode("Aggressive").is_err()); // Case sensitive
         assert!(validate_vad_mode("").is_err());
     }
+
+    #[test]
+    fn test_transcript_text_valid() {
+        assert!(validate_transcript_text("hello world").is_ok());
+        assert!(validate_transcript_text("你好世界").is_ok());
+        assert!(validate_transcript_text(&"a".repeat(8192)).is_ok());
+    }
+
+    #[test]
+    fn test_transcript_text_invalid() {
+        assert!(validate_transcript_text("").is_err());
+        assert!(validate_transcript_text("   ").is_err());
+        assert!(validate_transcript_text("hello\0world").is_err());
+        assert!(validate_transcript_text(&"a".repeat(8193)).is_err());
+    }
+
+    #[test]
+    fn test_localhost_url_valid() {
+        assert!(validate_localhost_url("http://localhost:8123/api/ember").is_ok());
+        assert!(validate_localhost_url("http://127.0.0.1:5000/hook").is_ok());
+        assert!(validate_localhost_url("https://[::1]/hook").is_ok());
+    }
+
+    #[test]
+    fn test_localhost_url_invalid() {
+        assert!(validate_localhost_url("http://example.com/hook").is_err());
+        assert!(validate_localhost_url("http://192.168.1.10/hook").is_err());
+        assert!(validate_localhost_url("http://localhost.evil.com/hook").is_err());
+        assert!(validate_localhost_url("file:///etc/passwd").is_err());
+        assert!(validate_localhost_url("http://user:pw@localhost/hook").is_err());
+        assert!(validate_localhost_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_range_and_choice() {
+        assert_eq!(validate_range("frame_ms", 20, 10, 100).unwrap(), 20);
+        assert!(validate_range("frame_ms", 5, 10, 100).is_err());
+        assert!(validate_range("threshold", f32::NAN, 0.0, 1.0).is_err());
+        assert!(validate_choice("provider", "cpu", &["cpu", "cuda"]).is_ok());
+        assert!(validate_choice("provider", "CPU", &["cpu", "cuda"]).is_err());
+    }
+
+    #[test]
+    fn test_keyword() {
+        assert!(validate_keyword("hey ember").is_ok());
+        assert!(validate_keyword("  ").is_err());
+        assert!(validate_keyword("hey\nember").is_err());
+        assert!(validate_keyword(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_lang_code_valid() {
+        assert!(validate_lang_code("en").is_ok());
+        assert!(validate_lang_code("zh").is_ok());
+        assert!(validate_lang_code("pt-BR").is_ok());
+    }
+
+    #[test]
+    fn test_lang_code_invalid() {
+        assert!(validate_lang_code("").is_err());
+        assert!(validate_lang_code("en_US").is_err());
+        assert!(validate_lang_code("../en").is_err());
+        assert!(validate_lang_code(&"a".repeat(17)).is_err());
+    }
 }
 
 #[cfg(test)]
@@ -3,6 +3,7 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+use crate::error::CommandError;
 #[cfg(feature = "kws_real")]
 use crate::ffi::sherpa_onnx_bindings::*;
 use anyhow::{bail, Context, Result};
@@ -380,7 +381,9 @@ impl SpeakerBiometrics {
         let mut state = self.enrollment_state.lock().unwrap();
 
         if state.is_some() {
-            bail!("Enrollment already in progress");
+            return Err(
+                CommandError::InvalidState("Enrollment already in progress".to_string()).into(),
+            );
         }
 
         log::info!("Starting enrollment for user: {}", user);
@@ -399,17 +402,22 @@ impl SpeakerBiometrics {
         let mut state = self.enrollment_state.lock().unwrap();
 
         let enrollment = state.as_mut().ok_or_else(|| {
-            anyhow::anyhow!("No enrollment in progress. Call enroll_start first.")
+            CommandError::InvalidState(
+                "No enrollment in progress. Call enroll_start first.".to_string(),
+            )
         })?;
 
         // Check minimum duration (rough estimate: samples / sample_rate * 1000)
         let duration_ms = (samples.len() as f32 / self.sample_rate as f32 * 1000.0) as u64;
         if duration_ms < self.config.utterance_min_ms {
-            bail!(
-                "Utterance too short: {}ms (minimum {}ms)",
-                duration_ms,
-                self.config.utterance_min_ms
-            );
+            return Err(CommandError::validation(
+                "samples",
+                format!(
+                    "Utterance too short: {}ms (minimum {}ms)",
+                    duration_ms, self.config.utterance_min_ms
+                ),
+            )
+            .into());
         }
 
         // Extract embedding
@@ -438,14 +446,15 @@ impl SpeakerBiometrics {
 
         let enrollment = state
             .take()
-            .ok_or_else(|| anyhow::anyhow!("No enrollment in progress"))?;
+            .ok_or_else(|| CommandError::InvalidState("No enrollment in progress".to_string()))?;
 
         if enrollment.embeddings.len() < enrollment.required_count {
-            bail!(
+            return Err(CommandError::InvalidState(format!(
                 "Not enough utterances: {}/{}",
                 enrollment.embeddings.len(),
                 enrollment.required_count
-            );
+            ))
+            .into());
         }
 
         // Average embeddings
@@ -489,7 +498,7 @@ impl SpeakerBiometrics {
         // Load voiceprint
         let profile_path = self.profile_path(user);
         if !profile_path.exists() {
-            bail!("No voiceprint found for user: {}", user);
+            return Err(CommandError::ProfileNotFound(user.to_string()).into());
         }
 
         let json = fs::read_to_string(&profile_path).context("Failed to read voiceprint file")?;
@@ -531,36 +540,44 @@ impl SpeakerBiometrics {
 
     /// Delete a user's voiceprint
     pub fn delete_profile(&self, user: &str) -> Result<()> {
-        let profile_path = self.profile_path(user);
-        if !profile_path.exists() {
-            bail!("No voiceprint found for user: {}", user);
-        }
-
-        fs::remove_file(&profile_path).context("Failed to delete voiceprint")?;
-        log::info!("Deleted voiceprint for user: {}", user);
-
-        Ok(())
+        delete_profile_in(&self.profiles_dir, user)
     }
 
     /// List all enrolled users
     pub fn list_profiles(&self) -> Result<Vec<String>> {
-        let mut users = Vec::new();
-
-        for entry in
-            fs::read_dir(&self.profiles_dir).context("Failed to read profiles directory")?
-        {
-            let entry = entry.context("Failed to read directory entry")?;
-            let path = entry.path();
-
-            if path.extension().and_then(|s| s.to_str()) == Some("voiceprint") {
-                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                    users.push(stem.to_string());
-                }
+        list_profiles_in(&self.profiles_dir)
+    }
+}
+
+/// List enrolled users from voiceprint files (no model needed)
+pub fn list_profiles_in(profiles_dir: &Path) -> Result<Vec<String>> {
+    let mut users = Vec::new();
+
+    for entry in fs::read_dir(profiles_dir).context("Failed to read profiles directory")? {
+        let entry = entry.context("Failed to read directory entry")?;
+        let path = entry.path();
+
+        if path.extension().and_then(|s| s.to_str()) == Some("voiceprint") {
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                users.push(stem.to_string());
             }
         }
+    }
+
+    Ok(users)
+}
 
-        Ok(users)
+/// Delete a user's voiceprint file (no model needed)
+pub fn delete_profile_in(profiles_dir: &Path, user: &str) -> Result<()> {
+    let profile_path = profiles_dir.join(format!("{}.voiceprint", user));
+    if !profile_path.exists() {
+        return Err(CommandError::ProfileNotFound(user.to_string()).into());
     }
+
+    fs::remove_file(&profile_path).context("Failed to delete voiceprint")?;
+    log::info!("Deleted voiceprint for user: {}", user);
+
+    Ok(())
 }
 
 #[cfg(feature = "kws_real")]
@@ -589,21 +606,21 @@ impl SpeakerBiometrics {
     }
 
     pub fn enroll_start(&self, _user: String) -> Result<()> {
-        bail!("Speaker biometrics not available")
+        Err(CommandError::Unavailable("Speaker biometrics not available".to_string()).into())
     }
 
     pub fn enroll_add_sample(&self, _samples: &[f32]) -> Result<EnrollmentProgress> {
-        bail!("Speaker biometrics not available")
+        Err(CommandError::Unavailable("Speaker biometrics not available".to_string()).into())
     }
 
     pub fn enroll_finalize(&self) -> Result<ProfileInfo> {
-        bail!("Speaker biometrics not available")
+        Err(CommandError::Unavailable("Speaker biometrics not available".to_string()).into())
     }
 
     pub fn enroll_cancel(&self) {}
 
     pub fn verify(&self, _user: &str, _samples: &[f32]) -> Result<VerificationResult> {
-        bail!("Speaker biometrics not available")
+        Err(CommandError::Unavailable("Speaker biometrics not available".to_string()).into())
     }
 
     pub fn profile_exists(&self, _user: &str) -> bool {
@@ -611,7 +628,7 @@ impl SpeakerBiometrics {
     }
 
     pub fn delete_profile(&self, _user: &str) -> Result<()> {
-        bail!("Speaker biometrics not available")
+        Err(CommandError::Unavailable("Speaker biometrics not available".to_string()).into())
     }
 
     pub fn list_profiles(&self) -> Result<Vec<String>> {
@@ -1,10 +1,14 @@
-//! Voice biometrics module
+//! Voice biometrics and text post-processing
 //!
 //! This module provides speaker enrollment and verification using
-//! Sherpa-ONNX ECAPA-TDNN embeddings with encrypted storage.
+//! Sherpa-ONNX ECAPA-TDNN embeddings with encrypted storage, and
+//! punctuation/casing restoration for recognized text.
 
 pub mod biometrics;
+pub mod punctuation;
 
 pub use biometrics::{
-    BiometricsConfig, EnrollmentProgress, ProfileInfo, SpeakerBiometrics, VerificationResult,
+    delete_profile_in, list_profiles_in, BiometricsConfig, EnrollmentProgress, ProfileInfo,
+    SpeakerBiometrics, VerificationResult,
 };
+pub use punctuation::{PunctRegistry, PunctuationConfig, PunctuationEngine, PunctuationResult};
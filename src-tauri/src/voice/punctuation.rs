@@ -0,0 +1,631 @@
+//! Punctuation and casing restoration for recognized text
+//!
+//! Recognizers emit lowercase, unpunctuated text. This module wraps the
+//! Sherpa-ONNX punctuation models and follows them with a rule-based casing
+//! pass (sentence starts, standalone "I"):
+//!
+//! - offline CT-Transformer models (`SherpaOfflinePunctuationAddPunct`), in
+//!   every `kws_real` build
+//! - online CNN-BiLSTM models (`SherpaOnnxOnlinePunctuationAddPunct`), which
+//!   also restore casing; the bundled Sherpa-ONNX v1.10.30 C API lacks them, so
+//!   they need a newer Sherpa-ONNX and the `punct_online` feature
+//!
+//! Models are chosen per language: `[punctuation.models]` in config.toml maps a
+//! language code to a model id, falling back to the defaults in
+//! `punct_registry.json`, whose `kind` says which of the two a model is. Model
+//! files live in `models/punct/<model_id>/`.
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Punctuation configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PunctuationConfig {
+    /// Reserved for applying it to recognizer output; nothing in the app
+    /// produces transcripts yet, so text goes through `punctuate_text`
+    pub enabled: bool,
+    /// Language assumed when the caller does not pass one
+    pub default_lang: String,
+    /// Per-language model overrides (language code -> model id)
+    pub models: HashMap<String, String>,
+    /// Capitalize sentence starts and standalone "i" after punctuation
+    pub restore_casing: bool,
+    /// Number of threads for model inference
+    pub num_threads: i32,
+    /// ONNX Runtime provider (cpu, cuda, coreml, etc.)
+    pub provider: String,
+}
+
+impl Default for PunctuationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_lang: "en".to_string(),
+            models: HashMap::new(),
+            restore_casing: true,
+            num_threads: 1,
+            provider: "cpu".to_string(),
+        }
+    }
+}
+
+/// Punctuation model flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctModelKind {
+    /// CT-Transformer, whole-utterance (supported)
+    Offline,
+    /// CNN-BiLSTM, streaming punctuation + casing (`punct_online` builds)
+    Online,
+}
+
+/// Punctuation model registry entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PunctModelEntry {
+    pub url: String,
+    pub langs: Vec<String>,
+    pub kind: PunctModelKind,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Punctuation model registry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PunctRegistry {
+    pub version: String,
+    pub models: HashMap<String, PunctModelEntry>,
+    /// Default model id per language code
+    #[serde(default)]
+    pub defaults: HashMap<String, String>,
+}
+
+impl PunctRegistry {
+    /// Load punctuation registry from file
+    pub fn load(registry_path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(registry_path)
+            .with_context(|| format!("Failed to read registry: {}", registry_path.display()))?;
+
+        let registry: PunctRegistry =
+            serde_json::from_str(&content).context("Failed to parse punctuation registry JSON")?;
+
+        log::info!(
+            "Punctuation registry loaded: {} models",
+            registry.models.len()
+        );
+        Ok(registry)
+    }
+
+    /// Pick a model for `lang`: config override, then registry default, then any
+    /// registry model listing the language. Region subtags fall back to the
+    /// primary language ("pt-BR" -> "pt").
+    pub fn resolve(
+        &self,
+        lang: &str,
+        overrides: &HashMap<String, String>,
+    ) -> Option<(String, PunctModelKind)> {
+        let lang = lang.to_lowercase();
+        let primary = lang.split('-').next().unwrap_or(&lang).to_string();
+
+        for key in [&lang, &primary] {
+            if let Some(model_id) = overrides.get(key.as_str()) {
+                // Hand-installed models that are not in the registry are assumed offline
+                let kind = self
+                    .models
+                    .get(model_id)
+                    .map(|e| e.kind)
+                    .unwrap_or(PunctModelKind::Offline);
+                return Some((model_id.clone(), kind));
+            }
+        }
+
+        for key in [&lang, &primary] {
+            if let Some(model_id) = self.defaults.get(key.as_str()) {
+                if let Some(entry) = self.models.get(model_id) {
+                    return Some((model_id.clone(), entry.kind));
+                }
+            }
+        }
+
+        let mut candidates: Vec<_> = self
+            .models
+            .iter()
+            .filter(|(_, e)| e.langs.iter().any(|l| l.eq_ignore_ascii_case(&primary)))
+            .collect();
+        // HashMap order is unstable; prefer offline models, then by id
+        candidates.sort_by_key(|(id, e)| (e.kind != PunctModelKind::Offline, id.to_string()));
+        candidates.first().map(|(id, e)| (id.to_string(), e.kind))
+    }
+}
+
+/// Result of `punctuate_text`
+#[derive(Debug, Clone, Serialize)]
+pub struct PunctuationResult {
+    pub text: String,
+    pub lang: String,
+    /// Model used for punctuation (None when only casing was applied)
+    pub model_id: Option<String>,
+    pub punctuated: bool,
+    pub cased: bool,
+}
+
+/// Caches loaded punctuation models and applies them to text
+pub struct PunctuationEngine {
+    models_root: PathBuf,
+    registry: PunctRegistry,
+    loaded: HashMap<String, Box<dyn AddPunct>>,
+    /// (num_threads, provider) the cached models were created with
+    loaded_settings: Option<(i32, String)>,
+}
+
+impl PunctuationEngine {
+    pub fn new(models_root: PathBuf, registry: PunctRegistry) -> Self {
+        Self {
+            models_root,
+            registry,
+            loaded: HashMap::new(),
+            loaded_settings: None,
+        }
+    }
+
+    /// Punctuate and re-case `text`. Model failures are logged and the text
+    /// falls back to casing only, so callers always get usable output.
+    pub fn punctuate(
+        &mut self,
+        config: &PunctuationConfig,
+        text: &str,
+        lang: Option<&str>,
+    ) -> PunctuationResult {
+        let lang = lang.unwrap_or(&config.default_lang).to_string();
+        let mut output = normalize_whitespace(text);
+        let mut model_id = None;
+
+        match self.add_punct(config, &output, &lang) {
+            Ok(Some((id, punctuated))) => {
+                output = punctuated;
+                model_id = Some(id);
+            }
+            Ok(None) => log::debug!("No punctuation model configured for '{}'", lang),
+            Err(e) => log::warn!("Punctuation model unavailable for '{}': {:#}", lang, e),
+        }
+
+        let cased = config.restore_casing;
+        if cased {
+            output = restore_casing(&output);
+        }
+
+        PunctuationResult {
+            text: output,
+            lang,
+            punctuated: model_id.is_some(),
+            model_id,
+            cased,
+        }
+    }
+
+    fn add_punct(
+        &mut self,
+        config: &PunctuationConfig,
+        text: &str,
+        lang: &str,
+    ) -> Result<Option<(String, String)>> {
+        let Some((model_id, kind)) = self.registry.resolve(lang, &config.models) else {
+            return Ok(None);
+        };
+
+        // Drop cached models if inference settings changed
+        let settings = (config.num_threads, config.provider.clone());
+        if self.loaded_settings.as_ref() != Some(&settings) {
+            self.loaded.clear();
+            self.loaded_settings = Some(settings);
+        }
+
+        if !self.loaded.contains_key(&model_id) {
+            let model_dir = self.models_root.join(&model_id);
+            if !model_dir.exists() {
+                bail!("Punctuation model not installed: {}", model_dir.display());
+            }
+            let punctuator: Box<dyn AddPunct> = match kind {
+                PunctModelKind::Offline => {
+                    Box::new(punctuator::Punctuator::new(config, &model_dir)?)
+                }
+                PunctModelKind::Online => Box::new(online_punctuator::OnlinePunctuator::new(
+                    config, &model_dir,
+                )?),
+            };
+            self.loaded.insert(model_id.clone(), punctuator);
+        }
+
+        let punctuated = self.loaded[&model_id].add_punct(text)?;
+        Ok(Some((model_id, punctuated)))
+    }
+}
+
+/// A loaded punctuation model
+trait AddPunct: Send {
+    fn add_punct(&self, text: &str) -> Result<String>;
+}
+
+/// `model.onnx`, or the int8 variant, in `model_dir`
+#[cfg_attr(not(feature = "kws_real"), allow(dead_code))]
+fn model_file(model_dir: &Path) -> Result<PathBuf> {
+    ["model.onnx", "model.int8.onnx"]
+        .iter()
+        .map(|f| model_dir.join(f))
+        .find(|p| p.exists())
+        .with_context(|| format!("No model.onnx found in {}", model_dir.display()))
+}
+
+/// Collapse whitespace runs into single spaces and trim the ends
+pub fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Rule-based casing: capitalize the first letter of each sentence and the
+/// standalone pronoun "i" (including contractions like "i'm"). Existing
+/// capitals are never lowered.
+pub fn restore_casing(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut cap_next = true;
+    let mut after_terminator = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+        let next = chars.get(i + 1).copied();
+
+        if c.is_alphabetic() {
+            let standalone_i = c == 'i'
+                && !prev.is_some_and(|p| p.is_alphanumeric() || p == '\'')
+                && !next.is_some_and(|n| n.is_alphanumeric());
+            if cap_next || standalone_i {
+                out.extend(c.to_uppercase());
+            } else {
+                out.push(c);
+            }
+            cap_next = false;
+            after_terminator = false;
+            continue;
+        }
+
+        match c {
+            '.' | '!' | '?' => after_terminator = true,
+            // Full-width terminators are not followed by a space
+            '。' | '！' | '？' => {
+                after_terminator = false;
+                cap_next = true;
+            }
+            c if c.is_whitespace() => {
+                if after_terminator {
+                    cap_next = true;
+                }
+            }
+            c if c.is_numeric() => {
+                cap_next = false;
+                after_terminator = false;
+            }
+            _ => after_terminator = false,
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(feature = "kws_real")]
+mod punctuator {
+    use super::{model_file, AddPunct, PunctuationConfig};
+    use crate::ffi::sherpa_onnx_bindings::*;
+    use anyhow::{bail, Context, Result};
+    use std::ffi::{CStr, CString};
+    use std::path::Path;
+
+    /// Owned Sherpa-ONNX offline punctuation model
+    pub struct Punctuator {
+        handle: *const SherpaOnnxOfflinePunctuation,
+    }
+
+    impl Punctuator {
+        pub fn new(config: &PunctuationConfig, model_dir: &Path) -> Result<Self> {
+            let model_path = model_file(model_dir)?;
+            let model_cstr = CString::new(model_path.to_str().unwrap())?;
+            let provider_cstr = CString::new(config.provider.as_str())?;
+
+            let punct_config = SherpaOnnxOfflinePunctuationConfig {
+                model: SherpaOnnxOfflinePunctuationModelConfig {
+                    ct_transformer: model_cstr.as_ptr(),
+                    num_threads: config.num_threads,
+                    debug: 0,
+                    provider: provider_cstr.as_ptr(),
+                },
+            };
+
+            let handle = unsafe { SherpaOnnxCreateOfflinePunctuation(&punct_config) };
+            if handle.is_null() {
+                bail!("Failed to create Sherpa-ONNX punctuation model. Check model files.");
+            }
+
+            log::info!("Punctuation model loaded from {}", model_path.display());
+            Ok(Self { handle })
+        }
+    }
+
+    impl AddPunct for Punctuator {
+        fn add_punct(&self, text: &str) -> Result<String> {
+            let text_cstr = CString::new(text).context("Text contains NUL bytes")?;
+
+            unsafe {
+                let result = SherpaOfflinePunctuationAddPunct(self.handle, text_cstr.as_ptr());
+                if result.is_null() {
+                    bail!("Punctuation model returned no text");
+                }
+                let punctuated = CStr::from_ptr(result).to_string_lossy().into_owned();
+                SherpaOfflinePunctuationFreeText(result);
+                Ok(punctuated)
+            }
+        }
+    }
+
+    impl Drop for Punctuator {
+        fn drop(&mut self) {
+            unsafe {
+                if !self.handle.is_null() {
+                    SherpaOnnxDestroyOfflinePunctuation(self.handle);
+                }
+            }
+        }
+    }
+
+    // The C API punctuation object has no thread affinity; access is serialized
+    // by the Mutex around PunctuationEngine in AppState
+    unsafe impl Send for Punctuator {}
+}
+
+#[cfg(not(feature = "kws_real"))]
+mod punctuator {
+    use super::{AddPunct, PunctuationConfig};
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    /// Placeholder when kws_real feature is not enabled
+    pub struct Punctuator;
+
+    impl Punctuator {
+        pub fn new(_config: &PunctuationConfig, _model_dir: &Path) -> Result<Self> {
+            bail!("Punctuation models require kws_real feature. Build with --features kws_real")
+        }
+    }
+
+    impl AddPunct for Punctuator {
+        fn add_punct(&self, _text: &str) -> Result<String> {
+            bail!("Punctuation not available")
+        }
+    }
+}
+
+#[cfg(feature = "punct_online")]
+mod online_punctuator {
+    use super::{model_file, AddPunct, PunctuationConfig};
+    use anyhow::{bail, Context, Result};
+    use std::ffi::{CStr, CString};
+    use std::os::raw::{c_char, c_int};
+    use std::path::Path;
+
+    // Online punctuation C API (Sherpa-ONNX after v1.10.30). Declared here
+    // rather than in the generated bindings, which follow the bundled header.
+    #[repr(C)]
+    struct SherpaOnnxOnlinePunctuationModelConfig {
+        cnn_bilstm: *const c_char,
+        bpe_vocab: *const c_char,
+        num_threads: c_int,
+        debug: c_int,
+        provider: *const c_char,
+    }
+
+    #[repr(C)]
+    struct SherpaOnnxOnlinePunctuationConfig {
+        model: SherpaOnnxOnlinePunctuationModelConfig,
+    }
+
+    #[repr(C)]
+    struct SherpaOnnxOnlinePunctuation {
+        _private: [u8; 0],
+    }
+
+    extern "C" {
+        fn SherpaOnnxCreateOnlinePunctuation(
+            config: *const SherpaOnnxOnlinePunctuationConfig,
+        ) -> *const SherpaOnnxOnlinePunctuation;
+        fn SherpaOnnxDestroyOnlinePunctuation(punct: *const SherpaOnnxOnlinePunctuation);
+        fn SherpaOnnxOnlinePunctuationAddPunct(
+            punct: *const SherpaOnnxOnlinePunctuation,
+            text: *const c_char,
+        ) -> *const c_char;
+        fn SherpaOnnxOnlinePunctuationFreeText(text: *const c_char);
+    }
+
+    /// Owned Sherpa-ONNX online (CNN-BiLSTM) punctuation model
+    pub struct OnlinePunctuator {
+        handle: *const SherpaOnnxOnlinePunctuation,
+    }
+
+    impl OnlinePunctuator {
+        pub fn new(config: &PunctuationConfig, model_dir: &Path) -> Result<Self> {
+            let model_path = model_file(model_dir)?;
+            let vocab_path = model_dir.join("bpe.vocab");
+            if !vocab_path.exists() {
+                bail!("No bpe.vocab found in {}", model_dir.display());
+            }
+
+            let model_cstr = CString::new(model_path.to_str().unwrap())?;
+            let vocab_cstr = CString::new(vocab_path.to_str().unwrap())?;
+            let provider_cstr = CString::new(config.provider.as_str())?;
+
+            let punct_config = SherpaOnnxOnlinePunctuationConfig {
+                model: SherpaOnnxOnlinePunctuationModelConfig {
+                    cnn_bilstm: model_cstr.as_ptr(),
+                    bpe_vocab: vocab_cstr.as_ptr(),
+                    num_threads: config.num_threads,
+                    debug: 0,
+                    provider: provider_cstr.as_ptr(),
+                },
+            };
+
+            let handle = unsafe { SherpaOnnxCreateOnlinePunctuation(&punct_config) };
+            if handle.is_null() {
+                bail!("Failed to create Sherpa-ONNX online punctuation model. Check model files.");
+            }
+
+            log::info!(
+                "Online punctuation model loaded from {}",
+                model_path.display()
+            );
+            Ok(Self { handle })
+        }
+    }
+
+    impl AddPunct for OnlinePunctuator {
+        fn add_punct(&self, text: &str) -> Result<String> {
+            let text_cstr = CString::new(text).context("Text contains NUL bytes")?;
+
+            unsafe {
+                let result = SherpaOnnxOnlinePunctuationAddPunct(self.handle, text_cstr.as_ptr());
+                if result.is_null() {
+                    bail!("Online punctuation model returned no text");
+                }
+                let punctuated = CStr::from_ptr(result).to_string_lossy().into_owned();
+                SherpaOnnxOnlinePunctuationFreeText(result);
+                Ok(punctuated)
+            }
+        }
+    }
+
+    impl Drop for OnlinePunctuator {
+        fn drop(&mut self) {
+            unsafe {
+                if !self.handle.is_null() {
+                    SherpaOnnxDestroyOnlinePunctuation(self.handle);
+                }
+            }
+        }
+    }
+
+    // Same as the offline model: access is serialized by the Mutex around
+    // PunctuationEngine in AppState
+    unsafe impl Send for OnlinePunctuator {}
+}
+
+#[cfg(not(feature = "punct_online"))]
+mod online_punctuator {
+    use super::{AddPunct, PunctuationConfig};
+    use anyhow::{bail, Result};
+    use std::path::Path;
+
+    /// Placeholder when punct_online feature is not enabled
+    pub struct OnlinePunctuator;
+
+    impl OnlinePunctuator {
+        pub fn new(_config: &PunctuationConfig, _model_dir: &Path) -> Result<Self> {
+            bail!(
+                "Online punctuation models need a Sherpa-ONNX C API newer than v1.10.30. Build with --features punct_online"
+            )
+        }
+    }
+
+    impl AddPunct for OnlinePunctuator {
+        fn add_punct(&self, _text: &str) -> Result<String> {
+            bail!("Online punctuation not available")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> PunctRegistry {
+        serde_json::from_str(
+            r#"{
+                "version": "1.0.0",
+                "models": {
+                    "ct-zh-en": { "url": "", "langs": ["zh", "en"], "kind": "offline" },
+                    "online-en": { "url": "", "langs": ["en"], "kind": "online" }
+                },
+                "defaults": { "zh": "ct-zh-en" }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_restore_casing() {
+        assert_eq!(
+            restore_casing("hello there. how are you? i'm fine, i think!"),
+            "Hello there. How are you? I'm fine, I think!"
+        );
+        assert_eq!(restore_casing("version 3.5 is out"), "Version 3.5 is out");
+        assert_eq!(restore_casing("it is in the index"), "It is in the index");
+        assert_eq!(restore_casing("你好。hello"), "你好。Hello");
+        assert_eq!(restore_casing("keep NASA caps"), "Keep NASA caps");
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(normalize_whitespace("  hello \n  world\t"), "hello world");
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        let registry = registry();
+        let mut overrides = HashMap::new();
+
+        // Registry default, then any offline model listing the language
+        assert_eq!(
+            registry.resolve("zh", &overrides),
+            Some(("ct-zh-en".to_string(), PunctModelKind::Offline))
+        );
+        assert_eq!(
+            registry.resolve("en-US", &overrides),
+            Some(("ct-zh-en".to_string(), PunctModelKind::Offline))
+        );
+        assert_eq!(registry.resolve("de", &overrides), None);
+
+        // Config override wins
+        overrides.insert("en".to_string(), "online-en".to_string());
+        assert_eq!(
+            registry.resolve("en", &overrides),
+            Some(("online-en".to_string(), PunctModelKind::Online))
+        );
+    }
+
+    #[test]
+    fn test_engine_falls_back_to_casing() {
+        let mut engine = PunctuationEngine::new(PathBuf::from("/nonexistent"), registry());
+        let config = PunctuationConfig::default();
+
+        let result = engine.punctuate(&config, "  i said hello  ", Some("en"));
+        assert_eq!(result.text, "I said hello");
+        assert!(!result.punctuated);
+        assert!(result.cased);
+    }
+
+    #[test]
+    fn test_online_model_selected_by_registry_default() {
+        let mut registry = registry();
+        registry
+            .defaults
+            .insert("en".to_string(), "online-en".to_string());
+        assert_eq!(
+            registry.resolve("en-GB", &HashMap::new()),
+            Some(("online-en".to_string(), PunctModelKind::Online))
+        );
+
+        // Not installed here: casing only, like a missing offline model
+        let mut engine = PunctuationEngine::new(PathBuf::from("/nonexistent"), registry);
+        let result = engine.punctuate(&PunctuationConfig::default(), "i said hello", None);
+        assert_eq!(result.text, "I said hello");
+        assert!(!result.punctuated);
+    }
+}
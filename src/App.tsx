@@ -23,6 +23,7 @@ import {
   SelectValue,
 } from "./components/ui/select";
 import { subscribeGlobalErrors } from "./lib/tauriSafe";
+import type { EventPayloads } from "./lib/events";
 
 const ADVANCED_MODE_KEY = "emberleaf_advanced_mode";
 const PREFLIGHT_COMPLETED_KEY = "emberleaf_preflight_completed";
@@ -82,7 +83,7 @@ function AppContent() {
     async function setupListener() {
       try {
         const { listen } = await import("@tauri-apps/api/event");
-        unlisten = await listen<{ device: string; reason: string }>(
+        unlisten = await listen<EventPayloads["audio:auto_probe_suggestion"]>(
           "audio:auto_probe_suggestion",
           (event) => {
             setSuggestedDevice(event.payload.device);
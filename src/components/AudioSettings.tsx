@@ -18,7 +18,9 @@ import {
   setPersistMonitorState,
   type DeviceInfo,
 } from "../lib/tauriSafe";
+import type { EventPayloads } from "../lib/events";
 import { useI18n } from "../lib/i18n";
+import { toUserMessage } from "../lib/errors";
 import { Button } from "./ui/button";
 import { Badge } from "./ui/badge";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "./ui/card";
@@ -58,22 +60,28 @@ export function AudioSettings() {
     }
   }
 
-  // Listen for monitor_guarded event
+  // Listen for monitor_guarded and device hotplug events
   useEffect(() => {
-    let unlisten: (() => void) | undefined;
+    const unlisten: (() => void)[] = [];
 
     async function setupListener() {
       try {
         const { listen } = await import("@tauri-apps/api/event");
-        unlisten = await listen<{ reason: string }>("audio:monitor_guarded", (event) => {
-          console.log("Monitor guarded:", event.payload.reason);
-          setMonitorGuarded(true);
+        unlisten.push(
+          await listen<EventPayloads["audio:monitor_guarded"]>("audio:monitor_guarded", (event) => {
+            console.log("Monitor guarded:", event.payload.reason);
+            setMonitorGuarded(true);
 
-          // Auto-clear after 8 seconds
-          setTimeout(() => {
-            setMonitorGuarded(false);
-          }, 8000);
-        });
+            // Auto-clear after 8 seconds
+            setTimeout(() => {
+              setMonitorGuarded(false);
+            }, 8000);
+          })
+        );
+
+        // Keep the device lists current while devices are plugged in and out
+        unlisten.push(await listen("audio:device_added", () => loadDevices()));
+        unlisten.push(await listen("audio:device_removed", () => loadDevices()));
       } catch (err) {
         console.log("Event listener not available (web mode)");
       }
@@ -82,7 +90,7 @@ export function AudioSettings() {
     setupListener();
 
     return () => {
-      if (unlisten) unlisten();
+      unlisten.forEach((fn) => fn());
     };
   }, []);
 
@@ -98,40 +106,46 @@ export function AudioSettings() {
 
       setInputDevices(inputs);
       setOutputDevices(outputs);
-      setSelectedInput(currentIn);
-      setSelectedOutput(currentOut);
+      // Selections hold labels, which tell devices with the same name apart
+      setSelectedInput(inputs.find((d) => d.name === currentIn)?.label ?? currentIn);
+      setSelectedOutput(outputs.find((d) => d.name === currentOut)?.label ?? currentOut);
     } catch (error) {
-      toast.error(`Failed to load devices: ${error}`);
+      toast.error(`Failed to load devices: ${toUserMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
   }
 
-  async function handleInputChange(deviceName: string) {
+  async function handleInputChange(label: string) {
+    const device = inputDevices.find((d) => d.label === label);
+    if (!device) return;
     try {
-      const result = await setInputDevice(deviceName, true);
-      setSelectedInput(deviceName);
+      const result = await setInputDevice(device.name, true, device.stable_id);
+      setSelectedInput(label);
       setNeedsRestart(true);
       toast.success(result);
     } catch (error) {
-      toast.error(`Failed to set input device: ${error}`);
+      toast.error(`Failed to set input device: ${toUserMessage(error)}`);
     }
   }
 
-  async function handleOutputChange(deviceName: string) {
+  async function handleOutputChange(label: string) {
+    const device = outputDevices.find((d) => d.label === label);
+    if (!device) return;
+    const deviceName = device.name;
     try {
-      const result = await setOutputDevice(deviceName, true);
-      setSelectedOutput(deviceName);
+      const result = await setOutputDevice(deviceName, true, device.stable_id);
+      setSelectedOutput(label);
       toast.success(result);
 
       // Auto-play test tone on output device change
       setTimeout(() => {
         playTestTone({ deviceName, frequencyHz: 660, durationMs: 250, volume: 0.15 })
           .then((msg) => toast.info(msg))
-          .catch((err) => toast.error(`Test tone failed: ${err}`));
+          .catch((err) => toast.error(`Test tone failed: ${toUserMessage(err)}`));
       }, 100);
     } catch (error) {
-      toast.error(`Failed to set output device: ${error}`);
+      toast.error(`Failed to set output device: ${toUserMessage(error)}`);
     }
   }
 
@@ -142,7 +156,7 @@ export function AudioSettings() {
       setNeedsRestart(false);
       toast.success(result);
     } catch (error) {
-      toast.error(`Failed to restart audio: ${error}`);
+      toast.error(`Failed to restart audio: ${toUserMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -150,22 +164,22 @@ export function AudioSettings() {
 
   async function handleTestTone() {
     try {
+      // Plays on the configured output device (the default if it is unplugged)
       const result = await playTestTone({
-        deviceName: selectedOutput ?? undefined,
         frequencyHz: 880,
         durationMs: 400,
         volume: 0.2,
       });
       toast.success(result);
     } catch (error) {
-      toast.error(`Test tone failed: ${error}`);
+      toast.error(`Test tone failed: ${toUserMessage(error)}`);
     }
   }
 
   async function handleMonitorToggle(checked: boolean) {
     try {
       if (checked) {
-        const result = await startMicMonitor(0.15);
+        const result = await startMicMonitor();
         setIsMonitoring(true);
         toast.success(result);
       } else {
@@ -174,7 +188,7 @@ export function AudioSettings() {
         toast.info(result);
       }
     } catch (error) {
-      toast.error(`Monitor toggle failed: ${error}`);
+      toast.error(`Monitor toggle failed: ${toUserMessage(error)}`);
       setIsMonitoring(false);
     }
   }
@@ -185,7 +199,7 @@ export function AudioSettings() {
       setPersistMonitorStateLocal(checked);
       toast.info(result);
     } catch (error) {
-      toast.error(`Failed to update persistence setting: ${error}`);
+      toast.error(`Failed to update persistence setting: ${toUserMessage(error)}`);
     }
   }
 
@@ -244,8 +258,8 @@ export function AudioSettings() {
               </SelectTrigger>
               <SelectContent>
                 {inputDevices.map((device) => (
-                  <SelectItem key={device.name} value={device.name}>
-                    {device.name} {device.is_default && "(Default)"}
+                  <SelectItem key={device.label} value={device.label}>
+                    {device.label} {device.is_default && "(Default)"}
                   </SelectItem>
                 ))}
               </SelectContent>
@@ -268,8 +282,8 @@ export function AudioSettings() {
               </SelectTrigger>
               <SelectContent>
                 {outputDevices.map((device) => (
-                  <SelectItem key={device.name} value={device.name}>
-                    {device.name} {device.is_default && "(Default)"}
+                  <SelectItem key={device.label} value={device.label}>
+                    {device.label} {device.is_default && "(Default)"}
                   </SelectItem>
                 ))}
               </SelectContent>
@@ -1,18 +1,21 @@
 /**
  * Device Loss Banner - Shows notifications when audio devices are lost/recovered
- * Handles device loss, fallback success/failure, and monitor guarded events
+ * Handles device loss, fallback success/failure, return of the configured
+ * device, and monitor guarded events
  */
 
 import { useEffect, useState } from "react";
 import { AlertCircle, CheckCircle, Info, X } from "lucide-react";
 import { Button } from "./ui/button";
-import { useI18n } from "../lib/i18n";
-import { isTauriEnv, restartAudioCaptureV2, type DeviceId } from "../lib/tauriSafe";
+import { format, useI18n } from "../lib/i18n";
+import { isTauriEnv, restartAudioCaptureV2 } from "../lib/tauriSafe";
+import type { EventPayloads } from "../lib/events";
 
 type BannerState =
   | { type: "none" }
   | { type: "fallback_ok"; deviceName: string }
   | { type: "fallback_failed"; deviceName: string; reason: string }
+  | { type: "restored"; deviceName: string }
   | { type: "monitor_guarded" };
 
 export function DeviceLossBanner({ onOpenDevicePicker }: { onOpenDevicePicker: () => void }) {
@@ -30,7 +33,7 @@ export function DeviceLossBanner({ onOpenDevicePicker }: { onOpenDevicePicker: (
         const { listen } = await import("@tauri-apps/api/event");
 
         // Device lost event
-        const unlistenLost = await listen<{ kind: string; previous: DeviceId }>(
+        const unlistenLost = await listen<EventPayloads["audio:device_lost"]>(
           "audio:device_lost",
           (event) => {
             const { previous } = event.payload;
@@ -41,7 +44,7 @@ export function DeviceLossBanner({ onOpenDevicePicker }: { onOpenDevicePicker: (
         unlisten.push(unlistenLost);
 
         // Fallback success
-        const unlistenFallbackOk = await listen<{ kind: string; new_device: string }>(
+        const unlistenFallbackOk = await listen<EventPayloads["audio:device_fallback_ok"]>(
           "audio:device_fallback_ok",
           (_event) => {
             console.log("Fallback OK");
@@ -58,7 +61,7 @@ export function DeviceLossBanner({ onOpenDevicePicker }: { onOpenDevicePicker: (
         unlisten.push(unlistenFallbackOk);
 
         // Fallback failed
-        const unlistenFallbackFailed = await listen<{ kind: string; reason: string }>(
+        const unlistenFallbackFailed = await listen<EventPayloads["audio:device_fallback_failed"]>(
           "audio:device_fallback_failed",
           (event) => {
             console.log("Fallback failed:", event.payload.reason);
@@ -71,8 +74,25 @@ export function DeviceLossBanner({ onOpenDevicePicker }: { onOpenDevicePicker: (
         );
         unlisten.push(unlistenFallbackFailed);
 
+        // Configured device plugged back in
+        const unlistenRestored = await listen<EventPayloads["audio:device_restored"]>(
+          "audio:device_restored",
+          (event) => {
+            console.log(`Device restored: ${event.payload.device.name}`);
+            setBannerState({ type: "restored", deviceName: event.payload.device.name });
+
+            // Auto-dismiss after 6 seconds
+            setTimeout(() => {
+              setBannerState((prev) =>
+                prev.type === "restored" ? { type: "none" } : prev
+              );
+            }, 6000);
+          }
+        );
+        unlisten.push(unlistenRestored);
+
         // Monitor guarded
-        const unlistenGuarded = await listen<{ reason: string }>(
+        const unlistenGuarded = await listen<EventPayloads["audio:monitor_guarded"]>(
           "audio:monitor_guarded",
           (event) => {
             console.log("Monitor guarded:", event.payload.reason);
@@ -144,6 +164,28 @@ export function DeviceLossBanner({ onOpenDevicePicker }: { onOpenDevicePicker: (
         </div>
       )}
 
+      {bannerState.type === "restored" && (
+        <div className="bg-green-50 dark:bg-green-950 border border-green-200 dark:border-green-800 rounded-lg p-4 shadow-lg">
+          <div className="flex items-start gap-3">
+            <CheckCircle className="h-5 w-5 text-green-600 dark:text-green-400 flex-shrink-0 mt-0.5" />
+            <div className="flex-1 min-w-0">
+              <p className="text-sm font-medium text-green-900 dark:text-green-100">
+                {format(t.deviceLoss?.restored || 'Switched back to "{name}".', {
+                  name: bannerState.deviceName,
+                })}
+              </p>
+            </div>
+            <button
+              onClick={handleDismiss}
+              className="text-green-600 hover:text-green-800 dark:text-green-400 dark:hover:text-green-200 flex-shrink-0"
+              aria-label="Dismiss"
+            >
+              <X className="h-4 w-4" />
+            </button>
+          </div>
+        </div>
+      )}
+
       {bannerState.type === "fallback_failed" && (
         <div className="bg-amber-50 dark:bg-amber-950 border border-amber-200 dark:border-amber-800 rounded-lg p-4 shadow-lg">
           <div className="flex items-start gap-3">
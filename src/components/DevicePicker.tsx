@@ -16,6 +16,7 @@ import {
 import { Button } from "./ui/button";
 import { Badge } from "./ui/badge";
 import { useI18n, format } from "../lib/i18n";
+import { toUserMessage } from "../lib/errors";
 import {
   listInputDevices,
   currentInputDevice,
@@ -50,20 +51,23 @@ export function DevicePicker({ open, onOpenChange, suggestedDevice }: DevicePick
         currentInputDevice(),
       ]);
       setDevices(deviceList);
-      setCurrentDevice(current);
-      setSelectedDevice(current);
+      // Selection holds labels, which tell devices with the same name apart
+      const currentLabel = deviceList.find((d) => d.name === current)?.label ?? current;
+      setCurrentDevice(currentLabel);
+      setSelectedDevice(currentLabel);
     } catch (err) {
-      toast.error(`Failed to load devices: ${err}`);
+      toast.error(`Failed to load devices: ${toUserMessage(err)}`);
     }
   }
 
   async function handleApply() {
-    if (!selectedDevice) return;
+    const device = devices.find((d) => d.label === selectedDevice);
+    if (!device) return;
 
     setApplying(true);
     try {
       // 1. Set the device (persist)
-      await setInputDevice(selectedDevice, true);
+      await setInputDevice(device.name, true, device.stable_id);
 
       // 2. Restart audio capture
       const result = await restartAudioCaptureV2();
@@ -76,21 +80,22 @@ export function DevicePicker({ open, onOpenChange, suggestedDevice }: DevicePick
         toast.error(result.message);
       }
     } catch (err) {
-      toast.error(`Failed to apply: ${err}`);
+      toast.error(`Failed to apply: ${toUserMessage(err)}`);
     } finally {
       setApplying(false);
     }
   }
 
   async function handleSaveForLater() {
-    if (!selectedDevice) return;
+    const device = devices.find((d) => d.label === selectedDevice);
+    if (!device) return;
 
     try {
-      await setInputDevice(selectedDevice, true);
+      await setInputDevice(device.name, true, device.stable_id);
       toast.info(t.saveForLater);
       onOpenChange(false);
     } catch (err) {
-      toast.error(`Failed to save: ${err}`);
+      toast.error(`Failed to save: ${toUserMessage(err)}`);
     }
   }
 
@@ -119,11 +124,11 @@ export function DevicePicker({ open, onOpenChange, suggestedDevice }: DevicePick
               <div className="space-y-2">
                 {suggested.map((device) => (
                   <DeviceItem
-                    key={device.name}
+                    key={device.label}
                     device={device}
-                    selected={selectedDevice === device.name}
-                    current={currentDevice === device.name}
-                    onSelect={() => setSelectedDevice(device.name)}
+                    selected={selectedDevice === device.label}
+                    current={currentDevice === device.label}
+                    onSelect={() => setSelectedDevice(device.label)}
                     badges={
                       <>
                         {device.is_default && <Badge variant="secondary">{t.defaultDevice}</Badge>}
@@ -143,11 +148,11 @@ export function DevicePicker({ open, onOpenChange, suggestedDevice }: DevicePick
               <div className="space-y-2">
                 {others.map((device) => (
                   <DeviceItem
-                    key={device.name}
+                    key={device.label}
                     device={device}
-                    selected={selectedDevice === device.name}
-                    current={currentDevice === device.name}
-                    onSelect={() => setSelectedDevice(device.name)}
+                    selected={selectedDevice === device.label}
+                    current={currentDevice === device.label}
+                    onSelect={() => setSelectedDevice(device.label)}
                     badges={
                       device.is_default ? (
                         <Badge variant="secondary">{t.defaultDevice}</Badge>
@@ -200,7 +205,9 @@ function DeviceItem({ device, selected, current, onSelect, badges }: DeviceItemP
 
   const stableIdText = device.stable_id
     ? format(t.deviceLoss?.stableId || "Stable ID: {id}", {
-        id: `${device.stable_id.host_api}#${device.stable_id.index}`,
+        id:
+          device.stable_id.bus_path ??
+          `${device.stable_id.host_api}#${device.stable_id.index}`,
       })
     : null;
 
@@ -218,7 +225,7 @@ function DeviceItem({ device, selected, current, onSelect, badges }: DeviceItemP
         <div className="flex-1 min-w-0">
           <div className="flex items-center gap-2">
             {selected && <Check className="h-4 w-4 text-primary flex-shrink-0" />}
-            <span className="text-sm font-medium truncate">{device.name}</span>
+            <span className="text-sm font-medium truncate">{device.label}</span>
             {device.stable_id && (
               <div
                 className="group relative"
@@ -1,24 +1,36 @@
 import { useState } from "react";
 import { toast } from "sonner";
-import { FileText, Copy, X } from "lucide-react";
+import { FileText, Copy, Package, X } from "lucide-react";
 import * as Dialog from "@radix-ui/react-dialog";
-import { getAudioSnapshot, type AudioSnapshot } from "../lib/tauriSafe";
+import {
+  createDiagnosticBundle,
+  getAudioSnapshot,
+  getPipelineMetrics,
+  type AudioSnapshot,
+  type PipelineMetricsSnapshot,
+} from "../lib/tauriSafe";
+import { toUserMessage } from "../lib/errors";
 import { Button } from "./ui/button";
+import { Switch } from "./ui/switch";
 import { cn } from "../lib/utils";
 
 export function DiagnoseAudioButton() {
   const [open, setOpen] = useState(false);
   const [snapshot, setSnapshot] = useState<AudioSnapshot | null>(null);
+  const [metrics, setMetrics] = useState<PipelineMetricsSnapshot | null>(null);
   const [loading, setLoading] = useState(false);
+  const [bundling, setBundling] = useState(false);
+  const [includeClips, setIncludeClips] = useState(false);
 
   async function handleOpen() {
     setOpen(true);
     setLoading(true);
     try {
-      const data = await getAudioSnapshot();
+      const [data, pipeline] = await Promise.all([getAudioSnapshot(), getPipelineMetrics()]);
       setSnapshot(data);
+      setMetrics(pipeline);
     } catch (error) {
-      toast.error(`Failed to get audio snapshot: ${error}`);
+      toast.error(`Failed to get audio snapshot: ${toUserMessage(error)}`);
       setOpen(false);
     } finally {
       setLoading(false);
@@ -32,7 +44,19 @@ export function DiagnoseAudioButton() {
       await navigator.clipboard.writeText(json);
       toast.success("Snapshot copied to clipboard");
     } catch (error) {
-      toast.error(`Failed to copy: ${error}`);
+      toast.error(`Failed to copy: ${toUserMessage(error)}`);
+    }
+  }
+
+  async function handleBundle() {
+    setBundling(true);
+    try {
+      const path = await createDiagnosticBundle(includeClips);
+      toast.success(`Bug report saved to ${path}`);
+    } catch (error) {
+      toast.error(`Failed to create bug report: ${toUserMessage(error)}`);
+    } finally {
+      setBundling(false);
     }
   }
 
@@ -74,6 +98,22 @@ export function DiagnoseAudioButton() {
               Audio Diagnostics Snapshot
             </Dialog.Title>
             <div className="flex items-center gap-2">
+              <label
+                className="flex items-center gap-1 text-sm text-muted-foreground"
+                title="Add debug recordings from the cache folder (only exist if audio.recorder is enabled)"
+              >
+                <Switch checked={includeClips} onCheckedChange={setIncludeClips} />
+                Include clips
+              </label>
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={handleBundle}
+                disabled={bundling}
+              >
+                <Package className="h-4 w-4" />
+                {bundling ? "Saving..." : "Save Bug Report"}
+              </Button>
               <Button
                 variant="outline"
                 size="sm"
@@ -119,19 +159,103 @@ export function DiagnoseAudioButton() {
                       <span className="text-muted-foreground">Processing Rate:</span>{" "}
                       {snapshot.debug_info.processing_rate}Hz
                     </div>
+                    <div className="col-span-2">
+                      <span className="text-muted-foreground">Capture Format:</span>{" "}
+                      {snapshot.debug_info.capture_format
+                        ? `${snapshot.debug_info.capture_format.sample_rate}Hz, ${snapshot.debug_info.capture_format.channels}ch, ${snapshot.debug_info.capture_format.sample_format}${snapshot.debug_info.capture_format.negotiated ? "" : " (device default)"}${snapshot.debug_info.capture_format.resampler ? ` → ${snapshot.debug_info.processing_rate}Hz via ${snapshot.debug_info.capture_format.resampler}` : ""}`
+                        : "(capture not running)"}
+                      {snapshot.debug_info.resample_errors > 0 &&
+                        ` · ${snapshot.debug_info.resample_errors} resampler errors`}
+                    </div>
+                    <div className="col-span-2">
+                      <span className="text-muted-foreground">Echo Cancellation:</span>{" "}
+                      {snapshot.debug_info.echo_cancellation === "active"
+                        ? "On"
+                        : snapshot.debug_info.echo_cancellation === "disabled"
+                          ? "Off"
+                          : `Off (needs 16000Hz processing, not ${snapshot.debug_info.processing_rate}Hz)`}
+                    </div>
+                    <div className="col-span-2">
+                      <span className="text-muted-foreground">Capture Queue:</span>{" "}
+                      {snapshot.capture_queue.overruns} overruns ({snapshot.capture_queue.dropped_samples} samples dropped),{" "}
+                      {snapshot.capture_queue.underruns} underruns
+                    </div>
                   </div>
                 </div>
 
+                <div className="space-y-2">
+                  <h3 className="font-medium">Pre-processing Levels</h3>
+                  {snapshot.debug_info.dsp_levels ? (
+                    <div className="grid grid-cols-3 gap-2 text-sm">
+                      <div>
+                        <span className="text-muted-foreground">Input:</span>{" "}
+                        {snapshot.debug_info.dsp_levels.input_rms_dbfs.toFixed(1)} dBFS
+                      </div>
+                      <div>
+                        <span className="text-muted-foreground">Output:</span>{" "}
+                        {snapshot.debug_info.dsp_levels.output_rms_dbfs.toFixed(1)} dBFS
+                      </div>
+                      <div>
+                        <span className="text-muted-foreground">AGC Gain:</span>{" "}
+                        {snapshot.debug_info.dsp_levels.agc_gain_db.toFixed(1)} dB
+                      </div>
+                    </div>
+                  ) : (
+                    <div className="text-sm text-muted-foreground">Capture not running</div>
+                  )}
+                </div>
+
+                {metrics && metrics.stages.length > 0 && (
+                  <div className="space-y-2">
+                    <h3 className="font-medium">Pipeline Latency</h3>
+                    <table className="text-sm w-full">
+                      <thead>
+                        <tr className="text-muted-foreground text-left">
+                          <th className="font-normal">Stage</th>
+                          <th className="font-normal">Count</th>
+                          <th className="font-normal">p50</th>
+                          <th className="font-normal">p95</th>
+                          <th className="font-normal">Max</th>
+                        </tr>
+                      </thead>
+                      <tbody className="font-mono text-xs">
+                        {metrics.stages.map((s) => (
+                          <tr key={s.stage}>
+                            <td>{s.stage}</td>
+                            <td>{s.latency.count}</td>
+                            <td>{s.latency.p50_ms.toFixed(2)} ms</td>
+                            <td>{s.latency.p95_ms.toFixed(2)} ms</td>
+                            <td>{s.latency.max_ms.toFixed(2)} ms</td>
+                          </tr>
+                        ))}
+                      </tbody>
+                    </table>
+                    <div className="text-sm">
+                      <span className="text-muted-foreground">KWS Real-time Factor:</span>{" "}
+                      {metrics.kws_rtf != null ? metrics.kws_rtf.toFixed(3) : "n/a"}
+                      {metrics.threads.map((t) => (
+                        <span key={t.thread}>
+                          {" · "}
+                          <span className="text-muted-foreground">{t.thread} CPU:</span>{" "}
+                          {t.cpu_percent.toFixed(1)}%
+                        </span>
+                      ))}
+                    </div>
+                  </div>
+                )}
+
                 <div className="space-y-2">
                   <h3 className="font-medium">Selected Devices</h3>
                   <div className="text-sm space-y-1">
                     <div>
                       <span className="text-muted-foreground">Input:</span>{" "}
                       {snapshot.selected_input_device || "(default)"}
+                      {!snapshot.input_device_available && " (unplugged, using default)"}
                     </div>
                     <div>
                       <span className="text-muted-foreground">Output:</span>{" "}
                       {snapshot.selected_output_device || "(default)"}
+                      {!snapshot.output_device_available && " (unplugged, using default)"}
                     </div>
                   </div>
                 </div>
@@ -142,6 +266,11 @@ export function DiagnoseAudioButton() {
                     {snapshot.input_devices.map((dev, i) => (
                       <li key={i} className="text-muted-foreground">
                         • {dev.name} {dev.is_default && "(default)"}
+                        {dev.channel_levels_dbfs && (
+                          <span className="font-mono text-xs">
+                            {" "}[{dev.channel_levels_dbfs.map((db, ch) => `ch${ch} ${db.toFixed(0)} dB`).join(", ")}]
+                          </span>
+                        )}
                       </li>
                     ))}
                   </ul>
@@ -15,6 +15,7 @@ import {
   type KwsTestPassPayload,
 } from "../lib/tauriSafe";
 import { useI18n, format } from "../lib/i18n";
+import { toUserMessage } from "../lib/errors";
 import { Button } from "./ui/button";
 import { Badge } from "./ui/badge";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "./ui/card";
@@ -73,7 +74,7 @@ export function KwsSettings() {
         setSelectedModelId(modelId);
       }
     } catch (error) {
-      toast.error(`Failed to load models: ${error}`);
+      toast.error(`Failed to load models: ${toUserMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
@@ -165,7 +166,7 @@ export function KwsSettings() {
       }
     } catch (error) {
       console.error("Test failed:", error);
-      toast.error(`Test error: ${error}`);
+      toast.error(`Test error: ${toUserMessage(error)}`);
       setIsTesting(false);
       setTestResult("fail");
     }
@@ -186,7 +187,7 @@ export function KwsSettings() {
       console.log("KWS Enable result:", result);
       // Success handled by onEnabled event
     } catch (error) {
-      toast.error(`Failed to enable KWS: ${error}`);
+      toast.error(`Failed to enable KWS: ${toUserMessage(error)}`);
       setIsEnabling(false);
     }
   }
@@ -198,7 +199,7 @@ export function KwsSettings() {
       console.log("KWS Disable result:", result);
       // Success handled by onDisabled event
     } catch (error) {
-      toast.error(`Failed to disable KWS: ${error}`);
+      toast.error(`Failed to disable KWS: ${toUserMessage(error)}`);
     } finally {
       setIsLoading(false);
     }
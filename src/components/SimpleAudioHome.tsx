@@ -10,6 +10,7 @@ import { Button } from "./ui/button";
 import { Badge } from "./ui/badge";
 import { Card, CardContent, CardHeader, CardTitle, CardDescription } from "./ui/card";
 import { useI18n, format } from "../lib/i18n";
+import { toUserMessage } from "../lib/errors";
 import {
   suggestInputDevice,
   playTestTone,
@@ -22,6 +23,7 @@ import {
   type KwsStatus,
   type KwsModelEntry,
 } from "../lib/tauriSafe";
+import type { EventPayloads } from "../lib/events";
 
 interface MicMeterProps {
   level: number;
@@ -78,7 +80,7 @@ export function SimpleAudioHome({ onChangeMicrophone }: { onChangeMicrophone: ()
     async function setupListener() {
       try {
         const { listen } = await import("@tauri-apps/api/event");
-        unlisten = await listen<number>("audio:rms", (event) => {
+        unlisten = await listen<EventPayloads["audio:rms"]>("audio:rms", (event) => {
           const rawLevel = event.payload;
           setMicLevel(rawLevel);
 
@@ -143,7 +145,7 @@ export function SimpleAudioHome({ onChangeMicrophone }: { onChangeMicrophone: ()
         toast.warning(result.reason);
       }
     } catch (err) {
-      toast.error(`${t.error}: ${err}`);
+      toast.error(`${t.error}: ${toUserMessage(err)}`);
     } finally {
       setProbing(false);
     }
@@ -152,14 +154,14 @@ export function SimpleAudioHome({ onChangeMicrophone }: { onChangeMicrophone: ()
   async function handleTestSound() {
     try {
       const result = await playTestTone({
-        deviceName: null, // Use default output
+        deviceName: null, // Configured output (the default if none or unplugged)
         frequencyHz: 660,
         durationMs: 250,
         volume: 0.15,
       });
       toast.info(result);
     } catch (err) {
-      toast.error(`${t.error}: ${err}`);
+      toast.error(`${t.error}: ${toUserMessage(err)}`);
     }
   }
 
@@ -223,7 +225,7 @@ export function SimpleAudioHome({ onChangeMicrophone }: { onChangeMicrophone: ()
       await kwsEnable(modelId);
       // Success handled by event
     } catch (err) {
-      toast.error(`${t.error}: ${err}`);
+      toast.error(`${t.error}: ${toUserMessage(err)}`);
       setKwsEnabling(false);
     }
   }
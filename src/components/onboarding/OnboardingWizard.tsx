@@ -8,6 +8,7 @@ import { useState, useEffect } from "react";
 import { CheckCircle2, Mic, Shield, Sparkles, ArrowRight, ArrowLeft } from "lucide-react";
 import { useI18n } from "../../lib/i18n";
 import { runPreflightChecks, PreflightReport, isTauriEnv } from "../../lib/tauriSafe";
+import type { EventPayloads } from "../../lib/events";
 import { listen } from "@tauri-apps/api/event";
 import { Card, CardContent, CardHeader, CardTitle } from "../ui/card";
 import { Button } from "../ui/button";
@@ -148,8 +149,8 @@ function PreflightStep({ onNext, onBack }: PreflightStepProps) {
     let unlisten: (() => void) | undefined;
     const setupListener = async () => {
       if (await isTauriEnv()) {
-        unlisten = await listen("preflight:done", (event) => {
-          setReport(event.payload as PreflightReport);
+        unlisten = await listen<EventPayloads["preflight:done"]>("preflight:done", (event) => {
+          setReport(event.payload);
           setLoading(false);
         });
       }
@@ -263,8 +264,8 @@ function MicTestStep({ onNext, onBack, onSkip }: MicTestStepProps) {
 
     const setupListener = async () => {
       if (await isTauriEnv()) {
-        unlisten = await listen<{ rms: number }>("audio:rms", (event) => {
-          const level = event.payload.rms;
+        unlisten = await listen<EventPayloads["audio:rms"]>("audio:rms", (event) => {
+          const level = event.payload;
           setRmsLevel(level);
 
           // Detect signal above threshold
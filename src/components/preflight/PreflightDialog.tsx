@@ -10,6 +10,7 @@ import { PreflightReport, PreflightItem, CheckStatus, runPreflightChecks } from
 import { useI18n } from "../../lib/i18n";
 import { listen } from "@tauri-apps/api/event";
 import { isTauriEnv } from "../../lib/tauriSafe";
+import type { EventPayloads } from "../../lib/events";
 import {
   Sheet,
   SheetContent,
@@ -169,8 +170,8 @@ export function PreflightDialog({ open, onOpenChange, onComplete }: PreflightDia
 
     const setupListener = async () => {
       if (await isTauriEnv()) {
-        unlisten = await listen("preflight:done", (event) => {
-          setReport(event.payload as PreflightReport);
+        unlisten = await listen<EventPayloads["preflight:done"]>("preflight:done", (event) => {
+          setReport(event.payload);
         });
       }
     };
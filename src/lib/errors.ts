@@ -1,16 +1,37 @@
 /**
- * Error mapping utilities for validation errors
- * Maps backend validation errors to localized user messages
+ * Error mapping utilities
+ * Maps backend command errors and audio:error payloads to localized user
+ * messages by their stable `code` (see docs/ERRORS.md)
  */
 
+import type { AudioErrorPayload } from "./events";
 import type { Language, Translations } from "./i18n/translations";
-import { translations } from "./i18n/translations";
+import { format, translations } from "./i18n/translations";
 
-export type ValidationErrorPayload = {
+export type ValidationErrorPayload = AudioErrorPayload;
+
+/**
+ * Error returned by a failed Tauri command (rejected invoke)
+ */
+export interface CommandError {
   code: string;
   message: string;
+  detail?: string;
   field?: string;
-  value?: unknown;
+  retryable: boolean;
+}
+
+/**
+ * Localized message for validation errors, by the rejected field
+ */
+const VALIDATION_FIELDS: Record<string, keyof Translations["errors"]> = {
+  device_name: "invalid_device_name",
+  frequency_hz: "invalid_frequency",
+  duration_ms: "invalid_duration",
+  gain: "invalid_gain",
+  threshold: "invalid_threshold",
+  text: "invalid_text",
+  lang: "invalid_lang",
 };
 
 /**
@@ -25,25 +46,72 @@ function getI18n(): { t: Translations; language: Language } {
   return { t: translations[language], language };
 }
 
+export function isCommandError(err: unknown): err is CommandError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as CommandError).code === "string" &&
+    typeof (err as CommandError).retryable === "boolean"
+  );
+}
+
+/**
+ * True if retrying (possibly after reconnecting a device) may succeed
+ */
+export function isRetryable(err: unknown): boolean {
+  return isCommandError(err) && err.retryable;
+}
+
+/**
+ * Toast title for config settings repaired at startup
+ */
+export function configRepairedMessage(): string {
+  return getI18n().t.errors.config_repaired;
+}
+
+/**
+ * Toast title for config.toml edited outside the app (`config:changed`)
+ */
+export function configReloadedMessage(): string {
+  return getI18n().t.errors.config_reloaded;
+}
+
+/**
+ * Toast title for an activated configuration profile (`config:profile_activated`)
+ */
+export function profileActivatedMessage(name: string): string {
+  return format(getI18n().t.errors.profile_activated, { name });
+}
+
 /**
  * Convert error payload to user-friendly localized message
- * Handles validation errors from audio:error events and Tauri invoke errors
+ * Handles command errors, audio:error payloads and plain errors
  */
 export function toUserMessage(err: unknown): string {
   const { t } = getI18n();
 
-  // Backend uniform payload from audio:error
+  // Command error or audio:error payload
   if (typeof err === "object" && err && "code" in err) {
-    const code = (err as any).code as string;
+    const { code, field, message } = err as {
+      code: string;
+      field?: string;
+      message?: string;
+    };
+    const key =
+      code === "validation" && field ? VALIDATION_FIELDS[field] : undefined;
+    if (key) {
+      return t.errors[key];
+    }
     // Try to find localized message for the error code
     if (code in t.errors) {
-      return (t.errors as any)[code];
+      return t.errors[code as keyof Translations["errors"]];
     }
     // Fallback to message from payload
-    return (err as any).message ?? t.errors.unknown_error;
+    return message ?? t.errors.unknown_error;
   }
 
-  // Tauri invoke errors often come as strings
+  // Web mode and frontend failures
+  if (err instanceof Error) return err.message;
   if (typeof err === "string") return err;
 
   // Fallback
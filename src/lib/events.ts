@@ -0,0 +1,320 @@
+// Generated by `npm run gen:events` from `src-tauri/src/events/catalog.rs`. Do not edit.
+
+/** Bumped when event names or payload shapes change */
+export const EVENT_SCHEMA_VERSION = 1;
+
+/**
+ * Audio failure shown to the user
+ *
+ * `code` selects a localized message in the frontend; `message` is the fallback. Validation failures also name the rejected `field` and `value`.
+ */
+export interface AudioErrorPayload {
+  code: string;
+  field?: string | null;
+  message: string;
+  value?: unknown;
+}
+
+/** Tagged audio event (`audio:event_tagged` payload) */
+export interface AudioEventTag {
+  label: string;
+  score: number;
+  /** Milliseconds since UNIX epoch */
+  ts: number;
+}
+
+/** Status of an individual preflight check */
+export type CheckStatus = "pass" | "warn" | "fail";
+
+/** `config.toml` was edited outside the app and reloaded */
+export interface ConfigChangedPayload {
+  /** Top-level sections whose values changed (e.g. `["kws", "vad"]`) */
+  sections: string[];
+  /** Invalid values that were reset to their defaults (the file is left as is) */
+  warnings: ConfigWarning[];
+}
+
+/** Something that was changed or ignored while loading the config */
+export interface ConfigWarning {
+  /** Setting concerned (e.g. `vad.threshold`); absent for the whole file */
+  field?: string | null;
+  message: string;
+}
+
+export interface DeviceChangedPayload {
+  device: DeviceId;
+  /** "input" or "output" */
+  kind: string;
+}
+
+/**
+ * Stable device identifier for persistence across reboots
+ *
+ * Matched by a score over all fields rather than compared exactly, so a device is found again after indices shift (see `audio/identity.rs`).
+ */
+export interface DeviceId {
+  /** Physical location: sysfs device path (USB port or PCI slot) */
+  bus_path?: string | null;
+  host_api: string;
+  /** Position in the host's enumeration (changes when devices are added) */
+  index: number;
+  name: string;
+  /** USB serial number, if the device reports one */
+  serial?: string | null;
+  /** USB vendor and product ID (`0d8c:0014`) */
+  usb_id?: string | null;
+}
+
+export interface DeviceLostPayload {
+  /** "input" or "output" */
+  kind: string;
+  previous: DeviceId;
+}
+
+export interface FallbackFailedPayload {
+  /** "input" or "output" */
+  kind: string;
+  reason: string;
+}
+
+export interface FallbackOkPayload {
+  /** "input" or "output" */
+  kind: string;
+  new_device: string;
+}
+
+/** Values at or below `le_ms` and above the previous bucket's bound */
+export interface HistogramBucket {
+  count: number;
+  /** Upper bound; absent for the overflow bucket */
+  le_ms?: number | null;
+}
+
+/** Latency distribution of one stage */
+export interface HistogramSnapshot {
+  buckets: HistogramBucket[];
+  count: number;
+  max_ms: number;
+  mean_ms: number;
+  /** Percentiles are bucket upper bounds (never above `max_ms`) */
+  p50_ms: number;
+  p95_ms: number;
+  p99_ms: number;
+}
+
+/** Outcome of one invocation (`hook:result` payload) */
+export interface HookResult {
+  duration_ms: number;
+  error?: string | null;
+  event: string;
+  exit_code?: number | null;
+  hook: string;
+  http_status?: number | null;
+  /** Truncated stdout or response body */
+  output?: string | null;
+  status: HookStatus;
+  /** Milliseconds since UNIX epoch */
+  ts: number;
+}
+
+export type HookStatus = "ok" | "failed" | "timeout" | "skipped";
+
+/** Matched intent (`intent:matched` payload) */
+export interface IntentMatch {
+  /** Alignment confidence (0.0-1.0) */
+  confidence: number;
+  intent: string;
+  /** Grammar phrase that matched */
+  phrase: string;
+  slots: Record<string, SlotValue>;
+  /** Normalized transcript */
+  text: string;
+}
+
+/** Real-KWS detection, used by the QA-019 test window */
+export interface KwsDetectionPayload {
+  keyword: string;
+  model_id: string;
+  /** Milliseconds since UNIX epoch */
+  ts: number;
+}
+
+/** Model download progress event */
+export interface ModelDownloadProgress {
+  downloaded: number;
+  model_id: string;
+  percent: number;
+  total: number;
+}
+
+export interface MonitorGuardedPayload {
+  /** e.g. "feedback_risk" when input and output are the same device */
+  reason: string;
+}
+
+/** Pipeline telemetry (`get_pipeline_metrics`, `audio:metrics` payload) */
+export interface PipelineMetricsSnapshot {
+  /** KWS decode time per second of audio fed (below 1.0 keeps up with real time) */
+  kws_rtf?: number | null;
+  /** Dropped audio: capture queue overruns and underruns */
+  queue: QueueStats;
+  stages: StageMetrics[];
+  /** Empty where per-thread CPU time is not available (Linux only) */
+  threads: ThreadCpu[];
+  /** Time the counts cover (since startup) */
+  uptime_ms: number;
+}
+
+/** Individual preflight check result */
+export interface PreflightItem {
+  fix_hint?: string | null;
+  message: string;
+  name: string;
+  status: CheckStatus;
+}
+
+/** Complete preflight report */
+export interface PreflightReport {
+  can_proceed: boolean;
+  items: PreflightItem[];
+  overall: CheckStatus;
+}
+
+export interface ProbeSuggestionPayload {
+  device: string;
+  reason: string;
+}
+
+/** A configuration profile was activated */
+export interface ProfileActivatedPayload {
+  /** Activated because its input device was plugged in */
+  auto: boolean;
+  name: string;
+  /** Profile values that were invalid and reset to their defaults */
+  warnings: ConfigWarning[];
+}
+
+/** Overrun and underrun counts over all captures since startup */
+export interface QueueStats {
+  /** Samples dropped by those callbacks */
+  dropped_samples: number;
+  /** Callbacks that found the ring full (worker stalled) */
+  overruns: number;
+  /** Waits for audio that timed out (device stopped delivering) */
+  underruns: number;
+}
+
+/** Audio capture restarted */
+export interface RestartOkPayload {
+  /** Input device name ("default" for the system default) */
+  device: string;
+  elapsed_ms: number;
+  /** Why the restart happened, if not requested by the user (e.g. "device_fallback") */
+  reason?: string | null;
+}
+
+/** Parsed slot value (serialized as `{ "type": ..., "value": ... }`) */
+export type SlotValue =
+  | { type: "duration"; value: { seconds: number } }
+  | { type: "number"; value: number }
+  | { type: "integer"; value: number }
+  | { type: "text"; value: string }
+  | { type: "enum"; value: string };
+
+/** Timing of one pipeline stage */
+export interface StageMetrics {
+  latency: HistogramSnapshot;
+  /** `capture`, `resample`, `preprocess`, `vad`, `kws_decode`, `emit` or `end_to_end` */
+  stage: string;
+}
+
+export interface TestTonePayload {
+  device: string;
+  duration_ms: number;
+  frequency_hz: number;
+  volume: number;
+}
+
+/** CPU use of one worker thread */
+export interface ThreadCpu {
+  /** CPU time since the thread started */
+  cpu_ms: number;
+  /** Share of one core over the last sample interval (100 = one full core) */
+  cpu_percent: number;
+  /** `kws` or `tagging` */
+  thread: string;
+}
+
+/** Wake-word detection event */
+export interface WakeWordEvent {
+  keyword: string;
+  score: number;
+}
+
+/** Payload type of every backend event, by name */
+export interface EventPayloads {
+  /** Input level (normalized RMS 0.0-1.0), ~20-50 times per second */
+  "audio:rms": number;
+  /** Audio failure or rejected command input */
+  "audio:error": AudioErrorPayload;
+  /** Audio capture restarted */
+  "audio:restart_ok": RestartOkPayload;
+  /** Restart ignored because the previous one was too recent */
+  "audio:restart_blocked": null;
+  /** Test tone finished playing */
+  "audio:test_tone_played": TestTonePayload;
+  /** Input device probe started */
+  "audio:auto_probe_started": null;
+  /** Probe found a better input device */
+  "audio:auto_probe_suggestion": ProbeSuggestionPayload;
+  /** Audio device plugged in (desktop app only) */
+  "audio:device_added": DeviceChangedPayload;
+  /** Audio device unplugged (desktop app only) */
+  "audio:device_removed": DeviceChangedPayload;
+  /** Configured device disappeared (desktop app only) */
+  "audio:device_lost": DeviceLostPayload;
+  /** Switched to the default device after a device was lost */
+  "audio:device_fallback_ok": FallbackOkPayload;
+  /** Could not switch to the default device */
+  "audio:device_fallback_failed": FallbackFailedPayload;
+  /** Switched back to the configured device after it was plugged in again */
+  "audio:device_restored": DeviceChangedPayload;
+  /** Mic monitor not resumed to avoid feedback */
+  "audio:monitor_guarded": MonitorGuardedPayload;
+  /** Sound event recognized by the audio tagger */
+  "audio:event_tagged": AudioEventTag;
+  /** Pipeline latency, CPU and drop counts, every 5 seconds while KWS runs */
+  "audio:metrics": PipelineMetricsSnapshot;
+  /** Wake word detected */
+  "wakeword::detected": WakeWordEvent;
+  /** KWS model download progress */
+  "kws:model_download_progress": ModelDownloadProgress;
+  /** Downloaded model matches its registry checksum (payload: model ID) */
+  "kws:model_verified": string;
+  /** Downloaded model failed verification and was removed (payload: model ID) */
+  "kws:model_verify_failed": string;
+  /** Real KWS enabled (payload: model ID) */
+  "kws:enabled": string;
+  /** Returned to the stub detector */
+  "kws:disabled": null;
+  /** Wake word detected while the QA test window was armed */
+  "kws:wake_test_pass": KwsDetectionPayload;
+  /** Internal: real-KWS detection for the test window listener */
+  "_kws_internal_detection": KwsDetectionPayload;
+  /** Preflight checks started */
+  "preflight:started": null;
+  /** One preflight check finished */
+  "preflight:item": PreflightItem;
+  /** All preflight checks finished */
+  "preflight:done": PreflightReport;
+  /** Transcript matched a voice command intent */
+  "intent:matched": IntentMatch;
+  /** Automation hook finished */
+  "hook:result": HookResult;
+  /** `config.toml` was edited outside the app and reloaded */
+  "config:changed": ConfigChangedPayload;
+  /** A configuration profile was applied and audio capture restarted with it */
+  "config:profile_activated": ProfileActivatedPayload;
+}
+
+export type EventName = keyof EventPayloads;
@@ -58,6 +58,7 @@ export interface Translations {
     lost: string;
     fallbackOk: string;
     fallbackFailed: string;
+    restored: string;
     change: string;
     retry: string;
     stableId: string;
@@ -156,9 +157,22 @@ export interface Translations {
     invalid_gain: string;
     invalid_threshold: string;
     invalid_sensitivity: string;
+    invalid_text: string;
+    invalid_lang: string;
     permission_denied: string;
     device_busy: string;
     device_not_found: string;
+    no_device: string;
+    timeout: string;
+    unsupported_format: string;
+    model_missing: string;
+    download_failed: string;
+    verification_failed: string;
+    profile_not_found: string;
+    io_error: string;
+    config_repaired: string;
+    config_reloaded: string;
+    profile_activated: string;
     unknown_error: string;
   };
 
@@ -247,6 +261,7 @@ export const translations: Record<Language, Translations> = {
       lost: '"{name}" was disconnected.',
       fallbackOk: "Switched to the default device.",
       fallbackFailed: "We couldn't switch automatically.",
+      restored: 'Switched back to "{name}".',
       change: "Change…",
       retry: "Retry",
       stableId: "Stable ID: {id}",
@@ -345,9 +360,22 @@ export const translations: Record<Language, Translations> = {
       invalid_gain: "Gain out of range (0.0–0.5).",
       invalid_threshold: "VAD threshold out of range (0.0–1.0).",
       invalid_sensitivity: "Sensitivity out of range (0.0–1.0).",
+      invalid_text: "Text is empty or too long (max 8192 bytes).",
+      invalid_lang: "Language code is invalid.",
       permission_denied: "Permission denied. Check your system settings.",
       device_busy: "Another application is using this device.",
       device_not_found: "Selected device was not found.",
+      no_device: "No audio device found. Please connect one.",
+      timeout: "The operation timed out. Please try again.",
+      unsupported_format: "This audio device is not supported.",
+      model_missing: "The wake word model is not installed.",
+      download_failed: "Download failed. Check your internet connection and try again.",
+      verification_failed: "The downloaded model was corrupted and has been removed. Please try again.",
+      profile_not_found: "No voice profile found for this user.",
+      io_error: "Could not read or write a file.",
+      config_repaired: "Some settings were invalid or outdated and have been reset.",
+      config_reloaded: "Settings reloaded from config.toml.",
+      profile_activated: 'Switched to profile "{name}".',
       unknown_error: "An unexpected error occurred.",
     },
 
@@ -434,6 +462,7 @@ export const translations: Record<Language, Translations> = {
       lost: 'Se desconectó "{name}".',
       fallbackOk: "Cambiamos al dispositivo predeterminado.",
       fallbackFailed: "No pudimos cambiar automáticamente.",
+      restored: 'Volvimos a "{name}".',
       change: "Cambiar…",
       retry: "Reintentar",
       stableId: "ID estable: {id}",
@@ -532,9 +561,22 @@ export const translations: Record<Language, Translations> = {
       invalid_gain: "Ganancia fuera de rango (0.0–0.5).",
       invalid_threshold: "Umbral VAD fuera de rango (0.0–1.0).",
       invalid_sensitivity: "Sensibilidad fuera de rango (0.0–1.0).",
+      invalid_text: "El texto está vacío o es demasiado largo (máx. 8192 bytes).",
+      invalid_lang: "El código de idioma no es válido.",
       permission_denied: "Permiso denegado. Revisa la configuración del sistema.",
       device_busy: "Otro programa está usando este dispositivo.",
       device_not_found: "No se encontró el dispositivo seleccionado.",
+      no_device: "No se encontró ningún dispositivo de audio. Conecta uno.",
+      timeout: "La operación tardó demasiado. Inténtalo de nuevo.",
+      unsupported_format: "Este dispositivo de audio no es compatible.",
+      model_missing: "El modelo de palabra de activación no está instalado.",
+      download_failed: "La descarga falló. Revisa tu conexión a internet e inténtalo de nuevo.",
+      verification_failed: "El modelo descargado estaba dañado y se eliminó. Inténtalo de nuevo.",
+      profile_not_found: "No hay perfil de voz para este usuario.",
+      io_error: "No se pudo leer o escribir un archivo.",
+      config_repaired: "Algunos ajustes no eran válidos o estaban desactualizados y se restablecieron.",
+      config_reloaded: "Ajustes recargados desde config.toml.",
+      profile_activated: 'Se cambió al perfil "{name}".',
       unknown_error: "Ocurrió un error inesperado.",
     },
 
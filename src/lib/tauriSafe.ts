@@ -6,6 +6,28 @@
  */
 
 import type { InvokeArgs } from "@tauri-apps/api/core";
+import {
+  EVENT_SCHEMA_VERSION,
+  type ConfigWarning,
+  type DeviceId,
+  type EventName,
+  type EventPayloads,
+  type KwsDetectionPayload,
+  type ModelDownloadProgress,
+  type PipelineMetricsSnapshot,
+  type QueueStats,
+} from "./events";
+
+export type {
+  CheckStatus,
+  ConfigWarning,
+  DeviceId,
+  IntentMatch,
+  PipelineMetricsSnapshot,
+  PreflightItem,
+  PreflightReport,
+  SlotValue,
+} from "./events";
 
 /**
  * Check if we're running in Tauri environment
@@ -34,28 +56,40 @@ export async function tauriInvoke<T>(
   return invoke<T>(cmd, args);
 }
 
-// ===== AUDIO DEVICE MANAGEMENT =====
-
-export interface DeviceId {
-  host_api: string;
-  index: number;
-  name: string;
+/**
+ * Typed event listener: the payload type comes from the generated event schema
+ * (see docs/events.md)
+ */
+export async function listenEvent<K extends EventName>(
+  event: K,
+  handler: (payload: EventPayloads[K]) => void
+): Promise<() => void> {
+  const { listen } = await import("@tauri-apps/api/event");
+  return listen<EventPayloads[K]>(event, (e) => handler(e.payload));
 }
 
+// ===== AUDIO DEVICE MANAGEMENT =====
+
 export interface DeviceInfo {
   name: string;
+  /** Display name; devices sharing a name get their port or a number */
+  label: string;
   is_default: boolean;
   host: string;
   max_channels: number;
   sample_rates: number[];
+  /** Sample formats the device offers, cheapest to convert first */
+  sample_formats?: string[];
   stable_id?: DeviceId;
+  /** Level per channel (dBFS) while the device is being captured */
+  channel_levels_dbfs?: number[];
 }
 
 export async function listInputDevices(): Promise<DeviceInfo[]> {
   if (!(await isTauriEnv())) {
     return [
-      { name: "Default Microphone", is_default: true, host: "web", max_channels: 1, sample_rates: [16000, 44100] },
-      { name: "USB Mic (Simulated)", is_default: false, host: "web", max_channels: 1, sample_rates: [16000, 48000] },
+      { name: "Default Microphone", label: "Default Microphone", is_default: true, host: "web", max_channels: 1, sample_rates: [16000, 44100] },
+      { name: "USB Mic (Simulated)", label: "USB Mic (Simulated)", is_default: false, host: "web", max_channels: 1, sample_rates: [16000, 48000] },
     ];
   }
   return tauriInvoke<DeviceInfo[]>("list_input_devices");
@@ -64,8 +98,8 @@ export async function listInputDevices(): Promise<DeviceInfo[]> {
 export async function listOutputDevices(): Promise<DeviceInfo[]> {
   if (!(await isTauriEnv())) {
     return [
-      { name: "Default Speakers", is_default: true, host: "web", max_channels: 2, sample_rates: [44100, 48000] },
-      { name: "HDMI Output (Simulated)", is_default: false, host: "web", max_channels: 2, sample_rates: [48000] },
+      { name: "Default Speakers", label: "Default Speakers", is_default: true, host: "web", max_channels: 2, sample_rates: [44100, 48000] },
+      { name: "HDMI Output (Simulated)", label: "HDMI Output (Simulated)", is_default: false, host: "web", max_channels: 2, sample_rates: [48000] },
     ];
   }
   return tauriInvoke<DeviceInfo[]>("list_output_devices");
@@ -81,24 +115,27 @@ export async function currentOutputDevice(): Promise<string | null> {
   return tauriInvoke<string | null>("current_output_device");
 }
 
+/** Pass the device's `stable_id` to pick one of several devices with the same name */
 export async function setInputDevice(
   name: string,
-  persist: boolean = true
+  persist: boolean = true,
+  stableId?: DeviceId
 ): Promise<string> {
   if (!(await isTauriEnv())) {
     return `[Web] Would set input to: ${name} (persist=${persist})`;
   }
-  return tauriInvoke<string>("set_input_device", { name, persist });
+  return tauriInvoke<string>("set_input_device", { name, persist, stableId });
 }
 
 export async function setOutputDevice(
   name: string,
-  persist: boolean = true
+  persist: boolean = true,
+  stableId?: DeviceId
 ): Promise<string> {
   if (!(await isTauriEnv())) {
     return `[Web] Would set output to: ${name} (persist=${persist})`;
   }
-  return tauriInvoke<string>("set_output_device", { name, persist });
+  return tauriInvoke<string>("set_output_device", { name, persist, stableId });
 }
 
 // ===== AUDIO RUNTIME MANAGEMENT =====
@@ -129,11 +166,12 @@ export async function playTestTone(opts?: {
 
 // ===== MICROPHONE MONITORING =====
 
+/** Without `gain`, the backend uses the last gain (`ui.monitor_gain`) */
 export async function startMicMonitor(gain?: number): Promise<string> {
   if (!(await isTauriEnv())) {
     return `[Web] Simulated mic monitor start (gain=${gain ?? 0.15})`;
   }
-  return tauriInvoke<string>("start_mic_monitor", { gain: gain ?? 0.15 });
+  return tauriInvoke<string>("start_mic_monitor", { gain });
 }
 
 export async function stopMicMonitor(): Promise<string> {
@@ -157,6 +195,7 @@ export interface UiConfig {
   min_touch_target_px: number;
   persist_monitor_state: boolean;
   monitor_was_on: boolean;
+  monitor_gain: number;
 }
 
 export interface AudioConfig {
@@ -167,10 +206,16 @@ export interface AudioConfig {
 }
 
 export interface AppConfig {
+  active_profile?: string;
   ui: UiConfig;
   audio: AudioConfig;
 }
 
+export async function getConfigWarnings(): Promise<ConfigWarning[]> {
+  if (!(await isTauriEnv())) return [];
+  return tauriInvoke<ConfigWarning[]>("get_config_warnings");
+}
+
 export async function getConfig(): Promise<AppConfig> {
   if (!(await isTauriEnv())) {
     return {
@@ -179,6 +224,7 @@ export async function getConfig(): Promise<AppConfig> {
         min_touch_target_px: 44,
         persist_monitor_state: false,
         monitor_was_on: false,
+        monitor_gain: 0.15,
       },
       audio: {
         device_name: null,
@@ -191,23 +237,42 @@ export async function getConfig(): Promise<AppConfig> {
   return tauriInvoke<AppConfig>("get_config");
 }
 
-// ===== PREFLIGHT CHECKS =====
-
-export type CheckStatus = "pass" | "warn" | "fail";
+// ===== CONFIGURATION PROFILES =====
 
-export interface PreflightItem {
+export interface ConfigProfileInfo {
   name: string;
-  status: CheckStatus;
-  message: string;
-  fix_hint?: string;
+  auto_activate: boolean;
+  input_device: string | null;
+  output_device: string | null;
+  active: boolean;
+}
+
+export async function listConfigProfiles(): Promise<ConfigProfileInfo[]> {
+  if (!(await isTauriEnv())) return [];
+  return tauriInvoke<ConfigProfileInfo[]>("list_config_profiles");
+}
+
+/** Save the current devices, KWS sensitivity, VAD threshold and monitor gain */
+export async function createConfigProfile(name: string, autoActivate = false): Promise<void> {
+  if (!(await isTauriEnv())) return;
+  return tauriInvoke<void>("create_config_profile", { name, autoActivate });
+}
+
+/** Apply a profile; audio capture restarts once with its settings */
+export async function activateConfigProfile(name: string): Promise<RestartResponse> {
+  if (!(await isTauriEnv())) {
+    return { ok: true, message: `[Web] Would activate profile: ${name}` };
+  }
+  return tauriInvoke<RestartResponse>("activate_config_profile", { name });
 }
 
-export interface PreflightReport {
-  items: PreflightItem[];
-  overall: CheckStatus;
-  can_proceed: boolean;
+export async function deleteConfigProfile(name: string): Promise<void> {
+  if (!(await isTauriEnv())) return;
+  return tauriInvoke<void>("delete_config_profile", { name });
 }
 
+// ===== PREFLIGHT CHECKS =====
+
 export async function runPreflightChecks(): Promise<PreflightReport> {
   if (!(await isTauriEnv())) {
     // Mock successful preflight in web mode
@@ -252,6 +317,30 @@ export interface AudioDebugInfo {
   samples_per_hop: number;
   input_device: string | null;
   output_device: string | null;
+  /** Levels before/after pre-processing; null while capture is stopped */
+  dsp_levels: DspLevels | null;
+  /** Format the running capture opened the device with */
+  capture_format: CaptureFormat | null;
+  /** Chunks lost to resampler failures since startup */
+  resample_errors: number;
+  /** unsupported_rate: AEC is enabled but only runs at a 16 kHz processing rate */
+  echo_cancellation: "active" | "disabled" | "unsupported_rate";
+}
+
+export interface CaptureFormat {
+  sample_rate: number;
+  channels: number;
+  sample_format: string;
+  /** False when the device default config was used */
+  negotiated: boolean;
+  /** Resampler to the processing rate; null when the device runs at it */
+  resampler: "sinc" | "fft" | "linear" | null;
+}
+
+export interface DspLevels {
+  input_rms_dbfs: number;
+  output_rms_dbfs: number;
+  agc_gain_db: number;
 }
 
 export async function getAudioDebug(): Promise<AudioDebugInfo> {
@@ -265,6 +354,10 @@ export async function getAudioDebug(): Promise<AudioDebugInfo> {
       samples_per_hop: 160,
       input_device: null,
       output_device: null,
+      dsp_levels: null,
+      capture_format: null,
+      resample_errors: 0,
+      echo_cancellation: "active",
     };
   }
   return tauriInvoke<AudioDebugInfo>("get_audio_debug");
@@ -278,7 +371,12 @@ export interface AudioSnapshot {
   output_devices: DeviceInfo[];
   selected_input_device: string | null;
   selected_output_device: string | null;
+  /** False when the selected device is unplugged and the default is used */
+  input_device_available: boolean;
+  output_device_available: boolean;
   monitor_active: boolean;
+  /** Capture queue counters since startup */
+  capture_queue: QueueStats;
   last_restart_ms: number;
   timestamp_ms: number;
 }
@@ -292,7 +390,10 @@ export async function getAudioSnapshot(): Promise<AudioSnapshot> {
       output_devices: await listOutputDevices(),
       selected_input_device: null,
       selected_output_device: null,
+      input_device_available: true,
+      output_device_available: true,
       monitor_active: false,
+      capture_queue: { overruns: 0, dropped_samples: 0, underruns: 0 },
       last_restart_ms: now - 30000, // 30s ago
       timestamp_ms: now,
     };
@@ -300,6 +401,28 @@ export async function getAudioSnapshot(): Promise<AudioSnapshot> {
   return tauriInvoke<AudioSnapshot>("get_audio_snapshot");
 }
 
+/** Per-stage latency histograms, KWS real-time factor and worker CPU use */
+export async function getPipelineMetrics(): Promise<PipelineMetricsSnapshot> {
+  if (!(await isTauriEnv())) {
+    return {
+      uptime_ms: 0,
+      stages: [],
+      kws_rtf: null,
+      threads: [],
+      queue: { overruns: 0, dropped_samples: 0, underruns: 0 },
+    };
+  }
+  return tauriInvoke<PipelineMetricsSnapshot>("get_pipeline_metrics");
+}
+
+/**
+ * Write a bug report bundle (tar.gz) to the cache directory and return its path.
+ * Debug recordings are only included with includeClips; voiceprints never are.
+ */
+export async function createDiagnosticBundle(includeClips: boolean): Promise<string> {
+  return tauriInvoke<string>("create_diagnostic_bundle", { includeClips });
+}
+
 // ===== BE-015 NEW COMMANDS =====
 
 export interface ProbeResult {
@@ -338,7 +461,12 @@ export async function restartAudioCaptureV2(): Promise<RestartResponse> {
 
 // ===== GLOBAL ERROR LISTENER (SEC-001B) =====
 
-import { toUserMessage } from "./errors";
+import {
+  configReloadedMessage,
+  configRepairedMessage,
+  profileActivatedMessage,
+  toUserMessage,
+} from "./errors";
 import { toast } from "sonner";
 
 let subscribed = false;
@@ -349,12 +477,51 @@ let subscribed = false;
  */
 export async function subscribeGlobalErrors() {
   if (subscribed || !(await isTauriEnv())) return;
-  const { listen } = await import("@tauri-apps/api/event");
-  await listen("audio:error", (e) => {
-    const msg = toUserMessage(e.payload as any);
-    toast.error(msg, { duration: 5000 });
+  await listenEvent("audio:error", (payload) => {
+    toast.error(toUserMessage(payload), { duration: 5000 });
   });
   subscribed = true;
+
+  // Generated event types must match the backend's payload shapes
+  const backendVersion = await tauriInvoke<number>("event_schema_version");
+  if (backendVersion !== EVENT_SCHEMA_VERSION) {
+    console.warn(
+      `Event schema mismatch: backend v${backendVersion}, frontend v${EVENT_SCHEMA_VERSION}. ` +
+        "Run `npm run gen:events` and rebuild."
+    );
+  }
+
+  // Settings the backend had to reset or migrate at startup
+  const configWarnings = await getConfigWarnings();
+  if (configWarnings.length > 0) {
+    toast.warning(configRepairedMessage(), {
+      description: formatConfigWarnings(configWarnings),
+      duration: 10000,
+    });
+  }
+
+  // config.toml edited by hand while the app runs
+  await listenEvent("config:changed", (payload) => {
+    if (payload.warnings.length > 0) {
+      toast.warning(configReloadedMessage(), {
+        description: formatConfigWarnings(payload.warnings),
+        duration: 10000,
+      });
+    } else {
+      toast.info(configReloadedMessage(), { description: payload.sections.join(", ") });
+    }
+  });
+
+  // Profile applied from settings or because its input device was plugged in
+  await listenEvent("config:profile_activated", (payload) => {
+    const description =
+      payload.warnings.length > 0 ? formatConfigWarnings(payload.warnings) : undefined;
+    toast.info(profileActivatedMessage(payload.name), { description });
+  });
+}
+
+function formatConfigWarnings(warnings: ConfigWarning[]): string {
+  return warnings.map((w) => (w.field ? `${w.field}: ${w.message}` : w.message)).join("\n");
 }
 
 // ===== KWS (KEYWORD SPOTTING) MANAGEMENT =====
@@ -446,21 +613,45 @@ export async function isPipewireLoopback(): Promise<boolean> {
   return tauriInvoke<boolean>("is_pipewire_loopback");
 }
 
-// ===== KWS EVENT SUBSCRIPTIONS =====
+// ===== TEXT POST-PROCESSING =====
 
-export interface KwsDownloadProgress {
-  model_id: string;
-  downloaded: number;
-  total: number;
-  percent: number;
+export interface PunctuationResult {
+  text: string;
+  lang: string;
+  model_id: string | null;
+  punctuated: boolean;
+  cased: boolean;
 }
 
-export interface KwsTestPassPayload {
-  model_id: string;
-  keyword: string;
-  ts: number;
+export async function punctuateText(text: string, lang?: string): Promise<PunctuationResult> {
+  if (!(await isTauriEnv())) {
+    return { text, lang: lang ?? "en", model_id: null, punctuated: false, cased: false };
+  }
+  return tauriInvoke<PunctuationResult>("punctuate_text", { text, lang });
 }
 
+// ===== INTENTS =====
+
+export async function matchIntent(text: string): Promise<IntentMatch | null> {
+  if (!(await isTauriEnv())) {
+    return null;
+  }
+  return tauriInvoke<IntentMatch | null>("match_intent", { text });
+}
+
+export async function reloadCommands(): Promise<number> {
+  if (!(await isTauriEnv())) {
+    return 0;
+  }
+  return tauriInvoke<number>("reload_commands");
+}
+
+// ===== KWS EVENT SUBSCRIPTIONS =====
+
+export type KwsDownloadProgress = ModelDownloadProgress;
+
+export type KwsTestPassPayload = KwsDetectionPayload;
+
 export interface KwsEventHandlers {
   onDownloadProgress?: (progress: KwsDownloadProgress) => void;
   onModelVerified?: (modelId: string) => void;
@@ -479,41 +670,39 @@ let kwsSubscribed = false;
 export async function subscribeKwsEvents(handlers: KwsEventHandlers) {
   if (kwsSubscribed || !(await isTauriEnv())) return;
 
-  const { listen } = await import("@tauri-apps/api/event");
-
   if (handlers.onDownloadProgress) {
-    await listen<KwsDownloadProgress>("kws:model_download_progress", (e) => {
-      handlers.onDownloadProgress?.(e.payload);
+    await listenEvent("kws:model_download_progress", (payload) => {
+      handlers.onDownloadProgress?.(payload);
     });
   }
 
   if (handlers.onModelVerified) {
-    await listen<string>("kws:model_verified", (e) => {
-      handlers.onModelVerified?.(e.payload);
+    await listenEvent("kws:model_verified", (payload) => {
+      handlers.onModelVerified?.(payload);
     });
   }
 
   if (handlers.onModelVerifyFailed) {
-    await listen<string>("kws:model_verify_failed", (e) => {
-      handlers.onModelVerifyFailed?.(e.payload);
+    await listenEvent("kws:model_verify_failed", (payload) => {
+      handlers.onModelVerifyFailed?.(payload);
     });
   }
 
   if (handlers.onEnabled) {
-    await listen<string>("kws:enabled", (e) => {
-      handlers.onEnabled?.(e.payload);
+    await listenEvent("kws:enabled", (payload) => {
+      handlers.onEnabled?.(payload);
     });
   }
 
   if (handlers.onDisabled) {
-    await listen("kws:disabled", () => {
+    await listenEvent("kws:disabled", () => {
       handlers.onDisabled?.();
     });
   }
 
   if (handlers.onWakeTestPass) {
-    await listen<KwsTestPassPayload>("kws:wake_test_pass", (e) => {
-      handlers.onWakeTestPass?.(e.payload);
+    await listenEvent("kws:wake_test_pass", (payload) => {
+      handlers.onWakeTestPass?.(payload);
     });
   }
 